@@ -30,9 +30,34 @@
 //!
 //! ## Graph Operations
 //! - `graph.check` - Validate pipeline
+//! - `graph.missing_elements` - List the elements of the graph missing from the registry
 //! - `graph.clear` - Clear current graph
 //! - `graph.pipeline_details` - Show pipeline details (enabled only when playing)
+//! - `graph.latency_budget` - Show the latency budget (enabled only when playing)
+//! - `graph.arrange_layout` - Arrange the graph or the selection with layout options and a live preview
 //! - `delete` - Delete selected elements (`Ctrl+D`, `Delete`, or `Ctrl+BackSpace`)
+//! - `graph.align_left` - Align the selected nodes on the left (`Ctrl+Alt+Left`)
+//! - `graph.align_right` - Align the selected nodes on the right (`Ctrl+Alt+Right`)
+//! - `graph.align_top` - Align the selected nodes on the top (`Ctrl+Alt+Up`)
+//! - `graph.align_bottom` - Align the selected nodes on the bottom (`Ctrl+Alt+Down`)
+//! - `graph.align_center` - Align the horizontal centres of the selected nodes
+//! - `graph.align_middle` - Align the vertical centres of the selected nodes
+//! - `graph.distribute_horizontal` - Space the selected nodes evenly horizontally (`Ctrl+Alt+H`)
+//! - `graph.distribute_vertical` - Space the selected nodes evenly vertically (`Ctrl+Alt+V`)
+//!
+//! ## View
+//! - `graph.zoom_fit` - Zoom to display all the nodes (`Ctrl+1`)
+//! - `graph.zoom_selection` - Zoom to display the selected nodes (`Ctrl+2`)
+//! - `graph.center_node` - Center the view on the selected node (`Ctrl+3`)
+//! - `graph.minimap` - Show or hide the minimap (`Ctrl+M`)
+//! - `graph.search` - Search the nodes, ports, properties and caps of the graph (`Ctrl+F`)
+//! - `graph.link_routing_<style>` - Draw the links of the graph as straight lines, bezier
+//!   curves or orthogonal segments going around the nodes
+//!
+//! ## Playback
+//! - `play_all` - Start the pipelines of all the tabs (<Ctrl+Shift+Space>)
+//! - `stop_all` - Stop the pipelines of all the tabs (<Ctrl+Shift+Escape>)
+//! - `clock_sync` - Select the tabs sharing a common clock and base time
 //!
 //! ## Element Operations
 //! - `node.add-to-favorite` - Add element to favorites
@@ -41,15 +66,36 @@
 //! - `node.request-pad-output` - Request output pad
 //! - `node.properties` - Show element properties
 //! - `node.duplicate` - Duplicate element
+//! - `node.replace` - Replace the element by another factory keeping its properties and links
+//! - `node.control` - Animate the element properties with keyframes
 //! - `port.delete` - Delete port
 //! - `port.properties` - Show port properties
+//! - `port.link_tab` - Link the port to a port of another tab
+//! - `port.unlink_tab` - Remove the link of the port to another tab
 //! - `link.delete` - Delete link
+//! - `link.record` - Record the data flowing through the link while playing
+//! - `link.caps_filter` - Edit the caps filter of the link
+//! - `node.frame` - Add a frame around the selected nodes
+//! - `node.pin` - Pin or unpin the node position, auto-arrange does not move pinned nodes
+//! - `node.save_template` - Save the selected nodes as a template of the library
+//! - `node.group_macro` - Group the selected nodes into a macro node expanded at launch time
+//!
+//! ## Annotations
+//! - `graph.add_note` - Add a sticky note where the graph was clicked
+//! - `graph.add_frame` - Add a coloured frame where the graph was clicked
+//! - `annotation.edit` - Edit the text of a note or the title of a frame
+//! - `annotation.color_<name>` - Change the colour of a note or frame
+//! - `annotation.delete` - Delete a note or frame
 //!
 //! ## Other
 //! - `preferences` - Show preferences dialog (<Ctrl+P>)
+//! - `ranks` - Edit the rank profiles overriding the rank of the plugin features
 //! - `about` - Show about dialog (<Ctrl+A>)
 //! - `logger.clear` - Clear log messages
 //! - `logger.copy` - Copy selected log messages to clipboard
+//! - `logger.save` - Save the GStreamer log as a GST_DEBUG text file
+//! - `logger.load` - Load a GST_DEBUG text file in the GStreamer log
+//! - `logger.categories` - Edit the GStreamer debug category thresholds
 //!
 //! # Recent Files
 //!
@@ -61,6 +107,7 @@ use gtk::prelude::*;
 use gtk::{gio, gio::SimpleAction, Button};
 use std::path::Path;
 
+use crate::graphmanager as GM;
 use crate::logger;
 use crate::GPS_ERROR;
 
@@ -106,6 +153,13 @@ impl GPSApp {
 
         application.add_action(&gio::SimpleAction::new("preferences", None));
         application.set_accels_for_action("app.preferences", &["<primary>p"]);
+        application.add_action(&gio::SimpleAction::new("ranks", None));
+
+        application.add_action(&gio::SimpleAction::new("play_all", None));
+        application.set_accels_for_action("app.play_all", &["<primary><shift>space"]);
+        application.add_action(&gio::SimpleAction::new("stop_all", None));
+        application.set_accels_for_action("app.stop_all", &["<primary><shift>Escape"]);
+        application.add_action(&gio::SimpleAction::new("clock_sync", None));
 
         application.add_action(&gio::SimpleAction::new("about", None));
         application.set_accels_for_action("app.about", &["<primary>a"]);
@@ -115,19 +169,69 @@ impl GPSApp {
 
         application.add_action(&gio::SimpleAction::new("logger.clear", None));
         application.add_action(&gio::SimpleAction::new("logger.copy", None));
+        application.add_action(&gio::SimpleAction::new("logger.save", None));
+        application.add_action(&gio::SimpleAction::new("logger.load", None));
+        application.add_action(&gio::SimpleAction::new("logger.categories", None));
 
         application.add_action(&gio::SimpleAction::new("graph.check", None));
+        application.add_action(&gio::SimpleAction::new("graph.missing_elements", None));
         application.add_action(&gio::SimpleAction::new("graph.clear", None));
         application.add_action(&gio::SimpleAction::new("graph.arrange_pipeline", None));
+        application.add_action(&gio::SimpleAction::new("graph.arrange_layout", None));
+
+        for (action_name, accel) in [
+            ("graph.align_left", Some("<primary><alt>Left")),
+            ("graph.align_right", Some("<primary><alt>Right")),
+            ("graph.align_top", Some("<primary><alt>Up")),
+            ("graph.align_bottom", Some("<primary><alt>Down")),
+            ("graph.align_center", None),
+            ("graph.align_middle", None),
+            ("graph.distribute_horizontal", Some("<primary><alt>h")),
+            ("graph.distribute_vertical", Some("<primary><alt>v")),
+        ] {
+            application.add_action(&gio::SimpleAction::new(action_name, None));
+            if let Some(accel) = accel {
+                application.set_accels_for_action(&format!("app.{action_name}"), &[accel]);
+            }
+        }
+
+        application.add_action(&gio::SimpleAction::new("graph.zoom_fit", None));
+        application.set_accels_for_action("app.graph.zoom_fit", &["<primary>1"]);
+        application.add_action(&gio::SimpleAction::new("graph.zoom_selection", None));
+        application.set_accels_for_action("app.graph.zoom_selection", &["<primary>2"]);
+        application.add_action(&gio::SimpleAction::new("graph.center_node", None));
+        application.set_accels_for_action("app.graph.center_node", &["<primary>3"]);
+        application.add_action(&gio::SimpleAction::new("graph.minimap", None));
+        application.set_accels_for_action("app.graph.minimap", &["<primary>m"]);
+        application.add_action(&gio::SimpleAction::new("graph.search", None));
+        application.set_accels_for_action("app.graph.search", &["<primary>f"]);
+        for routing in [
+            GM::LinkRouting::Straight,
+            GM::LinkRouting::Bezier,
+            GM::LinkRouting::Orthogonal,
+        ] {
+            application.add_action(&gio::SimpleAction::new(
+                &format!("graph.link_routing_{}", routing.to_string().to_lowercase()),
+                None,
+            ));
+        }
 
         let pipeline_details_action = gio::SimpleAction::new("graph.pipeline_details", None);
         pipeline_details_action.set_enabled(false); // Initially disabled
         application.add_action(&pipeline_details_action);
 
+        let latency_budget_action = gio::SimpleAction::new("graph.latency_budget", None);
+        latency_budget_action.set_enabled(false); // Initially disabled
+        application.add_action(&latency_budget_action);
+
         application.add_action(&gio::SimpleAction::new("port.delete", None));
         application.add_action(&gio::SimpleAction::new("port.properties", None));
+        application.add_action(&gio::SimpleAction::new("port.link_tab", None));
+        application.add_action(&gio::SimpleAction::new("port.unlink_tab", None));
 
         application.add_action(&gio::SimpleAction::new("link.delete", None));
+        application.add_action(&gio::SimpleAction::new("link.record", None));
+        application.add_action(&gio::SimpleAction::new("link.caps_filter", None));
 
         application.add_action(&gio::SimpleAction::new("node.add-to-favorite", None));
         application.add_action(&gio::SimpleAction::new("node.delete", None));
@@ -135,6 +239,23 @@ impl GPSApp {
         application.add_action(&gio::SimpleAction::new("node.request-pad-output", None));
         application.add_action(&gio::SimpleAction::new("node.properties", None));
         application.add_action(&gio::SimpleAction::new("node.duplicate", None));
+        application.add_action(&gio::SimpleAction::new("node.replace", None));
+        application.add_action(&gio::SimpleAction::new("node.control", None));
+        application.add_action(&gio::SimpleAction::new("node.frame", None));
+        application.add_action(&gio::SimpleAction::new("node.pin", None));
+        application.add_action(&gio::SimpleAction::new("node.save_template", None));
+        application.add_action(&gio::SimpleAction::new("node.group_macro", None));
+
+        application.add_action(&gio::SimpleAction::new("graph.add_note", None));
+        application.add_action(&gio::SimpleAction::new("graph.add_frame", None));
+        application.add_action(&gio::SimpleAction::new("annotation.edit", None));
+        application.add_action(&gio::SimpleAction::new("annotation.delete", None));
+        for (name, _) in GM::ANNOTATION_COLORS {
+            application.add_action(&gio::SimpleAction::new(
+                &format!("annotation.color_{}", name.to_lowercase()),
+                None,
+            ));
+        }
     }
 
     pub fn update_recent_files_menu(&self) {
@@ -18,6 +18,7 @@ use gtk::prelude::*;
 use gtk::{gio, Application, Label};
 
 use crate::gps as GPS;
+use crate::graphmanager as GM;
 use crate::logger;
 use crate::ui as GPSUI;
 use crate::{GPS_DEBUG, GPS_ERROR, GPS_TRACE, GPS_WARN};
@@ -33,6 +34,14 @@ const LINK_COLOR_PLAYING: (f64, f64, f64) = (0.2, 0.8, 0.2); // Green
 const LINK_COLOR_PAUSED: (f64, f64, f64) = (1.0, 0.6, 0.0); // Orange
 const LINK_COLOR_IDLE: (f64, f64, f64) = (0.5, 0.5, 0.5); // Gray
 
+fn link_color(state: &AppState) -> (f64, f64, f64) {
+    match state {
+        AppState::Playing => LINK_COLOR_PLAYING,
+        AppState::Paused => LINK_COLOR_PAUSED,
+        _ => LINK_COLOR_IDLE,
+    }
+}
+
 impl GPSApp {
     pub fn set_app_state(&self, state: AppState) {
         let status_bar: Label = self
@@ -41,24 +50,27 @@ impl GPSApp {
             .expect("Couldn't get status_bar");
         status_bar.set_text(&state.to_string());
 
-        // Enable/disable pipeline details menu based on state
+        // Enable/disable pipeline details and latency menus based on state
         // Only update if the action exists (may not exist during early initialization)
         if let Some(app) = gtk::gio::Application::default() {
-            if let Some(action) = app
-                .lookup_action("graph.pipeline_details")
-                .and_then(|a| a.downcast::<gio::SimpleAction>().ok())
-            {
-                let is_playing = matches!(state, AppState::Playing | AppState::Paused);
-                action.set_enabled(is_playing);
+            let is_running = matches!(state, AppState::Playing | AppState::Paused);
+            // The live elements only report their latency once playing
+            let is_playing = matches!(state, AppState::Playing);
+            for (action_name, enabled) in [
+                ("graph.pipeline_details", is_running),
+                ("graph.latency_budget", is_playing),
+            ] {
+                if let Some(action) = app
+                    .lookup_action(action_name)
+                    .and_then(|a| a.downcast::<gio::SimpleAction>().ok())
+                {
+                    action.set_enabled(enabled);
+                }
             }
         }
 
         // Update link color on the active graph tab only
-        let (r, g, b) = match state {
-            AppState::Playing => LINK_COLOR_PLAYING,
-            AppState::Paused => LINK_COLOR_PAUSED,
-            _ => LINK_COLOR_IDLE,
-        };
+        let (r, g, b) = link_color(&state);
         graphbook::current_graphtab(self)
             .graphview()
             .set_link_color(r, g, b);
@@ -69,6 +81,35 @@ impl GPSApp {
         }
     }
 
+    /// Reflect the new state of a tab player. The status bar and the menus follow
+    /// the current tab only, the tab label and links are updated for every tab.
+    pub fn set_player_state(&self, player: &GPS::Player, state: AppState) {
+        let graphtab = self
+            .graphbook
+            .borrow()
+            .values()
+            .find(|tab| tab.player().ptr_eq(player))
+            .cloned();
+        let Some(graphtab) = graphtab else {
+            self.set_app_state(state);
+            return;
+        };
+        graphtab.update_label();
+        if graphtab.id() == self.current_graphtab.get() {
+            self.set_app_state(state);
+            self.update_record_button();
+            return;
+        }
+        let (r, g, b) = link_color(&state);
+        graphtab.graphview().set_link_color(r, g, b);
+        if let AppState::Error(Some(ref msg)) = state {
+            GPSUI::message::display_error_dialog(
+                false,
+                &format!("{}: {}", graphtab.basename(), msg),
+            );
+        }
+    }
+
     pub fn set_app_preview(&self, paintable: &gdk::Paintable, n_sink: usize) {
         let picture = gtk::Picture::new();
         picture.set_paintable(Some(paintable));
@@ -132,6 +173,7 @@ impl GPSApp {
         GPSUI::logger::setup_logger_list(self, "treeview-app-logger", logger::LogType::App);
         GPSUI::logger::setup_logger_list(self, "treeview-msg-logger", logger::LogType::Message);
         GPSUI::logger::setup_logger_list(self, "treeview-gst-logger", logger::LogType::Gst);
+        GPSUI::validation::setup_problems_panel(self);
         let app_weak = self.downgrade();
         glib::spawn_future_local(async move {
             while let Ok(msg) = ready_rx.recv().await {
@@ -291,6 +333,12 @@ impl GPSApp {
             GPSUI::preferences::display_settings(&app);
         });
 
+        let app_weak = self.downgrade();
+        self.connect_app_menu_action("ranks", move |_, _| {
+            let app = upgrade_weak!(app_weak);
+            GPSUI::rank::display_rank_profiles(&app);
+        });
+
         let app_weak = self.downgrade();
         self.connect_app_menu_action("delete", move |_, _| {
             let app = upgrade_weak!(app_weak);
@@ -299,6 +347,111 @@ impl GPSApp {
                 .delete_selected();
         });
 
+        for (action_name, alignment) in [
+            ("graph.align_left", GM::NodeAlignment::Left),
+            ("graph.align_right", GM::NodeAlignment::Right),
+            ("graph.align_top", GM::NodeAlignment::Top),
+            ("graph.align_bottom", GM::NodeAlignment::Bottom),
+            ("graph.align_center", GM::NodeAlignment::Center),
+            ("graph.align_middle", GM::NodeAlignment::Middle),
+        ] {
+            let app_weak = self.downgrade();
+            self.connect_app_menu_action(action_name, move |_, _| {
+                let app = upgrade_weak!(app_weak);
+                graphbook::current_graphtab(&app)
+                    .graphview()
+                    .align_selected_nodes(alignment);
+            });
+        }
+
+        let app_weak = self.downgrade();
+        self.connect_app_menu_action("graph.zoom_fit", move |_, _| {
+            let app = upgrade_weak!(app_weak);
+            graphbook::current_graphtab(&app).graphview().zoom_to_fit();
+        });
+
+        let app_weak = self.downgrade();
+        self.connect_app_menu_action("graph.zoom_selection", move |_, _| {
+            let app = upgrade_weak!(app_weak);
+            graphbook::current_graphtab(&app)
+                .graphview()
+                .zoom_to_selection();
+        });
+
+        let app_weak = self.downgrade();
+        self.connect_app_menu_action("graph.center_node", move |_, _| {
+            let app = upgrade_weak!(app_weak);
+            let graphtab = graphbook::current_graphtab(&app);
+            let graphview = graphtab.graphview();
+            if let Some(node) = graphview.selected_nodes().first() {
+                graphview.center_on_node(node.id());
+            }
+        });
+
+        let app_weak = self.downgrade();
+        self.connect_app_menu_action("graph.minimap", move |_, _| {
+            let app = upgrade_weak!(app_weak);
+            let visible = !Settings::minimap_visible();
+            Settings::set_minimap_visible(visible);
+            for graphtab in app.graphbook.borrow().values() {
+                graphtab.minimap().set_visible(visible);
+            }
+        });
+
+        let app_weak = self.downgrade();
+        self.connect_app_menu_action("graph.search", move |_, _| {
+            let app = upgrade_weak!(app_weak);
+            app.show_graph_search();
+        });
+
+        for routing in [
+            GM::LinkRouting::Straight,
+            GM::LinkRouting::Bezier,
+            GM::LinkRouting::Orthogonal,
+        ] {
+            let app_weak = self.downgrade();
+            self.connect_app_menu_action(
+                &format!("graph.link_routing_{}", routing.to_string().to_lowercase()),
+                move |_, _| {
+                    let app = upgrade_weak!(app_weak);
+                    graphbook::current_graphtab(&app)
+                        .graphview()
+                        .set_link_routing(routing);
+                },
+            );
+        }
+
+        let app_weak = self.downgrade();
+        self.connect_app_menu_action("graph.check", move |_, _| {
+            let app = upgrade_weak!(app_weak);
+            app.check_graph();
+        });
+
+        let app_weak = self.downgrade();
+        self.connect_app_menu_action("graph.missing_elements", move |_, _| {
+            let app = upgrade_weak!(app_weak);
+            GPSUI::missing::display_missing_elements(&app);
+        });
+
+        let app_weak = self.downgrade();
+        self.connect_app_menu_action("graph.arrange_layout", move |_, _| {
+            let app = upgrade_weak!(app_weak);
+            GPSUI::layout::display_layout_dialog(&app);
+        });
+
+        for (action_name, orientation) in [
+            ("graph.distribute_horizontal", gtk::Orientation::Horizontal),
+            ("graph.distribute_vertical", gtk::Orientation::Vertical),
+        ] {
+            let app_weak = self.downgrade();
+            self.connect_app_menu_action(action_name, move |_, _| {
+                let app = upgrade_weak!(app_weak);
+                graphbook::current_graphtab(&app)
+                    .graphview()
+                    .distribute_selected_nodes(orientation);
+            });
+        }
+
         let app_weak = self.downgrade();
         self.connect_app_menu_action("undo", move |_, _| {
             let app = upgrade_weak!(app_weak);
@@ -320,8 +473,8 @@ impl GPSApp {
         let app_weak = self.downgrade();
         self.connect_button_action("button-play", move |_| {
             let app = upgrade_weak!(app_weak);
-            let _ = graphbook::current_graphtab(&app).player().start_pipeline(
-                &graphbook::current_graphtab(&app).graphview(),
+            let _ = app.start_graphtab(
+                &graphbook::current_graphtab(&app),
                 GPS::PipelineState::Playing,
             );
         });
@@ -329,8 +482,8 @@ impl GPSApp {
         let app_weak = self.downgrade();
         self.connect_button_action("button-pause", move |_| {
             let app = upgrade_weak!(app_weak);
-            let _ = graphbook::current_graphtab(&app).player().start_pipeline(
-                &graphbook::current_graphtab(&app).graphview(),
+            let _ = app.start_graphtab(
+                &graphbook::current_graphtab(&app),
                 GPS::PipelineState::Paused,
             );
         });
@@ -344,14 +497,45 @@ impl GPSApp {
                 .set_state(GPS::PipelineState::Stopped);
         });
 
+        let app_weak = self.downgrade();
+        self.connect_app_menu_action("play_all", move |_, _| {
+            let app = upgrade_weak!(app_weak);
+            app.play_all();
+        });
+
+        let app_weak = self.downgrade();
+        self.connect_app_menu_action("stop_all", move |_, _| {
+            let app = upgrade_weak!(app_weak);
+            app.stop_all();
+        });
+
+        let app_weak = self.downgrade();
+        self.connect_app_menu_action("clock_sync", move |_, _| {
+            let app = upgrade_weak!(app_weak);
+            GPSUI::playback::display_clock_sync_dialog(&app);
+        });
+
         let app_weak = self.downgrade();
         self.connect_button_action("button-clear", move |_| {
             let app = upgrade_weak!(app_weak);
             app.clear_graph();
         });
 
+        let app_weak = self.downgrade();
+        self.connect_button_action("button-snapshot", move |_| {
+            let app = upgrade_weak!(app_weak);
+            app.take_snapshot();
+        });
+
+        let app_weak = self.downgrade();
+        self.connect_button_action("button-record", move |_| {
+            let app = upgrade_weak!(app_weak);
+            app.toggle_recording();
+        });
+
         // Setup the favorite list
         GPSUI::elements::setup_favorite_list(self);
+        GPSUI::elements::setup_template_list(self);
         // Setup the favorite list
         GPSUI::elements::setup_elements_list(self);
         if pipeline_desc.is_empty() {
@@ -0,0 +1,147 @@
+// capture.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Snapshot and recording of the preview.
+//!
+//! A snapshot saves the last frame displayed by a video sink of the current tab. A
+//! recording branch is added on the selected link of the running pipeline and removed
+//! when the recording is stopped, the graph itself is never modified.
+
+use gtk::glib;
+use gtk::prelude::*;
+
+use crate::gps::capture::RecordingPreset;
+use crate::graphmanager::SelectionExt;
+use crate::logger;
+use crate::ui as GPSUI;
+use crate::ui::dialog::FileDialogType;
+use crate::{GPS_ERROR, GPS_INFO};
+
+use super::super::GPSApp;
+use super::graphbook;
+
+impl GPSApp {
+    /// Save the frame displayed in the current preview page to a PNG file.
+    pub fn take_snapshot(&self) {
+        let graphtab = graphbook::current_graphtab(self);
+        if !graphtab.player().is_playing() {
+            GPSUI::message::display_error_dialog(
+                false,
+                "The pipeline must be running to take a snapshot.",
+            );
+            return;
+        }
+        let notebook_preview: gtk::Notebook = self
+            .builder
+            .object("notebook-preview")
+            .expect("Couldn't get notebook-preview");
+        let index = notebook_preview.current_page().unwrap_or(0) as usize;
+        GPSUI::dialog::get_file(self, FileDialogType::SaveSnapshot, move |_app, filename| {
+            let display_error = |e: anyhow::Error| {
+                GPS_ERROR!("Unable to take a snapshot: {}", e);
+                GPSUI::message::display_error_dialog(
+                    false,
+                    &format!("Unable to take a snapshot: {e}"),
+                );
+            };
+            if let Err(e) = graphtab.player().snapshot(index, &filename, move |result| {
+                if let Err(e) = result {
+                    display_error(e);
+                }
+            }) {
+                display_error(e);
+            }
+        });
+    }
+
+    /// Stop the recording in progress, or ask for the recording settings of the
+    /// selected link.
+    pub fn toggle_recording(&self) {
+        let graphtab = graphbook::current_graphtab(self);
+        if graphtab.player().is_recording() {
+            self.stop_recording();
+            return;
+        }
+        let link = graphtab
+            .graphview()
+            .all_links(true)
+            .into_iter()
+            .find(|link| link.selected());
+        match link {
+            Some(link) => GPSUI::capture::display_recording_dialog(self, link.id()),
+            None => GPSUI::message::display_error_dialog(
+                false,
+                "Select the video link to record in the graph.",
+            ),
+        }
+    }
+
+    /// Record the data flowing through a link of the current tab while it is playing.
+    pub fn start_recording(&self, link_id: u32, preset: &RecordingPreset, location: &str) {
+        let graphtab = graphbook::current_graphtab(self);
+        let graphview = graphtab.graphview();
+        let Some(link) = graphview.link(link_id) else {
+            return;
+        };
+        let Some(node) = graphview.node(link.node_from) else {
+            return;
+        };
+        let Some(port) = node.port(link.port_from) else {
+            return;
+        };
+        if let Err(e) =
+            graphtab
+                .player()
+                .start_recording(&node.unique_name(), &port.name(), preset, location)
+        {
+            GPS_ERROR!("Unable to start the recording: {}", e);
+            GPSUI::message::display_error_dialog(
+                false,
+                &format!("Unable to start the recording: {e}"),
+            );
+        }
+        self.update_record_button();
+    }
+
+    pub fn stop_recording(&self) {
+        let graphtab = graphbook::current_graphtab(self);
+        let display_error = |e: anyhow::Error| {
+            GPS_ERROR!("Unable to stop the recording: {}", e);
+            GPSUI::message::display_error_dialog(
+                false,
+                &format!("Unable to stop the recording: {e}"),
+            );
+        };
+        // The file is finalized in the background, the pipeline keeps running
+        let stopped = graphtab
+            .player()
+            .stop_recording(move |result| match result {
+                Ok(location) => GPS_INFO!("Recording saved to {}", location),
+                Err(e) => display_error(e),
+            });
+        if let Err(e) = stopped {
+            display_error(e);
+        }
+        self.update_record_button();
+    }
+
+    /// Reflect the recording state of the current tab on the record button.
+    pub fn update_record_button(&self) {
+        let button: gtk::Button = self
+            .builder
+            .object("button-record")
+            .expect("Couldn't get button-record");
+        if graphbook::current_graphtab(self).player().is_recording() {
+            button.add_css_class("destructive-action");
+            button.set_tooltip_text(Some("Stop recording"));
+        } else {
+            button.remove_css_class("destructive-action");
+            button.set_tooltip_text(Some("Record the selected link"));
+        }
+    }
+}
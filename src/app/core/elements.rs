@@ -12,7 +12,7 @@
 //! including port (pad) creation, property updates, and link management. Handles special
 //! cases for URI source/sink elements with file chooser dialogs.
 
-use gtk::glib;
+use gtk::{glib, graphene};
 use std::collections::HashMap;
 
 use crate::gps as GPS;
@@ -20,7 +20,7 @@ use crate::graphmanager as GM;
 use crate::graphmanager::PropertyExt;
 use crate::logger;
 use crate::ui as GPSUI;
-use crate::GPS_DEBUG;
+use crate::{GPS_DEBUG, GPS_ERROR, GPS_INFO, GPS_WARN};
 
 use super::super::GPSApp;
 use super::graphbook;
@@ -188,6 +188,262 @@ impl GPSApp {
         id
     }
 
+    /// Insert a new element in the middle of a link, linking its first input and
+    /// output pads to both ends. The caps filter of the link is kept after the element.
+    pub fn insert_element_on_link(&self, link_id: u32, element_name: &str) -> anyhow::Result<u32> {
+        let graphview = graphbook::current_graphtab(self).graphview().clone();
+        let link = graphview
+            .link(link_id)
+            .ok_or_else(|| anyhow::anyhow!("Unable to retrieve link with id {}", link_id))?;
+        let (inputs, outputs) = GPS::PadInfo::pads(element_name, false);
+        let (Some(input), Some(output)) = (inputs.first(), outputs.first()) else {
+            anyhow::bail!("{} has no input and output pads", element_name);
+        };
+
+        // Undo the insertion in a single step
+        graphview.begin_batch();
+        let node =
+            graphview.create_node(element_name, GPS::ElementInfo::element_type(element_name));
+        let node_id = node.id();
+        graphview.add_node(node);
+        let port_input = self.create_port_with_caps(
+            node_id,
+            GM::PortDirection::Input,
+            GM::PortPresence::Always,
+            input.caps().unwrap_or("ANY").to_string(),
+        );
+        let port_output = self.create_port_with_caps(
+            node_id,
+            GM::PortDirection::Output,
+            GM::PortPresence::Always,
+            output.caps().unwrap_or("ANY").to_string(),
+        );
+        // Place the new node between the two linked nodes
+        let positions = graphview.node_positions();
+        if let (Some(from), Some(to)) =
+            (positions.get(&link.node_from), positions.get(&link.node_to))
+        {
+            let middle = graphene::Point::new((from.x() + to.x()) / 2.0, (from.y() + to.y()) / 2.0);
+            graphview.restore_node_positions(&HashMap::from([(node_id, middle)]));
+        }
+
+        graphview.remove_link(link_id);
+        self.create_link(link.node_from, node_id, link.port_from, port_input);
+        let link_after = graphview.create_link(node_id, link.node_to, port_output, link.port_to);
+        link_after.set_name(&link.name());
+        graphview.add_link(link_after);
+        graphview.end_batch();
+        GPS_DEBUG!(
+            "Inserted {} (id={}) on the link {}",
+            element_name,
+            node_id,
+            link_id
+        );
+        Ok(node_id)
+    }
+
+    /// Set the properties of a preset on a node, as one undoable property change.
+    pub fn apply_element_preset(&self, node_id: u32, properties: &GPS::preset::PresetProperties) {
+        let properties: HashMap<String, String> = properties
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        let graphview = graphbook::current_graphtab(self).graphview().clone();
+        graphview.begin_batch();
+        graphview.update_node_properties(node_id, &properties);
+        graphview.end_batch();
+    }
+
+    /// Apply the presets given as `element:preset` on the command line to the nodes of
+    /// the current graph using the element.
+    pub fn apply_command_line_presets(&self, presets: &[String]) {
+        for argument in presets {
+            let result =
+                GPS::preset::parse_preset_argument(argument).and_then(|(factory, name)| {
+                    let properties = GPS::preset::preset_properties(&factory, &name)?;
+                    Ok((factory, name, properties))
+                });
+            let (factory, name, properties) = match result {
+                Ok(preset) => preset,
+                Err(e) => {
+                    GPS_ERROR!("Unable to apply the preset {}: {}", argument, e);
+                    continue;
+                }
+            };
+            let nodes: Vec<GM::Node> = graphbook::current_graphtab(self)
+                .graphview()
+                .all_nodes(GM::NodeType::All)
+                .into_iter()
+                .filter(|node| node.name() == factory)
+                .collect();
+            if nodes.is_empty() {
+                GPS_WARN!("No element {} to apply the preset {} to", factory, name);
+            }
+            for node in nodes {
+                GPS_INFO!("Apply the preset {} to {}", name, node.unique_name());
+                self.apply_element_preset(node.id(), &properties);
+            }
+        }
+    }
+
+    /// Replace the element of a node by another factory. The properties accepted by the
+    /// new factory are carried over and the links are moved to the matching pads, with
+    /// request pads added if the static pads are not enough.
+    pub fn replace_element(&self, node_id: u32, element_name: &str) -> anyhow::Result<u32> {
+        let graphview = graphbook::current_graphtab(self).graphview().clone();
+        let old_node = graphview
+            .node(node_id)
+            .ok_or_else(|| anyhow::anyhow!("Unable to retrieve node with id {}", node_id))?;
+        if !GPS::missing::factory_exists(element_name) {
+            anyhow::bail!("The element {} is not available", element_name);
+        }
+
+        let mut node =
+            graphview.create_node(element_name, GPS::ElementInfo::element_type(element_name));
+        let properties = GPS::substitute::carried_properties(element_name, &old_node.properties());
+        node.update_properties(&properties);
+
+        let (inputs, outputs) = GPS::PadInfo::pads(element_name, false);
+        let mut port_map = HashMap::new();
+        for (direction, pads, prefix) in [
+            (GM::PortDirection::Input, inputs, "sink_"),
+            (GM::PortDirection::Output, outputs, "src_"),
+        ] {
+            let mut old_ports = old_node.all_ports(direction);
+            old_ports.sort_by_key(|port| port.id());
+            let mut new_ports: Vec<(GM::PortPresence, String)> = pads
+                .iter()
+                .map(|pad| {
+                    (
+                        GM::PortPresence::Always,
+                        pad.caps().unwrap_or("ANY").to_string(),
+                    )
+                })
+                .collect();
+            if let Some(request) =
+                GPS::ElementInfo::element_supports_new_pad_request(element_name, direction)
+            {
+                while new_ports.len() < old_ports.len() {
+                    new_ports.push((
+                        GM::PortPresence::Sometimes,
+                        request.caps().unwrap_or("ANY").to_string(),
+                    ));
+                }
+            }
+
+            let old_names_caps: Vec<(String, String)> = old_ports
+                .iter()
+                .map(|port| {
+                    (
+                        port.name(),
+                        PropertyExt::property(port, "_caps").unwrap_or_else(|| "ANY".to_string()),
+                    )
+                })
+                .collect();
+            let new_names_caps: Vec<(String, String)> = new_ports
+                .iter()
+                .enumerate()
+                .map(|(index, (_, caps))| (format!("{prefix}{index}"), caps.clone()))
+                .collect();
+            let matches = GPS::substitute::match_ports(&old_names_caps, &new_names_caps);
+
+            let mut port_ids = vec![];
+            for ((presence, caps), (name, _)) in new_ports.iter().zip(&new_names_caps) {
+                let port = graphview.create_port(name, direction, *presence);
+                port.update_properties(&HashMap::from([("_caps".to_string(), caps.clone())]));
+                port_ids.push(port.id());
+                graphview.add_port_to_node(&mut node, port);
+            }
+            for (old_port, new_index) in old_ports.iter().zip(matches) {
+                if let Some(new_index) = new_index {
+                    port_map.insert(old_port.id(), port_ids[new_index]);
+                } else if graphview.port_is_linked(old_port.id()).is_some() {
+                    GPS_WARN!(
+                        "No pad of {} matches {}, its link is removed",
+                        element_name,
+                        old_port.name()
+                    );
+                }
+            }
+        }
+
+        let new_id = graphview.replace_node(node_id, node, &port_map)?;
+        GPS_INFO!(
+            "Replaced {} (id={}) by {} (id={})",
+            old_node.name(),
+            node_id,
+            element_name,
+            new_id
+        );
+        Ok(new_id)
+    }
+
+    /// Embed a new element in an element-typed property of a node, such as the
+    /// `video-sink` of playbin, replacing the element already embedded in it. The
+    /// embedded element is a node without pads placed below its parent.
+    pub fn embed_element(
+        &self,
+        node_id: u32,
+        property_name: &str,
+        element_name: &str,
+    ) -> anyhow::Result<u32> {
+        let graphview = graphbook::current_graphtab(self).graphview().clone();
+        let parent = graphview
+            .node(node_id)
+            .ok_or_else(|| anyhow::anyhow!("Unable to retrieve node with id {}", node_id))?;
+        let param = GPS::ElementInfo::element_properties_by_feature_name(&parent.name())?
+            .remove(property_name)
+            .ok_or_else(|| {
+                anyhow::anyhow!("{} has no property {}", parent.name(), property_name)
+            })?;
+        if GPS::value::property_kind(&param) != GPS::value::PropertyKind::Element {
+            anyhow::bail!(
+                "The property {} of {} does not hold an element",
+                property_name,
+                parent.name()
+            );
+        }
+        if !GPS::missing::factory_exists(element_name) {
+            anyhow::bail!("The element {} is not available", element_name);
+        }
+
+        // Undo the replacement of the embedded element in a single step
+        graphview.begin_batch();
+        self.remove_embedded_element(node_id, property_name);
+        let node =
+            graphview.create_node(element_name, GPS::ElementInfo::element_type(element_name));
+        let child_id = node.id();
+        GPS::embed::set_embedded_parent(&node, node_id, property_name);
+        graphview.add_node(node);
+        let (x, y) = parent.position();
+        graphview.restore_node_positions(&HashMap::from([(
+            child_id,
+            graphene::Point::new(x, y + 120.0),
+        )]));
+        graphview.end_batch();
+        GPS_INFO!(
+            "Embedded {} (id={}) in the property {} of {}",
+            element_name,
+            child_id,
+            property_name,
+            parent.unique_name()
+        );
+        Ok(child_id)
+    }
+
+    /// Remove the node embedded in a property of a node, if any.
+    pub fn remove_embedded_element(&self, node_id: u32, property_name: &str) {
+        let graphview = graphbook::current_graphtab(self).graphview().clone();
+        if let Some(child) = GPS::embed::embedded_child(&graphview, node_id, property_name) {
+            GPS_DEBUG!(
+                "Remove {} embedded in the property {}",
+                child.unique_name(),
+                property_name
+            );
+            graphview.remove_node(child.id());
+        }
+    }
+
     pub fn create_link(
         &self,
         node_from_id: u32,
@@ -21,6 +21,7 @@ use std::cell::{Cell, Ref, RefCell};
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::Path;
+use std::rc::Rc;
 
 use crate::gps as GPS;
 use crate::graphmanager as GM;
@@ -31,7 +32,8 @@ use crate::ui::resources::GRAPHVIEW_THEME_CSS;
 use crate::{GPS_DEBUG, GPS_ERROR, GPS_TRACE, GPS_WARN};
 
 use super::super::settings::Settings;
-use super::super::{GPSApp, GPSAppWeak};
+use super::super::{AppState, GPSApp, GPSAppWeak};
+use super::templates;
 
 /// Creates a link between two ports, automatically handling direction.
 ///
@@ -53,6 +55,9 @@ fn create_directional_link(
     }
 }
 
+/// Distance of the minimap to the corner of the graph view
+const MINIMAP_MARGIN: i32 = 12;
+
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 enum TabState {
     #[default]
@@ -64,11 +69,17 @@ enum TabState {
 #[derive(Debug, Clone, Default)]
 pub struct GraphTab {
     graphview: RefCell<GM::GraphView>,
+    minimap: GM::Minimap,
+    search_bar: gtk::SearchBar,
+    search_entry: gtk::SearchEntry,
     player: RefCell<GPS::Player>,
     id: Cell<u32>,
     name: gtk::Label,
     filename: RefCell<String>,
     state: Cell<TabState>,
+    clock_shared: Cell<bool>,
+    /// Problems found by the last check of the graph, shared by the clones of the tab
+    diagnostics: Rc<RefCell<Option<Vec<GPS::validation::Diagnostic>>>>,
 }
 
 impl GraphTab {
@@ -103,15 +114,23 @@ impl GraphTab {
         // Sanitize filename to prevent path traversal attacks
         let sanitized_filename = Self::sanitize_filename(filename);
 
+        let graphview = GM::GraphView::new();
+        let minimap = GM::Minimap::new(&graphview);
+        minimap.set_visible(Settings::minimap_visible());
         let graphtab = GraphTab {
             id: Cell::new(id),
-            graphview: RefCell::new(GM::GraphView::new()),
+            graphview: RefCell::new(graphview),
+            minimap,
+            search_bar: gtk::SearchBar::new(),
+            search_entry: gtk::SearchEntry::new(),
             player: RefCell::new(
                 GPS::Player::new().expect("Unable to initialize GStreamer subsystem"),
             ),
             name: label,
             filename: RefCell::new(sanitized_filename),
             state: Cell::new(TabState::Undefined),
+            clock_shared: Cell::new(false),
+            diagnostics: Rc::new(RefCell::new(None)),
         };
         graphtab
             .graphview
@@ -124,6 +143,12 @@ impl GraphTab {
             .borrow()
             .set_dark_theme(Settings::dark_theme());
 
+        // Apply saved grid snapping setting
+        graphtab
+            .graphview
+            .borrow()
+            .set_grid_snap(Settings::grid_snap());
+
         // Apply custom graphview theme CSS from app
         graphtab
             .graphview
@@ -148,6 +173,18 @@ impl GraphTab {
         self.graphview.borrow()
     }
 
+    pub fn minimap(&self) -> &GM::Minimap {
+        &self.minimap
+    }
+
+    pub fn search_bar(&self) -> &gtk::SearchBar {
+        &self.search_bar
+    }
+
+    pub fn search_entry(&self) -> &gtk::SearchEntry {
+        &self.search_entry
+    }
+
     pub fn player(&self) -> Ref<'_, GPS::Player> {
         self.player.borrow()
     }
@@ -174,15 +211,42 @@ impl GraphTab {
         self.filename.borrow().clone()
     }
 
+    /// Problems found by the last check of the graph, `None` if it has not been checked.
+    pub fn diagnostics(&self) -> Option<Vec<GPS::validation::Diagnostic>> {
+        self.diagnostics.borrow().clone()
+    }
+
+    pub fn set_diagnostics(&self, diagnostics: Option<Vec<GPS::validation::Diagnostic>>) {
+        self.diagnostics.replace(diagnostics);
+    }
+
     pub fn set_modified(&self, modified: bool) {
-        let name = self.basename();
         if modified {
-            self.set_name(&(name + "*"));
             self.state.set(TabState::Modified);
         } else {
-            self.set_name(name.as_str());
             self.state.set(TabState::Saved);
         }
+        self.update_label();
+    }
+
+    /// Refresh the tab label with the file name, the modified marker and the
+    /// play state of the tab pipeline.
+    pub fn update_label(&self) {
+        let player_state = self.player().state();
+        let prefix = match player_state {
+            GPS::PipelineState::Playing => "▶ ",
+            GPS::PipelineState::Paused => "⏸ ",
+            GPS::PipelineState::Error => "⚠ ",
+            GPS::PipelineState::Stopped => "",
+        };
+        let modified = if self.state.get() == TabState::Saved {
+            ""
+        } else {
+            "*"
+        };
+        self.set_name(&format!("{}{}{}", prefix, self.basename(), modified));
+        self.name
+            .set_tooltip_text(Some(&format!("{} ({})", self.filename(), player_state)));
     }
 
     pub fn undefined(&self) -> bool {
@@ -192,6 +256,16 @@ impl GraphTab {
     pub fn modified(&self) -> bool {
         self.state.get() == TabState::Modified
     }
+
+    /// Whether the tab pipeline shares its clock and base time with the other
+    /// tabs sharing their clock.
+    pub fn clock_shared(&self) -> bool {
+        self.clock_shared.get()
+    }
+
+    pub fn set_clock_shared(&self, shared: bool) {
+        self.clock_shared.set(shared);
+    }
 }
 
 pub fn graphtab(app: &GPSApp, id: u32) -> GraphTab {
@@ -233,6 +307,8 @@ pub fn current_graphtab_set_filename(app: &GPSApp, filename: &str) {
         .get(&app.current_graphtab.get())
         .expect("the graphtab is available")
         .set_filename(filename);
+    // The other end of the inter-tab links displays the name of the tab
+    app.update_inter_link_peers(app.current_graphtab.get());
 }
 
 pub fn current_graphtab_set_modified(app: &GPSApp, modified: bool) {
@@ -250,13 +326,33 @@ pub fn setup_graphbook(app: &GPSApp) {
         .expect("Couldn't get the graphbook");
     let app_weak = app.downgrade();
     graphbook.connect_switch_page(move |_book, widget, page| {
+        // The page is an overlay holding the minimap over the scrolled graphview
         let graphview = widget
-            .first_child()
+            .downcast_ref::<gtk::Overlay>()
+            .and_then(|overlay| overlay.child())
+            .and_downcast::<gtk::ScrolledWindow>()
+            .and_then(|scrollwindow| scrollwindow.child())
             .expect("Unable to get the child from the graphbook, ie the scrolledWindow");
         if let Ok(graphview) = graphview.dynamic_cast::<GM::GraphView>() {
             let app = upgrade_weak!(app_weak);
             GPS_TRACE!("graphview.id() {} graphbook page {}", graphview.id(), page);
             app.current_graphtab.set(page);
+            // Reflect the play state of the newly selected tab
+            let player_state = app
+                .graphbook
+                .borrow()
+                .get(&page)
+                .map(|tab| tab.player().state());
+            if let Some(player_state) = player_state {
+                app.set_app_state(AppState::from(player_state));
+                app.update_record_button();
+            }
+            let diagnostics = app
+                .graphbook
+                .borrow()
+                .get(&page)
+                .and_then(|tab| tab.diagnostics());
+            GPSUI::validation::display_diagnostics(&app, diagnostics);
         }
     });
 }
@@ -276,6 +372,22 @@ pub fn create_graphtab(app: &GPSApp, id: u32, name: Option<&str>) {
         .child(&*graphtab(app, id).graphview())
         .build();
 
+    // Display the minimap in the bottom right corner of the graph
+    let overlay = gtk::Overlay::builder().child(&scrollwindow).build();
+    let minimap = gt.minimap();
+    minimap.set_halign(gtk::Align::End);
+    minimap.set_valign(gtk::Align::End);
+    minimap.set_margin_end(MINIMAP_MARGIN);
+    minimap.set_margin_bottom(MINIMAP_MARGIN);
+    overlay.add_overlay(minimap);
+
+    // Display the search bar in the top right corner of the graph
+    let search_bar = gt.search_bar();
+    search_bar.set_halign(gtk::Align::End);
+    search_bar.set_valign(gtk::Align::Start);
+    overlay.add_overlay(search_bar);
+    app.setup_graph_search(&gt);
+
     let tab_box = gtk::Box::new(gtk::Orientation::Horizontal, 0);
     let label = gt.widget_label();
     tab_box.append(label);
@@ -295,8 +407,8 @@ pub fn create_graphtab(app: &GPSApp, id: u32, name: Option<&str>) {
         }
     ));
     tab_box.append(&close_button);
-    graphbook.append_page(&scrollwindow, Some(&tab_box));
-    graphbook.set_tab_reorderable(&scrollwindow, true);
+    graphbook.append_page(&overlay, Some(&tab_box));
+    graphbook.set_tab_reorderable(&overlay, true);
 
     // Handle node link requests from graphview.
     //
@@ -394,8 +506,12 @@ pub fn create_graphtab(app: &GPSApp, id: u32, name: Option<&str>) {
             if let Some(node) = current_graphtab(&app).graphview().node(node_id) {
                 let description = GPS::ElementInfo::element_description(&node.name()).ok();
                 node.set_tooltip_markup(description.as_deref());
-                if !GPS::ElementInfo::element_factory_exists(&node.name()) {
+                if GPS::missing::factory_exists(&node.name()) {
+                    GPS::missing::record_node_plugin(&node);
+                } else if node.node_type() == Some(&GM::NodeType::Macro) {
                     node.set_light(true);
+                } else {
+                    GPSUI::missing::mark_missing_node(&node, true);
                 }
                 for port in node.all_ports(GM::PortDirection::All) {
                     let caps = PropertyExt::property(&port, "_caps");
@@ -470,38 +586,42 @@ pub fn create_graphtab(app: &GPSApp, id: u32, name: Option<&str>) {
                 current_graphtab(&app).graphview().clear();
             });
             let app_weak = app.downgrade();
-            app.connect_app_menu_action("graph.check", move |_, _| {
+            app.connect_app_menu_action("graph.pipeline_details", move |_, _| {
                 let app = upgrade_weak!(app_weak);
-                let render_parse_launch = current_graphtab(&app)
-                    .player()
-                    .pipeline_description_from_graphview(&current_graphtab(&app).graphview());
-                if current_graphtab(&app)
-                    .player()
-                    .create_pipeline(&render_parse_launch)
-                    .is_ok()
-                {
-                    GPSUI::message::display_message_dialog(
-                        &render_parse_launch,
-                        gtk::MessageType::Info,
-                        |_| {},
-                    );
-                } else {
-                    GPSUI::message::display_error_dialog(
-                        false,
-                        &format!("Unable to render:\n\n{render_parse_launch}"),
-                    );
-                }
+                GPSUI::properties::display_pipeline_details(&app);
             });
             let app_weak = app.downgrade();
-            app.connect_app_menu_action("graph.pipeline_details", move |_, _| {
+            app.connect_app_menu_action("graph.latency_budget", move |_, _| {
                 let app = upgrade_weak!(app_weak);
-                GPSUI::properties::display_pipeline_details(&app);
+                GPSUI::latency::display_latency_budget(&app);
             });
             let app_weak = app.downgrade();
             app.connect_app_menu_action("graph.arrange_pipeline", move |_, _| {
                 let app = upgrade_weak!(app_weak);
                 current_graphtab(&app).graphview().auto_arrange_graph(None);
             });
+            let (x, y) = (point.x() as f64, point.y() as f64);
+            for (action_name, kind) in [
+                ("graph.add_note", GM::AnnotationKind::Note),
+                ("graph.add_frame", GM::AnnotationKind::Frame),
+            ] {
+                let app_weak = app.downgrade();
+                app.connect_app_menu_action(action_name, move |_, _| {
+                    let app = upgrade_weak!(app_weak);
+                    let graphtab = current_graphtab(&app);
+                    let graphview = graphtab.graphview();
+                    let position = graphview.screen_to_canvas(x, y);
+                    let (width, height) = kind.default_size();
+                    let annotation = graphview.create_annotation(
+                        kind,
+                        "",
+                        graphene::Rect::new(position.x(), position.y(), width, height),
+                    );
+                    let annotation_id = annotation.id();
+                    graphview.add_annotation(annotation);
+                    GPSUI::annotation::display_annotation_dialog(&app, annotation_id);
+                });
+            }
             app.show_context_menu_at_position(
                 &*current_graphtab(&app).graphview(),
                 point.to_vec2().x() as f64,
@@ -543,6 +663,33 @@ pub fn create_graphtab(app: &GPSApp, id: u32, name: Option<&str>) {
                 app.disconnect_app_menu_action("port.delete");
             }
 
+            let port = app.port(node_id, port_id);
+            let linked = current_graphtab(&app)
+                .graphview()
+                .port_is_linked(port_id)
+                .is_some();
+            if GPS::interlink::port_inter_link(&port).is_some() {
+                app.disconnect_app_menu_action("port.link_tab");
+                let app_weak = app.downgrade();
+                app.connect_app_menu_action("port.unlink_tab", move |_, _| {
+                    let app = upgrade_weak!(app_weak);
+                    GPS_DEBUG!("port.unlink_tab port id {} node id {}", port_id, node_id);
+                    let tab_id = current_graphtab(&app).id();
+                    app.remove_inter_link(tab_id, node_id, port_id);
+                });
+            } else if !linked {
+                app.disconnect_app_menu_action("port.unlink_tab");
+                let app_weak = app.downgrade();
+                app.connect_app_menu_action("port.link_tab", move |_, _| {
+                    let app = upgrade_weak!(app_weak);
+                    GPS_DEBUG!("port.link_tab port id {} node id {}", port_id, node_id);
+                    GPSUI::interlink::display_inter_link_dialog(&app, node_id, port_id);
+                });
+            } else {
+                app.disconnect_app_menu_action("port.link_tab");
+                app.disconnect_app_menu_action("port.unlink_tab");
+            }
+
             let app_weak = app.downgrade();
             app.connect_app_menu_action("port.properties", move |_, _| {
                 let app = upgrade_weak!(app_weak);
@@ -582,6 +729,20 @@ pub fn create_graphtab(app: &GPSApp, id: u32, name: Option<&str>) {
                 current_graphtab(&app).graphview().remove_link(link_id);
             });
 
+            let app_weak = app.downgrade();
+            app.connect_app_menu_action("link.record", move |_, _| {
+                let app = upgrade_weak!(app_weak);
+                GPS_DEBUG!("link.record id: {}", link_id);
+                GPSUI::capture::display_recording_dialog(&app, link_id);
+            });
+
+            let app_weak = app.downgrade();
+            app.connect_app_menu_action("link.caps_filter", move |_, _| {
+                let app = upgrade_weak!(app_weak);
+                GPS_DEBUG!("link.caps_filter id: {}", link_id);
+                GPSUI::caps::display_caps_editor(&app, link_id);
+            });
+
             app.show_context_menu_at_position(
                 &*current_graphtab(&app).graphview(),
                 point.to_vec2().x() as f64,
@@ -615,6 +776,89 @@ pub fn create_graphtab(app: &GPSApp, id: u32, name: Option<&str>) {
                 GPS_DEBUG!("node.delete id: {}", node_id);
                 current_graphtab(&app).graphview().remove_node(node_id);
             });
+            let app_weak = app.downgrade();
+            app.connect_app_menu_action("node.frame", move |_, _| {
+                let app = upgrade_weak!(app_weak);
+                let frame_id = current_graphtab(&app).graphview().frame_selected_nodes("");
+                if let Some(frame_id) = frame_id {
+                    GPSUI::annotation::display_annotation_dialog(&app, frame_id);
+                }
+            });
+            let app_weak = app.downgrade();
+            app.connect_app_menu_action("node.pin", move |_, _| {
+                let app = upgrade_weak!(app_weak);
+                let tab = current_graphtab(&app);
+                let graphview = tab.graphview();
+                if let Some(node) = graphview.node(node_id) {
+                    GPS_DEBUG!("node.pin id: {} pinned: {}", node_id, !node.pinned());
+                    node.set_pinned(!node.pinned());
+                    graphview.graph_updated();
+                }
+            });
+            let app_weak = app.downgrade();
+            app.connect_app_menu_action("node.save_template", move |_, _| {
+                let app = upgrade_weak!(app_weak);
+                GPS_DEBUG!("node.save_template id: {}", node_id);
+                GPSUI::dialog::get_input(
+                    &app,
+                    "Save as template",
+                    "Template name",
+                    "",
+                    move |app, name| {
+                        let existing = templates::existing_template(&name);
+                        let save = move || {
+                            if let Err(e) = app.save_selection_as_template(&name, node_id) {
+                                GPS_ERROR!("Unable to save the template {}: {}", name, e);
+                            }
+                            GPSUI::elements::reset_template_list(&app);
+                        };
+                        match existing {
+                            Some(existing) => GPSUI::message::display_confirm_dialog(
+                                &format!(
+                                    "The template '{}' already exists in the library, replace it?",
+                                    existing
+                                ),
+                                "Replace",
+                                save,
+                            ),
+                            None => save(),
+                        }
+                    },
+                );
+            });
+            let app_weak = app.downgrade();
+            app.connect_app_menu_action("node.group_macro", move |_, _| {
+                let app = upgrade_weak!(app_weak);
+                GPS_DEBUG!("node.group_macro id: {}", node_id);
+                GPSUI::dialog::get_input(
+                    &app,
+                    "Group as macro node",
+                    "Macro name",
+                    "",
+                    move |app, name| {
+                        if let Err(e) = app.group_selection_as_macro(&name, node_id) {
+                            GPS_ERROR!("Unable to group the nodes as the macro {}: {}", name, e);
+                        }
+                    },
+                );
+            });
+            if node.node_type() == Some(&GM::NodeType::Macro) {
+                let app_weak = app.downgrade();
+                app.connect_app_menu_action("node.properties", move |_, _| {
+                    let app = upgrade_weak!(app_weak);
+                    GPS_DEBUG!("node.properties macro id {}", node_id);
+                    GPSUI::properties::display_macro_properties(&app, node_id);
+                });
+                app.disconnect_app_menu_action("node.replace");
+            } else {
+                // Also offered for missing elements, to swap them for an installed one
+                let app_weak = app.downgrade();
+                app.connect_app_menu_action("node.replace", move |_, _| {
+                    let app = upgrade_weak!(app_weak);
+                    GPS_DEBUG!("node.replace id: {}", node_id);
+                    GPSUI::substitute::display_replace_element(&app, node_id);
+                });
+            }
             if element_exists {
                 let app_weak = app.downgrade();
                 app.connect_app_menu_action("node.add-to-favorite", move |_, _| {
@@ -679,7 +923,64 @@ pub fn create_graphtab(app: &GPSApp, id: u32, name: Option<&str>) {
                         app.add_new_element(&node.name());
                     };
                 });
+                let app_weak = app.downgrade();
+                app.connect_app_menu_action("node.control", move |_, _| {
+                    let app = upgrade_weak!(app_weak);
+                    GPS_DEBUG!("node.control id: {}", node_id);
+                    GPSUI::control::display_property_control(&app, node_id);
+                });
+            } else {
+                app.disconnect_app_menu_action("node.control");
+            }
+            app.show_context_menu_at_position(
+                &*current_graphtab(&app).graphview(),
+                point.to_vec2().x() as f64,
+                point.to_vec2().y() as f64,
+                &menu,
+            );
+            None
+        }),
+    );
+
+    // When user clicks on a note or frame with right button
+    let app_weak = app.downgrade();
+    gt.graphview().connect_local(
+        "annotation-right-clicked",
+        false,
+        move |values: &[Value]| {
+            let app = upgrade_weak!(app_weak, None);
+            let annotation_id = values[1].get::<u32>().ok()?;
+            let point = values[2].get::<graphene::Point>().ok()?;
+            let menu: gio::MenuModel = app.builder.object("annotation_menu")?;
+
+            let app_weak = app.downgrade();
+            app.connect_app_menu_action("annotation.edit", move |_, _| {
+                let app = upgrade_weak!(app_weak);
+                GPSUI::annotation::display_annotation_dialog(&app, annotation_id);
+            });
+            let app_weak = app.downgrade();
+            app.connect_app_menu_action("annotation.delete", move |_, _| {
+                let app = upgrade_weak!(app_weak);
+                GPS_DEBUG!("annotation.delete id: {}", annotation_id);
+                current_graphtab(&app)
+                    .graphview()
+                    .remove_annotation(annotation_id);
+            });
+            for (name, color) in GM::ANNOTATION_COLORS {
+                let app_weak = app.downgrade();
+                app.connect_app_menu_action(
+                    &format!("annotation.color_{}", name.to_lowercase()),
+                    move |_, _| {
+                        let app = upgrade_weak!(app_weak);
+                        if let Ok(color) = gtk::gdk::RGBA::parse(color) {
+                            current_graphtab(&app)
+                                .graphview()
+                                .set_annotation_color(annotation_id, &color);
+                        }
+                    },
+                );
             }
+
             app.show_context_menu_at_position(
                 &*current_graphtab(&app).graphview(),
                 point.to_vec2().x() as f64,
@@ -687,6 +988,19 @@ pub fn create_graphtab(app: &GPSApp, id: u32, name: Option<&str>) {
                 &menu,
             );
             None
+        },
+    );
+
+    let app_weak = app.downgrade();
+    gt.graphview().connect_local(
+        "annotation-double-clicked",
+        false,
+        glib::clone!(move |values: &[Value]| {
+            let app = upgrade_weak!(app_weak, None);
+            let annotation_id = values[1].get::<u32>().expect("annotation id args[1]");
+            GPS_TRACE!("Annotation double clicked id={}", annotation_id);
+            GPSUI::annotation::display_annotation_dialog(&app, annotation_id);
+            None
         }),
     );
 
@@ -699,7 +1013,9 @@ pub fn create_graphtab(app: &GPSApp, id: u32, name: Option<&str>) {
             let node_id = values[1].get::<u32>().expect("node id args[1]");
             GPS_TRACE!("Node double clicked id={}", node_id);
             let node = current_graphtab(&app).graphview().node(node_id).unwrap();
-            if GPS::ElementInfo::element_factory_exists(&node.name()) {
+            if node.node_type() == Some(&GM::NodeType::Macro) {
+                GPSUI::properties::display_macro_properties(&app, node_id);
+            } else if GPS::ElementInfo::element_factory_exists(&node.name()) {
                 GPSUI::properties::display_plugin_properties(&app, &node.name(), node_id);
             }
             None
@@ -713,19 +1029,7 @@ pub fn create_graphtab(app: &GPSApp, id: u32, name: Option<&str>) {
             let app = upgrade_weak!(app_weak, None);
             let link_id = values[1].get::<u32>().expect("link id args[1]");
             GPS_TRACE!("link double clicked id={}", link_id);
-            let link = current_graphtab(&app).graphview().link(link_id).unwrap();
-            GPSUI::dialog::get_input(
-                &app,
-                "Enter caps filter description",
-                "description",
-                &link.name(),
-                move |app, link_desc| {
-                    current_graphtab(&app)
-                        .graphview()
-                        .set_link_name(link.id(), link_desc.as_str());
-                    GPS_DEBUG!("link double clicked id={} name={}", link.id(), link.name());
-                },
-            );
+            GPSUI::caps::display_caps_editor(&app, link_id);
             None
         }),
     );
@@ -0,0 +1,185 @@
+// interlink.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Links between ports of different graph tabs.
+//!
+//! Both ends of a link are stored as hidden port properties in their own graph, so the
+//! link is saved in both `.gps` files. The pipelines are connected at runtime by the
+//! inter or proxy elements generated in the pipeline description.
+
+use gtk::glib;
+use gtk::prelude::*;
+use std::collections::HashMap;
+
+use crate::gps::interlink::{self, InterLinkKind};
+use crate::graphmanager as GM;
+use crate::graphmanager::PropertyExt;
+use crate::logger;
+use crate::GPS_INFO;
+
+use super::super::GPSApp;
+use super::graphbook::{self, GraphTab};
+
+impl GPSApp {
+    /// Link the output port of a tab to the input port of another tab.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_inter_link(
+        &self,
+        kind: InterLinkKind,
+        from_tab: u32,
+        from_node: u32,
+        from_port: u32,
+        to_tab: u32,
+        to_node: u32,
+        to_port: u32,
+    ) {
+        let from = graphbook::graphtab(self, from_tab);
+        let to = graphbook::graphtab(self, to_tab);
+        let channel = interlink::new_channel();
+        GPS_INFO!(
+            "Linking {} to {} with {} channel {}",
+            from.basename(),
+            to.basename(),
+            kind,
+            channel
+        );
+        let from_properties: HashMap<String, String> = interlink::inter_link_properties(
+            kind,
+            &channel,
+            &to.basename(),
+            GM::PortDirection::Output,
+        )
+        .into_iter()
+        .collect();
+        from.graphview()
+            .update_port_properties(from_node, from_port, &from_properties);
+        let to_properties: HashMap<String, String> = interlink::inter_link_properties(
+            kind,
+            &channel,
+            &from.basename(),
+            GM::PortDirection::Input,
+        )
+        .into_iter()
+        .collect();
+        to.graphview()
+            .update_port_properties(to_node, to_port, &to_properties);
+
+        for tab in [&from, &to] {
+            tab.set_modified(true);
+            tab.graphview().queue_draw();
+        }
+    }
+
+    /// Remove both ends of the inter-tab link of a port.
+    pub fn remove_inter_link(&self, tab_id: u32, node_id: u32, port_id: u32) {
+        let tab = graphbook::graphtab(self, tab_id);
+        let Some(port) = tab.graphview().node(node_id).and_then(|n| n.port(port_id)) else {
+            return;
+        };
+        let Some((_kind, channel)) = interlink::port_inter_link(&port) else {
+            return;
+        };
+        let removed: HashMap<String, String> = interlink::inter_link_property_names()
+            .iter()
+            .map(|name| (name.to_string(), String::new()))
+            .collect();
+        let tabs: Vec<GraphTab> = self.graphbook.borrow().values().cloned().collect();
+        for tab in tabs {
+            let mut modified = false;
+            for node in tab.graphview().all_nodes(GM::NodeType::All) {
+                for port in node.all_ports(GM::PortDirection::All) {
+                    if interlink::port_inter_link(&port).map(|(_, c)| c).as_ref() == Some(&channel)
+                    {
+                        tab.graphview()
+                            .update_port_properties(node.id(), port.id(), &removed);
+                        modified = true;
+                    }
+                }
+            }
+            if modified {
+                GPS_INFO!("Removed the link {} from {}", channel, tab.basename());
+                tab.set_modified(true);
+                tab.graphview().queue_draw();
+            }
+        }
+    }
+
+    /// Refresh the peer name displayed next to the other end of the inter-tab links of
+    /// a tab, once the tab has been renamed.
+    pub fn update_inter_link_peers(&self, tab_id: u32) {
+        let renamed = graphbook::graphtab(self, tab_id);
+        let basename = renamed.basename();
+        let channels: Vec<String> = renamed
+            .graphview()
+            .all_nodes(GM::NodeType::All)
+            .iter()
+            .flat_map(|node| node.all_ports(GM::PortDirection::All))
+            .filter_map(|port| interlink::port_inter_link(&port).map(|(_, channel)| channel))
+            .collect();
+        if channels.is_empty() {
+            return;
+        }
+        let tabs: Vec<GraphTab> = self
+            .graphbook
+            .borrow()
+            .values()
+            .filter(|tab| tab.id() != tab_id)
+            .cloned()
+            .collect();
+        for tab in tabs {
+            let mut modified = false;
+            for node in tab.graphview().all_nodes(GM::NodeType::All) {
+                for port in node.all_ports(GM::PortDirection::All) {
+                    let Some((kind, channel)) = interlink::port_inter_link(&port) else {
+                        continue;
+                    };
+                    let peer = PropertyExt::property(&port, interlink::INTER_PEER_PROPERTY);
+                    if !channels.contains(&channel) || peer.as_ref() == Some(&basename) {
+                        continue;
+                    }
+                    let properties: HashMap<String, String> = interlink::inter_link_properties(
+                        kind,
+                        &channel,
+                        &basename,
+                        port.direction(),
+                    )
+                    .into_iter()
+                    .collect();
+                    tab.graphview()
+                        .update_port_properties(node.id(), port.id(), &properties);
+                    modified = true;
+                }
+            }
+            if modified {
+                GPS_INFO!(
+                    "Renamed the peer of the links of {} to {}",
+                    tab.basename(),
+                    basename
+                );
+                tab.set_modified(true);
+                tab.graphview().queue_draw();
+            }
+        }
+    }
+
+    /// Connect the proxy elements of a tab pipeline with the running pipelines of the
+    /// other tabs.
+    pub fn connect_inter_pipelines(&self, graphtab: &GraphTab) {
+        let Some(pipeline) = graphtab.player().pipeline() else {
+            return;
+        };
+        let peers: Vec<gst::Pipeline> = self
+            .graphbook
+            .borrow()
+            .values()
+            .filter(|tab| tab.id() != graphtab.id() && tab.player().is_playing())
+            .filter_map(|tab| tab.player().pipeline())
+            .collect();
+        interlink::connect_proxies(&pipeline, &peers);
+    }
+}
@@ -9,12 +9,18 @@
 //! Core application functionality modules.
 //!
 //! Organizes GPSApp implementation into focused modules for actions, UI bootstrap,
-//! element management, graph tabs, context menus, and panel layout.
+//! element management, graph tabs, context menus, panel layout, playback control, graph search, graph validation, subgraph templates and preview capture.
 
 // Core GPSApp implementation modules
 pub mod actions;
 pub mod bootstrap;
+pub mod capture;
 pub mod elements;
 pub mod graphbook;
+pub mod interlink;
 pub mod menu;
 pub mod panels;
+pub mod playback;
+pub mod search;
+pub mod templates;
+pub mod validation;
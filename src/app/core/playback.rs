@@ -0,0 +1,109 @@
+// playback.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Playback control across graph tabs.
+//!
+//! Each graph tab owns its own player so several pipelines can run at the same time,
+//! for example a sender and a receiver. Tabs can be started and stopped together and
+//! the tabs marked as sharing their clock run with a common `gst::Clock` and base time.
+
+use gst::prelude::*;
+use gtk::glib;
+
+use crate::gps as GPS;
+use crate::graphmanager as GM;
+use crate::logger;
+use crate::ui as GPSUI;
+use crate::{GPS_DEBUG, GPS_ERROR};
+
+use super::super::GPSApp;
+use super::graphbook::{self, GraphTab};
+
+impl GPSApp {
+    /// Clock and base time to use for a tab sharing its clock. Reuse the ones of a
+    /// running tab sharing its clock, or select the system clock with the current time.
+    fn shared_clock(&self, tab_id: u32) -> (gst::Clock, gst::ClockTime) {
+        let running = self
+            .graphbook
+            .borrow()
+            .values()
+            .filter(|tab| tab.id() != tab_id && tab.clock_shared())
+            .find_map(|tab| tab.player().clock_and_base_time());
+        running.unwrap_or_else(|| {
+            let clock = gst::SystemClock::obtain();
+            let base_time = clock.time();
+            (clock, base_time)
+        })
+    }
+
+    /// Start the pipeline of a tab, selecting the shared clock if the tab uses it.
+    pub fn start_graphtab(
+        &self,
+        graphtab: &GraphTab,
+        state: GPS::PipelineState,
+    ) -> anyhow::Result<GPS::PipelineState> {
+        let player = graphtab.player();
+        if !player.is_playing() {
+            // Placeholders of missing factories must be resolved or replaced first
+            let missing = GPS::missing::missing_elements(&graphtab.graphview());
+            if !missing.is_empty() {
+                if graphbook::current_graphtab(self).id() == graphtab.id() {
+                    GPSUI::missing::display_missing_elements(self);
+                }
+                anyhow::bail!(
+                    "Missing elements: {}",
+                    missing
+                        .iter()
+                        .map(|m| m.factory.as_str())
+                        .collect::<Vec<&str>>()
+                        .join(", ")
+                );
+            }
+            let shared_clock = graphtab
+                .clock_shared()
+                .then(|| self.shared_clock(graphtab.id()));
+            if let Some((_, base_time)) = shared_clock.as_ref() {
+                GPS_DEBUG!(
+                    "Tab {} uses the shared clock with base time {}",
+                    graphtab.id(),
+                    base_time
+                );
+            }
+            player.set_shared_clock(shared_clock);
+        }
+        let state = player.start_pipeline(&graphtab.graphview(), state)?;
+        self.connect_inter_pipelines(graphtab);
+        Ok(state)
+    }
+
+    /// Start the pipelines of all the tabs.
+    pub fn play_all(&self) {
+        let tabs: Vec<GraphTab> = self.graphbook.borrow().values().cloned().collect();
+        for tab in tabs {
+            if tab.graphview().all_nodes(GM::NodeType::All).is_empty() {
+                continue;
+            }
+            if let Err(e) = self.start_graphtab(&tab, GPS::PipelineState::Playing) {
+                GPS_ERROR!("Unable to start the pipeline of {}: {}", tab.basename(), e);
+            }
+        }
+    }
+
+    /// Stop the pipelines of all the tabs.
+    pub fn stop_all(&self) {
+        let tabs: Vec<GraphTab> = self.graphbook.borrow().values().cloned().collect();
+        for tab in tabs {
+            if !tab.player().is_playing() {
+                continue;
+            }
+            if let Err(e) = tab.player().set_state(GPS::PipelineState::Stopped) {
+                GPS_ERROR!("Unable to stop the pipeline of {}: {}", tab.basename(), e);
+            }
+        }
+    }
+}
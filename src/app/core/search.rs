@@ -0,0 +1,172 @@
+// search.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Search in the graph of a tab.
+//!
+//! Each graph tab owns a search bar displayed over the graph. All the nodes matching
+//! the text are highlighted, and Enter (or Ctrl+G/Ctrl+Shift+G) cycles through them,
+//! selecting the current match and bringing it into view.
+
+use gtk::glib;
+use gtk::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::graphmanager as GM;
+use crate::logger;
+use crate::GPS_TRACE;
+
+use super::super::GPSApp;
+use super::graphbook::{self, GraphTab};
+
+/// Matches of the last search and index of the current one.
+#[derive(Debug, Default)]
+struct SearchState {
+    matches: RefCell<Vec<u32>>,
+    current: Cell<usize>,
+}
+
+impl SearchState {
+    /// Move to the next (or previous) match and reveal it in the graph view.
+    fn step(&self, graphview: &GM::GraphView, forward: bool) -> Option<(usize, usize)> {
+        let matches = self.matches.borrow();
+        if matches.is_empty() {
+            return None;
+        }
+        let current = if forward {
+            (self.current.get() + 1) % matches.len()
+        } else {
+            (self.current.get() + matches.len() - 1) % matches.len()
+        };
+        self.current.set(current);
+        graphview.reveal_node(matches[current]);
+        Some((current, matches.len()))
+    }
+}
+
+fn update_match_label(label: &gtk::Label, position: Option<(usize, usize)>) {
+    match position {
+        Some((current, total)) => label.set_label(&format!("{} of {}", current + 1, total)),
+        None => label.set_label(""),
+    }
+}
+
+impl GPSApp {
+    /// Display the search bar of the current tab and focus its entry.
+    pub fn show_graph_search(&self) {
+        let graphtab = graphbook::current_graphtab(self);
+        graphtab.search_bar().set_search_mode(true);
+        let entry = graphtab.search_entry();
+        entry.grab_focus();
+        entry.select_region(0, -1);
+        // Highlight the matches of the previous search again
+        entry.emit_by_name::<()>("search-changed", &[]);
+    }
+
+    /// Build the search bar of a graph tab and connect it to its graph view.
+    pub(super) fn setup_graph_search(&self, graphtab: &GraphTab) {
+        let search_bar = graphtab.search_bar();
+        let entry = graphtab.search_entry();
+        let graphview = graphtab.graphview().clone();
+        let state = Rc::new(SearchState::default());
+
+        entry.set_placeholder_text(Some("Element, pad, property or caps"));
+        entry.set_width_chars(28);
+        let match_label = gtk::Label::new(None);
+        match_label.set_width_chars(8);
+        match_label.add_css_class("dim-label");
+        let previous_button = gtk::Button::from_icon_name("go-up-symbolic");
+        previous_button.set_tooltip_text(Some("Previous match (Ctrl+Shift+G)"));
+        let next_button = gtk::Button::from_icon_name("go-down-symbolic");
+        next_button.set_tooltip_text(Some("Next match (Enter)"));
+
+        let hbox = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        hbox.append(entry);
+        hbox.append(&match_label);
+        hbox.append(&previous_button);
+        hbox.append(&next_button);
+        search_bar.set_child(Some(&hbox));
+        search_bar.connect_entry(entry);
+        search_bar.set_show_close_button(true);
+
+        entry.connect_search_changed(glib::clone!(
+            #[weak]
+            graphview,
+            #[weak]
+            match_label,
+            #[strong]
+            state,
+            move |entry| {
+                let text = entry.text();
+                let matches = graphview.search_nodes(&text);
+                GPS_TRACE!("Search '{}' found {} nodes", text, matches.len());
+                graphview.highlight_nodes(&matches);
+                if matches.is_empty() && !text.is_empty() {
+                    entry.add_css_class("error");
+                } else {
+                    entry.remove_css_class("error");
+                }
+                let position = matches.first().map(|node_id| {
+                    graphview.reveal_node(*node_id);
+                    (0, matches.len())
+                });
+                state.current.set(0);
+                *state.matches.borrow_mut() = matches;
+                update_match_label(&match_label, position);
+            }
+        ));
+
+        let step = Rc::new(glib::clone!(
+            #[weak]
+            graphview,
+            #[weak]
+            match_label,
+            #[strong]
+            state,
+            move |forward: bool| {
+                update_match_label(&match_label, state.step(&graphview, forward));
+            }
+        ));
+        entry.connect_activate(glib::clone!(
+            #[strong]
+            step,
+            move |_| step(true)
+        ));
+        entry.connect_next_match(glib::clone!(
+            #[strong]
+            step,
+            move |_| step(true)
+        ));
+        entry.connect_previous_match(glib::clone!(
+            #[strong]
+            step,
+            move |_| step(false)
+        ));
+        next_button.connect_clicked(glib::clone!(
+            #[strong]
+            step,
+            move |_| step(true)
+        ));
+        previous_button.connect_clicked(move |_| step(false));
+
+        // Remove the highlight when the search bar is closed
+        search_bar.connect_search_mode_enabled_notify(glib::clone!(
+            #[weak]
+            graphview,
+            #[weak]
+            match_label,
+            move |search_bar| {
+                if !search_bar.is_search_mode() {
+                    graphview.highlight_nodes(&[]);
+                    state.matches.borrow_mut().clear();
+                    update_match_label(&match_label, None);
+                }
+            }
+        ));
+    }
+}
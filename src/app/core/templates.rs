@@ -0,0 +1,243 @@
+// templates.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Library of subgraph templates and macro nodes.
+//!
+//! A selection of nodes can be saved as a named template, one XML file per template in
+//! the templates directory of the settings. The templates are listed in the element
+//! panel and inserted into the current graph as a selected group of nodes.
+//!
+//! A selection can also be grouped into a macro node, shown as one node in the graph
+//! and expanded into a bin when the pipeline is launched. Its parameters are forwarded
+//! to properties of the inner elements.
+
+use gtk::glib;
+use gtk::graphene;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::graphmanager as GM;
+use crate::graphmanager::PropertyExt;
+use crate::logger;
+use crate::{GPS_DEBUG, GPS_WARN};
+
+use super::super::settings::Settings;
+use super::super::GPSApp;
+use super::graphbook;
+
+const TEMPLATE_FILE_EXTENSION: &str = "gpst";
+
+/// Path of the file of a template, with the characters not suited to a file name replaced.
+fn template_file_path(name: &str) -> PathBuf {
+    let file_name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let mut path = Settings::templates_dir_path();
+    path.push(format!("{}.{}", file_name.trim(), TEMPLATE_FILE_EXTENSION));
+    path
+}
+
+/// A template of the library with the file it was loaded from.
+pub struct TemplateFile {
+    pub path: PathBuf,
+    pub template: GM::GraphTemplate,
+}
+
+/// Load all the templates of the library, sorted by name.
+pub fn templates() -> Vec<TemplateFile> {
+    let mut templates = vec![];
+    let Ok(entries) = fs::read_dir(Settings::templates_dir_path()) else {
+        return templates;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(TEMPLATE_FILE_EXTENSION) {
+            continue;
+        }
+        match load_template(&path) {
+            Ok(template) => templates.push(TemplateFile { path, template }),
+            Err(e) => GPS_WARN!("Unable to load the template {}: {}", path.display(), e),
+        }
+    }
+    templates.sort_by(|a, b| a.template.name.cmp(&b.template.name));
+    templates
+}
+
+pub fn load_template(path: &Path) -> anyhow::Result<GM::GraphTemplate> {
+    let buffer = fs::read(path)?;
+    GM::GraphTemplate::load_from_xml(&buffer)
+}
+
+/// Name of the template which would be overwritten by saving a template with this
+/// name, different names can be stored in the same file such as "a/b" and "a_b".
+pub fn existing_template(name: &str) -> Option<String> {
+    let path = template_file_path(name.trim());
+    if !path.exists() {
+        return None;
+    }
+    Some(
+        load_template(&path)
+            .map(|template| template.name)
+            .unwrap_or_else(|_| path.display().to_string()),
+    )
+}
+
+pub fn save_template(template: &GM::GraphTemplate) -> anyhow::Result<()> {
+    let path = template_file_path(&template.name);
+    GPS_DEBUG!(
+        "Saving the template {} in {}",
+        template.name,
+        path.display()
+    );
+    fs::write(path, template.render_xml()?)?;
+    Ok(())
+}
+
+pub fn remove_template(path: &Path) -> anyhow::Result<()> {
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+impl GPSApp {
+    /// Ids of the selected nodes of the current graph, or only the given node if it is
+    /// not part of the selection.
+    fn selection_or_node(&self, node_id: u32) -> Vec<u32> {
+        let node_ids: Vec<u32> = graphbook::current_graphtab(self)
+            .graphview()
+            .selected_nodes()
+            .iter()
+            .map(|node| node.id())
+            .collect();
+        if node_ids.contains(&node_id) {
+            node_ids
+        } else {
+            vec![node_id]
+        }
+    }
+
+    /// Save the selected nodes of the current graph as a template, or only the given
+    /// node if it is not part of the selection.
+    pub fn save_selection_as_template(&self, name: &str, node_id: u32) -> anyhow::Result<()> {
+        let name = name.trim();
+        if name.is_empty() {
+            anyhow::bail!("The template needs a name");
+        }
+        let node_ids = self.selection_or_node(node_id);
+        let graphview = graphbook::current_graphtab(self).graphview().clone();
+        save_template(&graphview.template_from_nodes(name, &node_ids))
+    }
+
+    /// Group the selected nodes of the current graph, or only the given node if it is
+    /// not part of the selection, into a macro node.
+    pub fn group_selection_as_macro(&self, name: &str, node_id: u32) -> anyhow::Result<u32> {
+        let name = name.trim();
+        if name.is_empty() {
+            anyhow::bail!("The macro needs a name");
+        }
+        // The name is used in the names of the elements of the pipeline
+        if !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            anyhow::bail!("The macro name can only contain letters, digits, '-' and '_'");
+        }
+        let node_ids = self.selection_or_node(node_id);
+        let graphview = graphbook::current_graphtab(self).graphview().clone();
+        let macro_id = graphview.collapse_into_macro(name, &node_ids)?;
+        GPS_DEBUG!(
+            "Grouped the nodes {:?} into the macro node {}",
+            node_ids,
+            macro_id
+        );
+        Ok(macro_id)
+    }
+
+    /// Add a parameter to a macro node, forwarded to a property of one of its inner
+    /// nodes. The parameter starts with the value of this property.
+    pub fn add_macro_parameter(
+        &self,
+        node_id: u32,
+        name: &str,
+        template_node_id: u32,
+        property: &str,
+    ) -> anyhow::Result<()> {
+        let name = name.trim();
+        let graphview = graphbook::current_graphtab(self).graphview().clone();
+        let node = graphview
+            .node(node_id)
+            .ok_or_else(|| anyhow::anyhow!("No node with the id {}", node_id))?;
+        let mut template = GM::GraphTemplate::from_macro_node(&node)
+            .ok_or_else(|| anyhow::anyhow!("The node {} is not a macro", node.unique_name()))?;
+        if name.is_empty() || node.hidden_property(name) {
+            anyhow::bail!("Invalid parameter name '{}'", name);
+        }
+        if template.parameters.iter().any(|p| p.name == name) {
+            anyhow::bail!("The macro already has a parameter '{}'", name);
+        }
+        let value = template
+            .node(template_node_id)
+            .ok_or_else(|| anyhow::anyhow!("No inner node with the id {}", template_node_id))?
+            .properties
+            .get(property)
+            .cloned();
+        template.parameters.push(GM::TemplateParameter {
+            name: name.to_string(),
+            node: template_node_id,
+            property: property.to_string(),
+        });
+        // The template and the parameter value are undone in a single step
+        graphview.begin_batch();
+        let result = graphview.set_macro_template(node_id, &template);
+        if let (Ok(()), Some(value)) = (&result, value) {
+            graphview.modify_node_property(node_id, name, &value);
+        }
+        graphview.end_batch();
+        result
+    }
+
+    pub fn remove_macro_parameter(&self, node_id: u32, name: &str) -> anyhow::Result<()> {
+        let graphview = graphbook::current_graphtab(self).graphview().clone();
+        let node = graphview
+            .node(node_id)
+            .ok_or_else(|| anyhow::anyhow!("No node with the id {}", node_id))?;
+        let mut template = GM::GraphTemplate::from_macro_node(&node)
+            .ok_or_else(|| anyhow::anyhow!("The node {} is not a macro", node.unique_name()))?;
+        template.parameters.retain(|p| p.name != name);
+        graphview.begin_batch();
+        let result = graphview.set_macro_template(node_id, &template);
+        if result.is_ok() {
+            graphview.modify_node_property(node_id, name, "");
+        }
+        graphview.end_batch();
+        result
+    }
+
+    /// Insert a template of the library in the middle of the visible part of the graph.
+    pub fn insert_template(&self, path: &Path) -> anyhow::Result<()> {
+        let template = load_template(path)?;
+        let graphview = graphbook::current_graphtab(self).graphview().clone();
+        let origin = graphview
+            .visible_rect()
+            .map(|rect| rect.center())
+            .unwrap_or_else(|| graphene::Point::new(20.0, 20.0));
+        let node_ids = graphview.insert_template(&template, &origin);
+        GPS_DEBUG!(
+            "Inserted the template {} as nodes {:?}",
+            template.name,
+            node_ids
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+// validation.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Validation of the graph of a tab.
+//!
+//! Checking the graph lists its problems in the Problems panel. Clicking a problem
+//! selects the element, pad or link it is about, and the quick fixes change the graph
+//! before checking it again.
+
+use gtk::glib;
+
+use crate::gps as GPS;
+use crate::gps::validation::{Diagnostic, DiagnosticSeverity, DiagnosticTarget, QuickFix};
+use crate::graphmanager::SelectionExt;
+use crate::logger;
+use crate::ui as GPSUI;
+use crate::{GPS_DEBUG, GPS_ERROR};
+
+use super::super::GPSApp;
+use super::graphbook;
+
+impl GPSApp {
+    /// Validate the graph of the current tab and display its problems. If none would
+    /// prevent it, the pipeline is also created to catch the errors of GStreamer.
+    pub fn check_graph(&self) {
+        let graphtab = graphbook::current_graphtab(self);
+        let mut diagnostics = GPS::validation::validate_graph(&graphtab.graphview());
+        if !diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error)
+        {
+            let description = graphtab
+                .player()
+                .pipeline_description_from_graphview(&graphtab.graphview());
+            GPS_DEBUG!("Checking the pipeline {}", description);
+            if let Err(e) = graphtab.player().create_pipeline(&description) {
+                diagnostics.insert(
+                    0,
+                    Diagnostic {
+                        severity: DiagnosticSeverity::Error,
+                        target: DiagnosticTarget::Graph,
+                        message: format!("Unable to create the pipeline: {e}"),
+                        fix: None,
+                    },
+                );
+            }
+        }
+        graphtab.set_diagnostics(Some(diagnostics.clone()));
+        GPSUI::validation::display_diagnostics(self, Some(diagnostics));
+        GPSUI::validation::show_problems_panel(self);
+    }
+
+    /// Select the part of the graph a problem is about and bring it into view.
+    pub fn reveal_diagnostic_target(&self, target: DiagnosticTarget) {
+        let graphtab = graphbook::current_graphtab(self);
+        let graphview = graphtab.graphview();
+        match target {
+            DiagnosticTarget::Graph => {
+                graphview.zoom_to_fit();
+            }
+            DiagnosticTarget::Node(node_id) => {
+                graphview.reveal_node(node_id);
+            }
+            DiagnosticTarget::Port { node_id, port_id } => {
+                graphview.reveal_node(node_id);
+                if let Some(port) = graphview.node(node_id).and_then(|node| node.port(port_id)) {
+                    port.set_selected(true);
+                }
+            }
+            DiagnosticTarget::Link(link_id) => {
+                graphview.reveal_link(link_id);
+            }
+        }
+    }
+
+    /// Apply the quick fix of a problem and check the graph again.
+    pub fn apply_quick_fix(&self, fix: &QuickFix) {
+        GPS_DEBUG!("Applying quick fix {:?}", fix);
+        match fix {
+            QuickFix::InsertElement { link_id, factory } => {
+                if let Err(e) = self.insert_element_on_link(*link_id, factory) {
+                    GPS_ERROR!("Unable to insert {}: {}", factory, e);
+                }
+            }
+            QuickFix::RemoveNode(node_id) => {
+                graphbook::current_graphtab(self)
+                    .graphview()
+                    .remove_node(*node_id);
+            }
+        }
+        self.check_graph();
+    }
+}
@@ -16,6 +16,7 @@ use std::collections::HashMap;
 use std::ops;
 use std::rc::{Rc, Weak};
 
+use crate::gps as GPS;
 use crate::logger;
 use crate::ui as GPSUI;
 use crate::GPS_DEBUG;
@@ -60,6 +61,17 @@ pub enum AppState {
     Error(Option<String>),
 }
 
+impl From<GPS::PipelineState> for AppState {
+    fn from(state: GPS::PipelineState) -> Self {
+        match state {
+            GPS::PipelineState::Playing => AppState::Playing,
+            GPS::PipelineState::Paused => AppState::Paused,
+            GPS::PipelineState::Stopped => AppState::Stopped,
+            GPS::PipelineState::Error => AppState::Error(None),
+        }
+    }
+}
+
 impl fmt::Display for AppState {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -165,8 +177,14 @@ impl GPSApp {
 
     /// Initializes the UI content and sets up signal handlers.
     /// This is phase 2 of startup - called after GStreamer has initialized.
-    pub fn initialize_ui(self, application: &gtk::Application, pipeline_desc: &String) {
+    pub fn initialize_ui(
+        self,
+        application: &gtk::Application,
+        pipeline_desc: &String,
+        presets: &[String],
+    ) {
         self.build_ui(application, pipeline_desc);
+        self.apply_command_line_presets(presets);
 
         // Apply paned positions after UI is built and allocated
         let app_for_paned = self.clone();
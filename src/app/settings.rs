@@ -17,6 +17,8 @@ use crate::config;
 use crate::logger;
 use crate::{GPS_ERROR, GPS_INFO, GPS_WARN};
 
+const DEFAULT_GRID_SPACING: f64 = 20.0;
+
 fn default_ws_desc() -> String {
     String::from("ws://127.0.0.1:8444")
 }
@@ -33,12 +35,16 @@ pub struct Settings {
     pub session_count: u32,
     #[serde(default = "default_ws_desc")]
     pub ws_desc: String,
+    /// Rank profile applied when a pipeline is created, none if empty
+    pub active_rank_profile: String,
 
     // values must be emitted before tables
     pub favorites: Vec<String>,
     pub recent_open_files: Vec<String>,
     pub paned_positions: HashMap<String, i32>,
     pub preferences: HashMap<String, String>,
+    /// Rank profiles by name, in the `GST_PLUGIN_FEATURE_RANK` format
+    pub rank_profiles: HashMap<String, String>,
 }
 
 impl Settings {
@@ -119,6 +125,22 @@ impl Settings {
         path
     }
 
+    /// Directory holding the library of the subgraph templates.
+    pub fn templates_dir_path() -> PathBuf {
+        let mut path = Settings::default_app_folder();
+        path.push("templates");
+        Settings::create_path_if_not(&path);
+        path
+    }
+
+    /// File holding the studio presets of the elements.
+    pub fn presets_file_path() -> PathBuf {
+        let mut path = Settings::default_app_folder();
+        Settings::create_path_if_not(&path);
+        path.push("presets.toml");
+        path
+    }
+
     pub fn log_file_path() -> PathBuf {
         let mut path = Settings::default_app_folder();
         Settings::create_path_if_not(&path);
@@ -136,6 +158,14 @@ impl Settings {
         level.clone()
     }
 
+    pub fn set_gst_log_level(level: &str) {
+        let mut settings = Settings::load_settings();
+        settings
+            .preferences
+            .insert("gst_log_level".to_string(), level.to_string());
+        Settings::save_settings(&settings);
+    }
+
     /// Check if crash recovery dialog is enabled (default: true)
     pub fn crash_recovery_enabled() -> bool {
         let settings = Settings::load_settings();
@@ -155,6 +185,65 @@ impl Settings {
         Settings::save_settings(&settings);
     }
 
+    /// Spacing of the grid the nodes snap to when dragged, `None` if disabled
+    pub fn grid_snap() -> Option<f64> {
+        let settings = Settings::load_settings();
+        let enabled = settings
+            .preferences
+            .get("snap_to_grid")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+        enabled.then(|| Settings::grid_spacing_from(&settings))
+    }
+
+    /// Grid spacing in canvas units (default: 20)
+    pub fn grid_spacing() -> f64 {
+        Settings::grid_spacing_from(&Settings::load_settings())
+    }
+
+    fn grid_spacing_from(settings: &Settings) -> f64 {
+        settings
+            .preferences
+            .get("grid_spacing")
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|v| *v > 0.0)
+            .unwrap_or(DEFAULT_GRID_SPACING)
+    }
+
+    pub fn set_snap_to_grid(enabled: bool) {
+        let mut settings = Settings::load_settings();
+        settings
+            .preferences
+            .insert("snap_to_grid".to_string(), enabled.to_string());
+        Settings::save_settings(&settings);
+    }
+
+    pub fn set_grid_spacing(spacing: f64) {
+        let mut settings = Settings::load_settings();
+        settings
+            .preferences
+            .insert("grid_spacing".to_string(), spacing.to_string());
+        Settings::save_settings(&settings);
+    }
+
+    /// Check if the minimap is displayed over the graph (default: true)
+    pub fn minimap_visible() -> bool {
+        let settings = Settings::load_settings();
+        settings
+            .preferences
+            .get("minimap_visible")
+            .map(|v| v != "false")
+            .unwrap_or(true)
+    }
+
+    pub fn set_minimap_visible(visible: bool) {
+        let mut settings = Settings::load_settings();
+        settings
+            .preferences
+            .insert("minimap_visible".to_string(), visible.to_string());
+        Settings::save_settings(&settings);
+    }
+
     pub fn set_recent_pipeline_description(pipeline: &str) {
         let mut settings = Settings::load_settings();
         settings.recent_pipeline = pipeline.to_string();
@@ -188,6 +277,26 @@ impl Settings {
         Settings::save_settings(&settings);
     }
 
+    pub fn rank_profiles() -> HashMap<String, String> {
+        Settings::load_settings().rank_profiles
+    }
+
+    pub fn set_rank_profiles(profiles: &HashMap<String, String>) {
+        let mut settings = Settings::load_settings();
+        settings.rank_profiles = profiles.clone();
+        Settings::save_settings(&settings);
+    }
+
+    pub fn active_rank_profile() -> String {
+        Settings::load_settings().active_rank_profile
+    }
+
+    pub fn set_active_rank_profile(name: &str) {
+        let mut settings = Settings::load_settings();
+        settings.active_rank_profile = name.to_string();
+        Settings::save_settings(&settings);
+    }
+
     pub fn add_favorite(favorite: &str) {
         let mut settings = Settings::load_settings();
         settings.favorites.sort();
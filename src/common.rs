@@ -43,13 +43,7 @@ pub fn value_as_str(v: &glib::Value) -> Option<String> {
             .and_then(|s| s.get::<String>().ok());
     }
     if t.is_a(glib::Type::FLAGS) {
-        return v.get::<Vec<&glib::FlagsValue>>().ok().map(|flags| {
-            flags
-                .iter()
-                .copied()
-                .fold(0u32, |acc, val| acc | val.value())
-                .to_string()
-        });
+        return crate::gps::value::serialize_flags(v);
     }
     // For floats, use transform to match ElementInfo::element_property behavior
     if t.is_a(glib::Type::F64) || t.is_a(glib::Type::F32) {
@@ -59,6 +53,9 @@ pub fn value_as_str(v: &glib::Value) -> Option<String> {
             .and_then(|s| s.get::<String>().ok())
             .map(|s| s.replace(',', "."));
     }
+    if let Some(value) = crate::gps::value::serialize_value(v) {
+        return Some(value);
+    }
     match t {
         glib::Type::I8 => Some(str_some_value!(v, i8).to_string()),
         glib::Type::U8 => Some(str_some_value!(v, u8).to_string()),
@@ -68,7 +65,7 @@ pub fn value_as_str(v: &glib::Value) -> Option<String> {
         glib::Type::I64 => Some(str_some_value!(v, i64).to_string()),
         glib::Type::U64 => Some(str_some_value!(v, u64).to_string()),
         glib::Type::STRING => str_opt_value!(v, String).map(|s| s.to_lowercase()),
-        // Fallback for other types (e.g., GstCaps): try transform to string
+        // Fallback for other types: try transform to string
         // Match element_property() behavior with lowercase normalization
         _ => v
             .transform::<String>()
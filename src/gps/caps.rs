@@ -0,0 +1,191 @@
+// caps.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Caps filters set on the links of the graph.
+//!
+//! The filter of a link is stored as a caps string in the link name, which is inserted
+//! between the two elements when the pipeline description is built. `CapsFilter`
+//! splits this string in a media type, its caps features and typed fields so it can be
+//! edited field by field, and checks it against the caps of the linked pads.
+
+use anyhow::bail;
+use std::fmt;
+use std::str::FromStr;
+
+/// Value types proposed by the caps editor. Any other GType name can be typed as well.
+pub const CAPS_FIELD_TYPES: [&str; 6] = ["string", "int", "uint", "double", "fraction", "boolean"];
+
+/// Caps features which are implied and not written in the caps string.
+const DEFAULT_CAPS_FEATURE: &str = "memory:SystemMemory";
+
+/// One field of a caps structure, with its value serialized as in a caps string. The
+/// value can be a range `[ min, max ]` or a list `{ a, b }` of values of this type.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CapsField {
+    pub name: String,
+    pub value_type: String,
+    pub value: String,
+}
+
+impl CapsField {
+    pub fn new(name: &str, value_type: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value_type: value_type.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for CapsField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.value_type.is_empty() {
+            write!(f, "{}={}", self.name, self.value)
+        } else {
+            write!(f, "{}=({}){}", self.name, self.value_type, self.value)
+        }
+    }
+}
+
+/// Caps filter of a link, made of a single caps structure.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CapsFilter {
+    pub media_type: String,
+    /// Caps features such as `memory:DMABuf`
+    pub features: Vec<String>,
+    pub fields: Vec<CapsField>,
+}
+
+impl fmt::Display for CapsFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.media_type)?;
+        if !self.features.is_empty() {
+            write!(f, "({})", self.features.join(", "))?;
+        }
+        for field in &self.fields {
+            write!(f, ", {field}")?;
+        }
+        Ok(())
+    }
+}
+
+impl CapsFilter {
+    /// Split a caps filter description. An empty description gives an empty filter.
+    pub fn parse(description: &str) -> anyhow::Result<CapsFilter> {
+        let description = description.trim();
+        if description.is_empty() {
+            return Ok(CapsFilter::default());
+        }
+        let caps = gst::Caps::from_str(description)
+            .map_err(|_| anyhow::anyhow!("Invalid caps '{}'", description))?;
+        if caps.is_any() || caps.is_empty() {
+            bail!("'{}' does not describe a media type", description);
+        }
+        if caps.size() > 1 {
+            bail!("Only caps with a single structure can be edited");
+        }
+        let structure = caps
+            .structure(0)
+            .ok_or_else(|| anyhow::anyhow!("Caps '{}' have no structure", description))?;
+
+        let features = caps
+            .features(0)
+            .map(|features| {
+                features
+                    .to_string()
+                    .split(',')
+                    .map(|feature| feature.trim().to_string())
+                    .filter(|feature| !feature.is_empty() && feature != DEFAULT_CAPS_FEATURE)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut fields = vec![];
+        for (name, value) in structure.iter() {
+            // Serialize each field alone to get its type and its value as written in caps
+            let mut field_structure = gst::Structure::new_empty("field");
+            field_structure.set_value(name, value.clone());
+            let serialized = field_structure.to_string();
+            let prefix = format!("field, {name}=");
+            let field_desc = serialized
+                .strip_prefix(&prefix)
+                .unwrap_or(&serialized)
+                .trim_end_matches(';');
+            let field = match field_desc
+                .strip_prefix('(')
+                .and_then(|desc| desc.split_once(')'))
+            {
+                Some((value_type, value)) => CapsField::new(name, value_type, value),
+                None => CapsField::new(name, "", field_desc),
+            };
+            fields.push(field);
+        }
+
+        Ok(CapsFilter {
+            media_type: structure.name().to_string(),
+            features,
+            fields,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.media_type.is_empty()
+    }
+
+    /// Build the caps of the filter, failing if the description is not valid.
+    pub fn caps(&self) -> anyhow::Result<gst::Caps> {
+        if self.is_empty() {
+            bail!("The caps filter has no media type");
+        }
+        if let Some(field) = self.fields.iter().find(|field| field.name.is_empty()) {
+            bail!("The field with value '{}' has no name", field.value);
+        }
+        let description = self.to_string();
+        gst::Caps::from_str(&description)
+            .map_err(|_| anyhow::anyhow!("Invalid caps filter '{}'", description))
+    }
+
+    /// Check that the filter can be negotiated between the caps of the two linked pads.
+    /// Pads without known caps are not checked.
+    pub fn check_pads(&self, caps_from: Option<&str>, caps_to: Option<&str>) -> anyhow::Result<()> {
+        check_caps_with_pads(&self.caps()?, caps_from, caps_to)
+    }
+
+    /// Check a caps description which can not be split into a filter, such as caps
+    /// with several structures, against the caps of the two linked pads.
+    pub fn check_description(
+        description: &str,
+        caps_from: Option<&str>,
+        caps_to: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let caps = gst::Caps::from_str(description.trim())
+            .map_err(|_| anyhow::anyhow!("Invalid caps '{}'", description.trim()))?;
+        check_caps_with_pads(&caps, caps_from, caps_to)
+    }
+}
+
+/// Check that the caps intersect the caps of both linked pads, pads without known
+/// caps are not checked.
+fn check_caps_with_pads(
+    caps: &gst::Caps,
+    caps_from: Option<&str>,
+    caps_to: Option<&str>,
+) -> anyhow::Result<()> {
+    for (pad, pad_caps) in [("source", caps_from), ("sink", caps_to)] {
+        let Some(pad_caps) = pad_caps else {
+            continue;
+        };
+        let Ok(pad_caps) = gst::Caps::from_str(pad_caps) else {
+            continue;
+        };
+        if !caps.can_intersect(&pad_caps) {
+            bail!("The caps filter does not intersect the {} pad caps", pad);
+        }
+    }
+    Ok(())
+}
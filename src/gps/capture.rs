@@ -0,0 +1,457 @@
+// capture.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Snapshot and recording of a running pipeline.
+//!
+//! A snapshot encodes the `last-sample` of a video sink to PNG with a small one-shot
+//! pipeline. A recording inserts a `tee` on a pad of the running pipeline and feeds
+//! an encoder, a muxer and a filesink from its new branch, without modifying the
+//! graph. Stopping the recording sends EOS to the branch only so the file is
+//! finalized while the pipeline keeps running, then the branch and the inserted tee
+//! are removed.
+//!
+//! Encoding a snapshot and finalizing a recording complete asynchronously on the main
+//! context, so the UI is never blocked while waiting for the files to be written.
+//! When the whole pipeline is stopped, the recording is finalized synchronously with
+//! an EOS sent to the pipeline before it goes to Null.
+
+use crate::gps::ElementInfo;
+use crate::logger;
+
+use gst::glib;
+use gst::prelude::*;
+use std::time::Duration;
+
+/// Maximum time to wait for a snapshot to be encoded or a recording to be finalized.
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordingPreset {
+    pub name: &'static str,
+    /// Launch description of the encoding chain, from raw video to the muxer input
+    pub encoder: &'static str,
+    pub muxer: &'static str,
+    pub extension: &'static str,
+}
+
+pub const RECORDING_PRESETS: [RecordingPreset; 5] = [
+    RecordingPreset {
+        name: "H.264 / MP4 (high quality)",
+        encoder: "videoconvert ! x264enc tune=zerolatency speed-preset=veryfast bitrate=8000 ! h264parse",
+        muxer: "mp4mux",
+        extension: "mp4",
+    },
+    RecordingPreset {
+        name: "H.264 / MP4 (low quality)",
+        encoder: "videoconvert ! x264enc tune=zerolatency speed-preset=ultrafast bitrate=1500 ! h264parse",
+        muxer: "mp4mux",
+        extension: "mp4",
+    },
+    RecordingPreset {
+        name: "H.264 / Matroska",
+        encoder: "videoconvert ! x264enc tune=zerolatency speed-preset=veryfast bitrate=4000 ! h264parse",
+        muxer: "matroskamux",
+        extension: "mkv",
+    },
+    RecordingPreset {
+        name: "VP8 / WebM",
+        encoder: "videoconvert ! vp8enc deadline=1 target-bitrate=2000000",
+        muxer: "webmmux",
+        extension: "webm",
+    },
+    RecordingPreset {
+        name: "Motion JPEG / AVI",
+        encoder: "videoconvert ! jpegenc quality=90",
+        muxer: "avimux",
+        extension: "avi",
+    },
+];
+
+/// Find the video sinks of a pipeline able to provide their last sample, the gtk4
+/// paintable sinks first as they are the ones displayed in the preview.
+pub fn snapshot_sinks(pipeline: &gst::Pipeline) -> Vec<gst::Element> {
+    let bin = pipeline.upcast_ref::<gst::Bin>();
+    let mut sinks = ElementInfo::search_for_element(bin, "gtk4paintablesink");
+    for element in ElementInfo::search_for_element(bin, "") {
+        if element.element_flags().contains(gst::ElementFlags::SINK)
+            && element.find_property("last-sample").is_some()
+            && !sinks.contains(&element)
+        {
+            sinks.push(element);
+        }
+    }
+    sinks
+}
+
+/// Write the last sample rendered by `sink` to `location` as a PNG image, `f` is
+/// called from the main context once the image is written.
+pub fn snapshot_to_png<F>(sink: &gst::Element, location: &str, f: F) -> anyhow::Result<()>
+where
+    F: FnOnce(anyhow::Result<()>) + 'static,
+{
+    let sample = sink
+        .property::<Option<gst::Sample>>("last-sample")
+        .ok_or_else(|| anyhow::anyhow!("{} has no sample to capture", sink.name()))?;
+    sample_to_png(&sample, location, f)
+}
+
+/// Encode a raw video sample to a PNG file, `f` is called from the main context with
+/// the result of the encoding.
+pub fn sample_to_png<F>(sample: &gst::Sample, location: &str, f: F) -> anyhow::Result<()>
+where
+    F: FnOnce(anyhow::Result<()>) + 'static,
+{
+    let caps = sample
+        .caps()
+        .ok_or_else(|| anyhow::anyhow!("The sample has no caps"))?;
+    // The GL sinks, as gtk4paintablesink, keep their last sample in GL memory
+    let gl_memory = caps
+        .features(0)
+        .is_some_and(|features| features.contains("memory:GLMemory"));
+    let pipeline = gst::parse::launch(&format!(
+        "appsrc name=src format=time ! {}videoconvert ! pngenc snapshot=true ! filesink name=sink",
+        if gl_memory { "gldownload ! " } else { "" }
+    ))?
+    .downcast::<gst::Pipeline>()
+    .map_err(|_| anyhow::anyhow!("Unable to create the snapshot pipeline"))?;
+    let src = pipeline
+        .by_name("src")
+        .ok_or_else(|| anyhow::anyhow!("No appsrc in the snapshot pipeline"))?;
+    src.set_property("caps", caps.to_owned());
+    pipeline
+        .by_name("sink")
+        .ok_or_else(|| anyhow::anyhow!("No filesink in the snapshot pipeline"))?
+        .set_property("location", location);
+
+    let bus = pipeline
+        .bus()
+        .ok_or_else(|| anyhow::anyhow!("The snapshot pipeline has no bus"))?;
+    let (sender, receiver) = async_channel::bounded::<gst::Message>(1);
+    bus.set_sync_handler(move |_bus, msg| {
+        if matches!(
+            msg.view(),
+            gst::MessageView::Eos(_) | gst::MessageView::Error(_)
+        ) {
+            let _ = sender.try_send(msg.to_owned());
+        }
+        gst::BusSyncReply::Drop
+    });
+
+    pipeline.set_state(gst::State::Playing)?;
+    let ret = src.emit_by_name::<gst::FlowReturn>("push-sample", &[sample]);
+    if ret != gst::FlowReturn::Ok {
+        pipeline.set_state(gst::State::Null)?;
+        return Err(anyhow::anyhow!("Unable to push the sample: {:?}", ret));
+    }
+    let _ = src.emit_by_name::<gst::FlowReturn>("end-of-stream", &[]);
+
+    let location = location.to_string();
+    glib::MainContext::default().spawn_local(async move {
+        let msg = glib::future_with_timeout(CAPTURE_TIMEOUT, receiver.recv())
+            .await
+            .ok()
+            .and_then(Result::ok);
+        let _ = pipeline.set_state(gst::State::Null);
+        let result = match msg.as_ref().map(|msg| msg.view()) {
+            Some(gst::MessageView::Eos(_)) => {
+                GPS_INFO!("Snapshot written to {}", location);
+                Ok(())
+            }
+            Some(gst::MessageView::Error(err)) => Err(anyhow::anyhow!(
+                "Unable to encode the snapshot: {}",
+                err.error()
+            )),
+            _ => Err(anyhow::anyhow!("Timeout while encoding the snapshot")),
+        };
+        f(result);
+    });
+    Ok(())
+}
+
+/// Pads relinked when a tee is inserted, to restore the original link.
+#[derive(Debug)]
+struct TeeInsertion {
+    src_pad: gst::Pad,
+    peer: gst::Pad,
+    tee_src: gst::Pad,
+}
+
+/// A recording branch inserted in a running pipeline.
+#[derive(Debug)]
+pub struct Recording {
+    pipeline: gst::Pipeline,
+    tee: gst::Element,
+    tee_pad: gst::Pad,
+    bin: gst::Bin,
+    location: String,
+    insertion: Option<TeeInsertion>,
+}
+
+impl Recording {
+    /// Insert a recording branch on the source pad `pad_name` of the element
+    /// `element_name`. If the element is already a tee, a new branch is requested
+    /// from it, otherwise a tee is inserted between the pad and its peer.
+    pub fn start(
+        pipeline: &gst::Pipeline,
+        element_name: &str,
+        pad_name: &str,
+        preset: &RecordingPreset,
+        location: &str,
+    ) -> anyhow::Result<Recording> {
+        let element = pipeline
+            .by_name(element_name)
+            .ok_or_else(|| anyhow::anyhow!("Unable to find element {}", element_name))?;
+        // The pad can be a sometimes or request pad
+        let src_pad = element
+            .pads()
+            .into_iter()
+            .find(|p| p.name() == pad_name)
+            .ok_or_else(|| {
+                anyhow::anyhow!("Unable to find pad {} on {}", pad_name, element_name)
+            })?;
+        let caps = src_pad
+            .current_caps()
+            .unwrap_or_else(|| src_pad.query_caps(None));
+        if !caps.iter().any(|s| s.name().starts_with("video/")) {
+            return Err(anyhow::anyhow!(
+                "{}:{} has no video stream to record",
+                element_name,
+                pad_name
+            ));
+        }
+
+        let bin = gst::parse::bin_from_description(
+            &format!(
+                "queue name=queue ! {} ! {} name=muxer",
+                preset.encoder, preset.muxer
+            ),
+            false,
+        )?;
+        // The location is set as a property so any file name can be used
+        let filesink = gst::ElementFactory::make("filesink")
+            .property("location", location)
+            .build()?;
+        bin.add(&filesink)?;
+        bin.by_name("muxer")
+            .ok_or_else(|| anyhow::anyhow!("No muxer in the recording branch"))?
+            .link(&filesink)?;
+        let queue_sink = bin
+            .by_name("queue")
+            .and_then(|queue| queue.static_pad("sink"))
+            .ok_or_else(|| anyhow::anyhow!("No queue in the recording branch"))?;
+        let bin_sink = gst::GhostPad::with_target(&queue_sink)?;
+        bin.add_pad(&bin_sink)?;
+
+        let is_tee = element
+            .factory()
+            .map(|f| f.name() == "tee")
+            .unwrap_or(false);
+        let tee = if is_tee {
+            element.clone()
+        } else {
+            let tee = gst::ElementFactory::make("tee")
+                .property("allow-not-linked", true)
+                .build()?;
+            pipeline.add(&tee)?;
+            tee
+        };
+        pipeline.add(&bin)?;
+
+        let tee_pad = tee
+            .request_pad_simple("src_%u")
+            .ok_or_else(|| anyhow::anyhow!("Unable to request a pad from the tee"))?;
+        tee_pad.link(&bin_sink)?;
+        bin.sync_state_with_parent()?;
+
+        let insertion = if is_tee {
+            None
+        } else {
+            let peer = src_pad
+                .peer()
+                .ok_or_else(|| anyhow::anyhow!("Pad {} is not linked", pad_name))?;
+            let tee_sink = tee
+                .static_pad("sink")
+                .ok_or_else(|| anyhow::anyhow!("The tee has no sink pad"))?;
+            let tee_src = tee
+                .request_pad_simple("src_%u")
+                .ok_or_else(|| anyhow::anyhow!("Unable to request a pad from the tee"))?;
+            tee.sync_state_with_parent()?;
+
+            // Relink once the pad is idle so no buffer is in flight
+            let insertion = TeeInsertion {
+                src_pad: src_pad.clone(),
+                peer: peer.clone(),
+                tee_src: tee_src.clone(),
+            };
+            src_pad.add_probe(gst::PadProbeType::IDLE, move |pad, _info| {
+                let _ = pad.unlink(&peer);
+                if let Err(e) = pad.link(&tee_sink) {
+                    GPS_ERROR!("Unable to link {} to the tee: {}", pad.name(), e);
+                }
+                if let Err(e) = tee_src.link(&peer) {
+                    GPS_ERROR!("Unable to link the tee to {}: {}", peer.name(), e);
+                }
+                gst::PadProbeReturn::Remove
+            });
+            Some(insertion)
+        };
+
+        GPS_INFO!(
+            "Recording {}:{} to {} with {}",
+            element_name,
+            pad_name,
+            location,
+            preset.name
+        );
+        Ok(Recording {
+            pipeline: pipeline.clone(),
+            tee,
+            tee_pad,
+            bin,
+            location: location.to_string(),
+            insertion,
+        })
+    }
+
+    /// Detach the recording branch and finalize the file while the pipeline keeps
+    /// running. The branch, and the tee if it was inserted by `start`, are removed
+    /// from the main context once the file is finalized, then `f` is called with the
+    /// location of the file.
+    pub fn stop<F>(self, f: F) -> anyhow::Result<()>
+    where
+        F: FnOnce(anyhow::Result<String>) + 'static,
+    {
+        let bin_sink = self
+            .bin
+            .static_pad("sink")
+            .ok_or_else(|| anyhow::anyhow!("The recording branch has no sink pad"))?;
+
+        let (eos_sender, eos_receiver) = async_channel::bounded::<()>(1);
+        let filesink = self
+            .bin
+            .iterate_sinks()
+            .into_iter()
+            .flatten()
+            .next()
+            .and_then(|sink| sink.static_pad("sink"));
+        if let Some(filesink_pad) = filesink {
+            filesink_pad.add_probe(
+                gst::PadProbeType::EVENT_DOWNSTREAM,
+                move |_pad, info| match info.data {
+                    Some(gst::PadProbeData::Event(ref event))
+                        if event.type_() == gst::EventType::Eos =>
+                    {
+                        let _ = eos_sender.try_send(());
+                        gst::PadProbeReturn::Remove
+                    }
+                    _ => gst::PadProbeReturn::Ok,
+                },
+            );
+        }
+
+        let _ = self.tee_pad.unlink(&bin_sink);
+        bin_sink.send_event(gst::event::Eos::new());
+
+        glib::MainContext::default().spawn_local(async move {
+            let eos = glib::future_with_timeout(CAPTURE_TIMEOUT, eos_receiver.recv()).await;
+            if !matches!(eos, Ok(Ok(()))) {
+                GPS_WARN!("Timeout while finalizing {}", self.location);
+            }
+            f(self.remove_branch());
+        });
+        Ok(())
+    }
+
+    /// Finalize the file before the whole pipeline is stopped: EOS is sent to the
+    /// pipeline and the file is complete once the bus reports it, or an error.
+    pub fn finish(self) -> anyhow::Result<String> {
+        let bus = self
+            .pipeline
+            .bus()
+            .ok_or_else(|| anyhow::anyhow!("The pipeline has no bus"))?;
+        self.pipeline.send_event(gst::event::Eos::new());
+        let msg = bus.timed_pop_filtered(
+            gst::ClockTime::from_mseconds(CAPTURE_TIMEOUT.as_millis() as u64),
+            &[gst::MessageType::Eos, gst::MessageType::Error],
+        );
+        match msg.as_ref().map(|msg| msg.view()) {
+            Some(gst::MessageView::Eos(_)) => {
+                GPS_INFO!("Recording written to {}", self.location);
+                Ok(self.location)
+            }
+            Some(gst::MessageView::Error(err)) => Err(anyhow::anyhow!(
+                "Unable to finalize {}: {}",
+                self.location,
+                err.error()
+            )),
+            _ => Err(anyhow::anyhow!(
+                "Timeout while finalizing {}",
+                self.location
+            )),
+        }
+    }
+
+    fn remove_branch(self) -> anyhow::Result<String> {
+        self.bin.set_state(gst::State::Null)?;
+        self.pipeline.remove(&self.bin)?;
+        self.tee.release_request_pad(&self.tee_pad);
+        if let Some(insertion) = &self.insertion {
+            self.remove_tee(insertion);
+        }
+        GPS_INFO!("Recording written to {}", self.location);
+        Ok(self.location)
+    }
+
+    /// Link the pad back to its original peer once it is idle, then remove the tee
+    /// inserted by `start` from the main context.
+    fn remove_tee(&self, insertion: &TeeInsertion) {
+        let pipeline = self.pipeline.clone();
+        let tee = self.tee.clone();
+        let peer = insertion.peer.clone();
+        let tee_src = insertion.tee_src.clone();
+        insertion
+            .src_pad
+            .add_probe(gst::PadProbeType::IDLE, move |pad, _info| {
+                let _ = tee_src.unlink(&peer);
+                if let Some(tee_sink) = tee.static_pad("sink") {
+                    let _ = pad.unlink(&tee_sink);
+                }
+                if let Err(e) = pad.link(&peer) {
+                    GPS_ERROR!(
+                        "Unable to link {} back to {}: {}",
+                        pad.name(),
+                        peer.name(),
+                        e
+                    );
+                }
+                let pipeline = pipeline.clone();
+                let tee = tee.clone();
+                let tee_src = tee_src.clone();
+                glib::MainContext::default().invoke(move || {
+                    tee.release_request_pad(&tee_src);
+                    let _ = tee.set_state(gst::State::Null);
+                    let _ = pipeline.remove(&tee);
+                });
+                gst::PadProbeReturn::Remove
+            });
+    }
+}
+
+/// Default location of a recording in the user videos directory.
+pub fn default_recording_location(preset: &RecordingPreset) -> String {
+    let directory =
+        glib::user_special_dir(glib::UserDirectory::Videos).unwrap_or_else(glib::home_dir);
+    let timestamp = glib::DateTime::now_local()
+        .and_then(|now| now.format("%Y%m%d-%H%M%S"))
+        .map(|s| s.to_string())
+        .unwrap_or_default();
+    directory
+        .join(format!("gps-recording-{}.{}", timestamp, preset.extension))
+        .to_string_lossy()
+        .to_string()
+}
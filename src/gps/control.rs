@@ -0,0 +1,287 @@
+// control.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Keyframe automation of element properties with the GStreamer controller.
+//!
+//! The keyframes of a property are kept in a hidden node property,
+//! `_control.volume=linear;0=0.8;2.5=0`, with the times in seconds of stream time.
+//! When the pipeline starts, an interpolation control source is created from them
+//! and bound to the property of the element with a direct control binding.
+
+use crate::graphmanager as GM;
+use crate::graphmanager::PropertyExt;
+use crate::logger;
+
+use gst::glib;
+use gst::prelude::*;
+use gst_controller::prelude::*;
+use std::fmt;
+use std::str::FromStr;
+
+/// Prefix of the hidden node properties holding the keyframes of a property.
+pub const CONTROL_PREFIX: &str = "_control.";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InterpolationMode {
+    /// Keep the value of a keyframe until the next one
+    Step,
+    #[default]
+    Linear,
+    Cubic,
+}
+
+impl InterpolationMode {
+    pub const ALL: [InterpolationMode; 3] = [
+        InterpolationMode::Step,
+        InterpolationMode::Linear,
+        InterpolationMode::Cubic,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InterpolationMode::Step => "step",
+            InterpolationMode::Linear => "linear",
+            InterpolationMode::Cubic => "cubic",
+        }
+    }
+
+    fn controller_mode(&self) -> gst_controller::InterpolationMode {
+        match self {
+            InterpolationMode::Step => gst_controller::InterpolationMode::None,
+            InterpolationMode::Linear => gst_controller::InterpolationMode::Linear,
+            InterpolationMode::Cubic => gst_controller::InterpolationMode::Cubic,
+        }
+    }
+}
+
+impl FromStr for InterpolationMode {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        InterpolationMode::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.as_str() == value.trim())
+            .ok_or_else(|| anyhow::anyhow!("'{}' is not an interpolation mode", value))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe {
+    pub time: gst::ClockTime,
+    pub value: f64,
+}
+
+impl Keyframe {
+    pub fn new(time: gst::ClockTime, value: f64) -> Self {
+        Self { time, value }
+    }
+}
+
+pub fn seconds_to_time(seconds: f64) -> gst::ClockTime {
+    gst::ClockTime::from_nseconds((seconds.max(0.0) * 1e9).round() as u64)
+}
+
+pub fn time_to_seconds(time: gst::ClockTime) -> f64 {
+    time.nseconds() as f64 / 1e9
+}
+
+/// Keyframes of a property and the interpolation between them.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PropertyControl {
+    pub mode: InterpolationMode,
+    keyframes: Vec<Keyframe>,
+}
+
+impl PropertyControl {
+    pub fn new(mode: InterpolationMode) -> Self {
+        Self {
+            mode,
+            keyframes: vec![],
+        }
+    }
+
+    /// Keyframes sorted by time.
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    /// Add a keyframe, replacing the one at the same time.
+    pub fn set_keyframe(&mut self, keyframe: Keyframe) {
+        match self
+            .keyframes
+            .binary_search_by(|k| k.time.cmp(&keyframe.time))
+        {
+            Ok(index) => self.keyframes[index] = keyframe,
+            Err(index) => self.keyframes.insert(index, keyframe),
+        }
+    }
+
+    pub fn remove_keyframe(&mut self, time: gst::ClockTime) -> Option<Keyframe> {
+        let index = self.keyframes.iter().position(|k| k.time == time)?;
+        Some(self.keyframes.remove(index))
+    }
+
+    /// Read the keyframes written as `mode;time=value;...`.
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        let mut parts = value.trim().split(';');
+        let mode = parts
+            .next()
+            .unwrap_or_default()
+            .parse::<InterpolationMode>()?;
+        let mut control = Self::new(mode);
+        for part in parts.map(str::trim).filter(|part| !part.is_empty()) {
+            let (time, value) = part
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("'{}' is not a keyframe time=value", part))?;
+            let seconds = time
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+                .ok_or_else(|| anyhow::anyhow!("'{}' is not a valid time", time))?;
+            let value = value
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .ok_or_else(|| anyhow::anyhow!("'{}' is not a valid value", value))?;
+            control.set_keyframe(Keyframe::new(seconds_to_time(seconds), value));
+        }
+        Ok(control)
+    }
+
+    /// Interpolation control source holding the keyframes.
+    pub fn control_source(&self) -> gst_controller::InterpolationControlSource {
+        let source = gst_controller::InterpolationControlSource::new();
+        source.set_property("mode", self.mode.controller_mode());
+        for keyframe in &self.keyframes {
+            source.set(keyframe.time, keyframe.value);
+        }
+        source
+    }
+}
+
+impl fmt::Display for PropertyControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.mode.as_str())?;
+        for keyframe in &self.keyframes {
+            write!(f, ";{}={}", time_to_seconds(keyframe.time), keyframe.value)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn control_property_name(property: &str) -> String {
+    format!("{CONTROL_PREFIX}{property}")
+}
+
+/// Keyframes of the properties of a node, by property name.
+pub fn node_controls(node: &GM::Node) -> Vec<(String, PropertyControl)> {
+    let mut controls: Vec<(String, PropertyControl)> = node
+        .properties()
+        .iter()
+        .filter_map(|(name, value)| {
+            let property = name.strip_prefix(CONTROL_PREFIX)?;
+            match PropertyControl::parse(value) {
+                Ok(control) => Some((property.to_string(), control)),
+                Err(e) => {
+                    GPS_WARN!(
+                        "Unable to read the keyframes of {}.{}: {}",
+                        node.unique_name(),
+                        property,
+                        e
+                    );
+                    None
+                }
+            }
+        })
+        .collect();
+    controls.sort_by(|a, b| a.0.cmp(&b.0));
+    controls
+}
+
+/// Range of the values of a numeric property.
+pub fn value_range(param: &glib::ParamSpec) -> Option<(f64, f64)> {
+    if let Some(param) = param.downcast_ref::<glib::ParamSpecInt>() {
+        Some((param.minimum() as f64, param.maximum() as f64))
+    } else if let Some(param) = param.downcast_ref::<glib::ParamSpecUInt>() {
+        Some((param.minimum() as f64, param.maximum() as f64))
+    } else if let Some(param) = param.downcast_ref::<glib::ParamSpecInt64>() {
+        Some((param.minimum() as f64, param.maximum() as f64))
+    } else if let Some(param) = param.downcast_ref::<glib::ParamSpecUInt64>() {
+        Some((param.minimum() as f64, param.maximum() as f64))
+    } else if let Some(param) = param.downcast_ref::<glib::ParamSpecFloat>() {
+        Some((param.minimum() as f64, param.maximum() as f64))
+    } else {
+        param
+            .downcast_ref::<glib::ParamSpecDouble>()
+            .map(|param| (param.minimum(), param.maximum()))
+    }
+}
+
+/// Numeric properties which can be driven by a control source.
+pub fn controllable(param: &glib::ParamSpec) -> bool {
+    param.flags().contains(gst::PARAM_FLAG_CONTROLLABLE)
+        && param.flags().contains(glib::ParamFlags::WRITABLE)
+        && value_range(param).is_some()
+}
+
+/// Bind the keyframes of the nodes to the properties of the elements of a pipeline.
+pub fn bind_controls(pipeline: &gst::Pipeline, graphview: &GM::GraphView) {
+    for node in graphview.all_nodes(GM::NodeType::All) {
+        let controls = node_controls(&node);
+        if controls.is_empty() {
+            continue;
+        }
+        let Some(element) = pipeline.by_name(&node.unique_name()) else {
+            GPS_WARN!(
+                "Unable to find the element {} to bind its keyframes",
+                node.unique_name()
+            );
+            continue;
+        };
+        for (property, control) in controls {
+            if let Err(e) = bind_control(&element, &property, &control) {
+                GPS_WARN!(
+                    "Unable to bind the keyframes of {}.{}: {}",
+                    node.unique_name(),
+                    property,
+                    e
+                );
+            }
+        }
+    }
+}
+
+pub fn bind_control(
+    element: &gst::Element,
+    property: &str,
+    control: &PropertyControl,
+) -> anyhow::Result<()> {
+    let param = element
+        .find_property(property)
+        .ok_or_else(|| anyhow::anyhow!("no property {}", property))?;
+    if !controllable(&param) {
+        anyhow::bail!("the property {} is not controllable", property);
+    }
+    if control.is_empty() {
+        return Ok(());
+    }
+    let binding = gst_controller::DirectControlBinding::new_absolute(
+        element,
+        property,
+        &control.control_source(),
+    );
+    element.add_control_binding(&binding)?;
+    Ok(())
+}
@@ -0,0 +1,295 @@
+// debug_log.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! GStreamer debug log records and category thresholds.
+//!
+//! A `GstLogRecord` keeps every field of a GStreamer debug message separately so the
+//! log viewer can display them in columns. Records can be serialized to and parsed from
+//! the standard text format written by GStreamer on stderr (`GST_DEBUG_FILE`), which
+//! allows a session to be shared and reloaded.
+
+use gst::glib;
+use gst::glib::translate::IntoGlib;
+use std::fmt::Write as _;
+
+/// All the debug levels which can be selected for a category, ordered by verbosity.
+pub const DEBUG_LEVELS: [gst::DebugLevel; 9] = [
+    gst::DebugLevel::None,
+    gst::DebugLevel::Error,
+    gst::DebugLevel::Warning,
+    gst::DebugLevel::Fixme,
+    gst::DebugLevel::Info,
+    gst::DebugLevel::Debug,
+    gst::DebugLevel::Log,
+    gst::DebugLevel::Trace,
+    gst::DebugLevel::Memdump,
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GstLogRecord {
+    pub timestamp: String,
+    pub pid: u32,
+    pub thread: String,
+    pub level: String,
+    pub category: String,
+    pub file: String,
+    pub line: u32,
+    pub function: String,
+    pub object: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugCategoryInfo {
+    pub name: String,
+    pub description: String,
+    pub threshold: gst::DebugLevel,
+}
+
+/// Short name of a debug level as written by GStreamer ("WARN", "DEBUG", ...).
+pub fn debug_level_name(level: gst::DebugLevel) -> String {
+    match level {
+        gst::DebugLevel::None => String::from("NONE"),
+        gst::DebugLevel::Error => String::from("ERROR"),
+        gst::DebugLevel::Warning => String::from("WARN"),
+        gst::DebugLevel::Fixme => String::from("FIXME"),
+        gst::DebugLevel::Info => String::from("INFO"),
+        gst::DebugLevel::Debug => String::from("DEBUG"),
+        gst::DebugLevel::Log => String::from("LOG"),
+        gst::DebugLevel::Trace => String::from("TRACE"),
+        gst::DebugLevel::Memdump => String::from("MEMDUMP"),
+        _ => String::from("UNKNOWN"),
+    }
+}
+
+/// Parse a level either by its name ("warn", "WARNING") or by its numeric value ("2").
+pub fn debug_level_from_str(level: &str) -> Option<gst::DebugLevel> {
+    let level = level.trim();
+    if let Ok(value) = level.parse::<u32>() {
+        // 8 is not used by GStreamer, MEMDUMP is 9
+        return DEBUG_LEVELS
+            .iter()
+            .copied()
+            .find(|l| l.into_glib() as u32 == value);
+    }
+    match level.to_uppercase().as_str() {
+        "NONE" => Some(gst::DebugLevel::None),
+        "ERROR" => Some(gst::DebugLevel::Error),
+        "WARN" | "WARNING" => Some(gst::DebugLevel::Warning),
+        "FIXME" => Some(gst::DebugLevel::Fixme),
+        "INFO" => Some(gst::DebugLevel::Info),
+        "DEBUG" => Some(gst::DebugLevel::Debug),
+        "LOG" => Some(gst::DebugLevel::Log),
+        "TRACE" => Some(gst::DebugLevel::Trace),
+        "MEMDUMP" => Some(gst::DebugLevel::Memdump),
+        _ => None,
+    }
+}
+
+impl GstLogRecord {
+    /// Split a `file:line:function` location as displayed in the FILE column.
+    pub fn set_location(&mut self, location: &str) {
+        let mut parts = location.rsplitn(3, ':');
+        let function = parts.next().unwrap_or_default();
+        let line = parts.next().unwrap_or_default();
+        let file = parts.next().unwrap_or_default();
+        self.file = file.to_string();
+        self.line = line.parse::<u32>().unwrap_or(0);
+        self.function = function.to_string();
+    }
+
+    pub fn location(&self) -> String {
+        format!("{}:{}:{}", self.file, self.line, self.function)
+    }
+
+    /// Format the record the same way `gst_debug_log_default` does.
+    pub fn to_gst_debug_line(&self) -> String {
+        let mut line = String::new();
+        let _ = write!(
+            line,
+            "{} {:>5} {:>14} {:<5} {:>20} {}:",
+            self.timestamp,
+            self.pid,
+            self.thread,
+            self.level,
+            self.category,
+            self.location()
+        );
+        if !self.object.is_empty() {
+            let _ = write!(line, "<{}>", self.object);
+        }
+        let _ = write!(line, " {}", self.message);
+        line
+    }
+
+    /// Parse a line in the GST_DEBUG text format, returns `None` for lines which do
+    /// not look like a GStreamer debug message.
+    pub fn from_gst_debug_line(line: &str) -> Option<GstLogRecord> {
+        fn next_token(s: &str) -> Option<(&str, &str)> {
+            let s = s.trim_start();
+            match s.find(char::is_whitespace) {
+                Some(pos) => Some((&s[..pos], &s[pos..])),
+                None if !s.is_empty() => Some((s, "")),
+                None => None,
+            }
+        }
+
+        let (timestamp, rest) = next_token(line)?;
+        if !timestamp.contains(':') || !timestamp.contains('.') {
+            return None;
+        }
+        let (pid, rest) = next_token(rest)?;
+        let pid = pid.parse::<u32>().ok()?;
+        let (thread, rest) = next_token(rest)?;
+        let (level, rest) = next_token(rest)?;
+        debug_level_from_str(level)?;
+        let (category, rest) = next_token(rest)?;
+        let rest = rest.trim_start();
+
+        // Location is "file:line:function:" optionally followed by "<object>"
+        let (location, message) = match rest.find(":<") {
+            Some(pos) if rest[..pos].matches(':').count() == 2 => {
+                let end = rest[pos..]
+                    .find("> ")
+                    .map(|p| pos + p)
+                    .or_else(|| rest.ends_with('>').then(|| rest.len() - 1))?;
+                let object = &rest[pos + 2..end];
+                (
+                    (&rest[..pos], object),
+                    rest.get(end + 2..).unwrap_or_default(),
+                )
+            }
+            _ => {
+                let end = rest
+                    .find(": ")
+                    .or_else(|| rest.strip_suffix(':').map(|s| s.len()))?;
+                ((&rest[..end], ""), rest.get(end + 2..).unwrap_or_default())
+            }
+        };
+
+        let mut record = GstLogRecord {
+            timestamp: timestamp.to_string(),
+            pid,
+            thread: thread.to_string(),
+            level: level.to_string(),
+            category: category.to_string(),
+            object: location.1.to_string(),
+            message: message.to_string(),
+            ..Default::default()
+        };
+        record.set_location(location.0);
+        Some(record)
+    }
+}
+
+/// Build a log record from the arguments given to a GStreamer log function.
+#[allow(clippy::too_many_arguments)]
+pub fn log_record_from_gst(
+    category: gst::DebugCategory,
+    level: gst::DebugLevel,
+    file: &glib::GStr,
+    function: &glib::GStr,
+    line: u32,
+    object: Option<&gst::LoggedObject>,
+    message: &str,
+) -> GstLogRecord {
+    // SAFETY: g_thread_self() is always valid on a thread calling into GStreamer and
+    // the pointer is only used as an identifier, as GStreamer does in its own output.
+    let thread = unsafe { glib::ffi::g_thread_self() };
+    GstLogRecord {
+        timestamp: gst::get_timestamp().to_string(),
+        pid: std::process::id(),
+        thread: format!("{thread:p}"),
+        level: debug_level_name(level),
+        category: category.name().to_string(),
+        file: file.as_str().to_string(),
+        line,
+        function: function.as_str().to_string(),
+        object: object.map(|o| o.to_string()).unwrap_or_default(),
+        message: message.to_string(),
+    }
+}
+
+/// List all the registered debug categories sorted by name.
+pub fn debug_categories() -> Vec<DebugCategoryInfo> {
+    let mut categories: Vec<DebugCategoryInfo> = gst::DebugCategory::all_categories()
+        .iter()
+        .map(|category| DebugCategoryInfo {
+            name: category.name().to_string(),
+            description: category.description().unwrap_or_default().to_string(),
+            threshold: category.threshold(),
+        })
+        .collect();
+    categories.sort_by(|a, b| a.name.cmp(&b.name));
+    categories
+}
+
+/// Apply a new threshold on a registered category.
+pub fn set_category_threshold(name: &str, level: gst::DebugLevel) {
+    if let Some(category) = gst::DebugCategory::get(name) {
+        category.set_threshold(level);
+    }
+}
+
+/// Split a GST_DEBUG threshold string ("*:2,videotestsrc:DEBUG") into (category, level) pairs.
+pub fn parse_threshold_string(thresholds: &str) -> Vec<(String, String)> {
+    thresholds
+        .split(',')
+        .filter_map(|item| {
+            let item = item.trim();
+            if item.is_empty() {
+                return None;
+            }
+            match item.rsplit_once(':') {
+                Some((category, level)) => Some((category.to_string(), level.to_string())),
+                // A bare level applies to every category
+                None => Some((String::from("*"), item.to_string())),
+            }
+        })
+        .collect()
+}
+
+/// Replace or append the threshold of `category` in a GST_DEBUG threshold string.
+///
+/// GST_DEBUG is applied from left to right, so the default threshold `*` is kept
+/// first to not override the thresholds of the categories.
+pub fn threshold_string_with(thresholds: &str, category: &str, level: gst::DebugLevel) -> String {
+    let mut items = parse_threshold_string(thresholds);
+    let level = debug_level_name(level);
+    match items.iter_mut().find(|(name, _)| name == category) {
+        Some(item) => item.1 = level,
+        None => items.push((category.to_string(), level)),
+    }
+    // Stable sort, the order of the other categories is kept
+    items.sort_by_key(|(name, _)| name != "*");
+    items
+        .iter()
+        .map(|(name, level)| format!("{name}:{level}"))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Write the given records to `path` in the GST_DEBUG text format.
+pub fn save_log_file(path: &str, records: &[GstLogRecord]) -> anyhow::Result<()> {
+    let mut content = String::new();
+    for record in records {
+        content.push_str(&record.to_gst_debug_line());
+        content.push('\n');
+    }
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+/// Read a GST_DEBUG text file, skipping the lines which can not be parsed.
+pub fn load_log_file(path: &str) -> anyhow::Result<Vec<GstLogRecord>> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(content
+        .lines()
+        .filter_map(GstLogRecord::from_gst_debug_line)
+        .collect())
+}
@@ -6,6 +6,7 @@
 //
 // SPDX-License-Identifier: GPL-3.0-only
 
+use crate::gps::value;
 use crate::gps::PadInfo;
 use crate::graphmanager::{NodeType, PortDirection, PortPresence};
 use crate::logger;
@@ -20,6 +21,25 @@ pub struct ElementInfo {
     pub name: String,
     pub plugin_name: String,
     pub rank: i32,
+    /// Classification of the factory such as "Codec/Decoder/Video/Hardware"
+    pub klass: String,
+    pub long_name: String,
+    pub description: String,
+    /// Union of the caps of the sink pad templates
+    pub sink_caps: String,
+    /// Union of the caps of the src pad templates
+    pub src_caps: String,
+}
+
+/// Union of the caps of the pad templates of a factory in one direction.
+fn pad_templates_caps(factory: &gst::ElementFactory, direction: gst::PadDirection) -> String {
+    let mut caps = gst::Caps::new_empty();
+    for pad_template in factory.static_pad_templates() {
+        if pad_template.direction() == direction {
+            caps.merge(pad_template.caps());
+        }
+    }
+    caps.to_string()
 }
 
 impl ElementInfo {
@@ -33,6 +53,11 @@ impl ElementInfo {
             for feature in features {
                 let mut element = ElementInfo::default();
                 if let Ok(factory) = feature.downcast::<gst::ElementFactory>() {
+                    element.klass = factory.klass().to_string();
+                    element.long_name = factory.longname().to_string();
+                    element.description = factory.description().to_string();
+                    element.sink_caps = pad_templates_caps(&factory, gst::PadDirection::Sink);
+                    element.src_caps = pad_templates_caps(&factory, gst::PadDirection::Src);
                     let feature = factory.upcast::<gst::PluginFeature>();
 
                     element.name = gst::PluginFeature::name(&feature).as_str().to_owned();
@@ -157,13 +182,25 @@ impl ElementInfo {
 
     pub fn element_property(element: &gst::Element, property_name: &str) -> anyhow::Result<String> {
         let value = element.property_value(property_name);
+        match value::value_kind(value.type_()) {
+            // Keep the case of the caps and structures, which is meaningful
+            value::PropertyKind::Caps
+            | value::PropertyKind::Structure
+            | value::PropertyKind::Array
+            | value::PropertyKind::Fraction => {
+                return Ok(value::serialize_value(&value).unwrap_or_default())
+            }
+            // The embedded elements are nodes of their own
+            value::PropertyKind::Element => return Ok(String::new()),
+            value::PropertyKind::Other => {}
+        }
         if value.type_().is_a(glib::Type::ENUM) {
             let value = value.get::<&glib::EnumValue>().unwrap().nick().to_string();
             Ok(value)
         } else if value.type_().is_a(glib::Type::FLAGS) {
-            let value = value.get::<Vec<&glib::FlagsValue>>().unwrap();
-            let flags = value.iter().copied().fold(0, |acc, val| acc | val.value());
-            Ok(flags.to_string())
+            value::serialize_flags(&value).ok_or_else(|| {
+                anyhow::anyhow!("Unable to read the flags of the property {}", property_name)
+            })
         } else if value.type_().is_a(glib::Type::F64) || value.type_().is_a(glib::Type::F32) {
             let value = value
                 .transform::<String>()
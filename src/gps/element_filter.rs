@@ -0,0 +1,165 @@
+// element_filter.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Faceted filtering of the element factories.
+//!
+//! An `ElementFilter` combines a text search over the name, long name, description and
+//! klass of a factory with facets on the klass, the minimum rank, the plugin and the
+//! caps the factory accepts or produces.
+
+use crate::gps::ElementInfo;
+use crate::graphmanager::PortDirection;
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Family of factories, matched against one of the parts of the factory klass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementClass {
+    Source,
+    Sink,
+    Decoder,
+    Encoder,
+    Muxer,
+    Demuxer,
+    Parser,
+    Filter,
+    Effect,
+    /// Hardware accelerated factories
+    Hardware,
+}
+
+impl ElementClass {
+    pub const ALL: [ElementClass; 10] = [
+        ElementClass::Source,
+        ElementClass::Sink,
+        ElementClass::Decoder,
+        ElementClass::Encoder,
+        ElementClass::Muxer,
+        ElementClass::Demuxer,
+        ElementClass::Parser,
+        ElementClass::Filter,
+        ElementClass::Effect,
+        ElementClass::Hardware,
+    ];
+
+    pub fn matches(&self, klass: &str) -> bool {
+        let name = self.to_string();
+        klass.split('/').any(|part| part.trim() == name)
+    }
+}
+
+impl fmt::Display for ElementClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// Minimum ranks proposed by the rank facet, with their name.
+pub const ELEMENT_RANKS: [(&str, i32); 4] = [
+    ("Any", 0),
+    ("Marginal", 64),
+    ("Secondary", 128),
+    ("Primary", 256),
+];
+
+/// Factory with the caps of its pads parsed once, as it is matched on each search.
+#[derive(Debug)]
+pub struct FilterableElement {
+    pub info: ElementInfo,
+    sink_caps: Option<gst::Caps>,
+    src_caps: Option<gst::Caps>,
+}
+
+impl From<ElementInfo> for FilterableElement {
+    fn from(info: ElementInfo) -> Self {
+        let sink_caps = gst::Caps::from_str(&info.sink_caps).ok();
+        let src_caps = gst::Caps::from_str(&info.src_caps).ok();
+        FilterableElement {
+            info,
+            sink_caps,
+            src_caps,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ElementFilter {
+    /// Case insensitive text searched in the name, long name, description and klass
+    pub text: String,
+    pub class: Option<ElementClass>,
+    pub min_rank: i32,
+    pub plugin: Option<String>,
+    /// Caps the sink pads (`Input`) or src pads (`Output`) of the factory must intersect
+    caps: Option<(PortDirection, gst::Caps)>,
+}
+
+impl ElementFilter {
+    /// Only keep the factories whose pads of the given direction can intersect the caps.
+    /// An empty description removes the caps facet.
+    pub fn set_caps(&mut self, direction: PortDirection, caps: &str) -> anyhow::Result<()> {
+        if caps.trim().is_empty() {
+            self.caps = None;
+        } else {
+            self.caps = Some((direction, gst::Caps::from_str(caps.trim())?));
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+            && self.class.is_none()
+            && self.min_rank <= 0
+            && self.plugin.is_none()
+            && self.caps.is_none()
+    }
+
+    pub fn matches(&self, filterable: &FilterableElement) -> bool {
+        let element = &filterable.info;
+        if !self.text.is_empty() {
+            let text = self.text.to_lowercase();
+            if ![
+                &element.name,
+                &element.long_name,
+                &element.description,
+                &element.klass,
+            ]
+            .iter()
+            .any(|field| field.to_lowercase().contains(&text))
+            {
+                return false;
+            }
+        }
+        if let Some(class) = self.class {
+            if !class.matches(&element.klass) {
+                return false;
+            }
+        }
+        if element.rank < self.min_rank {
+            return false;
+        }
+        if let Some(plugin) = &self.plugin {
+            if &element.plugin_name != plugin {
+                return false;
+            }
+        }
+        if let Some((direction, caps)) = &self.caps {
+            let pad_caps = match direction {
+                PortDirection::Input => &filterable.sink_caps,
+                _ => &filterable.src_caps,
+            };
+            if !pad_caps
+                .as_ref()
+                .is_some_and(|pad_caps| pad_caps.can_intersect(caps))
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,55 @@
+// embed.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Elements set as the value of an object property of another element.
+//!
+//! The `video-sink` of playbin or the `filter` of a switch bin takes an element. Such an
+//! element is a node of its own, without pads, holding the parent node and the property
+//! in a hidden property. When the pipeline description is built, it is written as the
+//! value of the property, which the GStreamer parser turns into the child element.
+//! Removing the parent node removes the nodes embedded in it.
+
+use crate::graphmanager as GM;
+use crate::graphmanager::PropertyExt;
+
+pub use crate::graphmanager::EMBEDDED_PROPERTY;
+
+/// Parent node id and property of an embedded node.
+pub fn embedded_parent(node: &GM::Node) -> Option<(u32, String)> {
+    node.embedded_parent()
+}
+
+pub fn set_embedded_parent(node: &GM::Node, parent_id: u32, property: &str) {
+    node.add_property(EMBEDDED_PROPERTY, &format!("{parent_id}:{property}"));
+}
+
+/// Nodes embedded in the properties of a node, by property name.
+pub fn embedded_children(graphview: &GM::GraphView, parent_id: u32) -> Vec<(String, GM::Node)> {
+    let mut children: Vec<(String, GM::Node)> = graphview
+        .all_nodes(GM::NodeType::All)
+        .into_iter()
+        .filter_map(|node| match embedded_parent(&node) {
+            Some((node_id, property)) if node_id == parent_id => Some((property, node)),
+            _ => None,
+        })
+        .collect();
+    children.sort_by(|a, b| a.0.cmp(&b.0));
+    children
+}
+
+/// Node embedded in a property of a node.
+pub fn embedded_child(
+    graphview: &GM::GraphView,
+    parent_id: u32,
+    property: &str,
+) -> Option<GM::Node> {
+    embedded_children(graphview, parent_id)
+        .into_iter()
+        .find(|(name, _)| name == property)
+        .map(|(_, node)| node)
+}
@@ -0,0 +1,381 @@
+// inspect.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Documentation of an element factory in the manner of gst-inspect.
+//!
+//! `inspect_element` creates an element of the factory and collects its metadata, plugin,
+//! type hierarchy, interfaces, pad templates, properties and signals into sections of
+//! entries which can be searched and rendered as plain text.
+
+use crate::common;
+use crate::gps::ElementInfo;
+
+use gst::glib;
+use gst::glib::translate::IntoGlib;
+use gst::prelude::*;
+use std::fmt::Write as _;
+use std::num::NonZeroU32;
+
+/// Item of a section, such as a property or a pad template, with its details.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InspectEntry {
+    pub name: String,
+    pub details: Vec<String>,
+}
+
+impl InspectEntry {
+    fn new(name: &str, details: Vec<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            details,
+        }
+    }
+
+    /// Case insensitive search in the name and the details.
+    pub fn matches(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+        self.name.to_lowercase().contains(&text)
+            || self
+                .details
+                .iter()
+                .any(|detail| detail.to_lowercase().contains(&text))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InspectSection {
+    pub title: String,
+    pub entries: Vec<InspectEntry>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ElementInspection {
+    pub name: String,
+    pub sections: Vec<InspectSection>,
+}
+
+impl ElementInspection {
+    /// Render the inspection as indented plain text.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for section in &self.sections {
+            let _ = writeln!(text, "{}:", section.title);
+            for entry in &section.entries {
+                let _ = writeln!(text, "  {}", entry.name);
+                for detail in &entry.details {
+                    let _ = writeln!(text, "    {detail}");
+                }
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Caps with one line per structure and one indented line per field.
+pub fn pretty_caps(caps: &gst::CapsRef) -> Vec<String> {
+    if caps.is_any() {
+        return vec!["ANY".to_string()];
+    }
+    if caps.is_empty() {
+        return vec!["EMPTY".to_string()];
+    }
+    let mut lines = vec![];
+    for (structure, features) in caps.iter_with_features() {
+        let features = features.to_string();
+        if features.is_empty() || features == "memory:SystemMemory" {
+            lines.push(structure.name().to_string());
+        } else {
+            lines.push(format!("{}({})", structure.name(), features));
+        }
+        for (field, value) in structure.iter() {
+            let value = value
+                .serialize()
+                .map(|value| value.to_string())
+                .unwrap_or_else(|_| format!("{value:?}"));
+            lines.push(format!("  {field}: {value}"));
+        }
+    }
+    lines
+}
+
+/// Range of the values of a numeric, enum or flags property.
+fn param_range(param: &glib::ParamSpec) -> Option<String> {
+    macro_rules! range {
+        ($($spec:ty),*) => {
+            $(
+                if let Some(param) = param.downcast_ref::<$spec>() {
+                    return Some(format!("{} - {}", param.minimum(), param.maximum()));
+                }
+            )*
+        };
+    }
+    range!(
+        glib::ParamSpecInt,
+        glib::ParamSpecUInt,
+        glib::ParamSpecInt64,
+        glib::ParamSpecUInt64,
+        glib::ParamSpecLong,
+        glib::ParamSpecULong,
+        glib::ParamSpecFloat,
+        glib::ParamSpecDouble
+    );
+    if let Some(param) = param.downcast_ref::<glib::ParamSpecEnum>() {
+        let values: Vec<String> = param
+            .enum_class()
+            .values()
+            .iter()
+            .map(|value| format!("({}) {}", value.value(), value.nick()))
+            .collect();
+        return Some(values.join(", "));
+    }
+    if let Some(param) = param.downcast_ref::<glib::ParamSpecFlags>() {
+        let values: Vec<String> = param
+            .flags_class()
+            .values()
+            .iter()
+            .map(|value| format!("(0x{:08x}) {}", value.value(), value.nick()))
+            .collect();
+        return Some(values.join(", "));
+    }
+    None
+}
+
+/// Access and mutability flags of a property.
+fn param_flags(param: &glib::ParamSpec) -> String {
+    let flags = param.flags();
+    let mut names = vec![];
+    for (flag, name) in [
+        (glib::ParamFlags::READABLE, "readable"),
+        (glib::ParamFlags::WRITABLE, "writable"),
+        (glib::ParamFlags::CONSTRUCT_ONLY, "construct-only"),
+        (glib::ParamFlags::DEPRECATED, "deprecated"),
+        (gst::PARAM_FLAG_CONTROLLABLE, "controllable"),
+    ] {
+        if flags.contains(flag) {
+            names.push(name);
+        }
+    }
+    // Latest state in which the property can still be changed
+    let mutable = if flags.contains(gst::PARAM_FLAG_MUTABLE_PLAYING) {
+        "changeable in NULL, READY, PAUSED or PLAYING state"
+    } else if flags.contains(gst::PARAM_FLAG_MUTABLE_PAUSED) {
+        "changeable only in NULL, READY or PAUSED state"
+    } else if flags.contains(gst::PARAM_FLAG_MUTABLE_READY) {
+        "changeable only in NULL or READY state"
+    } else {
+        "changeable only in NULL state"
+    };
+    if flags.contains(glib::ParamFlags::WRITABLE) {
+        format!("{}, {}", names.join(", "), mutable)
+    } else {
+        names.join(", ")
+    }
+}
+
+fn properties_section(element: &gst::Element) -> InspectSection {
+    let mut params = element.list_properties().to_vec();
+    params.sort_by(|a, b| a.name().cmp(b.name()));
+    let entries = params
+        .iter()
+        .map(|param| {
+            let mut details = vec![];
+            if let Some(blurb) = param.blurb() {
+                details.push(blurb.to_string());
+            }
+            details.push(format!("Type: {}", param.value_type().name()));
+            if let Some(range) = param_range(param) {
+                details.push(format!("Range: {range}"));
+            }
+            if let Some(default) = common::value_as_str(param.default_value()) {
+                details.push(format!("Default: {default}"));
+            }
+            details.push(format!("Flags: {}", param_flags(param)));
+            InspectEntry::new(param.name(), details)
+        })
+        .collect();
+    InspectSection {
+        title: "Properties".to_string(),
+        entries,
+    }
+}
+
+/// Signals of the element type and its parents up to GstElement, split into the
+/// signals and the action signals.
+fn signals_sections(element: &gst::Element) -> (InspectSection, InspectSection) {
+    let mut signals = vec![];
+    let mut actions = vec![];
+    let mut type_ = Some(element.type_());
+    while let Some(t) = type_ {
+        if t == gst::Element::static_type() || t == gst::Object::static_type() {
+            break;
+        }
+        let mut n_ids = 0u32;
+        // SAFETY: the ids are owned by the returned array, freed after use
+        let ids = unsafe {
+            let ids = glib::gobject_ffi::g_signal_list_ids(t.into_glib(), &mut n_ids);
+            let list = std::slice::from_raw_parts(ids, n_ids as usize).to_vec();
+            glib::ffi::g_free(ids as *mut _);
+            list
+        };
+        for id in ids.into_iter().filter_map(NonZeroU32::new) {
+            // SAFETY: the id comes from the signal list of the type
+            let query = unsafe { glib::subclass::SignalId::new(id) }.query();
+            let params: Vec<String> = query
+                .param_types()
+                .iter()
+                .map(|param| glib::Type::from(*param).name().to_string())
+                .collect();
+            let signature = format!(
+                "{} user_function ({}{})",
+                glib::Type::from(query.return_type()).name(),
+                t.name(),
+                params
+                    .iter()
+                    .map(|param| format!(", {param}"))
+                    .collect::<String>()
+            );
+            let entry = InspectEntry::new(query.signal_name(), vec![signature]);
+            if query.flags().contains(glib::SignalFlags::ACTION) {
+                actions.push(entry);
+            } else {
+                signals.push(entry);
+            }
+        }
+        type_ = t.parent();
+    }
+    (
+        InspectSection {
+            title: "Signals".to_string(),
+            entries: signals,
+        },
+        InspectSection {
+            title: "Action signals".to_string(),
+            entries: actions,
+        },
+    )
+}
+
+/// Inspect the element factory with the given name.
+pub fn inspect_element(element_name: &str) -> anyhow::Result<ElementInspection> {
+    let feature = ElementInfo::element_feature(element_name)
+        .ok_or_else(|| anyhow::anyhow!("No element factory named {}", element_name))?;
+    let factory = feature
+        .downcast::<gst::ElementFactory>()
+        .map_err(|_| anyhow::anyhow!("{} is not an element factory", element_name))?;
+    let element = factory.create().build()?;
+    let mut sections = vec![];
+
+    let mut factory_entries = vec![InspectEntry::new(
+        "Rank",
+        vec![format!(
+            "{:?} ({})",
+            factory.rank(),
+            i32::from(factory.rank())
+        )],
+    )];
+    for key in factory.metadata_keys() {
+        if let Some(value) = factory.metadata(&key) {
+            factory_entries.push(InspectEntry::new(&key, vec![value.to_string()]));
+        }
+    }
+    sections.push(InspectSection {
+        title: "Factory details".to_string(),
+        entries: factory_entries,
+    });
+
+    if let Some(plugin) = factory.plugin() {
+        let filename = plugin
+            .filename()
+            .map(|filename| filename.display().to_string())
+            .unwrap_or_default();
+        sections.push(InspectSection {
+            title: "Plugin details".to_string(),
+            entries: vec![
+                InspectEntry::new("Name", vec![plugin.plugin_name().to_string()]),
+                InspectEntry::new("Description", vec![plugin.description().to_string()]),
+                InspectEntry::new("Filename", vec![filename]),
+                InspectEntry::new("Version", vec![plugin.version().to_string()]),
+                InspectEntry::new("License", vec![plugin.license().to_string()]),
+                InspectEntry::new("Source module", vec![plugin.source().to_string()]),
+                InspectEntry::new("Origin URL", vec![plugin.origin().to_string()]),
+            ],
+        });
+    }
+
+    // From GObject down to the type of the element
+    let mut hierarchy = vec![];
+    let mut type_ = Some(element.type_());
+    while let Some(t) = type_ {
+        hierarchy.push(t.name().to_string());
+        type_ = t.parent();
+    }
+    hierarchy.reverse();
+    let hierarchy = hierarchy
+        .iter()
+        .enumerate()
+        .map(|(depth, name)| {
+            if depth == 0 {
+                name.clone()
+            } else {
+                format!("{}+----{}", " ".repeat((depth - 1) * 6), name)
+            }
+        })
+        .collect();
+    sections.push(InspectSection {
+        title: "Hierarchy".to_string(),
+        entries: vec![InspectEntry::new(element.type_().name(), hierarchy)],
+    });
+
+    let mut interfaces: Vec<String> = element
+        .type_()
+        .interfaces()
+        .iter()
+        .map(|t| t.name().to_string())
+        .collect();
+    interfaces.sort();
+    sections.push(InspectSection {
+        title: "Implemented interfaces".to_string(),
+        entries: interfaces
+            .iter()
+            .map(|name| InspectEntry::new(name, vec![]))
+            .collect(),
+    });
+
+    let entries = factory
+        .static_pad_templates()
+        .iter()
+        .map(|pad_template| {
+            let mut details = vec![
+                format!("Direction: {:?}", pad_template.direction()),
+                format!("Availability: {:?}", pad_template.presence()),
+                "Capabilities:".to_string(),
+            ];
+            details.extend(
+                pretty_caps(&pad_template.caps())
+                    .into_iter()
+                    .map(|line| format!("  {line}")),
+            );
+            InspectEntry::new(pad_template.name_template(), details)
+        })
+        .collect();
+    sections.push(InspectSection {
+        title: "Pad templates".to_string(),
+        entries,
+    });
+
+    sections.push(properties_section(&element));
+    let (signals, actions) = signals_sections(&element);
+    sections.push(signals);
+    sections.push(actions);
+
+    Ok(ElementInspection {
+        name: element_name.to_string(),
+        sections,
+    })
+}
@@ -0,0 +1,196 @@
+// interlink.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Links between the pipelines of two graph tabs.
+//!
+//! An inter-pipeline link is stored as hidden properties on an unlinked output port in
+//! one graph and on an unlinked input port in another graph. Both ends share a channel
+//! name. When the pipeline description is generated, the output port is terminated by
+//! an inter sink and the input port is fed by the matching inter source.
+
+use crate::graphmanager as GM;
+use crate::graphmanager::PropertyExt;
+use crate::logger;
+
+use gst::glib;
+use gst::prelude::*;
+use std::fmt;
+
+pub const INTER_KIND_PROPERTY: &str = "_inter_kind";
+pub const INTER_CHANNEL_PROPERTY: &str = "_inter_channel";
+pub const INTER_PEER_PROPERTY: &str = "_inter_peer";
+
+const INTER_SINK_PREFIX: &str = "gps_inter_sink_";
+const INTER_SRC_PREFIX: &str = "gps_inter_src_";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterLinkKind {
+    Video,
+    Audio,
+    Proxy,
+}
+
+impl fmt::Display for InterLinkKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl InterLinkKind {
+    pub const ALL: [InterLinkKind; 3] = [
+        InterLinkKind::Video,
+        InterLinkKind::Audio,
+        InterLinkKind::Proxy,
+    ];
+
+    pub fn from_str(kind: &str) -> Option<InterLinkKind> {
+        match kind {
+            "Video" => Some(InterLinkKind::Video),
+            "Audio" => Some(InterLinkKind::Audio),
+            "Proxy" => Some(InterLinkKind::Proxy),
+            _ => None,
+        }
+    }
+
+    /// Select the kind of link from the caps of the port, raw video and audio use the
+    /// inter elements, anything else is forwarded as is through a proxy pair.
+    pub fn from_caps(caps: &str) -> InterLinkKind {
+        if caps.starts_with("video/x-raw") {
+            InterLinkKind::Video
+        } else if caps.starts_with("audio/x-raw") {
+            InterLinkKind::Audio
+        } else {
+            InterLinkKind::Proxy
+        }
+    }
+
+    pub fn sink_factory(&self) -> &'static str {
+        match self {
+            InterLinkKind::Video => "intervideosink",
+            InterLinkKind::Audio => "interaudiosink",
+            InterLinkKind::Proxy => "proxysink",
+        }
+    }
+
+    pub fn src_factory(&self) -> &'static str {
+        match self {
+            InterLinkKind::Video => "intervideosrc",
+            InterLinkKind::Audio => "interaudiosrc",
+            InterLinkKind::Proxy => "proxysrc",
+        }
+    }
+}
+
+/// Retrieves the kind and the channel of the inter-pipeline link of a port.
+pub fn port_inter_link(port: &GM::Port) -> Option<(InterLinkKind, String)> {
+    let kind = InterLinkKind::from_str(&PropertyExt::property(port, INTER_KIND_PROPERTY)?)?;
+    let channel = PropertyExt::property(port, INTER_CHANNEL_PROPERTY)?;
+    Some((kind, channel))
+}
+
+/// Hidden port properties describing one end of an inter-pipeline link. The stub label
+/// is displayed by the graph next to the port.
+pub fn inter_link_properties(
+    kind: InterLinkKind,
+    channel: &str,
+    peer: &str,
+    direction: GM::PortDirection,
+) -> Vec<(String, String)> {
+    let stub_label = if direction == GM::PortDirection::Output {
+        format!("→ {peer}")
+    } else {
+        format!("← {peer}")
+    };
+    vec![
+        (INTER_KIND_PROPERTY.to_string(), kind.to_string()),
+        (INTER_CHANNEL_PROPERTY.to_string(), channel.to_string()),
+        (INTER_PEER_PROPERTY.to_string(), peer.to_string()),
+        (GM::STUB_LABEL_PROPERTY.to_string(), stub_label),
+    ]
+}
+
+/// Hidden port properties to remove to unlink one end of an inter-pipeline link.
+pub fn inter_link_property_names() -> [&'static str; 4] {
+    [
+        INTER_KIND_PROPERTY,
+        INTER_CHANNEL_PROPERTY,
+        INTER_PEER_PROPERTY,
+        GM::STUB_LABEL_PROPERTY,
+    ]
+}
+
+/// Create a new channel name shared by both ends of a link.
+pub fn new_channel() -> String {
+    let uuid = glib::uuid_string_random();
+    format!("gps-{}", &uuid[..8])
+}
+
+pub fn sink_element_name(channel: &str) -> String {
+    format!("{INTER_SINK_PREFIX}{channel}")
+}
+
+pub fn src_element_name(channel: &str) -> String {
+    format!("{INTER_SRC_PREFIX}{channel}")
+}
+
+/// Launch description of the element terminating the sending end of a link.
+pub fn sink_description(kind: InterLinkKind, channel: &str) -> String {
+    match kind {
+        InterLinkKind::Proxy => format!(
+            "{} name={}",
+            kind.sink_factory(),
+            sink_element_name(channel)
+        ),
+        _ => format!(
+            "{} name={} channel={}",
+            kind.sink_factory(),
+            sink_element_name(channel),
+            channel
+        ),
+    }
+}
+
+/// Launch description of the element feeding the receiving end of a link.
+pub fn src_description(kind: InterLinkKind, channel: &str) -> String {
+    match kind {
+        InterLinkKind::Proxy => {
+            format!("{} name={}", kind.src_factory(), src_element_name(channel))
+        }
+        _ => format!(
+            "{} name={} channel={}",
+            kind.src_factory(),
+            src_element_name(channel),
+            channel
+        ),
+    }
+}
+
+/// The intervideo and interaudio elements find each other with their channel, a
+/// proxysrc has to be given the proxysink of the peer pipeline. Connect every proxysrc
+/// of `pipeline` to the matching proxysink found in `peers`, in both directions.
+pub fn connect_proxies(pipeline: &gst::Pipeline, peers: &[gst::Pipeline]) {
+    let bin = pipeline.upcast_ref::<gst::Bin>();
+    for peer in peers {
+        let peer_bin = peer.upcast_ref::<gst::Bin>();
+        for (src_bin, sink_bin) in [(bin, peer_bin), (peer_bin, bin)] {
+            for element in src_bin.iterate_elements().into_iter().flatten() {
+                let name = element.name();
+                let Some(channel) = name.strip_prefix(INTER_SRC_PREFIX) else {
+                    continue;
+                };
+                if element.find_property("proxysink").is_none() {
+                    continue;
+                }
+                if let Some(sink) = sink_bin.by_name(&sink_element_name(channel)) {
+                    GPS_INFO!("Connecting {} to {}", name, sink.name());
+                    element.set_property("proxysink", &sink);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+// latency.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Pipeline latency budget.
+//!
+//! Queries the latency on a running pipeline and on each of its elements, and maps the
+//! results on the source to sink paths of the graph so the latency added by each element
+//! can be displayed.
+
+use crate::graphmanager as GM;
+use crate::logger;
+
+use gst::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Maximum number of paths reported, a graph with many tees and muxers can have a
+/// combinatorial number of source to sink paths.
+const MAX_LATENCY_PATHS: usize = 64;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Latency {
+    pub live: bool,
+    pub min: gst::ClockTime,
+    pub max: Option<gst::ClockTime>,
+}
+
+impl Latency {
+    pub fn description(&self) -> String {
+        format!(
+            "live: {}, min: {}, max: {}",
+            self.live,
+            self.min.display(),
+            self.max
+                .map(|m| m.display().to_string())
+                .unwrap_or_else(|| String::from("none"))
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElementLatency {
+    pub unique_name: String,
+    pub factory_name: String,
+    pub node_id: u32,
+    /// Accumulated latency from the sources up to this element
+    pub latency: Option<Latency>,
+    /// Minimum latency added by this element compared to its upstream neighbour
+    pub own_min: Option<gst::ClockTime>,
+}
+
+fn query_latency<F: FnOnce(&mut gst::query::Latency) -> bool>(f: F) -> Option<Latency> {
+    let mut query = gst::query::Latency::new();
+    if f(&mut query) {
+        let (live, min, max) = query.result();
+        Some(Latency { live, min, max })
+    } else {
+        None
+    }
+}
+
+/// Query the global latency of the pipeline.
+pub fn pipeline_latency(pipeline: &gst::Pipeline) -> Option<Latency> {
+    query_latency(|query| pipeline.query(query))
+}
+
+/// Query the accumulated latency at the output of an element. The query is sent on the
+/// first source pad, or on the element itself for sinks.
+pub fn element_latency(element: &gst::Element) -> Option<Latency> {
+    if let Some(pad) = element.src_pads().first() {
+        query_latency(|query| pad.query(query))
+    } else {
+        query_latency(|query| element.query(query))
+    }
+}
+
+/// Collect the node ids along every source to sink path of the graph.
+pub fn source_to_sink_paths(graphview: &GM::GraphView) -> Vec<Vec<u32>> {
+    fn walk(
+        graphview: &GM::GraphView,
+        node: &GM::Node,
+        path: &mut Vec<u32>,
+        visited: &mut HashSet<u32>,
+        paths: &mut Vec<Vec<u32>>,
+    ) {
+        if paths.len() >= MAX_LATENCY_PATHS {
+            return;
+        }
+        path.push(node.id());
+        visited.insert(node.id());
+        let mut has_next = false;
+        for port in node.all_ports(GM::PortDirection::Output) {
+            if let Some((_port_to, node_to)) = graphview.port_connected_to(port.id()) {
+                if visited.contains(&node_to) {
+                    continue;
+                }
+                if let Some(next) = graphview.node(node_to) {
+                    has_next = true;
+                    walk(graphview, &next, path, visited, paths);
+                }
+            }
+        }
+        if !has_next {
+            paths.push(path.clone());
+        }
+        visited.remove(&node.id());
+        path.pop();
+    }
+
+    let mut paths = Vec::new();
+    for source in graphview.all_nodes(GM::NodeType::Source) {
+        let mut path = Vec::new();
+        let mut visited = HashSet::new();
+        walk(graphview, &source, &mut path, &mut visited, &mut paths);
+    }
+    paths
+}
+
+/// Build the latency budget of every source to sink path of the graph, the elements are
+/// looked up in the running pipeline by the unique name of their node.
+pub fn latency_budget(
+    pipeline: &gst::Pipeline,
+    graphview: &GM::GraphView,
+) -> Vec<Vec<ElementLatency>> {
+    let mut cache: HashMap<String, Option<Latency>> = HashMap::new();
+    let mut budget = Vec::new();
+    for path in source_to_sink_paths(graphview) {
+        let mut previous_min: Option<gst::ClockTime> = None;
+        let mut elements = Vec::new();
+        for node_id in path {
+            let Some(node) = graphview.node(node_id) else {
+                continue;
+            };
+            let unique_name = node.unique_name();
+            let latency = *cache.entry(unique_name.clone()).or_insert_with(|| {
+                pipeline
+                    .by_name(&unique_name)
+                    .and_then(|element| element_latency(&element))
+            });
+            let own_min = latency.map(|l| {
+                l.min
+                    .saturating_sub(previous_min.unwrap_or(gst::ClockTime::ZERO))
+            });
+            if let Some(latency) = latency {
+                previous_min = Some(latency.min);
+            }
+            GPS_TRACE!(
+                "Latency of {}: {}",
+                unique_name,
+                latency
+                    .map(|l| l.description())
+                    .unwrap_or_else(|| String::from("unavailable"))
+            );
+            elements.push(ElementLatency {
+                unique_name,
+                factory_name: node.name(),
+                node_id,
+                latency,
+                own_min,
+            });
+        }
+        budget.push(elements);
+    }
+    budget
+}
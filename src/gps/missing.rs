@@ -0,0 +1,159 @@
+// missing.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Elements of a graph whose factory is not in the registry.
+//!
+//! A graph shared between machines with different plugin sets can reference factories
+//! which are not installed. Their nodes are kept as placeholders with their ports and
+//! properties, and the plugin and the source module providing the factory are saved in
+//! hidden properties of each node so they can be reported on the machine missing it.
+//!
+//! The installer details follow the format of the `missing-plugin` messages posted by
+//! GStreamer, as used by the distribution plugin installers.
+
+use crate::graphmanager as GM;
+use crate::graphmanager::PropertyExt;
+
+use gst::prelude::*;
+use std::collections::HashMap;
+
+/// Hidden property of a node holding the name of the plugin providing its factory.
+pub const PLUGIN_PROPERTY: &str = "_plugin";
+/// Hidden property of a node holding the source module of the plugin, such as
+/// "gst-plugins-ugly".
+pub const PLUGIN_MODULE_PROPERTY: &str = "_plugin_module";
+
+const INSTALLER_APP_NAME: &str = "GstPipelineStudio";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MissingElement {
+    pub factory: String,
+    pub plugin: Option<String>,
+    pub module: Option<String>,
+    /// Nodes of the graph using the factory, the macro node for an inner element
+    pub node_ids: Vec<u32>,
+}
+
+impl MissingElement {
+    pub fn installer_detail(&self) -> String {
+        installer_detail(
+            "element",
+            &self.factory,
+            &format!("GStreamer element {}", self.factory),
+        )
+    }
+
+    /// Plugin and module known to provide the factory.
+    pub fn provider(&self) -> String {
+        provider(self.plugin.as_deref(), self.module.as_deref())
+    }
+}
+
+fn provider(plugin: Option<&str>, module: Option<&str>) -> String {
+    match (plugin, module) {
+        (Some(plugin), Some(module)) => format!("plugin {plugin} from {module}"),
+        (Some(plugin), None) => format!("plugin {plugin}"),
+        (None, Some(module)) => module.to_string(),
+        (None, None) => String::from("unknown plugin"),
+    }
+}
+
+/// Plugin and module which provided the factory of the node when it was saved.
+pub fn node_provider(node: &GM::Node) -> String {
+    provider(
+        PropertyExt::property(node, PLUGIN_PROPERTY).as_deref(),
+        PropertyExt::property(node, PLUGIN_MODULE_PROPERTY).as_deref(),
+    )
+}
+
+/// Installer detail string in the `gst-missing-plugins` format.
+pub fn installer_detail(type_name: &str, detail: &str, description: &str) -> String {
+    format!("gstreamer|1.0|{INSTALLER_APP_NAME}|{description}|{type_name}-{detail}")
+}
+
+/// Installer detail of a `missing-plugin` element message posted by an element such as
+/// decodebin, `None` if the structure is not such a message.
+pub fn missing_plugin_message_detail(structure: &gst::StructureRef) -> Option<String> {
+    if structure.name() != "missing-plugin" {
+        return None;
+    }
+    let type_name = structure.get::<String>("type").ok()?;
+    let detail = structure.get::<String>("detail").ok().or_else(|| {
+        structure
+            .get::<gst::Caps>("detail")
+            .ok()
+            .map(|c| c.to_string())
+    })?;
+    let description = structure
+        .get::<String>("name")
+        .unwrap_or_else(|_| detail.clone());
+    Some(installer_detail(&type_name, &detail, &description))
+}
+
+pub fn factory_exists(factory: &str) -> bool {
+    gst::Registry::get()
+        .find_feature(factory, gst::ElementFactory::static_type())
+        .is_some()
+}
+
+/// Save the plugin providing the factory of the node in its hidden properties.
+pub fn record_node_plugin(node: &GM::Node) {
+    let Some(plugin) = gst::ElementFactory::find(&node.name()).and_then(|f| f.plugin()) else {
+        return;
+    };
+    node.add_property(PLUGIN_PROPERTY, plugin.plugin_name().as_str());
+    node.add_property(PLUGIN_MODULE_PROPERTY, plugin.source().as_str());
+}
+
+fn add_missing(
+    missing: &mut Vec<MissingElement>,
+    factory: &str,
+    properties: &HashMap<String, String>,
+    node_id: u32,
+) {
+    if let Some(element) = missing.iter_mut().find(|m| m.factory == factory) {
+        if !element.node_ids.contains(&node_id) {
+            element.node_ids.push(node_id);
+        }
+        return;
+    }
+    missing.push(MissingElement {
+        factory: factory.to_string(),
+        plugin: properties.get(PLUGIN_PROPERTY).cloned(),
+        module: properties.get(PLUGIN_MODULE_PROPERTY).cloned(),
+        node_ids: vec![node_id],
+    });
+}
+
+/// Factories of the graph, including the inner elements of the macro nodes, which are
+/// not in the registry.
+pub fn missing_elements(graphview: &GM::GraphView) -> Vec<MissingElement> {
+    let mut missing = vec![];
+    let mut nodes = graphview.all_nodes(GM::NodeType::All);
+    nodes.sort_by_key(|node| node.id());
+    for node in nodes {
+        if node.node_type() == Some(&GM::NodeType::Macro) {
+            let Some(template) = GM::GraphTemplate::from_macro_node(&node) else {
+                continue;
+            };
+            for template_node in template.nodes {
+                if !factory_exists(&template_node.name) {
+                    add_missing(
+                        &mut missing,
+                        &template_node.name,
+                        &template_node.properties,
+                        node.id(),
+                    );
+                }
+            }
+        } else if !factory_exists(&node.name()) {
+            add_missing(&mut missing, &node.name(), &node.properties(), node.id());
+        }
+    }
+    missing
+}
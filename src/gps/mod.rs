@@ -1,16 +1,33 @@
+mod caps;
+pub mod capture;
+pub mod control;
+pub mod debug_log;
 mod dot_parser;
 mod element;
+mod element_filter;
+pub mod embed;
+pub mod inspect;
+pub mod interlink;
+pub mod latency;
+pub mod missing;
 mod pad;
 mod player;
+pub mod preset;
+pub mod rank;
+pub mod substitute;
+pub mod validation;
+pub mod value;
 pub mod websocket;
 
 #[cfg(test)]
 mod test;
 
+pub use caps::{CapsField, CapsFilter, CAPS_FIELD_TYPES};
 pub use dot_parser::GstDotLoader;
 // Re-export dot_parsing for tests only
 #[cfg(test)]
 pub(crate) use dot_parser::dot_parsing;
 pub use element::ElementInfo;
+pub use element_filter::{ElementClass, ElementFilter, FilterableElement, ELEMENT_RANKS};
 pub use pad::PadInfo;
 pub use player::{PipelineState, Player};
@@ -9,6 +9,15 @@
 use crate::app::settings;
 use crate::app::{AppState, GPSApp, GPSAppWeak};
 use crate::common;
+use crate::gps::capture;
+use crate::gps::control;
+use crate::gps::debug_log;
+use crate::gps::embed;
+use crate::gps::interlink;
+use crate::gps::latency;
+use crate::gps::missing;
+use crate::gps::rank;
+use crate::gps::value;
 use crate::gps::ElementInfo;
 use crate::graphmanager as GM;
 use crate::graphmanager::PropertyExt;
@@ -18,7 +27,7 @@ use gst::glib;
 use gst::prelude::*;
 use gtk::gdk;
 use std::cell::{Cell, RefCell};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fmt::Write as _;
 use std::ops;
@@ -66,18 +75,29 @@ fn gst_log_handler(
     file: &glib::GStr,
     function: &glib::GStr,
     line: u32,
-    _obj: Option<&gst::LoggedObject>,
+    obj: Option<&gst::LoggedObject>,
     message: &gst::DebugMessage,
 ) {
     if let Some(msg) = message.get() {
-        let log_message = format!(
-            "{}\t{}\t{}:{}:{}\t{}",
+        let record = debug_log::log_record_from_gst(
+            category,
             level,
-            category.name(),
+            file,
+            function,
             line,
-            file.as_str(),
-            function.as_str(),
-            msg.as_str()
+            obj,
+            msg.as_str(),
+        );
+        // Format: timestamp\tlevel\tcategory\tobject\tfile:line:function\tthread\tmessage
+        let log_message = format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            record.timestamp,
+            record.level,
+            record.category,
+            record.object,
+            record.location(),
+            record.thread,
+            record.message
         );
         GPS_GST_LOG!("{}", log_message);
     }
@@ -90,6 +110,9 @@ pub struct PlayerInner {
     current_state: Cell<PipelineState>,
     n_video_sink: Cell<usize>,
     bus_watch_guard: RefCell<Option<gst::bus::BusWatchGuard>>,
+    latency: Cell<Option<gst::ClockTime>>,
+    shared_clock: RefCell<Option<(gst::Clock, gst::ClockTime)>>,
+    recording: RefCell<Option<capture::Recording>>,
 }
 
 impl Player {
@@ -100,6 +123,9 @@ impl Player {
             current_state: Cell::new(PipelineState::Stopped),
             n_video_sink: Cell::new(0),
             bus_watch_guard: RefCell::new(None),
+            latency: Cell::new(None),
+            shared_clock: RefCell::new(None),
+            recording: RefCell::new(None),
         }));
         gst::log::add_log_function(gst_log_handler);
         Ok(pipeline)
@@ -144,6 +170,16 @@ impl Player {
             .parse::<bool>()
             .unwrap_or(true); // Default to true if invalid value
 
+        // The rank overrides of the active profile, before the video sink preference
+        // which takes precedence
+        let ranks = rank::active_profile_ranks();
+        if !ranks.is_empty() {
+            GPS_INFO!(
+                "Applying the rank overrides GST_PLUGIN_FEATURE_RANK={}",
+                rank::format_feature_ranks(&ranks)
+            );
+        }
+        rank::apply_feature_ranks(&ranks);
         if use_gtk4_sink {
             ElementInfo::element_update_rank("gtk4paintablesink", gst::Rank::PRIMARY);
         } else {
@@ -220,6 +256,7 @@ impl Player {
                     GPS_ERROR!("Unable to create a pipeline: {}", err);
                     err
                 })?;
+            control::bind_controls(&pipeline, graphview);
 
             let bus = pipeline
                 .bus()
@@ -230,6 +267,16 @@ impl Player {
                 pipeline.on_pipeline_message(msg);
                 glib::ControlFlow::Continue
             })?;
+            if let Some(latency) = self.latency.get() {
+                pipeline.set_latency(latency);
+            }
+            if let Some((clock, base_time)) = self.shared_clock.borrow().as_ref() {
+                // Disable the automatic base time selection so all the pipelines
+                // sharing the clock use the same running time.
+                pipeline.use_clock(Some(clock));
+                pipeline.set_start_time(gst::ClockTime::NONE);
+                pipeline.set_base_time(*base_time);
+            }
             *self.pipeline.borrow_mut() = Some(pipeline);
             *self.bus_watch_guard.borrow_mut() = Some(bus_watch_guard);
         }
@@ -260,6 +307,16 @@ impl Player {
                     pipeline.set_state(gst::State::Paused)?;
                 }
                 PipelineState::Stopped | PipelineState::Error => {
+                    // Finalize the recording file before tearing down the pipeline, no
+                    // EOS can be expected once the pipeline has failed
+                    let recording = self.recording.borrow_mut().take();
+                    if let Some(recording) = recording {
+                        if new_state == PipelineState::Stopped {
+                            if let Err(e) = recording.finish() {
+                                GPS_ERROR!("Unable to stop the recording: {}", e);
+                            }
+                        }
+                    }
                     pipeline.set_state(gst::State::Null)?;
                     self.n_video_sink.set(0);
                 }
@@ -271,7 +328,7 @@ impl Player {
                 PipelineState::Stopped => AppState::Stopped,
                 PipelineState::Error => AppState::Error(error_message),
             };
-            self.with_app(|app| app.set_app_state(app_state))?;
+            self.with_app(|app| app.set_player_state(self, app_state))?;
         }
         Ok(new_state)
     }
@@ -333,6 +390,138 @@ impl Player {
         self.n_video_sink.get()
     }
 
+    pub fn pipeline(&self) -> Option<gst::Pipeline> {
+        self.pipeline.borrow().clone()
+    }
+
+    /// Latency configured on the pipeline, `None` means the latency computed by the pipeline.
+    pub fn latency(&self) -> Option<gst::ClockTime> {
+        self.latency.get()
+    }
+
+    pub fn set_latency(&self, latency: Option<gst::ClockTime>) {
+        self.latency.set(latency);
+        if let Some(pipeline) = self.pipeline.borrow().as_ref() {
+            pipeline.set_latency(latency);
+            if pipeline.recalculate_latency().is_err() {
+                GPS_WARN!("Unable to recalculate the pipeline latency");
+            }
+        }
+    }
+
+    pub fn pipeline_latency(&self) -> Option<latency::Latency> {
+        self.pipeline
+            .borrow()
+            .as_ref()
+            .and_then(latency::pipeline_latency)
+    }
+
+    pub fn latency_budget(&self, graphview: &GM::GraphView) -> Vec<Vec<latency::ElementLatency>> {
+        match self.pipeline.borrow().as_ref() {
+            // The live elements only report their latency once playing
+            Some(pipeline) if self.state() == PipelineState::Playing => {
+                latency::latency_budget(pipeline, graphview)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Change a property of an element of the running pipeline.
+    pub fn set_element_property(
+        &self,
+        element_name: &str,
+        property_name: &str,
+        value: &str,
+    ) -> anyhow::Result<()> {
+        let pipeline = self
+            .pipeline()
+            .ok_or_else(|| anyhow::anyhow!("No pipeline running"))?;
+        let element = pipeline
+            .by_name(element_name)
+            .ok_or_else(|| anyhow::anyhow!("Unable to find element {}", element_name))?;
+        let pspec = element.find_property(property_name).ok_or_else(|| {
+            anyhow::anyhow!("Element {} has no property {}", element_name, property_name)
+        })?;
+        let value = glib::Value::deserialize(value, pspec.value_type()).map_err(|_| {
+            anyhow::anyhow!("Invalid value {} for property {}", value, property_name)
+        })?;
+        element.set_property_from_value(property_name, &value);
+        Ok(())
+    }
+
+    /// Save the last frame rendered by the video sink at `index` to a PNG file, `f` is
+    /// called once the file is written.
+    pub fn snapshot<F>(&self, index: usize, location: &str, f: F) -> anyhow::Result<()>
+    where
+        F: FnOnce(anyhow::Result<()>) + 'static,
+    {
+        let pipeline = self
+            .pipeline()
+            .ok_or_else(|| anyhow::anyhow!("No pipeline running"))?;
+        let sinks = capture::snapshot_sinks(&pipeline);
+        let sink = sinks
+            .get(index)
+            .or_else(|| sinks.first())
+            .ok_or_else(|| anyhow::anyhow!("No video sink to take a snapshot from"))?;
+        capture::snapshot_to_png(sink, location, f)
+    }
+
+    /// Record the output of the pad `pad_name` of `element_name` while playing.
+    pub fn start_recording(
+        &self,
+        element_name: &str,
+        pad_name: &str,
+        preset: &capture::RecordingPreset,
+        location: &str,
+    ) -> anyhow::Result<()> {
+        if !self.is_playing() {
+            return Err(anyhow::anyhow!("The pipeline is not running"));
+        }
+        if self.is_recording() {
+            return Err(anyhow::anyhow!("A recording is already in progress"));
+        }
+        let pipeline = self
+            .pipeline()
+            .ok_or_else(|| anyhow::anyhow!("No pipeline running"))?;
+        let recording =
+            capture::Recording::start(&pipeline, element_name, pad_name, preset, location)?;
+        *self.recording.borrow_mut() = Some(recording);
+        Ok(())
+    }
+
+    /// Stop the recording in progress, `f` is called with the location of the
+    /// recorded file once it is finalized.
+    pub fn stop_recording<F>(&self, f: F) -> anyhow::Result<()>
+    where
+        F: FnOnce(anyhow::Result<String>) + 'static,
+    {
+        let Some(recording) = self.recording.borrow_mut().take() else {
+            return Ok(());
+        };
+        recording.stop(f)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.borrow().is_some()
+    }
+
+    /// Clock and base time to use for the next pipeline started by this player, `None`
+    /// lets the pipeline select its own clock.
+    pub fn set_shared_clock(&self, shared_clock: Option<(gst::Clock, gst::ClockTime)>) {
+        *self.shared_clock.borrow_mut() = shared_clock;
+    }
+
+    /// Clock and base time of the running pipeline.
+    pub fn clock_and_base_time(&self) -> Option<(gst::Clock, gst::ClockTime)> {
+        let pipeline = self.pipeline.borrow();
+        let pipeline = pipeline.as_ref()?;
+        Some((pipeline.clock()?, pipeline.base_time()?))
+    }
+
+    pub fn ptr_eq(&self, other: &Player) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+
     pub fn downgrade(&self) -> PlayerWeak {
         PlayerWeak(Rc::downgrade(&self.0))
     }
@@ -406,7 +595,20 @@ impl Player {
                 GPS_MSG_LOG!("{}\tAsyncDone\tAsync operation completed", src_name);
             }
             MessageView::Latency(_) => {
-                GPS_MSG_LOG!("{}\tLatency\tLatency changed", src_name);
+                // The application is in charge of redistributing the latency
+                if let Some(pipeline) = self.pipeline() {
+                    if pipeline.recalculate_latency().is_err() {
+                        GPS_WARN!("Unable to recalculate the pipeline latency");
+                    }
+                }
+                match self.pipeline_latency() {
+                    Some(latency) => GPS_MSG_LOG!(
+                        "{}\tLatency\tLatency changed ({})",
+                        src_name,
+                        latency.description()
+                    ),
+                    None => GPS_MSG_LOG!("{}\tLatency\tLatency changed", src_name),
+                }
             }
             MessageView::ClockLost(_) => {
                 GPS_MSG_LOG!("{}\tClockLost\tClock lost", src_name);
@@ -437,6 +639,12 @@ impl Player {
                     .map(|s| s.name().to_string())
                     .unwrap_or_else(|| "unknown".to_string());
                 GPS_MSG_LOG!("{}\tElement\t{}", src_name, structure_name);
+                if let Some(detail) = elem
+                    .structure()
+                    .and_then(missing::missing_plugin_message_detail)
+                {
+                    GPS_WARN!("{} is missing a plugin: {}", src_name, detail);
+                }
             }
             MessageView::SegmentDone(_) => {
                 GPS_MSG_LOG!("{}\tSegmentDone\tSegment done", src_name);
@@ -532,31 +740,214 @@ impl Player {
     }
 
     // Render graph methods
-    fn process_gst_node(
+    fn write_node_property(description: &mut String, name: &str, value: &str) {
+        // Quote values containing spaces or special chars for GStreamer parser
+        // but only if not already quoted
+        let needs_quoting = (value.contains(' ') || value.contains('(') || value.contains(')'))
+            && !value.starts_with('"');
+        if needs_quoting {
+            let _ = write!(description, "{name}=\"{value}\" ");
+        } else {
+            let _ = write!(description, "{name}={value} ");
+        }
+    }
+
+    /// Name of the element created for a node of the template of a macro node.
+    fn macro_element_name(node: &GM::Node, template_node: &GM::TemplateNode) -> String {
+        format!(
+            "{}_{}{}",
+            node.unique_name(),
+            template_node.name,
+            template_node.id
+        )
+    }
+
+    /// Pad of the inner element a port of a macro node stands for, as `element.pad`.
+    fn macro_port_pad(
+        node: &GM::Node,
+        template: &GM::GraphTemplate,
+        port_id: u32,
+    ) -> Option<String> {
+        let template_port_id = node
+            .port(port_id)
+            .and_then(|port| PropertyExt::property(&port, GM::MACRO_PORT_PROPERTY))
+            .and_then(|id| id.parse::<u32>().ok())?;
+        let (template_node, template_port) = template.port(template_port_id)?;
+        Some(format!(
+            "{}.{}",
+            Self::macro_element_name(node, template_node),
+            template_port.name
+        ))
+    }
+
+    /// Write the description of an embedded element as the value of a property.
+    fn write_embedded_property(description: &mut String, name: &str, child_description: &str) {
+        let _ = write!(
+            description,
+            "{}=\"{}\" ",
+            name,
+            child_description
+                .trim_end()
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+        );
+    }
+
+    /// Write the properties of a node of the template of a macro node, with the nodes of
+    /// the template embedded in its properties described as the value of these properties.
+    fn write_macro_node_properties(
+        node: &GM::Node,
+        template: &GM::GraphTemplate,
+        template_node: &GM::TemplateNode,
+        values: &HashMap<String, String>,
+        description: &mut String,
+    ) {
+        let mut properties: Vec<(String, String)> = template
+            .node_properties(template_node, values)
+            .into_iter()
+            .filter(|(name, _)| !node.hidden_property(name))
+            .collect();
+        properties.sort();
+        for (name, value) in properties {
+            Self::write_node_property(description, &name, &value);
+        }
+        for (property, child) in template.embedded_nodes(template_node.id) {
+            let mut child_description = format!(
+                "{} name={} ",
+                child.name,
+                Self::macro_element_name(node, child)
+            );
+            Self::write_macro_node_properties(
+                node,
+                template,
+                child,
+                values,
+                &mut child_description,
+            );
+            Self::write_embedded_property(description, &property, &child_description);
+        }
+    }
+
+    /// Describe the element linked to a port, or refer to it if it has already been described.
+    fn process_link_target(
+        graphview: &GM::GraphView,
+        node_id: u32,
+        port_id: u32,
+        elements: &mut HashSet<String>,
+        description: &mut String,
+    ) {
+        let Some(node) = graphview.node(node_id) else {
+            return;
+        };
+        if node.node_type() == Some(&GM::NodeType::Macro) {
+            // Linking to the pad of the inner element creates the ghost pad of the bin
+            if let Some(pad) = GM::GraphTemplate::from_macro_node(&node)
+                .and_then(|template| Self::macro_port_pad(&node, &template, port_id))
+            {
+                let _ = write!(description, "{pad} ");
+            }
+            if !elements.contains(&node.unique_name()) {
+                Self::process_macro_node(graphview, &node, elements, description);
+            }
+        } else if elements.contains(&node.unique_name()) {
+            let _ = write!(description, "{}. ", node.unique_name());
+        } else {
+            Self::process_gst_node(graphview, &node, elements, description);
+        }
+    }
+
+    /// Describe a macro node as a bin holding the elements of its template, with its
+    /// parameters forwarded to the properties of these elements.
+    fn process_macro_node(
         graphview: &GM::GraphView,
         node: &GM::Node,
         elements: &mut HashSet<String>,
         description: &mut String,
     ) {
         let unique_name = node.unique_name();
-        let _ = write!(description, "{} name={} ", node.name(), unique_name);
         elements.insert(unique_name.clone());
-        // Node properties
-        for (name, value) in node.properties().iter() {
-            // This allows having an index in front of a property such as an enum.
-            if !node.hidden_property(name) {
-                // Quote values containing spaces or special chars for GStreamer parser
-                // but only if not already quoted
-                let needs_quoting =
-                    (value.contains(' ') || value.contains('(') || value.contains(')'))
-                        && !value.starts_with('"');
-                if needs_quoting {
-                    let _ = write!(description, "{name}=\"{value}\" ");
-                } else {
-                    let _ = write!(description, "{name}={value} ");
+        let Some(template) = GM::GraphTemplate::from_macro_node(node) else {
+            GPS_ERROR!("The macro node {} has no valid template", unique_name);
+            return;
+        };
+        let _ = write!(description, "bin.( name={unique_name} ");
+        let values = node.properties().clone();
+        // The embedded nodes are described in the properties of their parent
+        for template_node in template
+            .nodes
+            .iter()
+            .filter(|template_node| template_node.embedded_parent().is_none())
+        {
+            let _ = write!(
+                description,
+                "{} name={} ",
+                template_node.name,
+                Self::macro_element_name(node, template_node)
+            );
+            Self::write_macro_node_properties(node, &template, template_node, &values, description);
+            for template_port in &template_node.ports {
+                for (name, value) in template_port.properties.iter() {
+                    if !node.hidden_property(name) {
+                        let _ = write!(description, "{}::{}={} ", template_port.name, name, value);
+                    }
+                }
+            }
+        }
+        for link in &template.links {
+            let (Some((node_from, port_from)), Some((node_to, port_to))) =
+                (template.port(link.port_from), template.port(link.port_to))
+            else {
+                continue;
+            };
+            let _ = write!(
+                description,
+                "{}.{} ! ",
+                Self::macro_element_name(node, node_from),
+                port_from.name
+            );
+            if !link.name.is_empty() {
+                let _ = write!(description, "{} ! ", link.name);
+            }
+            let _ = write!(
+                description,
+                "{}.{} ",
+                Self::macro_element_name(node, node_to),
+                port_to.name
+            );
+        }
+        description.push_str(") ");
+
+        for port in node.all_ports(GM::PortDirection::Output) {
+            let Some((port_to, node_to)) = graphview.port_connected_to(port.id()) else {
+                continue;
+            };
+            let Some(pad) = Self::macro_port_pad(node, &template, port.id()) else {
+                continue;
+            };
+            let _ = write!(description, "{pad} ! ");
+            if let Some(link) = graphview.port_link(port.id()) {
+                if !link.name().is_empty() {
+                    let _ = write!(description, "{} ! ", link.name());
                 }
             }
+            Self::process_link_target(graphview, node_to, port_to, elements, description);
         }
+    }
+
+    fn process_gst_node(
+        graphview: &GM::GraphView,
+        node: &GM::Node,
+        elements: &mut HashSet<String>,
+        description: &mut String,
+    ) {
+        if node.node_type() == Some(&GM::NodeType::Macro) {
+            Self::process_macro_node(graphview, node, elements, description);
+            return;
+        }
+        let unique_name = node.unique_name();
+        let _ = write!(description, "{} name={} ", node.name(), unique_name);
+        elements.insert(unique_name.clone());
+        Self::write_node_properties(graphview, node, elements, description);
         // Port properties
         let ports = node.all_ports(GM::PortDirection::All);
         for port in ports {
@@ -570,7 +961,7 @@ impl Player {
         let ports = node.all_ports(GM::PortDirection::Output);
         let n_ports = ports.len();
         for port in ports {
-            if let Some((_port_to, node_to)) = graphview.port_connected_to(port.id()) {
+            if let Some((port_to, node_to)) = graphview.port_connected_to(port.id()) {
                 if n_ports > 1 {
                     let _ = write!(description, "{unique_name}. ! ");
                 } else {
@@ -581,24 +972,89 @@ impl Player {
                     }
                     description.push_str("! ");
                 }
-                if let Some(node) = graphview.node(node_to) {
-                    if elements.contains(&node.unique_name()) {
-                        let _ = write!(description, "{}. ", node.unique_name());
-                    } else {
-                        Self::process_gst_node(graphview, &node, elements, description);
-                    }
+                Self::process_link_target(graphview, node_to, port_to, elements, description);
+            } else if let Some((kind, channel)) = interlink::port_inter_link(&port) {
+                // The output is sent to another tab pipeline
+                if n_ports > 1 {
+                    let _ = write!(description, "{unique_name}. ! ");
+                } else {
+                    description.push_str("! ");
                 }
+                let _ = write!(
+                    description,
+                    "{} ",
+                    interlink::sink_description(kind, &channel)
+                );
+            }
+        }
+    }
+
+    /// Write the properties of a node, with the nodes embedded in its properties
+    /// described as the value of these properties.
+    fn write_node_properties(
+        graphview: &GM::GraphView,
+        node: &GM::Node,
+        elements: &mut HashSet<String>,
+        description: &mut String,
+    ) {
+        for (name, value) in node.properties().iter() {
+            // This allows having an index in front of a property such as an enum.
+            if !node.hidden_property(name) {
+                Self::write_node_property(description, name, value);
             }
         }
+        for (property, child) in embed::embedded_children(graphview, node.id()) {
+            let child_name = child.unique_name();
+            elements.insert(child_name.clone());
+            let mut child_description = format!("{} name={} ", child.name(), child_name);
+            Self::write_node_properties(graphview, &child, elements, &mut child_description);
+            Self::write_embedded_property(description, &property, &child_description);
+        }
     }
 
     pub fn pipeline_description_from_graphview(&self, graphview: &GM::GraphView) -> String {
-        let source_nodes = graphview.all_nodes(GM::NodeType::Source);
+        // The embedded nodes are described in the properties of their parent
+        let source_nodes: Vec<GM::Node> = graphview
+            .all_nodes(GM::NodeType::Source)
+            .into_iter()
+            .filter(|node| embed::embedded_parent(node).is_none())
+            .collect();
         let mut elements: HashSet<String> = HashSet::new();
         let mut description = String::new();
         for source_node in source_nodes {
             Self::process_gst_node(graphview, &source_node, &mut elements, &mut description);
         }
+        // Macro nodes not fed by a source, such as a macro holding a source
+        for node in graphview.all_nodes(GM::NodeType::Macro) {
+            if !elements.contains(&node.unique_name()) {
+                Self::process_macro_node(graphview, &node, &mut elements, &mut description);
+            }
+        }
+        // Inputs fed by another tab pipeline
+        for node in graphview.all_nodes(GM::NodeType::All) {
+            let input_ports = node.all_ports(GM::PortDirection::Input);
+            for port in input_ports.iter() {
+                if graphview.port_is_linked(port.id()).is_some() {
+                    continue;
+                }
+                let Some((kind, channel)) = interlink::port_inter_link(port) else {
+                    continue;
+                };
+                if !elements.contains(&node.unique_name()) {
+                    Self::process_gst_node(graphview, &node, &mut elements, &mut description);
+                }
+                let _ = write!(
+                    description,
+                    "{} ! {}.",
+                    interlink::src_description(kind, &channel),
+                    node.unique_name()
+                );
+                if input_ports.len() > 1 {
+                    description.push_str(&port.name());
+                }
+                description.push(' ');
+            }
+        }
         description
     }
 
@@ -732,11 +1188,7 @@ impl Player {
                     (*ptr).default_value
                 };
                 let current = element.property_value(prop_name);
-                if let Ok(flags) = current.get::<Vec<&glib::FlagsValue>>() {
-                    let current_int = flags
-                        .iter()
-                        .copied()
-                        .fold(0u32, |acc, val| acc | val.value());
+                if let Some(current_int) = value::flags_mask(&current) {
                     return current_int == default_int;
                 } else {
                     GPS_DEBUG!(
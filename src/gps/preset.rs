@@ -0,0 +1,250 @@
+// preset.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Named sets of element properties.
+//!
+//! The elements implementing the `GstPreset` interface, such as the encoders, come with
+//! presets stored by GStreamer. They are loaded into an instance of the element and the
+//! properties set by the preset are read back as the string values kept by the nodes,
+//! and saved the other way around.
+//!
+//! The studio presets work for any element: they are kept by factory in `presets.toml`
+//! in the configuration directory, so a pipeline launched from the command line can use
+//! them as well.
+
+use crate::app::settings::Settings;
+use crate::gps::ElementInfo;
+use crate::logger;
+
+use gst::glib;
+use gst::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::rc::Rc;
+
+/// Properties of a preset, by property name.
+pub type PresetProperties = BTreeMap<String, String>;
+
+/// Where a preset is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresetSource {
+    /// Preset of the `GstPreset` interface of the element
+    Element,
+    /// Preset of the studio, saved in the configuration directory
+    Studio,
+}
+
+/// Presets of the studio, by factory then by preset name.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct StudioPresets {
+    pub factories: BTreeMap<String, BTreeMap<String, PresetProperties>>,
+}
+
+impl StudioPresets {
+    pub fn load_from_file(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Presets of the configuration directory, empty if they can not be read.
+    pub fn load() -> Self {
+        let path = Settings::presets_file_path();
+        Self::load_from_file(&path).unwrap_or_else(|e| {
+            GPS_ERROR!("Unable to load the presets {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        self.save_to_file(&Settings::presets_file_path())
+    }
+
+    pub fn names(&self, factory: &str) -> Vec<String> {
+        self.factories
+            .get(factory)
+            .map(|presets| presets.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn preset(&self, factory: &str, name: &str) -> Option<&PresetProperties> {
+        self.factories.get(factory)?.get(name)
+    }
+
+    pub fn set_preset(&mut self, factory: &str, name: &str, properties: PresetProperties) {
+        self.factories
+            .entry(factory.to_string())
+            .or_default()
+            .insert(name.to_string(), properties);
+    }
+
+    pub fn remove_preset(&mut self, factory: &str, name: &str) -> bool {
+        let Some(presets) = self.factories.get_mut(factory) else {
+            return false;
+        };
+        let removed = presets.remove(name).is_some();
+        if presets.is_empty() {
+            self.factories.remove(factory);
+        }
+        removed
+    }
+}
+
+fn create_preset_element(factory: &str) -> anyhow::Result<gst::Preset> {
+    let element = gst::ElementFactory::make(factory).build()?;
+    element
+        .dynamic_cast::<gst::Preset>()
+        .map_err(|_| anyhow::anyhow!("The element {} does not implement GstPreset", factory))
+}
+
+/// Check if the factory implements the `GstPreset` interface.
+pub fn supports_preset(factory: &str) -> bool {
+    gst::ElementFactory::find(factory)
+        .and_then(|f| f.load().ok())
+        .is_some_and(|f| f.element_type().is_a(gst::Preset::static_type()))
+}
+
+/// Names of the `GstPreset` presets of the factory, empty if it does not support them.
+pub fn element_preset_names(factory: &str) -> Vec<String> {
+    if !supports_preset(factory) {
+        return vec![];
+    }
+    create_preset_element(factory)
+        .map(|preset| {
+            preset
+                .preset_names()
+                .iter()
+                .map(|name| name.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Properties which can be written back to an element and saved in a node
+fn preset_params(element: &gst::Element) -> Vec<glib::ParamSpec> {
+    element
+        .list_properties()
+        .iter()
+        .filter(|param| {
+            param
+                .flags()
+                .contains(glib::ParamFlags::READABLE | glib::ParamFlags::WRITABLE)
+                && !param.flags().contains(glib::ParamFlags::CONSTRUCT_ONLY)
+                && param.owner_type() != gst::Object::static_type()
+        })
+        .cloned()
+        .collect()
+}
+
+/// Properties set by a `GstPreset` preset, including the ones set to their default.
+pub fn load_element_preset(factory: &str, name: &str) -> anyhow::Result<PresetProperties> {
+    let preset = create_preset_element(factory)?;
+    let element = preset
+        .dynamic_cast_ref::<gst::Element>()
+        .ok_or_else(|| anyhow::anyhow!("The preset of {} is not an element", factory))?;
+    // The preset sets its properties on the element, which notifies each of them
+    let notified = Rc::new(RefCell::new(HashSet::new()));
+    let handler = element.connect_notify_local(
+        None,
+        glib::clone!(
+            #[strong]
+            notified,
+            move |_, param| {
+                notified.borrow_mut().insert(param.name().to_string());
+            }
+        ),
+    );
+    let loaded = preset.load_preset(name);
+    element.disconnect(handler);
+    loaded.map_err(|_| anyhow::anyhow!("Unable to load the preset {} of {}", name, factory))?;
+    let notified = notified.borrow();
+    Ok(preset_params(element)
+        .iter()
+        .filter(|param| notified.contains(param.name()))
+        .filter_map(|param| {
+            let value = ElementInfo::element_property(element, param.name()).ok()?;
+            Some((param.name().to_string(), value))
+        })
+        .collect())
+}
+
+/// Save the properties as a `GstPreset` preset of the factory, in the user preset
+/// directory of GStreamer.
+pub fn save_element_preset(
+    factory: &str,
+    name: &str,
+    properties: &HashMap<String, String>,
+) -> anyhow::Result<()> {
+    let preset = create_preset_element(factory)?;
+    let element = preset
+        .dynamic_cast_ref::<gst::Element>()
+        .ok_or_else(|| anyhow::anyhow!("The preset of {} is not an element", factory))?;
+    for (property, value) in properties {
+        let Some(param) = element.find_property(property) else {
+            continue;
+        };
+        if let Ok(value) = glib::Value::deserialize(value.trim_matches('"'), param.value_type()) {
+            element.set_property_from_value(property, &value);
+        }
+    }
+    preset
+        .save_preset(name)
+        .map_err(|_| anyhow::anyhow!("Unable to save the preset {} of {}", name, factory))
+}
+
+pub fn delete_element_preset(factory: &str, name: &str) -> anyhow::Result<()> {
+    create_preset_element(factory)?
+        .delete_preset(name)
+        .map_err(|_| anyhow::anyhow!("Unable to delete the preset {} of {}", name, factory))
+}
+
+/// All the presets usable for a factory, the studio presets first.
+pub fn preset_names(factory: &str) -> Vec<(PresetSource, String)> {
+    let mut names: Vec<(PresetSource, String)> = StudioPresets::load()
+        .names(factory)
+        .into_iter()
+        .map(|name| (PresetSource::Studio, name))
+        .collect();
+    names.extend(
+        element_preset_names(factory)
+            .into_iter()
+            .map(|name| (PresetSource::Element, name)),
+    );
+    names
+}
+
+/// Properties of a preset of a factory, looked up in the studio presets then in the
+/// `GstPreset` presets.
+pub fn preset_properties(factory: &str, name: &str) -> anyhow::Result<PresetProperties> {
+    if let Some(properties) = StudioPresets::load().preset(factory, name) {
+        return Ok(properties.clone());
+    }
+    if element_preset_names(factory).iter().any(|n| n == name) {
+        return load_element_preset(factory, name);
+    }
+    anyhow::bail!("No preset {} for the element {}", name, factory)
+}
+
+/// Parse a `factory:preset` command line argument.
+pub fn parse_preset_argument(argument: &str) -> anyhow::Result<(String, String)> {
+    match argument.split_once(':') {
+        Some((factory, name)) if !factory.trim().is_empty() && !name.trim().is_empty() => {
+            Ok((factory.trim().to_string(), name.trim().to_string()))
+        }
+        _ => anyhow::bail!("Invalid preset '{}', expected element:preset", argument),
+    }
+}
@@ -0,0 +1,221 @@
+// rank.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Overrides of the rank of the plugin features.
+//!
+//! A rank profile is a named list of features with the rank to force on them, kept in
+//! the `GST_PLUGIN_FEATURE_RANK` format (`avdec_h264:MAX,vah264dec:NONE`) so it can be
+//! exported to run the same pipeline outside of the application. Setting the rank of
+//! a feature to NONE keeps it out of the autoplugging elements such as decodebin.
+//!
+//! The original ranks are remembered when a profile is applied, so switching to another
+//! profile restores the features the new one does not override.
+
+use crate::app::settings::Settings;
+use crate::gps::ElementInfo;
+use crate::logger;
+
+use gst::glib;
+use gst::prelude::*;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Names accepted for a rank in `GST_PLUGIN_FEATURE_RANK`, besides a number.
+pub const RANK_NAMES: [(&str, i32); 5] = [
+    ("NONE", 0),
+    ("MARGINAL", 64),
+    ("SECONDARY", 128),
+    ("PRIMARY", 256),
+    ("MAX", i32::MAX),
+];
+
+/// Profile forcing the software implementations over the hardware accelerated ones.
+pub const PROFILE_SOFTWARE_ONLY: &str = "Software only";
+/// Profile preferring the VA-API hardware accelerated factories.
+pub const PROFILE_VAAPI: &str = "VA-API";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureRank {
+    pub feature: String,
+    pub rank: i32,
+}
+
+// Rank of the features before the first override, to restore them
+static ORIGINAL_RANKS: Mutex<Option<HashMap<String, i32>>> = Mutex::new(None);
+
+// Built-in profiles, built once as they need a scan of the registry
+static BUILTIN_PROFILES: Mutex<Option<HashMap<String, Vec<FeatureRank>>>> = Mutex::new(None);
+
+/// Name of a rank as written in `GST_PLUGIN_FEATURE_RANK`, the number if it has no name.
+pub fn rank_name(rank: i32) -> String {
+    RANK_NAMES
+        .iter()
+        .find(|(_, value)| *value == rank)
+        .map(|(name, _)| name.to_string())
+        .unwrap_or_else(|| rank.to_string())
+}
+
+/// Parse a rank either by its name ("primary", "MAX") or by its numeric value ("257").
+pub fn rank_from_str(rank: &str) -> Option<i32> {
+    let rank = rank.trim();
+    if let Ok(value) = rank.parse::<i32>() {
+        return (value >= 0).then_some(value);
+    }
+    RANK_NAMES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(rank))
+        .map(|(_, value)| *value)
+}
+
+/// Parse a `GST_PLUGIN_FEATURE_RANK` string, the last rank of a feature wins.
+pub fn parse_feature_ranks(ranks: &str) -> anyhow::Result<Vec<FeatureRank>> {
+    let mut feature_ranks: Vec<FeatureRank> = vec![];
+    for item in ranks.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+        let (feature, rank) = item
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("Missing the rank of '{}'", item))?;
+        let feature = feature.trim();
+        if feature.is_empty() {
+            anyhow::bail!("Missing the feature name of '{}'", item);
+        }
+        let rank = rank_from_str(rank)
+            .ok_or_else(|| anyhow::anyhow!("Invalid rank '{}' for {}", rank.trim(), feature))?;
+        feature_ranks.retain(|r| r.feature != feature);
+        feature_ranks.push(FeatureRank {
+            feature: feature.to_string(),
+            rank,
+        });
+    }
+    Ok(feature_ranks)
+}
+
+/// Render the ranks as a `GST_PLUGIN_FEATURE_RANK` string.
+pub fn format_feature_ranks(ranks: &[FeatureRank]) -> String {
+    ranks
+        .iter()
+        .map(|r| format!("{}:{}", r.feature, rank_name(r.rank)))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Rank of every element factory of the plugin set to NONE, to keep it out of the
+/// autoplugging.
+pub fn plugin_blacklist(plugin_name: &str) -> Vec<FeatureRank> {
+    let registry = gst::Registry::get();
+    let mut ranks: Vec<FeatureRank> = registry
+        .features_by_plugin(plugin_name)
+        .into_iter()
+        .map(|feature| FeatureRank {
+            feature: feature.name().to_string(),
+            rank: 0,
+        })
+        .collect();
+    ranks.sort_by(|a, b| a.feature.cmp(&b.feature));
+    ranks
+}
+
+/// Whether the profile is provided by default, it can be edited but not deleted.
+pub fn is_builtin_profile(name: &str) -> bool {
+    name == PROFILE_SOFTWARE_ONLY || name == PROFILE_VAAPI
+}
+
+/// Profiles provided by default, built from the factories of the registry.
+pub fn builtin_profiles() -> HashMap<String, Vec<FeatureRank>> {
+    let mut profiles = BUILTIN_PROFILES.lock().unwrap_or_else(|e| e.into_inner());
+    profiles.get_or_insert_with(registry_profiles).clone()
+}
+
+fn registry_profiles() -> HashMap<String, Vec<FeatureRank>> {
+    let elements = ElementInfo::elements_list().unwrap_or_default();
+    let hardware = || {
+        elements
+            .iter()
+            .filter(|e| e.klass.split('/').any(|part| part.trim() == "Hardware"))
+    };
+    let mut profiles = HashMap::new();
+    profiles.insert(
+        PROFILE_SOFTWARE_ONLY.to_string(),
+        hardware()
+            .map(|e| FeatureRank {
+                feature: e.name.clone(),
+                rank: 0,
+            })
+            .collect(),
+    );
+    profiles.insert(
+        PROFILE_VAAPI.to_string(),
+        hardware()
+            .filter(|e| e.plugin_name == "va" || e.plugin_name == "vaapi")
+            .map(|e| FeatureRank {
+                feature: e.name.clone(),
+                rank: i32::from(gst::Rank::PRIMARY) + 1,
+            })
+            .collect(),
+    );
+    profiles
+}
+
+/// Profiles saved in the settings, with the built-in profiles which were not saved.
+pub fn rank_profiles() -> HashMap<String, Vec<FeatureRank>> {
+    let mut profiles = builtin_profiles();
+    for (name, ranks) in Settings::rank_profiles() {
+        match parse_feature_ranks(&ranks) {
+            Ok(ranks) => {
+                profiles.insert(name, ranks);
+            }
+            Err(e) => GPS_ERROR!("Invalid ranks in the profile {}: {}", name, e),
+        }
+    }
+    profiles
+}
+
+/// Ranks of the profile selected in the settings, empty if there is none.
+pub fn active_profile_ranks() -> Vec<FeatureRank> {
+    let name = Settings::active_rank_profile();
+    if name.is_empty() {
+        return vec![];
+    }
+    // Only the selected profile is parsed, the saved one replaces the built-in one
+    if let Some(ranks) = Settings::rank_profiles().remove(&name) {
+        match parse_feature_ranks(&ranks) {
+            Ok(ranks) => return ranks,
+            Err(e) => GPS_ERROR!("Invalid ranks in the profile {}: {}", name, e),
+        }
+    }
+    if !is_builtin_profile(&name) {
+        GPS_WARN!("The rank profile {} does not exist", name);
+        return vec![];
+    }
+    builtin_profiles().remove(&name).unwrap_or_default()
+}
+
+/// Force the ranks on the features of the registry, after restoring the ranks changed
+/// by a previous call. Return the features which are not in the registry.
+pub fn apply_feature_ranks(ranks: &[FeatureRank]) -> Vec<String> {
+    let mut original_ranks = ORIGINAL_RANKS.lock().unwrap_or_else(|e| e.into_inner());
+    let original_ranks = original_ranks.get_or_insert_with(HashMap::new);
+    for (feature, rank) in original_ranks.iter() {
+        ElementInfo::element_update_rank(feature, gst::Rank::from(*rank));
+    }
+    let mut missing = vec![];
+    for feature_rank in ranks {
+        let Some(feature) = ElementInfo::element_feature(&feature_rank.feature) else {
+            GPS_WARN!(
+                "Unable to override the rank of {}: no such feature",
+                feature_rank.feature
+            );
+            missing.push(feature_rank.feature.clone());
+            continue;
+        };
+        original_ranks
+            .entry(feature_rank.feature.clone())
+            .or_insert_with(|| feature.rank().into());
+        feature.set_rank(gst::Rank::from(feature_rank.rank));
+    }
+    missing
+}
@@ -0,0 +1,120 @@
+// substitute.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Substitution of the factory of an element.
+//!
+//! Replacing `x264enc` by `vaapih264enc` keeps the place of the element in the graph:
+//! the candidates are the factories with pads in the same directions accepting the
+//! caps of the replaced element, the properties are carried over when the new factory
+//! has a property with the same name accepting the value, and the links are moved to
+//! the ports matching by name, direction and caps.
+
+use crate::gps::{ElementInfo, PadInfo};
+use crate::graphmanager as GM;
+use crate::graphmanager::PropertyExt;
+
+use gst::glib;
+use gst::prelude::*;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Union of the caps of the ports of a node in one direction, `None` if it has no port
+/// in this direction.
+fn node_caps(node: &GM::Node, direction: GM::PortDirection) -> Option<gst::Caps> {
+    let ports = node.all_ports(direction);
+    if ports.is_empty() {
+        return None;
+    }
+    let mut caps = gst::Caps::new_empty();
+    for port in ports {
+        let port_caps = PropertyExt::property(&port, "_caps")
+            .and_then(|caps| gst::Caps::from_str(&caps).ok())
+            .unwrap_or_else(gst::Caps::new_any);
+        caps.merge(port_caps);
+    }
+    Some(caps)
+}
+
+/// Check that the pads of a factory in one direction exist if and only if the node has
+/// ports in this direction, and accept their caps.
+fn pads_accept(factory_caps: &str, node_caps: &Option<gst::Caps>) -> bool {
+    let factory_caps = gst::Caps::from_str(factory_caps).unwrap_or_else(|_| gst::Caps::new_empty());
+    match node_caps {
+        Some(node_caps) => factory_caps.can_intersect(node_caps),
+        None => factory_caps.is_empty(),
+    }
+}
+
+/// Factories able to replace the element of a node, by decreasing rank then by name.
+pub fn replacement_candidates(node: &GM::Node) -> Vec<ElementInfo> {
+    let sink_caps = node_caps(node, GM::PortDirection::Input);
+    let src_caps = node_caps(node, GM::PortDirection::Output);
+    let mut candidates: Vec<ElementInfo> = ElementInfo::elements_list()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|element| {
+            element.name != node.name()
+                && pads_accept(&element.sink_caps, &sink_caps)
+                && pads_accept(&element.src_caps, &src_caps)
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.rank.cmp(&a.rank).then_with(|| a.name.cmp(&b.name)));
+    candidates
+}
+
+/// Properties of the replaced element which can be set on the new factory: it has a
+/// writable property with the same name and the value can be read as its type.
+pub fn carried_properties(
+    factory: &str,
+    properties: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let Ok(params) = ElementInfo::element_properties_by_feature_name(factory) else {
+        return HashMap::new();
+    };
+    properties
+        .iter()
+        .filter(|(name, value)| {
+            params.get(name.as_str()).is_some_and(|param| {
+                param.flags().contains(glib::ParamFlags::WRITABLE)
+                    && glib::Value::deserialize(value.trim_matches('"'), param.value_type()).is_ok()
+            })
+        })
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+/// For each replaced port, given by its name and caps, the index of the new port taking
+/// its links: the free port with the same name and compatible caps, else the first free
+/// port with compatible caps.
+pub fn match_ports(
+    old_ports: &[(String, String)],
+    new_ports: &[(String, String)],
+) -> Vec<Option<usize>> {
+    let mut used = vec![false; new_ports.len()];
+    let mut matches = vec![None; old_ports.len()];
+    for same_name in [true, false] {
+        for (old_index, (old_name, old_caps)) in old_ports.iter().enumerate() {
+            if matches[old_index].is_some() {
+                continue;
+            }
+            let found = new_ports
+                .iter()
+                .zip(&used)
+                .position(|((name, caps), used)| {
+                    !used
+                        && (!same_name || name == old_name)
+                        && PadInfo::caps_compatible(caps, old_caps)
+                });
+            if let Some(index) = found {
+                used[index] = true;
+                matches[old_index] = Some(index);
+            }
+        }
+    }
+    matches
+}
@@ -170,6 +170,133 @@ mod element_test {
             }
         });
     }
+
+    #[test]
+    fn test_inspect_element() {
+        test_synced(|| {
+            use crate::gps::inspect;
+            use std::str::FromStr;
+
+            let section = |inspection: &inspect::ElementInspection, title: &str| {
+                inspection
+                    .sections
+                    .iter()
+                    .find(|section| section.title == title)
+                    .cloned()
+                    .unwrap()
+            };
+
+            let inspection = inspect::inspect_element("identity").unwrap();
+            let pads = section(&inspection, "Pad templates");
+            let names: Vec<&str> = pads.entries.iter().map(|e| e.name.as_str()).collect();
+            assert!(names.contains(&"sink"));
+            assert!(names.contains(&"src"));
+            assert!(pads.entries[0]
+                .details
+                .iter()
+                .any(|detail| detail.starts_with("Availability")));
+
+            let properties = section(&inspection, "Properties");
+            let silent = properties
+                .entries
+                .iter()
+                .find(|e| e.name == "silent")
+                .unwrap();
+            assert!(silent.details.contains(&"Type: gboolean".to_string()));
+            assert!(silent
+                .details
+                .iter()
+                .any(|d| d.starts_with("Flags: readable")));
+
+            let hierarchy = section(&inspection, "Hierarchy");
+            assert_eq!(hierarchy.entries[0].details[0], "GObject");
+            assert!(hierarchy.entries[0]
+                .details
+                .last()
+                .unwrap()
+                .ends_with("GstIdentity"));
+
+            let signals = section(&inspection, "Signals");
+            assert!(signals.entries.iter().any(|e| e.name == "handoff"));
+            assert!(signals.entries.iter().any(|e| e.matches("HANDOFF")));
+            assert!(inspection.to_text().contains("Pad templates:\n  sink"));
+
+            let inspection = inspect::inspect_element("filesrc").unwrap();
+            let interfaces = section(&inspection, "Implemented interfaces");
+            assert!(interfaces.entries.iter().any(|e| e.name == "GstURIHandler"));
+
+            assert!(inspect::inspect_element("nonexistentelement12345").is_err());
+
+            let caps = gst::Caps::from_str("video/x-raw, format=I420, width=320").unwrap();
+            assert_eq!(
+                inspect::pretty_caps(&caps),
+                vec!["video/x-raw", "  format: I420", "  width: 320"]
+            );
+        });
+    }
+
+    #[test]
+    fn test_element_filter_facets() {
+        test_synced(|| {
+            use crate::gps::{ElementClass, ElementFilter, FilterableElement};
+            use crate::graphmanager::PortDirection;
+
+            let elements: Vec<FilterableElement> = ElementInfo::elements_list()
+                .unwrap()
+                .into_iter()
+                .map(FilterableElement::from)
+                .collect();
+            let element = |name: &str| elements.iter().find(|e| e.info.name == name).unwrap();
+            let videotestsrc = element("videotestsrc");
+            let fakesink = element("fakesink");
+            assert!(videotestsrc.info.klass.contains("Source"));
+            assert!(!videotestsrc.info.long_name.is_empty());
+
+            let mut filter = ElementFilter::default();
+            assert!(filter.is_empty());
+            assert!(filter.matches(videotestsrc));
+
+            // Text search over the metadata, case insensitive
+            filter.text = "TEST".to_string();
+            assert!(filter.matches(videotestsrc));
+            assert!(!filter.matches(fakesink));
+            filter.text.clear();
+
+            filter.class = Some(ElementClass::Sink);
+            assert!(!filter.matches(videotestsrc));
+            assert!(filter.matches(fakesink));
+            filter.class = None;
+
+            filter.min_rank = 256;
+            assert!(!filter.matches(fakesink));
+            filter.min_rank = 0;
+
+            filter.plugin = Some(videotestsrc.info.plugin_name.clone());
+            assert!(filter.matches(videotestsrc));
+            assert!(!filter.matches(fakesink));
+            filter.plugin = None;
+
+            filter
+                .set_caps(PortDirection::Output, "video/x-raw, format=I420")
+                .unwrap();
+            assert!(filter.matches(videotestsrc));
+            filter
+                .set_caps(PortDirection::Output, "audio/x-raw")
+                .unwrap();
+            assert!(!filter.matches(videotestsrc));
+            // fakesink accepts any caps but produces none
+            filter
+                .set_caps(PortDirection::Input, "audio/x-raw")
+                .unwrap();
+            assert!(filter.matches(fakesink));
+            assert!(!filter.matches(videotestsrc));
+            assert!(filter
+                .set_caps(PortDirection::Input, "video/x-raw, width=(int)abc")
+                .is_err());
+            filter.set_caps(PortDirection::Input, "").unwrap();
+            assert!(filter.is_empty());
+        });
+    }
 }
 
 #[cfg(test)]
@@ -228,6 +355,28 @@ mod player_test {
         });
     }
 
+    #[test]
+    fn test_player_ptr_eq() {
+        test_synced(|| {
+            let player = Player::new().unwrap();
+            let other = Player::new().unwrap();
+            assert!(player.ptr_eq(&player.clone()));
+            assert!(!player.ptr_eq(&other));
+        });
+    }
+
+    #[test]
+    fn test_shared_clock_without_pipeline() {
+        test_synced(|| {
+            let player = Player::new().unwrap();
+            let clock = gst::SystemClock::obtain();
+            player.set_shared_clock(Some((clock, gst::ClockTime::from_seconds(1))));
+            // The clock is only applied when a pipeline is started
+            assert!(player.clock_and_base_time().is_none());
+            player.set_shared_clock(None);
+        });
+    }
+
     #[test]
     fn test_position_without_pipeline() {
         test_synced(|| {
@@ -754,6 +903,99 @@ mod caps_test {
     }
 }
 
+// =============================================================================
+// Link caps filter tests
+// =============================================================================
+
+#[cfg(test)]
+mod caps_filter_test {
+    use super::*;
+    use crate::gps::{CapsField, CapsFilter};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_caps_filter_parse_fields_and_features() {
+        test_synced(|| {
+            let filter = CapsFilter::parse(
+                "video/x-raw(memory:DMABuf), format={ NV12, I420 }, width=[ 1, 1920 ], framerate=30/1",
+            )
+            .expect("caps filter should parse");
+            assert_eq!(filter.media_type, "video/x-raw");
+            assert_eq!(filter.features, vec!["memory:DMABuf".to_string()]);
+            assert_eq!(filter.fields.len(), 3);
+            assert_eq!(filter.fields[0].name, "format");
+            assert_eq!(filter.fields[0].value_type, "string");
+            assert_eq!(filter.fields[0].value, "{ NV12, I420 }");
+            assert_eq!(filter.fields[1].value_type, "int");
+            assert_eq!(filter.fields[1].value, "[ 1, 1920 ]");
+            assert_eq!(filter.fields[2].value_type, "fraction");
+            assert_eq!(filter.fields[2].value, "30/1");
+
+            // The description built from the fields describes the same caps
+            let caps = filter.caps().expect("caps filter should be valid");
+            let original = gst::Caps::from_str(
+                "video/x-raw(memory:DMABuf), format={ NV12, I420 }, width=[ 1, 1920 ], framerate=30/1",
+            )
+            .unwrap();
+            assert!(caps.is_strictly_equal(&original));
+            assert_eq!(CapsFilter::parse(&filter.to_string()).unwrap(), filter);
+        });
+    }
+
+    #[test]
+    fn test_caps_filter_empty_and_invalid() {
+        test_synced(|| {
+            let filter = CapsFilter::parse("").expect("empty filter should parse");
+            assert!(filter.is_empty());
+            assert!(filter.caps().is_err());
+
+            assert!(CapsFilter::parse("invalid/caps/string!").is_err());
+            assert!(CapsFilter::parse("video/x-raw; audio/x-raw").is_err());
+
+            let filter = CapsFilter {
+                media_type: "video/x-raw".to_string(),
+                features: vec![],
+                fields: vec![CapsField::new("width", "int", "not a number")],
+            };
+            assert!(filter.caps().is_err());
+        });
+    }
+
+    #[test]
+    fn test_caps_filter_check_pads() {
+        test_synced(|| {
+            let filter = CapsFilter::parse("video/x-raw, format=I420").unwrap();
+            assert!(filter
+                .check_pads(
+                    Some("video/x-raw"),
+                    Some("video/x-raw, format={ I420, NV12 }")
+                )
+                .is_ok());
+            assert!(filter.check_pads(None, Some("ANY")).is_ok());
+            assert!(filter
+                .check_pads(Some("video/x-raw"), Some("audio/x-raw"))
+                .is_err());
+            assert!(filter
+                .check_pads(Some("video/x-raw, format=NV12"), None)
+                .is_err());
+        });
+    }
+
+    #[test]
+    fn test_caps_filter_check_description() {
+        test_synced(|| {
+            // Caps with several structures can not be split but are still checked
+            let description = "video/x-raw, format=I420; video/x-raw, format=NV12";
+            assert!(CapsFilter::parse(description).is_err());
+            assert!(CapsFilter::check_description(description, Some("video/x-raw"), None).is_ok());
+            assert!(CapsFilter::check_description(description, None, Some("audio/x-raw")).is_err());
+            assert!(
+                CapsFilter::check_description("video/x-raw, width=(int)abc", None, None).is_err()
+            );
+        });
+    }
+}
+
 /// Tests for GStreamer element default value validation.
 ///
 /// These tests scan all elements and check that ParamSpec defaults match actual values.
@@ -2101,3 +2343,1345 @@ mod websocket_test {
         assert!(matches!(result, Err(WebSocketError::Cancelled)));
     }
 }
+
+// =============================================================================
+// GStreamer debug log tests
+// =============================================================================
+
+#[cfg(test)]
+mod debug_log_test {
+    use super::*;
+    use crate::gps::debug_log::{self, GstLogRecord};
+
+    #[test]
+    fn test_debug_level_from_str() {
+        test_synced(|| {
+            assert_eq!(
+                debug_log::debug_level_from_str("WARN"),
+                Some(gst::DebugLevel::Warning)
+            );
+            assert_eq!(
+                debug_log::debug_level_from_str("warning"),
+                Some(gst::DebugLevel::Warning)
+            );
+            assert_eq!(
+                debug_log::debug_level_from_str("5"),
+                Some(gst::DebugLevel::Debug)
+            );
+            assert_eq!(
+                debug_log::debug_level_from_str("9"),
+                Some(gst::DebugLevel::Memdump)
+            );
+            assert_eq!(debug_log::debug_level_from_str("8"), None);
+            assert_eq!(debug_log::debug_level_from_str("verbose"), None);
+        });
+    }
+
+    #[test]
+    fn test_parse_gst_debug_line_with_object() {
+        test_synced(|| {
+            let line = "0:00:00.123456789 12345 0x55d5c6a0e0 WARN           videotestsrc gstvideotestsrc.c:1234:gst_video_test_src_fill:<videotestsrc0> could not fill";
+            let record = GstLogRecord::from_gst_debug_line(line).expect("Valid line");
+            assert_eq!(record.timestamp, "0:00:00.123456789");
+            assert_eq!(record.pid, 12345);
+            assert_eq!(record.thread, "0x55d5c6a0e0");
+            assert_eq!(record.level, "WARN");
+            assert_eq!(record.category, "videotestsrc");
+            assert_eq!(record.file, "gstvideotestsrc.c");
+            assert_eq!(record.line, 1234);
+            assert_eq!(record.function, "gst_video_test_src_fill");
+            assert_eq!(record.object, "videotestsrc0");
+            assert_eq!(record.message, "could not fill");
+        });
+    }
+
+    #[test]
+    fn test_parse_gst_debug_line_without_object() {
+        test_synced(|| {
+            let line = "0:00:00.000001000  4242 0x1 INFO                GST_INIT gst.c:605:init_pre: Initializing GStreamer Core Library";
+            let record = GstLogRecord::from_gst_debug_line(line).expect("Valid line");
+            assert_eq!(record.category, "GST_INIT");
+            assert_eq!(record.location(), "gst.c:605:init_pre");
+            assert!(record.object.is_empty());
+            assert_eq!(record.message, "Initializing GStreamer Core Library");
+
+            // Lines which are not GStreamer debug messages are skipped
+            assert!(GstLogRecord::from_gst_debug_line("Setting pipeline to PAUSED ...").is_none());
+            assert!(GstLogRecord::from_gst_debug_line("").is_none());
+        });
+    }
+
+    #[test]
+    fn test_gst_debug_line_roundtrip() {
+        test_synced(|| {
+            let mut record = GstLogRecord {
+                timestamp: String::from("0:00:01.500000000"),
+                pid: 7,
+                thread: String::from("0x7f00"),
+                level: String::from("DEBUG"),
+                category: String::from("GST_PADS"),
+                object: String::from("queue0:src"),
+                message: String::from("pushing buffer: size 42"),
+                ..Default::default()
+            };
+            record.set_location("gstpad.c:4000:gst_pad_push");
+            let line = record.to_gst_debug_line();
+            assert_eq!(GstLogRecord::from_gst_debug_line(&line), Some(record));
+        });
+    }
+
+    #[test]
+    fn test_threshold_string_with() {
+        test_synced(|| {
+            assert_eq!(
+                debug_log::threshold_string_with("", "videotestsrc", gst::DebugLevel::Debug),
+                "videotestsrc:DEBUG"
+            );
+            assert_eq!(
+                debug_log::threshold_string_with(
+                    "*:2,videotestsrc:5",
+                    "videotestsrc",
+                    gst::DebugLevel::Log
+                ),
+                "*:2,videotestsrc:LOG"
+            );
+            assert_eq!(
+                debug_log::threshold_string_with("3", "GST_PADS", gst::DebugLevel::Error),
+                "*:3,GST_PADS:ERROR"
+            );
+            // The default threshold added last stays first to not override the others
+            assert_eq!(
+                debug_log::threshold_string_with("videotestsrc:5", "*", gst::DebugLevel::Warning),
+                "*:WARNING,videotestsrc:5"
+            );
+        });
+    }
+
+    #[test]
+    fn test_debug_categories_list() {
+        test_synced(|| {
+            let categories = debug_log::debug_categories();
+            assert!(!categories.is_empty());
+            assert!(categories.iter().any(|c| c.name == "GST_PADS"));
+            // Sorted by name
+            assert!(categories.windows(2).all(|w| w[0].name <= w[1].name));
+        });
+    }
+}
+
+#[cfg(test)]
+mod latency_test {
+    use super::*;
+    use crate::gps::latency::{self, Latency};
+
+    #[test]
+    fn test_latency_description() {
+        test_synced(|| {
+            let latency = Latency {
+                live: true,
+                min: gst::ClockTime::from_mseconds(20),
+                max: None,
+            };
+            assert_eq!(
+                latency.description(),
+                "live: true, min: 0:00:00.020000000, max: none"
+            );
+        });
+    }
+
+    #[test]
+    fn test_player_latency_setting() {
+        test_synced(|| {
+            let player = Player::new().unwrap();
+            assert!(player.latency().is_none());
+            player.set_latency(Some(gst::ClockTime::from_mseconds(200)));
+            assert_eq!(player.latency(), Some(gst::ClockTime::from_mseconds(200)));
+            player.set_latency(None);
+            assert!(player.latency().is_none());
+            // No pipeline running, nothing to query
+            assert!(player.pipeline_latency().is_none());
+        });
+    }
+
+    #[test]
+    fn test_element_latency_query() {
+        test_synced(|| {
+            let pipeline =
+                gst::parse::launch("fakesrc name=src ! queue name=q ! fakesink name=sink")
+                    .unwrap()
+                    .downcast::<gst::Pipeline>()
+                    .unwrap();
+            pipeline.set_state(gst::State::Paused).unwrap();
+            let _ = pipeline.state(gst::ClockTime::from_seconds(5));
+
+            let latency = latency::pipeline_latency(&pipeline).expect("pipeline latency");
+            assert!(!latency.live);
+            let queue = pipeline.by_name("q").unwrap();
+            assert!(latency::element_latency(&queue).is_some());
+
+            pipeline.set_state(gst::State::Null).unwrap();
+        });
+    }
+}
+
+#[cfg(test)]
+mod interlink_test {
+    use super::*;
+    use crate::gps::interlink::{self, InterLinkKind};
+    use crate::graphmanager::{GraphView, NodeType, PortDirection, PropertyExt};
+
+    fn add_inter_link(port: &crate::graphmanager::Port, direction: PortDirection) {
+        for (name, value) in
+            interlink::inter_link_properties(InterLinkKind::Video, "gps-test", "peer", direction)
+        {
+            port.add_property(&name, &value);
+        }
+    }
+
+    #[test]
+    fn test_inter_link_kind_from_caps() {
+        test_synced(|| {
+            assert_eq!(
+                InterLinkKind::from_caps("video/x-raw, format=I420"),
+                InterLinkKind::Video
+            );
+            assert_eq!(
+                InterLinkKind::from_caps("audio/x-raw"),
+                InterLinkKind::Audio
+            );
+            assert_eq!(
+                InterLinkKind::from_caps("video/x-h264"),
+                InterLinkKind::Proxy
+            );
+        });
+    }
+
+    #[test]
+    fn test_inter_link_sender_description() {
+        test_synced(|| {
+            let graphview = GraphView::new();
+            let source = graphview.create_node_with_port("videotestsrc", NodeType::Source, 0, 1);
+            graphview.add_node(source.clone());
+            let port = source.all_ports(PortDirection::Output)[0].clone();
+            add_inter_link(&port, PortDirection::Output);
+            assert_eq!(port.stub_label().as_deref(), Some("→ peer"));
+
+            let player = Player::new().unwrap();
+            let description = player.pipeline_description_from_graphview(&graphview);
+            assert!(description
+                .contains("! intervideosink name=gps_inter_sink_gps-test channel=gps-test"));
+        });
+    }
+
+    #[test]
+    fn test_inter_link_receiver_description() {
+        test_synced(|| {
+            let graphview = GraphView::new();
+            let sink = graphview.create_node_with_port("fakesink", NodeType::Sink, 1, 0);
+            graphview.add_node(sink.clone());
+            let port = sink.all_ports(PortDirection::Input)[0].clone();
+            add_inter_link(&port, PortDirection::Input);
+
+            let player = Player::new().unwrap();
+            let description = player.pipeline_description_from_graphview(&graphview);
+            assert!(description.contains(&format!(
+                "intervideosrc name=gps_inter_src_gps-test channel=gps-test ! {}.",
+                sink.unique_name()
+            )));
+            assert!(description.contains(&format!("fakesink name={}", sink.unique_name())));
+        });
+    }
+}
+
+#[cfg(test)]
+mod capture_test {
+    use super::*;
+    use crate::gps::capture::{self, RECORDING_PRESETS};
+
+    #[test]
+    fn test_recording_location_extension() {
+        test_synced(|| {
+            for preset in RECORDING_PRESETS.iter() {
+                let location = capture::default_recording_location(preset);
+                assert!(location.ends_with(&format!(".{}", preset.extension)));
+            }
+        });
+    }
+
+    #[test]
+    fn test_snapshot_sinks() {
+        test_synced(|| {
+            let pipeline = gst::parse::launch("videotestsrc ! fakesink name=sink")
+                .unwrap()
+                .downcast::<gst::Pipeline>()
+                .unwrap();
+            let sinks = capture::snapshot_sinks(&pipeline);
+            assert_eq!(sinks.len(), 1);
+            assert_eq!(sinks[0].name(), "sink");
+            // No sample has been rendered yet
+            let location = gtk::glib::tmp_dir().join("gps-snapshot.png");
+            assert!(
+                capture::snapshot_to_png(&sinks[0], &location.to_string_lossy(), |_| {}).is_err()
+            );
+        });
+    }
+
+    #[test]
+    fn test_start_stop_recording() {
+        test_synced(|| {
+            use std::cell::RefCell;
+            use std::rc::Rc;
+
+            let pipeline = gst::parse::launch("videotestsrc is-live=true name=src ! fakesink")
+                .unwrap()
+                .downcast::<gst::Pipeline>()
+                .unwrap();
+            pipeline.set_state(gst::State::Playing).unwrap();
+            // Motion JPEG / AVI only needs elements from the good plugins
+            let preset = &RECORDING_PRESETS[4];
+            let location = gtk::glib::tmp_dir()
+                .join(format!("gps-recording-test.{}", preset.extension))
+                .to_string_lossy()
+                .to_string();
+            let recording =
+                capture::Recording::start(&pipeline, "src", "src", preset, &location).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(500));
+
+            let result = Rc::new(RefCell::new(None));
+            let stopped = result.clone();
+            recording
+                .stop(move |r| {
+                    stopped.replace(Some(r));
+                })
+                .unwrap();
+            let context = gtk::glib::MainContext::default();
+            while result.borrow().is_none() {
+                context.iteration(true);
+            }
+            let recorded = result.take().unwrap().unwrap();
+            assert_eq!(recorded, location);
+            assert!(std::fs::metadata(&location).unwrap().len() > 0);
+            // The branch and the inserted tee are removed once the source is idle
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+            while pipeline.children().len() > 2 && std::time::Instant::now() < deadline {
+                context.iteration(false);
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            assert_eq!(pipeline.children().len(), 2);
+            pipeline.set_state(gst::State::Null).unwrap();
+            let _ = std::fs::remove_file(&location);
+        });
+    }
+
+    #[test]
+    fn test_recording_needs_video() {
+        test_synced(|| {
+            let pipeline = gst::parse::launch("audiotestsrc is-live=true name=src ! fakesink")
+                .unwrap()
+                .downcast::<gst::Pipeline>()
+                .unwrap();
+            pipeline.set_state(gst::State::Playing).unwrap();
+            let location = gtk::glib::tmp_dir()
+                .join("gps-recording-audio.avi")
+                .to_string_lossy()
+                .to_string();
+            assert!(capture::Recording::start(
+                &pipeline,
+                "src",
+                "src",
+                &RECORDING_PRESETS[4],
+                &location
+            )
+            .is_err());
+            // Nothing has been added to the pipeline
+            assert_eq!(pipeline.children().len(), 2);
+            pipeline.set_state(gst::State::Null).unwrap();
+        });
+    }
+}
+
+#[cfg(test)]
+mod validation_test {
+    use super::*;
+    use crate::gps::validation::{self, DiagnosticSeverity, DiagnosticTarget, QuickFix};
+    use crate::graphmanager::{GraphView, NodeType, PortDirection, PropertyExt};
+
+    fn link_nodes(graphview: &GraphView, node_from: u32, node_to: u32) -> u32 {
+        let port_from = graphview
+            .node(node_from)
+            .unwrap()
+            .all_ports(PortDirection::Output)[0]
+            .id();
+        let port_to = graphview
+            .node(node_to)
+            .unwrap()
+            .all_ports(PortDirection::Input)[0]
+            .id();
+        let link = graphview.create_link(node_from, node_to, port_from, port_to);
+        let link_id = link.id();
+        graphview.add_link(link);
+        link_id
+    }
+
+    #[test]
+    fn test_validation_reports_caps_properties_and_unknown_elements() {
+        test_synced(|| {
+            let graphview = GraphView::new();
+            let source = graphview.create_node_with_port("videotestsrc", NodeType::Source, 0, 1);
+            source.add_property("pattern", "not-a-pattern");
+            source.add_property("not-a-property", "1");
+            source.all_ports(PortDirection::Output)[0]
+                .add_property("_caps", "video/x-raw, format=I420");
+            graphview.add_node(source.clone());
+            let sink = graphview.create_node_with_port("fakesink", NodeType::Sink, 1, 0);
+            sink.all_ports(PortDirection::Input)[0]
+                .add_property("_caps", "video/x-raw, format=RGBA");
+            graphview.add_node(sink.clone());
+            let link_id = link_nodes(&graphview, source.id(), sink.id());
+            let unknown = graphview.create_node("gps-unknown-element", NodeType::Transform);
+            graphview.add_node(unknown.clone());
+
+            let diagnostics = validation::validate_graph(&graphview);
+            assert!(diagnostics
+                .iter()
+                .all(|d| d.severity == DiagnosticSeverity::Error));
+
+            let caps_mismatch = diagnostics
+                .iter()
+                .find(|d| d.target == DiagnosticTarget::Link(link_id))
+                .expect("caps mismatch should be reported on the link");
+            assert_eq!(
+                caps_mismatch.fix,
+                Some(QuickFix::InsertElement {
+                    link_id,
+                    factory: "videoconvert".to_string()
+                })
+            );
+
+            let unknown_element = diagnostics
+                .iter()
+                .find(|d| d.target == DiagnosticTarget::Node(unknown.id()))
+                .expect("unknown factory should be reported");
+            assert_eq!(
+                unknown_element.fix,
+                Some(QuickFix::RemoveNode(unknown.id()))
+            );
+
+            let property_errors = diagnostics
+                .iter()
+                .filter(|d| d.target == DiagnosticTarget::Node(source.id()))
+                .count();
+            assert_eq!(property_errors, 2);
+        });
+    }
+
+    #[test]
+    fn test_validation_reports_cycles_names_and_missing_sink() {
+        test_synced(|| {
+            let graphview = GraphView::new();
+            let first = graphview.create_node_with_port("identity", NodeType::Transform, 1, 1);
+            graphview.add_node(first.clone());
+            let second = graphview.create_node_with_port("identity", NodeType::Transform, 1, 1);
+            second.set_unique_name(&first.unique_name());
+            graphview.add_node(second.clone());
+            link_nodes(&graphview, first.id(), second.id());
+            let back_link = link_nodes(&graphview, second.id(), first.id());
+
+            let diagnostics = validation::validate_graph(&graphview);
+            assert!(diagnostics
+                .iter()
+                .any(|d| d.target == DiagnosticTarget::Graph));
+            assert!(diagnostics
+                .iter()
+                .any(|d| d.target == DiagnosticTarget::Link(back_link)));
+            assert!(diagnostics
+                .iter()
+                .any(|d| d.target == DiagnosticTarget::Node(second.id())));
+            assert!(!diagnostics
+                .iter()
+                .any(|d| d.target == DiagnosticTarget::Node(first.id())));
+        });
+    }
+}
+
+#[cfg(test)]
+mod macro_test {
+    use super::*;
+    use crate::gps::validation;
+    use crate::graphmanager::{
+        GraphTemplate, GraphView, NodeType, PortDirection, PortPresence, PropertyExt,
+        TemplateParameter,
+    };
+
+    fn add_element(graphview: &GraphView, name: &str, node_type: NodeType) -> u32 {
+        let mut node = graphview.create_node(name, node_type.clone());
+        if node_type != NodeType::Source {
+            let port = graphview.create_port("sink", PortDirection::Input, PortPresence::Always);
+            graphview.add_port_to_node(&mut node, port);
+        }
+        if node_type != NodeType::Sink {
+            let port = graphview.create_port("src", PortDirection::Output, PortPresence::Always);
+            graphview.add_port_to_node(&mut node, port);
+        }
+        let node_id = node.id();
+        graphview.add_node(node);
+        node_id
+    }
+
+    fn link_nodes(graphview: &GraphView, node_from: u32, node_to: u32) {
+        let port_from = graphview
+            .node(node_from)
+            .unwrap()
+            .all_ports(PortDirection::Output)[0]
+            .id();
+        let port_to = graphview
+            .node(node_to)
+            .unwrap()
+            .all_ports(PortDirection::Input)[0]
+            .id();
+        graphview.add_link(graphview.create_link(node_from, node_to, port_from, port_to));
+    }
+
+    #[test]
+    fn test_macro_node_expanded_into_bin() {
+        test_synced(|| {
+            let graphview = GraphView::new();
+            let source = add_element(&graphview, "videotestsrc", NodeType::Source);
+            graphview
+                .node(source)
+                .unwrap()
+                .add_property("num-buffers", "5");
+            let convert = add_element(&graphview, "videoconvert", NodeType::Transform);
+            let scale = add_element(&graphview, "videoscale", NodeType::Transform);
+            let sink = add_element(&graphview, "fakesink", NodeType::Sink);
+            link_nodes(&graphview, source, convert);
+            link_nodes(&graphview, convert, scale);
+            link_nodes(&graphview, scale, sink);
+            graphview.clear_undo_history();
+
+            let macro_id = graphview
+                .collapse_into_macro("scaler", &[convert, scale])
+                .expect("Should be able to group the nodes");
+            assert_eq!(graphview.all_nodes(NodeType::All).len(), 3);
+            // The grouping is undone in a single step
+            assert_eq!(graphview.undo_count(), 1);
+            assert!(graphview.undo());
+            assert_eq!(graphview.all_nodes(NodeType::All).len(), 4);
+            assert_eq!(graphview.all_links(true).len(), 3);
+            assert!(graphview.redo());
+            assert_eq!(graphview.all_nodes(NodeType::All).len(), 3);
+            let macro_node = graphview.node(macro_id).unwrap();
+            assert_eq!(macro_node.node_type(), Some(&NodeType::Macro));
+            assert_eq!(macro_node.all_ports(PortDirection::Input).len(), 1);
+            assert_eq!(macro_node.all_ports(PortDirection::Output).len(), 1);
+            assert!(graphview.node_is_linked(source).is_some());
+            assert!(graphview.node_is_linked(sink).is_some());
+
+            // Forward a parameter of the macro to the inner videoscale
+            let mut template = GraphTemplate::from_macro_node(&macro_node).unwrap();
+            template.parameters.push(TemplateParameter {
+                name: "method".to_string(),
+                node: scale,
+                property: "method".to_string(),
+            });
+            graphview
+                .set_macro_template(macro_id, &template)
+                .expect("Should be able to update the macro template");
+            assert!(graphview.undo());
+            assert!(GraphTemplate::from_macro_node(&macro_node)
+                .unwrap()
+                .parameters
+                .is_empty());
+            assert!(graphview.redo());
+            assert_eq!(
+                GraphTemplate::from_macro_node(&macro_node)
+                    .unwrap()
+                    .parameters
+                    .len(),
+                1
+            );
+            macro_node.add_property("method", "1");
+
+            let player = Player::new().unwrap();
+            let description = player.pipeline_description_from_graphview(&graphview);
+            let unique_name = macro_node.unique_name();
+            assert!(description.contains(&format!("bin.( name={unique_name} ")));
+            assert!(description.contains(&format!(
+                "videoscale name={unique_name}_videoscale{scale} method=1 "
+            )));
+            assert!(description.contains(&format!("! {unique_name}_videoconvert{convert}.sink ")));
+            assert!(
+                description.contains(&format!("{unique_name}_videoscale{scale}.src ! fakesink"))
+            );
+            assert!(gst::parse::launch(&description).is_ok());
+
+            assert!(validation::validate_graph(&graphview).is_empty());
+        });
+    }
+
+    #[test]
+    fn test_macro_node_rejects_nested_macros() {
+        test_synced(|| {
+            let graphview = GraphView::new();
+            let convert = add_element(&graphview, "videoconvert", NodeType::Transform);
+            let macro_id = graphview
+                .collapse_into_macro("convert", &[convert])
+                .unwrap();
+            assert!(graphview
+                .collapse_into_macro("nested", &[macro_id])
+                .is_err());
+        });
+    }
+}
+
+#[cfg(test)]
+mod rank_test {
+    use super::*;
+    use crate::gps::rank::{self, FeatureRank};
+
+    #[test]
+    fn test_feature_ranks_string() {
+        let ranks = rank::parse_feature_ranks("avdec_h264:MAX, vah264dec:none,foo:257,").unwrap();
+        assert_eq!(
+            ranks,
+            vec![
+                FeatureRank {
+                    feature: "avdec_h264".to_string(),
+                    rank: i32::MAX,
+                },
+                FeatureRank {
+                    feature: "vah264dec".to_string(),
+                    rank: 0,
+                },
+                FeatureRank {
+                    feature: "foo".to_string(),
+                    rank: 257,
+                },
+            ]
+        );
+        assert_eq!(
+            rank::format_feature_ranks(&ranks),
+            "avdec_h264:MAX,vah264dec:NONE,foo:257"
+        );
+        // The last rank of a feature wins
+        let ranks = rank::parse_feature_ranks("foo:NONE,foo:PRIMARY").unwrap();
+        assert_eq!(rank::format_feature_ranks(&ranks), "foo:PRIMARY");
+        assert!(rank::parse_feature_ranks("").unwrap().is_empty());
+        assert!(rank::parse_feature_ranks("foo").is_err());
+        assert!(rank::parse_feature_ranks(":PRIMARY").is_err());
+        assert!(rank::parse_feature_ranks("foo:HIGH").is_err());
+        assert!(rank::parse_feature_ranks("foo:-1").is_err());
+    }
+
+    #[test]
+    fn test_apply_feature_ranks() {
+        test_synced(|| {
+            let rank_of = |name: &str| -> i32 {
+                ElementInfo::element_feature(name)
+                    .expect("Should find the feature")
+                    .rank()
+                    .into()
+            };
+            let fakesink = rank_of("fakesink");
+            let identity = rank_of("identity");
+            let missing = rank::apply_feature_ranks(&[
+                FeatureRank {
+                    feature: "fakesink".to_string(),
+                    rank: 300,
+                },
+                FeatureRank {
+                    feature: "nonexistentelement".to_string(),
+                    rank: 0,
+                },
+            ]);
+            assert_eq!(missing, vec!["nonexistentelement".to_string()]);
+            assert_eq!(rank_of("fakesink"), 300);
+
+            // Switching profile restores the features it does not override
+            rank::apply_feature_ranks(&rank::parse_feature_ranks("identity:MARGINAL").unwrap());
+            assert_eq!(rank_of("fakesink"), fakesink);
+            assert_eq!(rank_of("identity"), 64);
+
+            rank::apply_feature_ranks(&[]);
+            assert_eq!(rank_of("identity"), identity);
+
+            let blacklist = rank::plugin_blacklist("coreelements");
+            assert!(blacklist
+                .iter()
+                .any(|r| r.feature == "fakesink" && r.rank == 0));
+            assert!(rank::plugin_blacklist("nonexistentplugin").is_empty());
+        });
+    }
+
+    #[test]
+    fn test_builtin_profiles() {
+        test_synced(|| {
+            let profiles = rank::builtin_profiles();
+            assert!(profiles.contains_key(rank::PROFILE_SOFTWARE_ONLY));
+            assert!(profiles.contains_key(rank::PROFILE_VAAPI));
+            assert!(profiles.keys().all(|name| rank::is_builtin_profile(name)));
+            assert!(!rank::is_builtin_profile("Custom"));
+            // Built once from the registry
+            assert_eq!(rank::builtin_profiles(), profiles);
+        });
+    }
+}
+
+#[cfg(test)]
+mod missing_test {
+    use super::*;
+    use crate::gps::missing;
+    use crate::graphmanager::{GraphView, NodeType, PortDirection, PropertyExt};
+
+    #[test]
+    fn test_missing_elements_kept_as_placeholders() {
+        test_synced(|| {
+            let graphview = GraphView::new();
+            let sink = graphview.create_node_with_port("fakesink", NodeType::Sink, 1, 0);
+            missing::record_node_plugin(&sink);
+            assert_eq!(
+                PropertyExt::property(&sink, missing::PLUGIN_PROPERTY).as_deref(),
+                Some("coreelements")
+            );
+            graphview.add_node(sink);
+            // A graph saved on a machine providing the element
+            let unknown =
+                graphview.create_node_with_port("gps-unknown-encoder", NodeType::Transform, 1, 1);
+            unknown.add_property("bitrate", "2000");
+            unknown.add_property(missing::PLUGIN_PROPERTY, "unknown");
+            unknown.add_property(missing::PLUGIN_MODULE_PROPERTY, "gst-plugins-unknown");
+            graphview.add_node(unknown.clone());
+
+            let buffer = graphview.render_xml().unwrap();
+            let loaded = GraphView::new();
+            loaded.load_from_xml(buffer).unwrap();
+            let placeholder = loaded
+                .node(unknown.id())
+                .expect("placeholder should be kept");
+            assert_eq!(placeholder.all_ports(PortDirection::All).len(), 2);
+            assert_eq!(
+                PropertyExt::property(&placeholder, "bitrate").as_deref(),
+                Some("2000")
+            );
+
+            let missing = missing::missing_elements(&loaded);
+            assert_eq!(missing.len(), 1);
+            assert_eq!(missing[0].factory, "gps-unknown-encoder");
+            assert_eq!(missing[0].node_ids, vec![unknown.id()]);
+            assert_eq!(
+                missing[0].provider(),
+                "plugin unknown from gst-plugins-unknown"
+            );
+            assert_eq!(
+                missing[0].installer_detail(),
+                "gstreamer|1.0|GstPipelineStudio|GStreamer element gps-unknown-encoder|element-gps-unknown-encoder"
+            );
+        });
+    }
+
+    #[test]
+    fn test_missing_plugin_message_detail() {
+        test_synced(|| {
+            let structure = gst::Structure::builder("missing-plugin")
+                .field("type", "decoder")
+                .field("detail", gst::Caps::builder("video/x-h265").build())
+                .field("name", "H.265 decoder")
+                .build();
+            assert_eq!(
+                missing::missing_plugin_message_detail(&structure).as_deref(),
+                Some("gstreamer|1.0|GstPipelineStudio|H.265 decoder|decoder-video/x-h265")
+            );
+            let structure = gst::Structure::new_empty("other-message");
+            assert!(missing::missing_plugin_message_detail(&structure).is_none());
+        });
+    }
+}
+
+#[cfg(test)]
+mod substitute_test {
+    use super::*;
+    use crate::gps::substitute;
+    use crate::graphmanager::{GraphView, NodeType, PropertyExt};
+    use std::collections::HashMap;
+
+    fn ports(ports: &[(&str, &str)]) -> Vec<(String, String)> {
+        ports
+            .iter()
+            .map(|(name, caps)| (name.to_string(), caps.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_match_ports() {
+        test_synced(|| {
+            let old_ports = ports(&[
+                ("sink_0", "video/x-raw"),
+                ("sink_1", "audio/x-raw"),
+                ("sink_2", "text/x-raw"),
+            ]);
+            // Same name first, then the first free port with compatible caps
+            let new_ports = ports(&[
+                ("sink_0", "audio/x-raw"),
+                ("sink_1", "video/x-raw"),
+                ("sink_2", "ANY"),
+            ]);
+            assert_eq!(
+                substitute::match_ports(&old_ports, &new_ports),
+                vec![Some(1), Some(0), Some(2)]
+            );
+            let new_ports = ports(&[("sink_0", "video/x-raw")]);
+            assert_eq!(
+                substitute::match_ports(&old_ports, &new_ports),
+                vec![Some(0), None, None]
+            );
+            assert_eq!(
+                substitute::match_ports(&old_ports, &[]),
+                vec![None, None, None]
+            );
+        });
+    }
+
+    #[test]
+    fn test_carried_properties() {
+        test_synced(|| {
+            let properties = HashMap::from([
+                ("sync".to_string(), "true".to_string()),
+                ("num-buffers".to_string(), "10".to_string()),
+                ("dump".to_string(), "not a boolean".to_string()),
+                ("location".to_string(), "/tmp/out.raw".to_string()),
+            ]);
+            let carried = substitute::carried_properties("fakesink", &properties);
+            assert_eq!(
+                carried,
+                HashMap::from([
+                    ("sync".to_string(), "true".to_string()),
+                    ("num-buffers".to_string(), "10".to_string()),
+                ])
+            );
+            assert!(substitute::carried_properties("nonexistentelement", &properties).is_empty());
+        });
+    }
+
+    #[test]
+    fn test_replacement_candidates() {
+        test_synced(|| {
+            let graphview = GraphView::new();
+            let sink = graphview.create_node_with_port("fakesink", NodeType::Sink, 1, 0);
+            sink.all_ports(crate::graphmanager::PortDirection::Input)[0]
+                .add_property("_caps", "ANY");
+            let candidates: Vec<String> = substitute::replacement_candidates(&sink)
+                .into_iter()
+                .map(|element| element.name)
+                .collect();
+            assert!(candidates.contains(&"filesink".to_string()));
+            assert!(!candidates.contains(&"fakesink".to_string()));
+            // Elements with a src pad can not replace a sink
+            assert!(!candidates.contains(&"identity".to_string()));
+            assert!(!candidates.contains(&"fakesrc".to_string()));
+        });
+    }
+}
+
+#[cfg(test)]
+mod preset_test {
+    use super::*;
+    use crate::gps::preset::{self, PresetProperties, StudioPresets};
+
+    #[test]
+    fn test_studio_presets_file() {
+        let path = std::env::temp_dir().join(format!("gps-presets-{}.toml", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            StudioPresets::load_from_file(&path).unwrap(),
+            StudioPresets::default()
+        );
+
+        let mut presets = StudioPresets::default();
+        presets.set_preset(
+            "x264enc",
+            "streaming",
+            PresetProperties::from([
+                ("bitrate".to_string(), "2048".to_string()),
+                ("tune".to_string(), "zerolatency".to_string()),
+            ]),
+        );
+        presets.set_preset(
+            "fakesink",
+            "quiet",
+            PresetProperties::from([("silent".to_string(), "true".to_string())]),
+        );
+        presets.save_to_file(&path).unwrap();
+        let loaded = StudioPresets::load_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, presets);
+        assert_eq!(loaded.names("x264enc"), vec!["streaming".to_string()]);
+        assert_eq!(
+            loaded.preset("fakesink", "quiet").unwrap().get("silent"),
+            Some(&"true".to_string())
+        );
+
+        let mut presets = loaded;
+        assert!(presets.remove_preset("fakesink", "quiet"));
+        assert!(!presets.remove_preset("fakesink", "quiet"));
+        assert!(presets.names("fakesink").is_empty());
+    }
+
+    #[test]
+    fn test_preset_argument() {
+        assert_eq!(
+            preset::parse_preset_argument("x264enc: streaming").unwrap(),
+            ("x264enc".to_string(), "streaming".to_string())
+        );
+        assert!(preset::parse_preset_argument("x264enc").is_err());
+        assert!(preset::parse_preset_argument(":streaming").is_err());
+        assert!(preset::parse_preset_argument("x264enc:").is_err());
+    }
+
+    #[test]
+    fn test_element_presets() {
+        test_synced(|| {
+            assert!(!preset::supports_preset("fakesink"));
+            assert!(preset::element_preset_names("fakesink").is_empty());
+            assert!(preset::load_element_preset("fakesink", "any").is_err());
+
+            // The properties of a preset are kept even when they have their default value
+            if !preset::supports_preset("x264enc") {
+                return;
+            }
+            let properties =
+                std::collections::HashMap::from([("bitrate".to_string(), "2048".to_string())]);
+            preset::save_element_preset("x264enc", "gps-test-defaults", &properties).unwrap();
+            let loaded = preset::load_element_preset("x264enc", "gps-test-defaults");
+            preset::delete_element_preset("x264enc", "gps-test-defaults").unwrap();
+            assert_eq!(
+                loaded.unwrap().get("bitrate").map(String::as_str),
+                Some("2048")
+            );
+        });
+    }
+}
+
+#[cfg(test)]
+mod value_test {
+    use super::*;
+    use crate::gps::embed;
+    use crate::gps::validation::{self, DiagnosticSeverity, DiagnosticTarget, QuickFix};
+    use crate::gps::value::{self, PropertyKind};
+    use crate::graphmanager::{GraphTemplate, GraphView, NodeType, PropertyExt};
+    use gtk::graphene;
+
+    #[test]
+    fn test_fraction() {
+        assert_eq!(value::parse_fraction("30/1"), Some((30, 1)));
+        assert_eq!(value::parse_fraction("\"25 / 2\""), Some((25, 2)));
+        assert_eq!(value::parse_fraction("5"), Some((5, 1)));
+        assert_eq!(value::parse_fraction("1/0"), None);
+        assert_eq!(value::parse_fraction("a/b"), None);
+        assert_eq!(value::format_fraction(30000, 1001), "30000/1001");
+    }
+
+    #[test]
+    fn test_array() {
+        assert_eq!(value::split_array("< 1, 2 >"), vec!["1", "2"]);
+        assert_eq!(
+            value::split_array("<\"a, b\", (string)c>"),
+            vec!["\"a, b\"", "(string)c"]
+        );
+        assert_eq!(
+            value::split_array("< < 1, 2 >, { 3, 4 }, [ 5, 6 ] >"),
+            vec!["< 1, 2 >", "{ 3, 4 }", "[ 5, 6 ]"]
+        );
+        assert!(value::split_array("<>").is_empty());
+        assert!(value::split_array("").is_empty());
+        let items = vec!["1".to_string(), "< 2, 3 >".to_string()];
+        assert_eq!(value::format_array(&items), "< 1, < 2, 3 > >");
+        assert_eq!(value::split_array(&value::format_array(&items)), items);
+        assert_eq!(value::format_array(&[]), "<>");
+    }
+
+    #[test]
+    fn test_property_kinds() {
+        test_synced(|| {
+            let capsfilter = ElementInfo::element_properties_by_feature_name("capsfilter").unwrap();
+            assert_eq!(
+                value::property_kind(&capsfilter["caps"]),
+                PropertyKind::Caps
+            );
+            let playbin = ElementInfo::element_properties_by_feature_name("playbin").unwrap();
+            assert_eq!(
+                value::property_kind(&playbin["video-sink"]),
+                PropertyKind::Element
+            );
+            assert_eq!(value::property_kind(&playbin["uri"]), PropertyKind::Other);
+            assert_eq!(
+                value::value_kind(gst::Structure::static_type()),
+                PropertyKind::Structure
+            );
+            assert_eq!(
+                value::value_kind(gst::Array::static_type()),
+                PropertyKind::Array
+            );
+            assert_eq!(
+                value::value_kind(gst::Fraction::static_type()),
+                PropertyKind::Fraction
+            );
+        });
+    }
+
+    #[test]
+    fn test_caps_property_keeps_case() {
+        test_synced(|| {
+            let element = gst::ElementFactory::make("capsfilter").build().unwrap();
+            element.set_property(
+                "caps",
+                gst::Caps::builder("video/x-raw")
+                    .field("format", "NV12")
+                    .build(),
+            );
+            let value = ElementInfo::element_property(&element, "caps").unwrap();
+            assert!(value.contains("format=(string)NV12"), "{}", value);
+        });
+    }
+
+    fn param(element_name: &str, property_name: &str) -> gst::glib::ParamSpec {
+        ElementInfo::element_properties_by_feature_name(element_name).unwrap()[property_name]
+            .clone()
+    }
+
+    #[test]
+    fn test_check_value() {
+        test_synced(|| {
+            let sleep_time = param("identity", "sleep-time");
+            assert!(value::check_value(&sleep_time, "100").is_ok());
+            assert!(value::check_value(&sleep_time, "").is_ok());
+            assert!(value::check_value(&sleep_time, "-1").is_err());
+            assert!(value::check_value(&sleep_time, "abc").is_err());
+
+            let volume = param("audiotestsrc", "volume");
+            assert!(value::check_value(&volume, "0.5").is_ok());
+            let error = value::check_value(&volume, "1.5").unwrap_err().to_string();
+            assert!(error.contains("out of the range [0, 1]"), "{}", error);
+
+            let pattern = param("videotestsrc", "pattern");
+            assert!(value::check_value(&pattern, "ball").is_ok());
+            assert!(value::check_value(&pattern, "0").is_ok());
+            assert!(value::check_value(&pattern, "not-a-pattern").is_err());
+
+            let flags = param("playbin", "flags");
+            assert!(value::check_value(&flags, "video+audio+soft-volume").is_ok());
+            assert!(value::check_value(&flags, "3").is_ok());
+            assert!(value::check_value(&flags, "video+not-a-flag").is_err());
+
+            let caps = param("capsfilter", "caps");
+            assert!(value::check_value(&caps, "video/x-raw, format=NV12").is_ok());
+            assert!(value::check_value(&caps, "video/x-raw, width=(int)abc").is_err());
+        });
+    }
+
+    #[test]
+    fn test_check_location() {
+        test_synced(|| {
+            let location = param("filesrc", "location");
+            let missing = std::env::temp_dir().join("gps-test-missing-file.mp4");
+            let missing = missing.to_str().unwrap();
+            assert!(validation::check_property("filesrc", &location, missing).is_err());
+            let existing = std::env::temp_dir();
+            let existing = existing.to_str().unwrap();
+            assert!(validation::check_property("filesrc", &location, existing).is_ok());
+            // Only the sources read an existing file
+            let location = param("filesink", "location");
+            assert!(validation::check_property("filesink", &location, missing).is_ok());
+        });
+    }
+
+    #[test]
+    fn test_value_hint() {
+        test_synced(|| {
+            assert_eq!(
+                value::value_hint(&param("audiotestsrc", "volume")).as_deref(),
+                Some("From 0 to 1, default 0.8")
+            );
+            assert!(value::value_hint(&param("videotestsrc", "pattern")).is_none());
+        });
+    }
+
+    #[test]
+    fn test_flags_round_trip() {
+        test_synced(|| {
+            let param = param("playbin", "flags");
+            let flags = param
+                .downcast_ref::<gst::glib::ParamSpecFlags>()
+                .unwrap()
+                .flags_class();
+            let mask = value::parse_flags(&flags, "video+audio|soft-volume").unwrap();
+            assert_eq!(mask, 1 | 2 | 16);
+            assert_eq!(value::parse_flags(&flags, "19").unwrap(), mask);
+            assert!(value::parse_flags(&flags, "video+not-a-flag").is_err());
+            let formatted = value::format_flags(&flags, mask);
+            assert_eq!(formatted, "video+audio+soft-volume");
+            assert_eq!(value::parse_flags(&flags, &formatted).unwrap(), mask);
+            // Bits without a nick are kept as a number
+            assert_eq!(
+                value::format_flags(&flags, 1 << 31),
+                (1u32 << 31).to_string()
+            );
+
+            // The serialized flags are accepted by the pipeline parser
+            let pipeline = gst::parse::launch(&format!("playbin name=pb flags={formatted}"))
+                .unwrap()
+                .downcast::<gst::Element>()
+                .unwrap();
+            let value = ElementInfo::element_property(&pipeline, "flags").unwrap();
+            assert_eq!(value, formatted);
+            assert_eq!(
+                value::flags_mask(&pipeline.property_value("flags")),
+                Some(mask)
+            );
+        });
+    }
+
+    fn embedded_graph() -> (GraphView, u32, u32) {
+        let graphview = GraphView::new();
+        let playbin = graphview.create_node("playbin", NodeType::Source);
+        playbin.add_property("uri", "file:///tmp/gps-test.mp4");
+        graphview.add_node(playbin.clone());
+        let sink = graphview.create_node("fakesink", NodeType::Sink);
+        sink.add_property("sync", "false");
+        embed::set_embedded_parent(&sink, playbin.id(), "video-sink");
+        graphview.add_node(sink.clone());
+        (graphview, playbin.id(), sink.id())
+    }
+
+    #[test]
+    fn test_embedded_child_description() {
+        test_synced(|| {
+            let (graphview, playbin_id, sink_id) = embedded_graph();
+            let sink = graphview.node(sink_id).unwrap();
+            assert_eq!(
+                embed::embedded_parent(&sink),
+                Some((playbin_id, "video-sink".to_string()))
+            );
+            assert!(sink.hidden_property(embed::EMBEDDED_PROPERTY));
+            let children = embed::embedded_children(&graphview, playbin_id);
+            assert_eq!(children.len(), 1);
+            assert_eq!(children[0].0, "video-sink");
+            assert!(embed::embedded_child(&graphview, playbin_id, "audio-sink").is_none());
+
+            let player = Player::new().unwrap();
+            let description = player.pipeline_description_from_graphview(&graphview);
+            let embedded = format!(
+                "video-sink=\"fakesink name={} sync=false\"",
+                sink.unique_name()
+            );
+            assert!(description.contains(&embedded), "{}", description);
+            assert_eq!(description.matches("fakesink name=").count(), 1);
+            assert!(!description.contains(embed::EMBEDDED_PROPERTY));
+        });
+    }
+
+    #[test]
+    fn test_embedded_child_validation() {
+        test_synced(|| {
+            let (graphview, playbin_id, sink_id) = embedded_graph();
+            let diagnostics = validation::validate_graph(&graphview);
+            assert!(!diagnostics
+                .iter()
+                .any(|d| d.target == DiagnosticTarget::Node(sink_id)));
+
+            // A graph file may refer to a parent which does not exist
+            let sink = graphview.node(sink_id).unwrap();
+            embed::set_embedded_parent(&sink, playbin_id + 100, "video-sink");
+            let diagnostics = validation::validate_graph(&graphview);
+            let diagnostic = diagnostics
+                .iter()
+                .find(|d| d.target == DiagnosticTarget::Node(sink_id))
+                .expect("The orphan embedded node should be reported");
+            assert_eq!(diagnostic.severity, DiagnosticSeverity::Error);
+            assert_eq!(diagnostic.fix, Some(QuickFix::RemoveNode(sink_id)));
+        });
+    }
+
+    #[test]
+    fn test_embedded_child_follows_parent() {
+        test_synced(|| {
+            let (graphview, playbin_id, sink_id) = embedded_graph();
+            graphview.clear_undo_history();
+            graphview.remove_node(playbin_id);
+            assert!(graphview.node(sink_id).is_none());
+            assert_eq!(graphview.undo_count(), 1);
+            assert!(graphview.undo());
+            let sink = graphview.node(sink_id).unwrap();
+            assert_eq!(
+                embed::embedded_parent(&sink),
+                Some((playbin_id, "video-sink".to_string()))
+            );
+
+            // The embedded node is copied with its parent and embedded in the copy
+            let template = graphview.template_from_nodes("player", &[playbin_id]);
+            assert_eq!(template.nodes.len(), 2);
+            let new_ids = graphview.insert_template(&template, &graphene::Point::new(0.0, 0.0));
+            assert_eq!(new_ids.len(), 2);
+            let new_playbin = new_ids
+                .iter()
+                .filter_map(|id| graphview.node(*id))
+                .find(|node| node.name() == "playbin")
+                .unwrap();
+            let children = embed::embedded_children(&graphview, new_playbin.id());
+            assert_eq!(children.len(), 1);
+            assert_eq!(children[0].0, "video-sink");
+            assert_ne!(children[0].1.id(), sink_id);
+
+            // The embedded node is grouped with its parent
+            let macro_id = graphview
+                .collapse_into_macro("player", &[playbin_id])
+                .unwrap();
+            assert!(graphview.node(sink_id).is_none());
+            let macro_node = graphview.node(macro_id).unwrap();
+            let template = GraphTemplate::from_macro_node(&macro_node).unwrap();
+            assert_eq!(template.embedded_nodes(playbin_id).len(), 1);
+            let player = Player::new().unwrap();
+            let description = player.pipeline_description_from_graphview(&graphview);
+            assert!(
+                description.contains("video-sink=\"fakesink name="),
+                "{}",
+                description
+            );
+            assert!(!description.contains(embed::EMBEDDED_PROPERTY));
+        });
+    }
+}
+
+#[cfg(test)]
+mod control_test {
+    use super::*;
+    use crate::gps::control::{self, InterpolationMode, Keyframe, PropertyControl};
+    use crate::graphmanager::{GraphView, NodeType, PropertyExt};
+
+    #[test]
+    fn test_parse_keyframes() {
+        let control = PropertyControl::parse("cubic;1.5=1;0=0.25").unwrap();
+        assert_eq!(control.mode, InterpolationMode::Cubic);
+        // The keyframes are sorted by time
+        assert_eq!(
+            control.keyframes(),
+            &[
+                Keyframe::new(gst::ClockTime::ZERO, 0.25),
+                Keyframe::new(gst::ClockTime::from_mseconds(1500), 1.0),
+            ]
+        );
+        assert_eq!(control.to_string(), "cubic;0=0.25;1.5=1");
+        assert_eq!(
+            PropertyControl::parse(&control.to_string()).unwrap(),
+            control
+        );
+        assert_eq!(
+            PropertyControl::parse("step").unwrap(),
+            PropertyControl::new(InterpolationMode::Step)
+        );
+        assert!(PropertyControl::parse("smooth;0=1").is_err());
+        assert!(PropertyControl::parse("linear;0").is_err());
+        assert!(PropertyControl::parse("linear;-1=0").is_err());
+        assert!(PropertyControl::parse("linear;1=abc").is_err());
+    }
+
+    #[test]
+    fn test_set_keyframe() {
+        let mut control = PropertyControl::default();
+        assert_eq!(control.mode, InterpolationMode::Linear);
+        control.set_keyframe(Keyframe::new(gst::ClockTime::from_seconds(2), 1.0));
+        control.set_keyframe(Keyframe::new(gst::ClockTime::ZERO, 0.0));
+        control.set_keyframe(Keyframe::new(gst::ClockTime::from_seconds(2), 0.5));
+        assert_eq!(control.to_string(), "linear;0=0;2=0.5");
+        assert!(control
+            .remove_keyframe(gst::ClockTime::from_seconds(1))
+            .is_none());
+        assert!(control.remove_keyframe(gst::ClockTime::ZERO).is_some());
+        assert_eq!(control.to_string(), "linear;2=0.5");
+    }
+
+    #[test]
+    fn test_control_source_interpolation() {
+        test_synced(|| {
+            let linear = PropertyControl::parse("linear;0=0;2=1").unwrap();
+            let source = linear.control_source();
+            let middle = ControlSourceExt::value(&source, gst::ClockTime::from_seconds(1)).unwrap();
+            assert!((middle - 0.5).abs() < 1e-9, "{}", middle);
+            // The last value is kept after the last keyframe
+            assert_eq!(
+                ControlSourceExt::value(&source, gst::ClockTime::from_seconds(5)),
+                Some(1.0)
+            );
+
+            let step = PropertyControl::parse("step;0=0;2=1").unwrap();
+            let source = step.control_source();
+            assert_eq!(
+                ControlSourceExt::value(&source, gst::ClockTime::from_seconds(1)),
+                Some(0.0)
+            );
+            assert_eq!(
+                ControlSourceExt::value(&source, gst::ClockTime::from_seconds(2)),
+                Some(1.0)
+            );
+        });
+    }
+
+    #[test]
+    fn test_controllable_properties() {
+        test_synced(|| {
+            let properties =
+                ElementInfo::element_properties_by_feature_name("audiotestsrc").unwrap();
+            assert!(control::controllable(&properties["volume"]));
+            assert_eq!(
+                control::value_range(&properties["volume"]),
+                Some((0.0, 1.0))
+            );
+            assert!(!control::controllable(&properties["is-live"]));
+        });
+    }
+
+    #[test]
+    fn test_bind_controls() {
+        test_synced(|| {
+            let graphview = GraphView::new();
+            let src = graphview.create_node("audiotestsrc", NodeType::Source);
+            src.add_property(&control::control_property_name("volume"), "linear;0=0;1=1");
+            src.add_property(&control::control_property_name("is-live"), "step;0=1");
+            graphview.add_node(src.clone());
+            assert!(src.hidden_property(&control::control_property_name("volume")));
+            let controls = control::node_controls(&src);
+            assert_eq!(controls.len(), 2);
+            assert_eq!(controls[1].0, "volume");
+            assert_eq!(controls[1].1.keyframes().len(), 2);
+
+            let player = Player::new().unwrap();
+            let description = format!(
+                "{} ! fakesink",
+                player.pipeline_description_from_graphview(&graphview)
+            );
+            assert!(
+                !description.contains(control::CONTROL_PREFIX),
+                "{}",
+                description
+            );
+            let pipeline = gst::parse::launch(&description)
+                .unwrap()
+                .downcast::<gst::Pipeline>()
+                .unwrap();
+            control::bind_controls(&pipeline, &graphview);
+            let element = pipeline.by_name(&src.unique_name()).unwrap();
+            let binding = element.control_binding("volume").unwrap();
+            let value = ControlBindingExt::value(&binding, gst::ClockTime::from_mseconds(500))
+                .and_then(|v| v.get::<f64>().ok())
+                .unwrap();
+            assert!((value - 0.5).abs() < 1e-9, "{}", value);
+            // Only the controllable properties are bound
+            assert!(element.control_binding("is-live").is_none());
+
+            assert!(element
+                .sync_values(gst::ClockTime::from_mseconds(250))
+                .is_ok());
+            let volume = element.property::<f64>("volume");
+            assert!((volume - 0.25).abs() < 1e-9, "{}", volume);
+        });
+    }
+}
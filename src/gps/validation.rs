@@ -0,0 +1,428 @@
+// validation.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Validation of the graph before the pipeline is built.
+//!
+//! `validate_graph` walks the nodes, ports and links of a graph and reports the
+//! problems which would prevent the pipeline from being created or from running,
+//! each one attached to the element, pad or link it concerns. Some of them come with
+//! a quick fix the user can apply from the validation panel.
+
+use crate::gps::embed;
+use crate::gps::missing;
+use crate::gps::value;
+use crate::gps::{CapsFilter, ElementInfo, PadInfo};
+use crate::graphmanager as GM;
+use crate::graphmanager::PropertyExt;
+
+use gst::glib;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Elements proposed to convert the data flowing through a link whose caps do not match.
+const CONVERTER_ELEMENTS: [&str; 4] = [
+    "videoconvert",
+    "audioconvert",
+    "videoscale",
+    "audioresample",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiagnosticSeverity {
+    /// The pipeline can not be created or will fail to run
+    Error,
+    /// The pipeline can be created but might not behave as expected
+    Warning,
+}
+
+impl fmt::Display for DiagnosticSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// Part of the graph a diagnostic is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticTarget {
+    Graph,
+    Node(u32),
+    Port { node_id: u32, port_id: u32 },
+    Link(u32),
+}
+
+/// Change of the graph solving the problem reported by a diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuickFix {
+    /// Insert an element of this factory in the middle of the link
+    InsertElement { link_id: u32, factory: String },
+    /// Remove the node and its links
+    RemoveNode(u32),
+}
+
+impl fmt::Display for QuickFix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QuickFix::InsertElement { factory, .. } => write!(f, "Insert {factory}"),
+            QuickFix::RemoveNode(_) => write!(f, "Remove node"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: DiagnosticSeverity,
+    pub target: DiagnosticTarget,
+    pub message: String,
+    pub fix: Option<QuickFix>,
+}
+
+impl Diagnostic {
+    fn new(severity: DiagnosticSeverity, target: DiagnosticTarget, message: String) -> Self {
+        Self {
+            severity,
+            target,
+            message,
+            fix: None,
+        }
+    }
+
+    fn with_fix(mut self, fix: Option<QuickFix>) -> Self {
+        self.fix = fix;
+        self
+    }
+}
+
+/// Find an element whose pad templates accept the caps of both sides of a link.
+fn converter_for_caps(caps_from: &str, caps_to: &str) -> Option<&'static str> {
+    CONVERTER_ELEMENTS.iter().copied().find(|factory| {
+        let (inputs, outputs) = PadInfo::pads(factory, false);
+        let accepts = |pads: &[PadInfo], caps: &str| {
+            pads.iter()
+                .any(|pad| PadInfo::caps_compatible(pad.caps().unwrap_or("ANY"), caps))
+        };
+        accepts(&inputs, caps_from) && accepts(&outputs, caps_to)
+    })
+}
+
+/// Check a value of a property of an element: its type and range, and for the
+/// `location` of a file source, that the file exists.
+pub fn check_property(
+    element_name: &str,
+    param: &glib::ParamSpec,
+    value: &str,
+) -> anyhow::Result<()> {
+    value::check_value(param, value)?;
+    let location = value.trim().trim_matches('"');
+    // Patterns such as the ones of multifilesrc are resolved by the element
+    if param.name() == "location"
+        && !location.is_empty()
+        && !location.contains('%')
+        && ElementInfo::element_is_uri_src_handler(element_name)
+            .is_some_and(|(_, supports_file)| supports_file)
+        && !std::path::Path::new(location).exists()
+    {
+        anyhow::bail!("The file {} does not exist", location);
+    }
+    Ok(())
+}
+
+/// Check that the value of each property of the node can be set on its element.
+fn validate_properties(node: &GM::Node, diagnostics: &mut Vec<Diagnostic>) {
+    let Ok(params) = ElementInfo::element_properties_by_feature_name(&node.name()) else {
+        return;
+    };
+    let mut properties: Vec<(String, String)> = node
+        .properties()
+        .iter()
+        .filter(|(name, _)| !node.hidden_property(name))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    properties.sort();
+    for (name, value) in properties {
+        let target = DiagnosticTarget::Node(node.id());
+        let Some(param) = params.get(&name) else {
+            diagnostics.push(Diagnostic::new(
+                DiagnosticSeverity::Error,
+                target,
+                format!("{} has no property '{}'", node.unique_name(), name),
+            ));
+            continue;
+        };
+        if let Err(e) = check_property(&node.name(), param, &value) {
+            diagnostics.push(Diagnostic::new(
+                DiagnosticSeverity::Error,
+                target,
+                format!(
+                    "Invalid value '{}' for the property '{}' of {}: {}",
+                    value,
+                    name,
+                    node.unique_name(),
+                    e
+                ),
+            ));
+        }
+    }
+}
+
+/// Check the elements of a macro node and the properties its parameters are forwarded to.
+fn validate_macro(node: &GM::Node, diagnostics: &mut Vec<Diagnostic>) {
+    let target = DiagnosticTarget::Node(node.id());
+    let Some(template) = GM::GraphTemplate::from_macro_node(node) else {
+        diagnostics.push(Diagnostic::new(
+            DiagnosticSeverity::Error,
+            target,
+            format!("The macro {} has no valid template", node.unique_name()),
+        ));
+        return;
+    };
+    for template_node in &template.nodes {
+        if !ElementInfo::element_factory_exists(&template_node.name) {
+            diagnostics.push(Diagnostic::new(
+                DiagnosticSeverity::Error,
+                target,
+                format!(
+                    "The element factory {} of the macro {} is not available",
+                    template_node.name,
+                    node.unique_name()
+                ),
+            ));
+        }
+    }
+    for parameter in &template.parameters {
+        let param = template.node(parameter.node).and_then(|template_node| {
+            ElementInfo::element_properties_by_feature_name(&template_node.name)
+                .ok()
+                .and_then(|params| params.get(&parameter.property).cloned())
+        });
+        let Some(param) = param else {
+            diagnostics.push(Diagnostic::new(
+                DiagnosticSeverity::Error,
+                target,
+                format!(
+                    "The parameter '{}' of {} is forwarded to an unknown property",
+                    parameter.name,
+                    node.unique_name()
+                ),
+            ));
+            continue;
+        };
+        let Some(value) = PropertyExt::property(node, &parameter.name) else {
+            continue;
+        };
+        if let Err(e) = value::check_value(&param, &value) {
+            diagnostics.push(Diagnostic::new(
+                DiagnosticSeverity::Error,
+                target,
+                format!(
+                    "Invalid value '{}' for the parameter '{}' of {}: {}",
+                    value,
+                    parameter.name,
+                    node.unique_name(),
+                    e
+                ),
+            ));
+        }
+    }
+}
+
+/// Report the links closing a cycle, found with a depth first search from each node.
+/// The links are given as (link id, node from, node to).
+fn validate_cycles(node_ids: &[u32], links: &[(u32, u32, u32)], diagnostics: &mut Vec<Diagnostic>) {
+    let mut adjacency: HashMap<u32, Vec<(u32, u32)>> = HashMap::new();
+    for (link_id, node_from, node_to) in links {
+        adjacency
+            .entry(*node_from)
+            .or_default()
+            .push((*link_id, *node_to));
+    }
+    let mut visited = HashSet::new();
+    for node_id in node_ids {
+        if !visited.insert(*node_id) {
+            continue;
+        }
+        // Nodes of the explored path, with the index of their next link to follow
+        let mut path = HashSet::from([*node_id]);
+        let mut stack = vec![(*node_id, 0)];
+        while let Some((node_id, index)) = stack.last().copied() {
+            let Some((link_id, node_to)) = adjacency
+                .get(&node_id)
+                .and_then(|node_links| node_links.get(index))
+                .copied()
+            else {
+                path.remove(&node_id);
+                stack.pop();
+                continue;
+            };
+            if let Some(last) = stack.last_mut() {
+                last.1 += 1;
+            }
+            if path.contains(&node_to) {
+                diagnostics.push(Diagnostic::new(
+                    DiagnosticSeverity::Error,
+                    DiagnosticTarget::Link(link_id),
+                    "This link closes a cycle in the pipeline".to_string(),
+                ));
+            } else if visited.insert(node_to) {
+                path.insert(node_to);
+                stack.push((node_to, 0));
+            }
+        }
+    }
+}
+
+/// Validate the graph and return the problems found, errors first.
+pub fn validate_graph(graphview: &GM::GraphView) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let mut nodes = graphview.all_nodes(GM::NodeType::All);
+    nodes.sort_by_key(|node| node.id());
+    let mut links = graphview.all_links(true);
+    links.extend(graphview.all_links(false));
+    links.sort_by_key(|link| link.id);
+
+    if !nodes.is_empty()
+        && !nodes
+            .iter()
+            .any(|node| node.node_type() == Some(&GM::NodeType::Sink))
+    {
+        diagnostics.push(Diagnostic::new(
+            DiagnosticSeverity::Error,
+            DiagnosticTarget::Graph,
+            "The pipeline has no sink element".to_string(),
+        ));
+    }
+
+    let mut unique_names: HashMap<String, u32> = HashMap::new();
+    for node in &nodes {
+        let target = DiagnosticTarget::Node(node.id());
+        let unique_name = node.unique_name();
+        if let Some(node_id) = unique_names.get(&unique_name) {
+            diagnostics.push(Diagnostic::new(
+                DiagnosticSeverity::Error,
+                target,
+                format!("The name {unique_name} is already used by the node {node_id}"),
+            ));
+        } else {
+            unique_names.insert(unique_name.clone(), node.id());
+        }
+
+        if node.node_type() == Some(&GM::NodeType::Macro) {
+            validate_macro(node, &mut diagnostics);
+        } else if !missing::factory_exists(&node.name()) {
+            diagnostics.push(
+                Diagnostic::new(
+                    DiagnosticSeverity::Error,
+                    target,
+                    format!(
+                        "The element factory {} is not available, install the {}",
+                        node.name(),
+                        missing::node_provider(node)
+                    ),
+                )
+                .with_fix(Some(QuickFix::RemoveNode(node.id()))),
+            );
+            continue;
+        }
+
+        if let Some((parent_id, property)) = embed::embedded_parent(node) {
+            // The embedded nodes have no pads, they are set as a property of their parent
+            if graphview.node(parent_id).is_none() {
+                diagnostics.push(
+                    Diagnostic::new(
+                        DiagnosticSeverity::Error,
+                        target,
+                        format!(
+                            "{unique_name} is embedded in the property '{property}' of a removed node"
+                        ),
+                    )
+                    .with_fix(Some(QuickFix::RemoveNode(node.id()))),
+                );
+            }
+        } else if nodes.len() > 1 && graphview.node_is_linked(node.id()).is_none() {
+            diagnostics.push(
+                Diagnostic::new(
+                    DiagnosticSeverity::Warning,
+                    target,
+                    format!("{unique_name} is not linked to any element"),
+                )
+                .with_fix(Some(QuickFix::RemoveNode(node.id()))),
+            );
+        } else {
+            let mut ports = node.all_ports(GM::PortDirection::All);
+            ports.sort_by_key(|port| port.id());
+            for port in ports {
+                if port.presence() == GM::PortPresence::Always
+                    && graphview.port_is_linked(port.id()).is_none()
+                {
+                    diagnostics.push(Diagnostic::new(
+                        DiagnosticSeverity::Warning,
+                        DiagnosticTarget::Port {
+                            node_id: node.id(),
+                            port_id: port.id(),
+                        },
+                        format!("The pad {} of {} is not linked", port.name(), unique_name),
+                    ));
+                }
+            }
+        }
+        if node.node_type() != Some(&GM::NodeType::Macro) {
+            validate_properties(node, &mut diagnostics);
+        }
+    }
+
+    for link in &links {
+        let target = DiagnosticTarget::Link(link.id);
+        let port_caps = |node_id: u32, port_id: u32| {
+            graphview
+                .node(node_id)
+                .and_then(|node| node.port(port_id))
+                .and_then(|port| PropertyExt::property(&port, "_caps"))
+        };
+        let caps_from = port_caps(link.node_from, link.port_from);
+        let caps_to = port_caps(link.node_to, link.port_to);
+        if let (Some(caps_from), Some(caps_to)) = (&caps_from, &caps_to) {
+            if !PadInfo::caps_compatible(caps_from, caps_to) {
+                let fix =
+                    converter_for_caps(caps_from, caps_to).map(|factory| QuickFix::InsertElement {
+                        link_id: link.id,
+                        factory: factory.to_string(),
+                    });
+                diagnostics.push(
+                    Diagnostic::new(
+                        DiagnosticSeverity::Error,
+                        target,
+                        "The caps of the linked pads do not intersect".to_string(),
+                    )
+                    .with_fix(fix),
+                );
+                continue;
+            }
+        }
+        if !link.name().is_empty() {
+            if let Err(e) = CapsFilter::check_description(
+                &link.name(),
+                caps_from.as_deref(),
+                caps_to.as_deref(),
+            ) {
+                diagnostics.push(Diagnostic::new(
+                    DiagnosticSeverity::Error,
+                    target,
+                    format!("Caps filter '{}': {}", link.name(), e),
+                ));
+            }
+        }
+    }
+
+    let node_ids: Vec<u32> = nodes.iter().map(|node| node.id()).collect();
+    let edges: Vec<(u32, u32, u32)> = links
+        .iter()
+        .map(|link| (link.id, link.node_from, link.node_to))
+        .collect();
+    validate_cycles(&node_ids, &edges, &mut diagnostics);
+    diagnostics.sort_by_key(|diagnostic| diagnostic.severity);
+    diagnostics
+}
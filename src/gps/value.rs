@@ -0,0 +1,337 @@
+// value.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Property values edited with a dedicated editor.
+//!
+//! The nodes keep the properties as the strings of a pipeline description. The caps,
+//! structures, arrays and fractions are split into their parts here so they can be
+//! edited piece by piece, and written back in the same serialization.
+
+use gst::glib;
+use gst::prelude::*;
+use std::str::FromStr;
+
+/// Kind of editor matching the type of a property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyKind {
+    Caps,
+    Structure,
+    /// `GstValueArray` or `GValueArray`, serialized as `< a, b >`
+    Array,
+    Fraction,
+    /// Child element such as the `video-sink` of playbin
+    Element,
+    Other,
+}
+
+/// Kind of editor for the values of a type.
+pub fn value_kind(value_type: glib::Type) -> PropertyKind {
+    if value_type.is_a(gst::Caps::static_type()) {
+        PropertyKind::Caps
+    } else if value_type.is_a(gst::Structure::static_type()) {
+        PropertyKind::Structure
+    } else if value_type.is_a(gst::Array::static_type())
+        || value_type.is_a(glib::ValueArray::static_type())
+    {
+        PropertyKind::Array
+    } else if value_type.is_a(gst::Fraction::static_type()) {
+        PropertyKind::Fraction
+    } else if value_type.is_a(gst::Element::static_type()) {
+        PropertyKind::Element
+    } else {
+        PropertyKind::Other
+    }
+}
+
+pub fn property_kind(param: &glib::ParamSpec) -> PropertyKind {
+    value_kind(param.value_type())
+}
+
+/// Serialize a caps, structure, array or fraction value as written in a pipeline
+/// description. `None` for the other values and the unset ones.
+pub fn serialize_value(value: &glib::Value) -> Option<String> {
+    match value_kind(value.type_()) {
+        PropertyKind::Caps
+        | PropertyKind::Structure
+        | PropertyKind::Array
+        | PropertyKind::Fraction => value
+            .serialize()
+            .ok()
+            .map(|value| value.to_string())
+            .filter(|value| value != "NULL"),
+        _ => None,
+    }
+}
+
+/// Parse a fraction written as `num/den`, or as a single integer.
+pub fn parse_fraction(value: &str) -> Option<(i32, i32)> {
+    let value = value.trim().trim_matches('"');
+    let (numerator, denominator) = value.split_once('/').unwrap_or((value, "1"));
+    let numerator = numerator.trim().parse::<i32>().ok()?;
+    let denominator = denominator.trim().parse::<i32>().ok()?;
+    (denominator != 0).then_some((numerator, denominator))
+}
+
+pub fn format_fraction(numerator: i32, denominator: i32) -> String {
+    format!("{numerator}/{denominator}")
+}
+
+/// Split a serialized array `< a, b >` into its items, keeping the nested lists,
+/// ranges, structures and quoted strings whole.
+pub fn split_array(value: &str) -> Vec<String> {
+    let value = value.trim().trim_matches('"').trim();
+    let value = value
+        .strip_prefix('<')
+        .and_then(|v| v.strip_suffix('>'))
+        .unwrap_or(value);
+    let mut items = vec![];
+    let mut item = String::new();
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for c in value.chars() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            quoted = !quoted;
+        } else if !quoted {
+            match c {
+                '<' | '{' | '[' | '(' => depth += 1,
+                '>' | '}' | ']' | ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    items.push(item.trim().to_string());
+                    item.clear();
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        item.push(c);
+    }
+    if !item.trim().is_empty() || !items.is_empty() {
+        items.push(item.trim().to_string());
+    }
+    items
+}
+
+pub fn format_array(items: &[String]) -> String {
+    if items.is_empty() {
+        return String::from("<>");
+    }
+    format!("< {} >", items.join(", "))
+}
+
+fn check_range<T>(value: &str, minimum: T, maximum: T) -> anyhow::Result<()>
+where
+    T: std::str::FromStr + PartialOrd + std::fmt::Display,
+{
+    let number = value
+        .parse::<T>()
+        .map_err(|_| anyhow::anyhow!("'{}' is not a valid number", value))?;
+    if number < minimum || number > maximum {
+        anyhow::bail!(
+            "{} is out of the range [{}, {}]",
+            value,
+            format_bound(&minimum),
+            format_bound(&maximum)
+        );
+    }
+    Ok(())
+}
+
+// The limits of the floating point properties are often the largest values of the type
+fn format_bound<T: std::fmt::Display>(bound: &T) -> String {
+    let bound = bound.to_string();
+    match bound.parse::<f64>() {
+        Ok(number) if number.abs() >= 1e9 && bound.len() > 12 => format!("{number:e}"),
+        _ => bound,
+    }
+}
+
+/// Combined value of flags written as nicks or names joined by `+` or `|`, such as
+/// `video+audio+soft-volume`, or as a number.
+pub fn parse_flags(flags: &glib::FlagsClass, value: &str) -> anyhow::Result<u32> {
+    let value = value.trim().trim_matches('"');
+    value
+        .split(['+', '|'])
+        .map(str::trim)
+        .try_fold(0u32, |mask, part| {
+            let bits = if let Ok(number) = part.parse::<u32>() {
+                number
+            } else if let Some(flag) = flags
+                .value_by_nick(part)
+                .or_else(|| flags.value_by_name(part))
+            {
+                flag.value()
+            } else {
+                anyhow::bail!("'{}' is not a known flag", part);
+            };
+            Ok(mask | bits)
+        })
+}
+
+/// Write combined flags with the nicks of the flags, `video+audio+soft-volume`, the form
+/// read back by `gst::parse::launch`. The flags are written as a number if some of the
+/// bits have no nick.
+pub fn format_flags(flags: &glib::FlagsClass, mask: u32) -> String {
+    // Single flags first, so that the values grouping several flags are only used
+    // for the bits left
+    let mut values: Vec<&glib::FlagsValue> =
+        flags.values().iter().filter(|v| v.value() != 0).collect();
+    values.sort_by_key(|v| v.value().count_ones());
+    let mut remaining = mask;
+    let mut nicks = vec![];
+    for flag in values {
+        if mask & flag.value() == flag.value() && remaining & flag.value() != 0 {
+            nicks.push(flag.nick());
+            remaining &= !flag.value();
+        }
+    }
+    if remaining != 0 {
+        return mask.to_string();
+    }
+    if nicks.is_empty() {
+        return flags
+            .values()
+            .iter()
+            .find(|v| v.value() == 0)
+            .map(|v| v.nick().to_string())
+            .unwrap_or_else(|| String::from("0"));
+    }
+    // Keep the order of the flags of the class
+    nicks.sort_by_key(|nick| {
+        flags
+            .values()
+            .iter()
+            .position(|v| v.nick() == *nick)
+            .unwrap_or_default()
+    });
+    nicks.join("+")
+}
+
+/// Combined bits of a flags value.
+pub fn flags_mask(value: &glib::Value) -> Option<u32> {
+    value
+        .get::<Vec<&glib::FlagsValue>>()
+        .ok()
+        .map(|flags| flags.iter().fold(0u32, |mask, flag| mask | flag.value()))
+}
+
+/// Serialize a flags value with the nicks of its flags.
+pub fn serialize_flags(value: &glib::Value) -> Option<String> {
+    let mask = flags_mask(value)?;
+    Some(
+        glib::FlagsClass::with_type(value.type_())
+            .map(|flags| format_flags(&flags, mask))
+            .unwrap_or_else(|| mask.to_string()),
+    )
+}
+
+fn check_flags(param: &glib::ParamSpecFlags, value: &str) -> anyhow::Result<()> {
+    let flags = param.flags_class();
+    let known = flags
+        .values()
+        .iter()
+        .fold(0u32, |mask, flag| mask | flag.value());
+    let mask =
+        parse_flags(&flags, value).map_err(|e| anyhow::anyhow!("{} for {}", e, param.name()))?;
+    if mask & !known != 0 {
+        anyhow::bail!("{} holds unknown flags", value);
+    }
+    Ok(())
+}
+
+/// Check a value entered for a property against its type and its range.
+/// An empty value resets the property to its default and is always valid.
+pub fn check_value(param: &glib::ParamSpec, value: &str) -> anyhow::Result<()> {
+    let value = value.trim().trim_matches('"');
+    if value.is_empty() {
+        return Ok(());
+    }
+    if let Some(param) = param.downcast_ref::<glib::ParamSpecInt>() {
+        check_range(value, param.minimum(), param.maximum())
+    } else if let Some(param) = param.downcast_ref::<glib::ParamSpecUInt>() {
+        check_range(value, param.minimum(), param.maximum())
+    } else if let Some(param) = param.downcast_ref::<glib::ParamSpecInt64>() {
+        check_range(value, param.minimum(), param.maximum())
+    } else if let Some(param) = param.downcast_ref::<glib::ParamSpecUInt64>() {
+        check_range(value, param.minimum(), param.maximum())
+    } else if let Some(param) = param.downcast_ref::<glib::ParamSpecFloat>() {
+        check_range(value, param.minimum(), param.maximum())
+    } else if let Some(param) = param.downcast_ref::<glib::ParamSpecDouble>() {
+        check_range(value, param.minimum(), param.maximum())
+    } else if let Some(param) = param.downcast_ref::<glib::ParamSpecEnum>() {
+        let enums = param.enum_class();
+        let known = enums
+            .values()
+            .iter()
+            .any(|v| v.nick() == value || v.name() == value || v.value().to_string() == value);
+        if !known {
+            anyhow::bail!("'{}' is not a value of {}", value, param.name());
+        }
+        Ok(())
+    } else if let Some(param) = param.downcast_ref::<glib::ParamSpecFlags>() {
+        check_flags(param, value)
+    } else if let Some(param) = param.downcast_ref::<gst::ParamSpecFraction>() {
+        let (numerator, denominator) = parse_fraction(value)
+            .ok_or_else(|| anyhow::anyhow!("'{}' is not a fraction such as 30/1", value))?;
+        let fraction = gst::Fraction::new(numerator, denominator);
+        if fraction < param.minimum() || fraction > param.maximum() {
+            anyhow::bail!(
+                "{} is out of the range [{}, {}]",
+                value,
+                param.minimum(),
+                param.maximum()
+            );
+        }
+        Ok(())
+    } else if value_kind(param.value_type()) == PropertyKind::Caps {
+        gst::Caps::from_str(value)
+            .map(|_| ())
+            .map_err(|_| anyhow::anyhow!("'{}' are not valid caps", value))
+    } else if value_kind(param.value_type()) == PropertyKind::Element
+        || param.value_type().is_a(glib::Type::STRING)
+    {
+        Ok(())
+    } else {
+        glib::Value::deserialize(value, param.value_type())
+            .map(|_| ())
+            .map_err(|_| anyhow::anyhow!("'{}' is not a valid {}", value, param.value_type()))
+    }
+}
+
+/// Range and default of a numeric or fraction property, shown next to its entry.
+pub fn value_hint(param: &glib::ParamSpec) -> Option<String> {
+    fn hint<T: std::fmt::Display>(minimum: T, maximum: T, default: T) -> Option<String> {
+        Some(format!(
+            "From {} to {}, default {}",
+            format_bound(&minimum),
+            format_bound(&maximum),
+            format_bound(&default)
+        ))
+    }
+    if let Some(param) = param.downcast_ref::<glib::ParamSpecInt>() {
+        hint(param.minimum(), param.maximum(), param.default_value())
+    } else if let Some(param) = param.downcast_ref::<glib::ParamSpecUInt>() {
+        hint(param.minimum(), param.maximum(), param.default_value())
+    } else if let Some(param) = param.downcast_ref::<glib::ParamSpecInt64>() {
+        hint(param.minimum(), param.maximum(), param.default_value())
+    } else if let Some(param) = param.downcast_ref::<glib::ParamSpecUInt64>() {
+        hint(param.minimum(), param.maximum(), param.default_value())
+    } else if let Some(param) = param.downcast_ref::<glib::ParamSpecFloat>() {
+        hint(param.minimum(), param.maximum(), param.default_value())
+    } else if let Some(param) = param.downcast_ref::<glib::ParamSpecDouble>() {
+        hint(param.minimum(), param.maximum(), param.default_value())
+    } else if let Some(param) = param.downcast_ref::<gst::ParamSpecFraction>() {
+        hint(param.minimum(), param.maximum(), param.default_value())
+    } else {
+        None
+    }
+}
@@ -0,0 +1,151 @@
+// annotation.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GraphManager
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Sticky notes and coloured frames placed on the canvas.
+//!
+//! Annotations only document the graph: they are drawn behind the nodes and saved
+//! with the graph, but they are neither nodes nor links so they never take part in
+//! the pipeline. Moving a frame moves the nodes and notes it contains.
+
+use gtk::{gdk, graphene};
+use std::cell::Cell;
+use std::fmt;
+
+use super::SelectionExt;
+
+/// Height of the title bar used to grab a frame.
+pub const FRAME_TITLE_HEIGHT: f32 = 24.0;
+/// Size of the square handle in the bottom right corner used to resize an annotation.
+pub const ANNOTATION_HANDLE_SIZE: f32 = 12.0;
+/// Smallest size an annotation can be resized to.
+pub const ANNOTATION_MIN_SIZE: f32 = 40.0;
+
+/// Colors proposed for the annotations, the first ones are the defaults of the notes
+/// and of the frames.
+pub const ANNOTATION_COLORS: [(&str, &str); 5] = [
+    ("Yellow", "#fff2a8"),
+    ("Blue", "#a8c8f0"),
+    ("Green", "#b4e6b4"),
+    ("Red", "#f5b4b4"),
+    ("Grey", "#d2d2d2"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationKind {
+    /// Free text displayed over a coloured background
+    Note,
+    /// Coloured area grouping the nodes placed on it, with a title
+    Frame,
+}
+
+impl fmt::Display for AnnotationKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl AnnotationKind {
+    pub fn from_str(kind_name: &str) -> AnnotationKind {
+        match kind_name {
+            "Frame" => AnnotationKind::Frame,
+            _ => AnnotationKind::Note,
+        }
+    }
+
+    /// Size of a new annotation, in canvas space.
+    pub fn default_size(&self) -> (f32, f32) {
+        match self {
+            AnnotationKind::Note => (180.0, 100.0),
+            AnnotationKind::Frame => (360.0, 240.0),
+        }
+    }
+
+    fn default_color(&self) -> &'static str {
+        match self {
+            AnnotationKind::Note => ANNOTATION_COLORS[0].1,
+            AnnotationKind::Frame => ANNOTATION_COLORS[1].1,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Annotation {
+    pub id: u32,
+    pub kind: AnnotationKind,
+    pub text: String,
+    /// Position and size in canvas space
+    pub rect: graphene::Rect,
+    pub color: gdk::RGBA,
+    pub selected: Cell<bool>,
+}
+
+impl Annotation {
+    pub fn new(id: u32, kind: AnnotationKind, text: &str, rect: graphene::Rect) -> Self {
+        Self {
+            id,
+            kind,
+            text: text.to_string(),
+            rect,
+            color: gdk::RGBA::parse(kind.default_color()).unwrap_or(gdk::RGBA::WHITE),
+            selected: Cell::new(false),
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Check if the annotation can be grabbed at this point, anywhere on a note or
+    /// on the title bar of a frame.
+    pub fn grab_contains(&self, point: &graphene::Point) -> bool {
+        match self.kind {
+            AnnotationKind::Note => self.rect.contains_point(point),
+            AnnotationKind::Frame => graphene::Rect::new(
+                self.rect.x(),
+                self.rect.y(),
+                self.rect.width(),
+                FRAME_TITLE_HEIGHT,
+            )
+            .contains_point(point),
+        }
+    }
+
+    /// Check if the point is on the resize handle of the annotation.
+    pub fn handle_contains(&self, point: &graphene::Point) -> bool {
+        graphene::Rect::new(
+            self.rect.x() + self.rect.width() - ANNOTATION_HANDLE_SIZE,
+            self.rect.y() + self.rect.height() - ANNOTATION_HANDLE_SIZE,
+            ANNOTATION_HANDLE_SIZE,
+            ANNOTATION_HANDLE_SIZE,
+        )
+        .contains_point(point)
+    }
+
+    /// Check if an area is fully inside a frame. Notes never contain anything.
+    pub fn frame_contains(&self, rect: &graphene::Rect) -> bool {
+        self.kind == AnnotationKind::Frame
+            && rect.x() >= self.rect.x()
+            && rect.y() >= self.rect.y()
+            && rect.x() + rect.width() <= self.rect.x() + self.rect.width()
+            && rect.y() + rect.height() <= self.rect.y() + self.rect.height()
+    }
+}
+
+impl SelectionExt for Annotation {
+    fn toggle_selected(&self) {
+        self.set_selected(!self.selected.get());
+    }
+
+    fn set_selected(&self, selected: bool) {
+        self.selected.set(selected);
+    }
+
+    fn selected(&self) -> bool {
+        self.selected.get()
+    }
+}
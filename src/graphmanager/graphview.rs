@@ -14,11 +14,18 @@ use xml::writer::EmitterConfig;
 use xml::writer::XmlEvent as XMLWEvent;
 
 use super::{
+    annotation::{
+        Annotation, AnnotationKind, ANNOTATION_HANDLE_SIZE, ANNOTATION_MIN_SIZE, FRAME_TITLE_HEIGHT,
+    },
     link::*,
-    node::{Node, NodeType},
+    node::{Node, NodeType, EMBEDDED_PROPERTY},
     port::{Port, PortDirection, PortPresence},
     property::PropertyExt,
     selection::SelectionExt,
+    template::{
+        GraphTemplate, TemplateLink, TemplateNode, TemplatePort, MACRO_PORT_PROPERTY,
+        MACRO_TEMPLATE_PROPERTY,
+    },
 };
 
 use once_cell::sync::Lazy;
@@ -34,7 +41,10 @@ use gtk::{
 use log::{debug, error, info, trace, warn};
 
 use std::cell::RefMut;
-use std::{cmp::Ordering, collections::HashMap};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
 
 static GRAPHVIEW_STYLE: &str = include_str!("graphview.css");
 pub static GRAPHVIEW_XML_VERSION: &str = "0.1";
@@ -94,6 +104,38 @@ impl DotLoadContext {
 // Default link colors (RGB values 0.0-1.0)
 const LINK_COLOR_DEFAULT: (f64, f64, f64) = (0.5, 0.5, 0.5); // Gray
 const LINK_COLOR_SELECTED: (f64, f64, f64) = (1.0, 0.18, 0.18); // Red
+/// Longest caps filter displayed in the badge of a link, longer ones are truncated.
+const LINK_BADGE_MAX_CHARS: usize = 40;
+/// Length of the stub drawn next to a port with a stub label.
+const PORT_STUB_LENGTH: f64 = 30.0;
+/// Space kept around the nodes when zooming to fit them in the view.
+const ZOOM_TO_RECT_MARGIN: f64 = 40.0;
+/// Opacity of the rubber-band selection rectangle fill.
+const RUBBERBAND_FILL_ALPHA: f64 = 0.15;
+/// Opacity of the background of the frames, the notes are opaque.
+const FRAME_FILL_ALPHA: f32 = 0.35;
+/// Space between the border of an annotation and its text.
+const ANNOTATION_TEXT_PADDING: f32 = 6.0;
+/// Space kept around the selected nodes when framing them.
+const FRAME_SELECTION_MARGIN: f32 = 30.0;
+/// Smallest space kept between two nodes of the same stage by auto-arrange.
+const ARRANGE_MIN_GAP: f32 = 20.0;
+/// Space between two nodes of the same stage in the compact layout, as a fraction of
+/// the spacing across the flow.
+const COMPACT_SPACING_FACTOR: f32 = 0.25;
+
+/// Alignment of the selected nodes, relative to the bounding box of the selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeAlignment {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    /// Same horizontal centre, the nodes are stacked in a column
+    Center,
+    /// Same vertical centre, the nodes are lined up in a row
+    Middle,
+}
 
 /// Connection info for edge maps.
 ///
@@ -105,6 +147,30 @@ struct EdgeInfo {
     node_id: u32,
     /// Index of the port on that node (used to adjust Y position for better routing)
     port_index: usize,
+    /// Index of the port on the node owning the edge list
+    local_port_index: usize,
+}
+
+/// Direction in which the stages of the pipeline follow each other when auto-arranging.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LayoutOrientation {
+    /// Sources on the left, sinks on the right
+    #[default]
+    LeftToRight,
+    /// Sources at the top, sinks at the bottom
+    TopToBottom,
+}
+
+/// Placement of the nodes within a stage when auto-arranging.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LayoutMode {
+    /// Nodes of a stage are spread at a regular interval
+    #[default]
+    Layered,
+    /// Nodes are packed using their actual size, the crossings of the links are
+    /// reduced and each node is aligned with the nodes it is linked to, which keeps
+    /// the links short and straight
+    Compact,
 }
 
 /// Configuration options for auto-arrange
@@ -115,8 +181,12 @@ pub struct AutoArrangeOptions {
     /// This is the space between the right edge of the widest node in one stage
     /// and the left edge of nodes in the next stage. The actual X distance between
     /// stages depends on node widths plus this gap value.
+    ///
+    /// From top to bottom, this is the horizontal spacing between nodes in the same stage.
     pub horizontal_spacing: f32,
     /// Vertical spacing between nodes in the same stage (default: 100.0)
+    ///
+    /// From top to bottom, this is the vertical gap between stages.
     pub vertical_spacing: f32,
     /// Starting X position for the first stage (default: 50.0)
     pub start_x: f32,
@@ -128,6 +198,13 @@ pub struct AutoArrangeOptions {
     /// Number of barycenter refinement iterations (default: 4).
     /// More iterations may improve layout quality but increase computation time.
     pub barycenter_iterations: usize,
+    /// Direction of the flow (default: left to right)
+    pub orientation: LayoutOrientation,
+    /// Placement of the nodes within a stage (default: layered)
+    pub mode: LayoutMode,
+    /// Arrange only the selected nodes where they are, the others keep their
+    /// position (default: false)
+    pub selected_only: bool,
 }
 
 impl Default for AutoArrangeOptions {
@@ -139,6 +216,9 @@ impl Default for AutoArrangeOptions {
             start_y: 50.0,
             port_offset_factor: 0.3,
             barycenter_iterations: 4,
+            orientation: LayoutOrientation::default(),
+            mode: LayoutMode::default(),
+            selected_only: false,
         }
     }
 }
@@ -159,6 +239,21 @@ mod imp {
         offset: graphene::Point,
         /// Original position when drag started (for undo)
         original_position: graphene::Point,
+        /// The other selected nodes moved along with the dragged one and their original position
+        selection: Vec<(glib::WeakRef<Node>, graphene::Point)>,
+    }
+
+    pub struct AnnotationDragState {
+        /// The dragged annotation as it was when the drag started
+        pub(super) annotation: Annotation,
+        /// Offset of the pointer to the origin of the annotation in canvas space
+        pub(super) offset: graphene::Point,
+        /// True if the annotation is resized instead of moved
+        pub(super) resize: bool,
+        /// The nodes inside the dragged frame and their original position
+        pub(super) nodes: Vec<(u32, graphene::Point)>,
+        /// The notes and frames inside the dragged frame as they were when the drag started
+        pub(super) annotations: Vec<Annotation>,
     }
 
     pub struct GraphView {
@@ -171,11 +266,21 @@ mod imp {
         pub(super) port_selected: RefCell<Option<Port>>,
         pub(super) mouse_position: Cell<(f64, f64)>,
         pub dragged_node: RefCell<Option<DragState>>,
+        /// Notes and frames in drawing order, the frames first
+        pub(super) annotations: RefCell<Vec<Annotation>>,
+        pub(super) current_annotation_id: Cell<u32>,
+        pub(super) dragged_annotation: RefCell<Option<AnnotationDragState>>,
+        /// Start and end of the rubber-band selection in canvas space
+        pub(super) rubberband: Cell<Option<(graphene::Point, graphene::Point)>>,
+        /// Spacing of the grid the dragged nodes snap to, if enabled
+        pub(super) grid_snap: Cell<Option<f64>>,
         pub hadjustment: RefCell<Option<gtk::Adjustment>>,
         pub vadjustment: RefCell<Option<gtk::Adjustment>>,
         pub zoom_factor: Cell<f64>,
         /// RGB color for links (0.0-1.0 range)
         pub(super) link_color: Cell<(f64, f64, f64)>,
+        /// Shape of the links, saved with the graph
+        pub(super) link_routing: Cell<LinkRouting>,
         /// Custom CSS provider for app-injected styles
         pub(super) custom_css_provider: RefCell<Option<gtk::CssProvider>>,
         /// Undo/redo stack for graph operations
@@ -194,10 +299,16 @@ mod imp {
                 port_selected: RefCell::new(None),
                 mouse_position: Cell::new((0.0, 0.0)),
                 dragged_node: RefCell::new(None),
+                annotations: RefCell::new(Vec::new()),
+                current_annotation_id: Cell::new(0),
+                dragged_annotation: RefCell::new(None),
+                rubberband: Cell::new(None),
+                grid_snap: Cell::new(None),
                 hadjustment: RefCell::new(None),
                 vadjustment: RefCell::new(None),
                 zoom_factor: Cell::new(1.0),
                 link_color: Cell::new(LINK_COLOR_DEFAULT),
+                link_routing: Cell::new(LinkRouting::default()),
                 custom_css_provider: RefCell::new(None),
                 undo_stack: RefCell::new(crate::graphmanager::undo::UndoStack::new()),
             }
@@ -254,6 +365,22 @@ mod imp {
                         .screen_space_to_canvas_space_transform()
                         .transform_point(&graphene::Point::new(x as f32, y as f32));
 
+                    // Dragging a selected node moves the whole selection
+                    let selection = if node.selected() {
+                        widget
+                            .selected_nodes()
+                            .iter()
+                            .filter(|n| n.id() != node.id())
+                            .filter_map(|n| {
+                                widget
+                                    .node_position(n)
+                                    .map(|position| (n.downgrade(), position))
+                            })
+                            .collect()
+                    } else {
+                        Vec::new()
+                    };
+
                     Some(DragState {
                         node: node.clone().downgrade(),
                         offset: graphene::Point::new(
@@ -261,8 +388,38 @@ mod imp {
                             canvas_cursor_pos.y() - canvas_node_pos.y(),
                         ),
                         original_position: canvas_node_pos,
+                        selection,
                     })
                 } else {
+                    let canvas_cursor_pos = widget
+                        .imp()
+                        .screen_space_to_canvas_space_transform()
+                        .transform_point(&graphene::Point::new(x as f32, y as f32));
+                    if let Some(annotation) = widget.annotation_at(&canvas_cursor_pos) {
+                        // The user targeted a note or the title of a frame, move or resize it.
+                        let resize = annotation.handle_contains(&canvas_cursor_pos);
+                        let (nodes, annotations) = if resize {
+                            (Vec::new(), Vec::new())
+                        } else {
+                            widget.annotation_contents(&annotation)
+                        };
+                        *widget.imp().dragged_annotation.borrow_mut() = Some(AnnotationDragState {
+                            offset: graphene::Point::new(
+                                canvas_cursor_pos.x() - annotation.rect.x(),
+                                canvas_cursor_pos.y() - annotation.rect.y(),
+                            ),
+                            annotation,
+                            resize,
+                            nodes,
+                            annotations,
+                        });
+                    } else {
+                        // The user targeted the empty canvas, start a rubber-band selection.
+                        widget
+                            .imp()
+                            .rubberband
+                            .set(Some((canvas_cursor_pos, canvas_cursor_pos)));
+                    }
                     None
                 }
             });
@@ -272,12 +429,6 @@ mod imp {
                     .unwrap()
                     .dynamic_cast::<super::GraphView>()
                     .expect("drag-update event is not on the GraphView");
-                let dragged_node = widget.imp().dragged_node.borrow();
-                let Some(DragState { node, offset, .. }) = dragged_node.as_ref() else {
-                    return;
-                };
-                let Some(node) = node.upgrade() else { return };
-
                 let (start_x, start_y) = drag_controller
                     .start_point()
                     .expect("Drag has no start point");
@@ -287,13 +438,51 @@ mod imp {
                 let transform = widget.imp().screen_space_to_canvas_space_transform();
                 let canvas_node_origin = transform.transform_point(&onscreen_node_origin);
 
-                widget.move_node(
-                    &node,
-                    &graphene::Point::new(
-                        canvas_node_origin.x() - offset.x(),
-                        canvas_node_origin.y() - offset.y(),
-                    ),
+                if let Some((start, _)) = widget.imp().rubberband.get() {
+                    widget
+                        .imp()
+                        .rubberband
+                        .set(Some((start, canvas_node_origin)));
+                    widget.select_nodes_in_rect(&start, &canvas_node_origin);
+                    return;
+                }
+
+                if let Some(drag) = widget.imp().dragged_annotation.borrow().as_ref() {
+                    widget.drag_annotation(drag, &canvas_node_origin);
+                    return;
+                }
+
+                let dragged_node = widget.imp().dragged_node.borrow();
+                let Some(DragState {
+                    node,
+                    offset,
+                    original_position,
+                    selection,
+                }) = dragged_node.as_ref()
+                else {
+                    return;
+                };
+                let Some(node) = node.upgrade() else { return };
+
+                let position = widget.snap_to_grid(&graphene::Point::new(
+                    canvas_node_origin.x() - offset.x(),
+                    canvas_node_origin.y() - offset.y(),
+                ));
+                widget.move_node(&node, &position);
+
+                // Keep the other selected nodes at the same distance of the dragged one
+                let (dx, dy) = (
+                    position.x() - original_position.x(),
+                    position.y() - original_position.y(),
                 );
+                for (other, other_position) in selection {
+                    if let Some(other) = other.upgrade() {
+                        widget.move_node(
+                            &other,
+                            &graphene::Point::new(other_position.x() + dx, other_position.y() + dy),
+                        );
+                    }
+                }
             });
 
             drag_controller.connect_drag_end(|drag_controller, _x, _y| {
@@ -303,29 +492,53 @@ mod imp {
                     .dynamic_cast::<super::GraphView>()
                     .expect("drag-update event is not on the GraphView");
 
+                if widget.imp().rubberband.take().is_some() {
+                    widget.queue_draw();
+                    return;
+                }
+
+                let dragged_annotation = widget.imp().dragged_annotation.take();
+                if let Some(drag) = dragged_annotation {
+                    widget.end_annotation_drag(drag);
+                    return;
+                }
+
                 // Record undo action for node move
                 let dragged_node = widget.imp().dragged_node.borrow();
                 if let Some(DragState {
                     node,
                     original_position,
+                    selection,
                     ..
                 }) = dragged_node.as_ref()
                 {
-                    if let Some(node) = node.upgrade() {
-                        if let Some(new_position) = widget.node_position(&node) {
-                            // Only record if the position actually changed
-                            if (new_position.x() - original_position.x()).abs() > 0.1
-                                || (new_position.y() - original_position.y()).abs() > 0.1
-                            {
-                                widget.imp().undo_stack.borrow_mut().push(
-                                    crate::graphmanager::undo::UndoAction::MoveNode {
-                                        node_id: node.id(),
-                                        old_position: *original_position,
-                                        new_position,
-                                    },
-                                );
-                            }
-                        }
+                    // Only record the nodes whose position actually changed
+                    let moves: Vec<(u32, graphene::Point, graphene::Point)> =
+                        std::iter::once((node, original_position))
+                            .chain(selection.iter().map(|(node, position)| (node, position)))
+                            .filter_map(|(node, old_position)| {
+                                let node = node.upgrade()?;
+                                let new_position = widget.node_position(&node)?;
+                                ((new_position.x() - old_position.x()).abs() > 0.1
+                                    || (new_position.y() - old_position.y()).abs() > 0.1)
+                                    .then_some((node.id(), *old_position, new_position))
+                            })
+                            .collect();
+                    if moves.len() == 1 {
+                        let (node_id, old_position, new_position) = moves[0];
+                        widget.imp().undo_stack.borrow_mut().push(
+                            crate::graphmanager::undo::UndoAction::MoveNode {
+                                node_id,
+                                old_position,
+                                new_position,
+                            },
+                        );
+                    } else if !moves.is_empty() {
+                        widget
+                            .imp()
+                            .undo_stack
+                            .borrow_mut()
+                            .push(crate::graphmanager::undo::UndoAction::BatchMoveNodes { moves });
                     }
                 }
 
@@ -378,12 +591,23 @@ mod imp {
                             let node = target
                                 .dynamic_cast::<Node>()
                                 .expect("click event is not on the Node");
-                            widget.unselect_all();
-                            node.set_selected(true);
+                            // Keep the selection so the menu can act on all the selected nodes
+                            if !node.selected() {
+                                widget.unselect_all();
+                                node.set_selected(true);
+                            }
                             obj.emit_by_name::<()>(
                                 "node-right-clicked",
                                 &[&node.id(), &graphene::Point::new(x as f32, y as f32)],
                             );
+                        } else if let Some(annotation) =
+                            widget.annotation_at(&widget.screen_to_canvas(x, y))
+                        {
+                            widget.select_annotation(annotation.id());
+                            obj.emit_by_name::<()>(
+                                "annotation-right-clicked",
+                                &[&annotation.id(), &graphene::Point::new(x as f32, y as f32)],
+                            );
                         } else {
                             widget.unselect_all();
                             obj.emit_by_name::<()>(
@@ -410,8 +634,20 @@ mod imp {
                             let node = target
                                 .dynamic_cast::<Node>()
                                 .expect("click event is not on the Node");
-                            widget.unselect_all();
-                            node.toggle_selected();
+                            if gesture.current_event_state().intersects(
+                                gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::SHIFT_MASK,
+                            ) {
+                                // Add or remove the node from the selection
+                                widget.unselect_links();
+                                node.toggle_selected();
+                            } else if !node.selected() {
+                                widget.unselect_all();
+                                node.set_selected(true);
+                            }
+                        } else if let Some(annotation) =
+                            widget.annotation_at(&widget.screen_to_canvas(x, y))
+                        {
+                            widget.select_annotation(annotation.id());
                         } else {
                             widget.point_on_link(&graphene::Point::new(
                                 x.floor() as f32,
@@ -526,6 +762,16 @@ mod imp {
                                     widget.set_selected_port(None);
                                 } else {
                                     info!(" node id {}", node.id());
+                                    // A click without drag on a node of the selection selects it only
+                                    if node.selected()
+                                        && !gesture.current_event_state().intersects(
+                                            gdk::ModifierType::CONTROL_MASK
+                                                | gdk::ModifierType::SHIFT_MASK,
+                                        )
+                                    {
+                                        widget.unselect_all();
+                                        node.set_selected(true);
+                                    }
                                     if _n_press % 2 == 0 {
                                         info!("double clicked node id {}", node.id());
                                         obj.emit_by_name::<()>(
@@ -538,10 +784,20 @@ mod imp {
                                     }
                                 }
                             } else if _n_press % 2 == 0 {
-                                if let Some(link) = widget.point_on_link(&graphene::Point::new(
-                                    x.floor() as f32,
-                                    y.floor() as f32,
-                                )) {
+                                if let Some(annotation) =
+                                    widget.annotation_at(&widget.screen_to_canvas(x, y))
+                                {
+                                    info!("double clicked annotation id {}", annotation.id());
+                                    obj.emit_by_name::<()>(
+                                        "annotation-double-clicked",
+                                        &[
+                                            &annotation.id(),
+                                            &graphene::Point::new(x as f32, y as f32),
+                                        ],
+                                    );
+                                } else if let Some(link) = widget.point_on_link(
+                                    &graphene::Point::new(x.floor() as f32, y.floor() as f32),
+                                ) {
                                     info!("double clicked link id {}", link.id());
                                     obj.emit_by_name::<()>(
                                         "link-double-clicked",
@@ -664,6 +920,12 @@ mod imp {
                     Signal::builder("node-link-request")
                         .param_types([u32::static_type(), u32::static_type(), u32::static_type()])
                         .build(),
+                    Signal::builder("annotation-right-clicked")
+                        .param_types([u32::static_type(), graphene::Point::static_type()])
+                        .build(),
+                    Signal::builder("annotation-double-clicked")
+                        .param_types([u32::static_type(), graphene::Point::static_type()])
+                        .build(),
                 ]
             });
             SIGNALS.as_ref()
@@ -749,6 +1011,8 @@ mod imp {
             Try to use relative units (em) and colours from the theme as much as possible. */
             let widget = &*self.obj();
             let alloc = widget.allocation();
+            // Draw the notes and frames behind the nodes
+            self.draw_annotations(snapshot);
             // Draw all children
             // Draw all visible children
             self.nodes
@@ -758,22 +1022,47 @@ mod imp {
                 .filter(|(node, _)| alloc.intersect(&node.allocation()).is_some())
                 .for_each(|(node, _)| widget.snapshot_child(node, snapshot));
 
+            let obstacles = self.route_obstacles();
             for link in self.links.borrow().values() {
                 if let Some((from_x, from_y, to_x, to_y)) = self.link_coordinates(link) {
+                    let route = self.route_link(
+                        &graphene::Point::new(from_x as f32, from_y as f32),
+                        &graphene::Point::new(to_x as f32, to_y as f32),
+                        &obstacles,
+                    );
                     self.draw_link(
                         snapshot,
                         link.active(),
                         link.selected(),
                         link.name().as_str(),
                         link.thickness as f64,
-                        &graphene::Point::new(from_x as f32, from_y as f32),
-                        &graphene::Point::new(to_x as f32, to_y as f32),
+                        &route,
                     );
                 } else {
                     warn!("Could not get link coordinates: {:?}", link);
                 }
             }
 
+            // Draw the stubs of the ports connected outside of this graph
+            for (node, _) in self
+                .nodes
+                .borrow()
+                .values()
+                .filter(|(node, _)| alloc.intersect(&node.allocation()).is_some())
+            {
+                for port in node.ports().values() {
+                    if let Some(label) = port.stub_label() {
+                        let (x, y) = self.link_from_coordinates(node.id(), port.id());
+                        self.draw_port_stub(
+                            snapshot,
+                            &graphene::Point::new(x as f32, y as f32),
+                            port.direction(),
+                            &label,
+                        );
+                    }
+                }
+            }
+
             if self.port_selected.borrow().is_some() {
                 let port = self.port_selected.borrow();
                 let port = port.as_ref().unwrap();
@@ -784,14 +1073,20 @@ mod imp {
                     .expect("Unable to cast to Node");
                 let (from_x, from_y) = self.link_from_coordinates(node.id(), port.id());
                 let (to_x, to_y) = self.mouse_position.get();
-                self.draw_link(
-                    snapshot,
-                    false,
-                    false,
-                    "",
-                    2.0,
+                let route = self.route_link(
                     &graphene::Point::new(from_x as f32, from_y as f32),
                     &graphene::Point::new(to_x as f32, to_y as f32),
+                    &obstacles,
+                );
+                self.draw_link(snapshot, false, false, "", 2.0, &route);
+            }
+
+            if let Some((start, end)) = self.rubberband.get() {
+                let transform = self.canvas_space_to_screen_space_transform();
+                self.draw_rubberband(
+                    snapshot,
+                    &transform.transform_point(&start),
+                    &transform.transform_point(&end),
                 );
             }
         }
@@ -819,7 +1114,7 @@ mod imp {
         /// Returns a [`gsk::Transform`] matrix that can translate from screen space to canvas space.
         ///
         /// This is the inverted form of [`Self::canvas_space_to_screen_space_transform()`], see that function for a more detailed explanation.
-        fn screen_space_to_canvas_space_transform(&self) -> gsk::Transform {
+        pub(super) fn screen_space_to_canvas_space_transform(&self) -> gsk::Transform {
             self.canvas_space_to_screen_space_transform()
                 .invert()
                 .unwrap()
@@ -866,6 +1161,39 @@ mod imp {
 
             (x, y)
         }
+        /// Retrieves the rectangles of the nodes in screen space, for the links to go
+        /// around them. Only the orthogonal links avoid the nodes.
+        pub(super) fn route_obstacles(&self) -> Vec<graphene::Rect> {
+            if self.link_routing.get() != LinkRouting::Orthogonal {
+                return Vec::new();
+            }
+            let widget = &*self.obj();
+            self.nodes
+                .borrow()
+                .values()
+                .filter_map(|(node, _)| node.compute_bounds(widget))
+                .collect()
+        }
+
+        /// Retrieves the path of a link between two points in screen space, following
+        /// the routing of the graph.
+        pub(super) fn route_link(
+            &self,
+            from: &graphene::Point,
+            to: &graphene::Point,
+            obstacles: &[graphene::Rect],
+        ) -> Vec<graphene::Point> {
+            let zoom_factor = self.zoom_factor.get() as f32;
+            link_route(
+                self.link_routing.get(),
+                from,
+                to,
+                obstacles,
+                LINK_ROUTE_STUB * zoom_factor,
+                LINK_ROUTE_MARGIN * zoom_factor,
+            )
+        }
+
         /// Retrieves coordinates for the drawn link to start at and to end at.
         ///
         /// # Returns
@@ -875,7 +1203,166 @@ mod imp {
             let (to_x, to_y) = self.link_to_coordinates(link.node_to, link.port_to);
             Some((from_x, from_y, to_x, to_y))
         }
-        #[allow(clippy::too_many_arguments)]
+        /// Draw a short dashed line leaving the port with the stub label at its end.
+        fn draw_port_stub(
+            &self,
+            snapshot: &gtk::Snapshot,
+            point: &graphene::Point,
+            direction: PortDirection,
+            label: &str,
+        ) {
+            let alloc = self.obj().allocation();
+            let stub_cr = snapshot.append_cairo(&graphene::Rect::new(
+                0.0,
+                0.0,
+                alloc.width() as f32,
+                alloc.height() as f32,
+            ));
+            let (x, y) = (point.x() as f64, point.y() as f64);
+            let end_x = if direction == PortDirection::Output {
+                x + PORT_STUB_LENGTH
+            } else {
+                x - PORT_STUB_LENGTH
+            };
+            let color = self.link_color.get();
+            stub_cr.set_source_rgb(color.0, color.1, color.2);
+            stub_cr.set_line_width(2.0);
+            stub_cr.set_dash(&[4.0, 3.0], 0.0);
+            stub_cr.move_to(x, y);
+            stub_cr.line_to(end_x, y);
+            if let Err(e) = stub_cr.stroke() {
+                warn!("Failed to draw port stub: {}", e);
+            }
+            let text_x = if direction == PortDirection::Output {
+                end_x + 4.0
+            } else {
+                let width = stub_cr
+                    .text_extents(label)
+                    .map(|extents| extents.x_advance())
+                    .unwrap_or_default();
+                end_x - 4.0 - width
+            };
+            stub_cr.move_to(text_x, y + 4.0);
+            let _ = stub_cr.show_text(label);
+        }
+
+        /// Draw the frames then the notes, in canvas space.
+        fn draw_annotations(&self, snapshot: &gtk::Snapshot) {
+            let annotations = self.annotations.borrow();
+            if annotations.is_empty() {
+                return;
+            }
+            let widget = &*self.obj();
+            let (r, g, b) = LINK_COLOR_SELECTED;
+            let selected_color = gdk::RGBA::new(r as f32, g as f32, b as f32, 1.0);
+            let text_color = gdk::RGBA::new(0.1, 0.1, 0.1, 0.9);
+
+            snapshot.save();
+            snapshot.transform(Some(&self.canvas_space_to_screen_space_transform()));
+            for annotation in annotations.iter() {
+                let rect = &annotation.rect;
+                let mut fill = annotation.color;
+                let mut border =
+                    gdk::RGBA::new(fill.red() * 0.6, fill.green() * 0.6, fill.blue() * 0.6, 1.0);
+                let mut border_width = 1.0;
+                if annotation.kind == AnnotationKind::Frame {
+                    fill.set_alpha(FRAME_FILL_ALPHA);
+                    snapshot.append_color(&fill, rect);
+                    // The title bar is opaque to show where to grab the frame
+                    let mut title = annotation.color;
+                    title.set_alpha(0.9);
+                    snapshot.append_color(
+                        &title,
+                        &graphene::Rect::new(rect.x(), rect.y(), rect.width(), FRAME_TITLE_HEIGHT),
+                    );
+                } else {
+                    snapshot.append_color(&fill, rect);
+                }
+                if annotation.selected() {
+                    border = selected_color;
+                    border_width = 2.0;
+                }
+                snapshot.append_border(
+                    &gsk::RoundedRect::from_rect(*rect, 0.0),
+                    &[border_width; 4],
+                    &[border; 4],
+                );
+                if annotation.selected() {
+                    snapshot.append_color(
+                        &border,
+                        &graphene::Rect::new(
+                            rect.x() + rect.width() - ANNOTATION_HANDLE_SIZE,
+                            rect.y() + rect.height() - ANNOTATION_HANDLE_SIZE,
+                            ANNOTATION_HANDLE_SIZE,
+                            ANNOTATION_HANDLE_SIZE,
+                        ),
+                    );
+                }
+
+                if annotation.text.is_empty() {
+                    continue;
+                }
+                let layout = widget.create_pango_layout(Some(&annotation.text));
+                let text_width = (rect.width() - 2.0 * ANNOTATION_TEXT_PADDING).max(1.0);
+                layout.set_width((text_width as i32) * gtk::pango::SCALE);
+                if annotation.kind == AnnotationKind::Frame {
+                    layout.set_ellipsize(gtk::pango::EllipsizeMode::End);
+                } else {
+                    layout.set_wrap(gtk::pango::WrapMode::WordChar);
+                }
+                let text_height = if annotation.kind == AnnotationKind::Frame {
+                    FRAME_TITLE_HEIGHT
+                } else {
+                    rect.height()
+                } - ANNOTATION_TEXT_PADDING;
+                snapshot.push_clip(&graphene::Rect::new(
+                    rect.x() + ANNOTATION_TEXT_PADDING,
+                    rect.y(),
+                    text_width,
+                    text_height.max(1.0),
+                ));
+                snapshot.save();
+                snapshot.translate(&graphene::Point::new(
+                    rect.x() + ANNOTATION_TEXT_PADDING,
+                    rect.y() + ANNOTATION_TEXT_PADDING / 2.0,
+                ));
+                snapshot.append_layout(&layout, &text_color);
+                snapshot.restore();
+                snapshot.pop();
+            }
+            snapshot.restore();
+        }
+
+        /// Draw the rectangle of the rubber-band selection between two screen points.
+        fn draw_rubberband(
+            &self,
+            snapshot: &gtk::Snapshot,
+            start: &graphene::Point,
+            end: &graphene::Point,
+        ) {
+            let alloc = self.obj().allocation();
+            let band_cr = snapshot.append_cairo(&graphene::Rect::new(
+                0.0,
+                0.0,
+                alloc.width() as f32,
+                alloc.height() as f32,
+            ));
+            let (r, g, b) = LINK_COLOR_SELECTED;
+            band_cr.rectangle(
+                start.x().min(end.x()) as f64,
+                start.y().min(end.y()) as f64,
+                (end.x() - start.x()).abs() as f64,
+                (end.y() - start.y()).abs() as f64,
+            );
+            band_cr.set_source_rgba(r, g, b, RUBBERBAND_FILL_ALPHA);
+            let _ = band_cr.fill_preserve();
+            band_cr.set_source_rgb(r, g, b);
+            band_cr.set_line_width(1.0);
+            if let Err(e) = band_cr.stroke() {
+                warn!("Failed to draw the rubber-band selection: {}", e);
+            }
+        }
+
         fn draw_link(
             &self,
             snapshot: &gtk::Snapshot,
@@ -883,9 +1370,11 @@ mod imp {
             selected: bool,
             name: &str,
             thickness: f64,
-            point_from: &graphene::Point,
-            point_to: &graphene::Point,
+            route: &[graphene::Point],
         ) {
+            let (Some(point_from), Some(point_to)) = (route.first(), route.last()) else {
+                return;
+            };
             let alloc = self.obj().allocation();
 
             let link_cr = snapshot.append_cairo(&graphene::Rect::new(
@@ -911,7 +1400,23 @@ mod imp {
             link_cr.set_source_rgb(color.0, color.1, color.2);
 
             link_cr.move_to(point_from.x() as f64, point_from.y() as f64);
-            link_cr.line_to(point_to.x() as f64, point_to.y() as f64);
+            if self.link_routing.get() == LinkRouting::Bezier {
+                let zoom_factor = self.zoom_factor.get() as f32;
+                let (control_from, control_to) =
+                    bezier_control_points(point_from, point_to, LINK_ROUTE_STUB * zoom_factor);
+                link_cr.curve_to(
+                    control_from.x() as f64,
+                    control_from.y() as f64,
+                    control_to.x() as f64,
+                    control_to.y() as f64,
+                    point_to.x() as f64,
+                    point_to.y() as f64,
+                );
+            } else {
+                for point in &route[1..] {
+                    link_cr.line_to(point.x() as f64, point.y() as f64);
+                }
+            }
             link_cr.set_line_width(2.0);
 
             if let Err(e) = link_cr.stroke() {
@@ -919,12 +1424,64 @@ mod imp {
             };
             trace!("the link name is {}", name);
             if !name.is_empty() {
-                let x = (point_from.x() + point_to.x()) / 2.0 + 20.0;
-                let y = (point_from.y() + point_to.y()) / 2.0 + 20.0;
-                link_cr.move_to(x as f64, y as f64);
-                let _ = link_cr.show_text(name);
+                self.draw_link_badge(&link_cr, name, &route_midpoint(route), color);
             }
         }
+
+        /// Draw the name of a link, its caps filter, in a rounded badge centered on the link.
+        fn draw_link_badge(
+            &self,
+            link_cr: &gtk::cairo::Context,
+            name: &str,
+            middle: &graphene::Point,
+            color: (f64, f64, f64),
+        ) {
+            let label = if name.chars().count() > LINK_BADGE_MAX_CHARS {
+                let truncated: String = name.chars().take(LINK_BADGE_MAX_CHARS - 1).collect();
+                format!("{truncated}…")
+            } else {
+                name.to_string()
+            };
+            link_cr.set_font_size(11.0);
+            let Ok(extents) = link_cr.text_extents(&label) else {
+                return;
+            };
+            let padding = 4.0;
+            let width = extents.x_advance() + 2.0 * padding;
+            let height = 11.0 + 2.0 * padding;
+            let x = middle.x() as f64 - width / 2.0;
+            let y = middle.y() as f64 - height / 2.0;
+            let radius = height / 2.0;
+
+            link_cr.set_dash(&[], 0.0);
+            link_cr.new_sub_path();
+            link_cr.arc(
+                x + width - radius,
+                y + radius,
+                radius,
+                -std::f64::consts::FRAC_PI_2,
+                std::f64::consts::FRAC_PI_2,
+            );
+            link_cr.arc(
+                x + radius,
+                y + radius,
+                radius,
+                std::f64::consts::FRAC_PI_2,
+                3.0 * std::f64::consts::FRAC_PI_2,
+            );
+            link_cr.close_path();
+            link_cr.set_source_rgb(1.0, 1.0, 1.0);
+            let _ = link_cr.fill_preserve();
+            link_cr.set_source_rgb(color.0, color.1, color.2);
+            link_cr.set_line_width(1.0);
+            let _ = link_cr.stroke();
+
+            link_cr.move_to(
+                x + padding - extents.x_bearing(),
+                middle.y() as f64 - extents.y_bearing() - extents.height() / 2.0,
+            );
+            let _ = link_cr.show_text(&label);
+        }
     }
 }
 
@@ -1003,6 +1560,21 @@ impl GraphView {
         private.link_color.get()
     }
 
+    /// Set the shape of the links of this graph, saved with the graph.
+    pub fn set_link_routing(&self, routing: LinkRouting) {
+        let private = imp::GraphView::from_obj(self);
+        if private.link_routing.get() != routing {
+            private.link_routing.set(routing);
+            self.graph_updated();
+            self.queue_draw();
+        }
+    }
+
+    pub fn link_routing(&self) -> LinkRouting {
+        let private = imp::GraphView::from_obj(self);
+        private.link_routing.get()
+    }
+
     /// Set custom CSS for the graphview
     ///
     /// This allows the app to inject custom CSS that overrides the default styles.
@@ -1040,6 +1612,7 @@ impl GraphView {
     ///
     pub fn clear(&self) {
         self.remove_all_nodes();
+        self.remove_all_annotations();
         self.graph_updated();
     }
 
@@ -1172,9 +1745,24 @@ impl GraphView {
         self.scroll_to_position(x, y);
     }
 
-    /// Remove node from the graphview
+    /// Remove node from the graphview, with the nodes embedded in its properties
     ///
     pub fn remove_node(&self, id: u32) {
+        let embedded_ids = self.embedded_node_ids(id);
+        if embedded_ids.is_empty() {
+            self.remove_node_and_links(id);
+            return;
+        }
+        // Undo the removal of the node and its embedded nodes in a single step
+        self.begin_batch();
+        for embedded_id in embedded_ids {
+            self.remove_node(embedded_id);
+        }
+        self.remove_node_and_links(id);
+        self.end_batch();
+    }
+
+    fn remove_node_and_links(&self, id: u32) {
         let private = imp::GraphView::from_obj(self);
 
         // Collect node data and connected links before removal
@@ -1245,6 +1833,21 @@ impl GraphView {
         nodes_list
     }
 
+    /// Ids of the nodes embedded in the properties of a node, see `EMBEDDED_PROPERTY`.
+    pub fn embedded_node_ids(&self, parent_id: u32) -> Vec<u32> {
+        let mut node_ids: Vec<u32> = self
+            .all_nodes(NodeType::All)
+            .iter()
+            .filter(|node| {
+                node.embedded_parent()
+                    .is_some_and(|(id, _)| id == parent_id)
+            })
+            .map(|node| node.id())
+            .collect();
+        node_ids.sort();
+        node_ids
+    }
+
     /// Get the node with the specified node id inside the graphview.
     ///
     /// Returns `None` if the node is not in the graphview.
@@ -1499,24 +2102,29 @@ impl GraphView {
     ///
     pub fn delete_selected(&self) {
         let private = imp::GraphView::from_obj(self);
+        // Deleting the selection is undone as a single step
+        private.undo_stack.borrow_mut().begin_batch();
         let mut link_id = None;
-        let mut node_id = None;
         for link in private.links.borrow_mut().values() {
             if link.selected() {
                 link_id = Some(link.id);
             }
         }
-        for node in private.nodes.borrow_mut().values() {
-            if node.0.selected() {
-                node_id = Some(node.0.id());
-            }
-        }
         if let Some(id) = link_id {
             self.remove_link(id);
         }
-        if let Some(id) = node_id {
-            self.remove_node(id);
+        for node in self.selected_nodes() {
+            // An embedded node is already removed along with its parent
+            if self.node(node.id()).is_some() {
+                self.remove_node(node.id());
+            }
+        }
+        for annotation in self.all_annotations() {
+            if annotation.selected() {
+                self.remove_annotation(annotation.id());
+            }
         }
+        private.undo_stack.borrow_mut().end_batch();
 
         self.graph_updated();
     }
@@ -1534,7 +2142,8 @@ impl GraphView {
         writer.write(
             XMLWEvent::start_element("Graph")
                 .attr("id", &private.id.get().to_string())
-                .attr("version", GRAPHVIEW_XML_VERSION),
+                .attr("version", GRAPHVIEW_XML_VERSION)
+                .attr("link_routing", &self.link_routing().to_string()),
         )?;
 
         //Get the nodes
@@ -1547,7 +2156,8 @@ impl GraphView {
                     .attr("type", &node.node_type().unwrap().to_string())
                     .attr("pos_x", &node.position().0.to_string())
                     .attr("pos_y", &node.position().1.to_string())
-                    .attr("light", &node.light().to_string()),
+                    .attr("light", &node.light().to_string())
+                    .attr("pinned", &node.pinned().to_string()),
             )?;
             // Sort ports by name to ensure consistent ordering when saving/loading
             // This preserves visual port positions (e.g., sink_0 before sink_1)
@@ -1597,6 +2207,23 @@ impl GraphView {
             )?;
             writer.write(XMLWEvent::end_element())?;
         }
+        //Get the notes and frames, the text is kept as content to preserve the new lines
+        for annotation in private.annotations.borrow().iter() {
+            writer.write(
+                XMLWEvent::start_element("Annotation")
+                    .attr("id", &annotation.id.to_string())
+                    .attr("kind", &annotation.kind.to_string())
+                    .attr("pos_x", &annotation.rect.x().to_string())
+                    .attr("pos_y", &annotation.rect.y().to_string())
+                    .attr("width", &annotation.rect.width().to_string())
+                    .attr("height", &annotation.rect.height().to_string())
+                    .attr("color", &annotation.color.to_string()),
+            )?;
+            if !annotation.text.is_empty() {
+                writer.write(XMLWEvent::characters(&annotation.text))?;
+            }
+            writer.write(XMLWEvent::end_element())?;
+        }
         writer.write(XMLWEvent::end_element())?;
         Ok(buffer)
     }
@@ -1618,6 +2245,7 @@ impl GraphView {
         let mut current_port: Option<Port> = None;
         let mut current_port_properties: HashMap<String, String> = HashMap::new();
         let mut current_link: Option<Link> = None;
+        let mut current_annotation: Option<Annotation> = None;
         for e in parser {
             match e {
                 Ok(XMLREvent::StartElement {
@@ -1641,6 +2269,12 @@ impl GraphView {
                             } else {
                                 warn!("No file format version found");
                             }
+                            self.set_link_routing(
+                                attrs
+                                    .get::<String>(&String::from("link_routing"))
+                                    .map(|routing| LinkRouting::from_str(routing))
+                                    .unwrap_or_default(),
+                            );
                         }
                         "Node" => {
                             // Clear properties from any previous node before starting a new one
@@ -1666,6 +2300,9 @@ impl GraphView {
                             let light: &String = attrs
                                 .get::<String>(&String::from("light"))
                                 .unwrap_or(&default_value);
+                            let pinned: &String = attrs
+                                .get::<String>(&String::from("pinned"))
+                                .unwrap_or(&default_value);
                             let node = self.create_node_with_id(
                                 id.parse::<u32>().unwrap(),
                                 name,
@@ -1676,6 +2313,7 @@ impl GraphView {
                                 pos_y.parse::<f32>().unwrap(),
                             );
                             node.set_light(light.parse::<bool>().unwrap());
+                            node.set_pinned(pinned.parse::<bool>().unwrap_or(false));
                             current_node = Some(node);
                         }
                         "Property" => {
@@ -1748,6 +2386,37 @@ impl GraphView {
                             link.set_name(name.parse::<String>().unwrap().as_str());
                             current_link = Some(link);
                         }
+                        "Annotation" => {
+                            // An annotation is not worth failing to load the graph
+                            let id = attrs.get("id").and_then(|id| id.parse::<u32>().ok());
+                            let (Some(id), Some(kind)) = (id, attrs.get("kind")) else {
+                                warn!("Skipping an annotation without a valid id or kind");
+                                continue;
+                            };
+                            let value = |name: &str, default: f32| {
+                                attrs
+                                    .get(name)
+                                    .and_then(|v| v.parse::<f32>().ok())
+                                    .unwrap_or(default)
+                            };
+                            let mut annotation = Annotation::new(
+                                id,
+                                AnnotationKind::from_str(kind),
+                                "",
+                                graphene::Rect::new(
+                                    value("pos_x", 0.0),
+                                    value("pos_y", 0.0),
+                                    value("width", ANNOTATION_MIN_SIZE),
+                                    value("height", ANNOTATION_MIN_SIZE),
+                                ),
+                            );
+                            if let Some(color) =
+                                attrs.get("color").and_then(|c| gdk::RGBA::parse(c).ok())
+                            {
+                                annotation.color = color;
+                            }
+                            current_annotation = Some(annotation);
+                        }
                         _ => warn!("name unknown: {}", name),
                     }
                 }
@@ -1801,9 +2470,19 @@ impl GraphView {
                             }
                             current_link = None;
                         }
+                        "Annotation" => {
+                            if let Some(annotation) = current_annotation.take() {
+                                self.insert_annotation(annotation);
+                            }
+                        }
                         _ => warn!("name unknown: {}", name),
                     }
                 }
+                Ok(XMLREvent::Characters(text)) => {
+                    if let Some(annotation) = current_annotation.as_mut() {
+                        annotation.text.push_str(&text);
+                    }
+                }
                 Err(e) => {
                     error!("Error: {}", e);
                     break;
@@ -2300,75 +2979,269 @@ impl GraphView {
     fn unselect_all(&self) {
         self.unselect_nodes();
         self.unselect_links();
-        self.queue_draw();
-    }
-
-    fn point_on_link(&self, point: &graphene::Point) -> Option<Link> {
-        let private = imp::GraphView::from_obj(self);
-        self.unselect_all();
-        for link in private.links.borrow_mut().values() {
-            if let Some((from_x, from_y, to_x, to_y)) = private.link_coordinates(link) {
-                let quad = graphene::Quad::new(
-                    &graphene::Point::new(from_x as f32, from_y as f32 - link.thickness as f32),
-                    &graphene::Point::new(to_x as f32, to_y as f32 - link.thickness as f32),
-                    &graphene::Point::new(to_x as f32, to_y as f32 + link.thickness as f32),
-                    &graphene::Point::new(from_x as f32, from_y as f32 + link.thickness as f32),
-                );
-                if quad.contains(point) {
-                    link.toggle_selected();
-                    self.queue_draw();
-                    return Some(link.clone());
-                }
-            }
+        for annotation in imp::GraphView::from_obj(self).annotations.borrow().iter() {
+            annotation.set_selected(false);
         }
         self.queue_draw();
-        None
-    }
-
-    pub fn graph_updated(&self) {
-        let private = imp::GraphView::from_obj(self);
-        self.queue_allocate();
-        self.emit_by_name::<()>("graph-updated", &[&private.id.get()]);
     }
 
-    fn next_node_id(&self) -> u32 {
+    /// Retrieves the selected nodes.
+    pub fn selected_nodes(&self) -> Vec<Node> {
         let private = imp::GraphView::from_obj(self);
         private
-            .current_node_id
-            .set(private.current_node_id.get() + 1);
-        private.current_node_id.get()
+            .nodes
+            .borrow()
+            .values()
+            .filter(|(node, _)| node.selected())
+            .map(|(node, _)| node.clone())
+            .collect()
     }
 
-    fn next_port_id(&self) -> u32 {
+    /// Select the nodes intersecting the rectangle between two points in canvas space.
+    fn select_nodes_in_rect(&self, start: &graphene::Point, end: &graphene::Point) {
         let private = imp::GraphView::from_obj(self);
-        private
-            .current_port_id
-            .set(private.current_port_id.get() + 1);
-        private.current_port_id.get()
+        let rect = graphene::Rect::new(
+            start.x().min(end.x()),
+            start.y().min(end.y()),
+            (end.x() - start.x()).abs(),
+            (end.y() - start.y()).abs(),
+        );
+        for (node, point) in private.nodes.borrow().values() {
+            let node_rect = graphene::Rect::new(
+                point.x(),
+                point.y(),
+                node.width() as f32,
+                node.height() as f32,
+            );
+            node.set_selected(rect.intersection(&node_rect).is_some());
+        }
+        self.queue_draw();
     }
 
-    fn next_link_id(&self) -> u32 {
+    /// Snap the nodes to a grid while they are dragged, `None` disables the grid.
+    pub fn set_grid_snap(&self, spacing: Option<f64>) {
         let private = imp::GraphView::from_obj(self);
         private
-            .current_link_id
-            .set(private.current_link_id.get() + 1);
-        private.current_link_id.get()
+            .grid_snap
+            .set(spacing.filter(|spacing| *spacing > 0.0));
     }
 
-    fn set_selected_port(&self, port: Option<&Port>) {
-        if port.is_some() {
-            self.unselect_all();
-        }
+    pub fn grid_snap(&self) -> Option<f64> {
         let private = imp::GraphView::from_obj(self);
-        *private.port_selected.borrow_mut() = port.cloned();
+        private.grid_snap.get()
     }
 
-    fn selected_port(&self) -> RefMut<'_, Option<Port>> {
-        let private = imp::GraphView::from_obj(self);
-        private.port_selected.borrow_mut()
+    /// Closest point of the grid, or the point itself when the grid is disabled.
+    pub fn snap_to_grid(&self, point: &graphene::Point) -> graphene::Point {
+        match self.grid_snap() {
+            Some(spacing) => graphene::Point::new(
+                ((point.x() as f64 / spacing).round() * spacing) as f32,
+                ((point.y() as f64 / spacing).round() * spacing) as f32,
+            ),
+            None => *point,
+        }
     }
 
-    fn set_mouse_position(&self, x: f64, y: f64) {
+    /// Move several nodes as one undoable action.
+    ///
+    /// Returns true if at least one node has moved.
+    fn move_nodes(&self, moves: Vec<(u32, graphene::Point, graphene::Point)>) -> bool {
+        let private = imp::GraphView::from_obj(self);
+        let moves: Vec<_> = moves
+            .into_iter()
+            .filter(|(_, old_pos, new_pos)| {
+                (new_pos.x() - old_pos.x()).abs() > 0.1 || (new_pos.y() - old_pos.y()).abs() > 0.1
+            })
+            .collect();
+        if moves.is_empty() {
+            return false;
+        }
+        for (node_id, _, new_pos) in &moves {
+            if let Some(node) = self.node(*node_id) {
+                self.move_node(&node, new_pos);
+            }
+        }
+        private
+            .undo_stack
+            .borrow_mut()
+            .push(crate::graphmanager::undo::UndoAction::BatchMoveNodes { moves });
+        self.graph_updated();
+        true
+    }
+
+    /// Position and size of the selected nodes in canvas space.
+    fn selected_node_rects(&self) -> Vec<(u32, graphene::Rect)> {
+        let private = imp::GraphView::from_obj(self);
+        private
+            .nodes
+            .borrow()
+            .values()
+            .filter(|(node, _)| node.selected())
+            .map(|(node, point)| {
+                (
+                    node.id(),
+                    graphene::Rect::new(
+                        point.x(),
+                        point.y(),
+                        node.width() as f32,
+                        node.height() as f32,
+                    ),
+                )
+            })
+            .collect()
+    }
+
+    /// Align the selected nodes on the bounding box of the selection.
+    ///
+    /// Returns false if less than two nodes are selected or no node has moved.
+    pub fn align_selected_nodes(&self, alignment: NodeAlignment) -> bool {
+        let rects = self.selected_node_rects();
+        if rects.len() < 2 {
+            return false;
+        }
+        let bounds = rects
+            .iter()
+            .skip(1)
+            .fold(rects[0].1, |bounds, (_, rect)| bounds.union(rect));
+        let moves = rects
+            .iter()
+            .map(|(node_id, rect)| {
+                let (x, y) = match alignment {
+                    NodeAlignment::Left => (bounds.x(), rect.y()),
+                    NodeAlignment::Right => (bounds.x() + bounds.width() - rect.width(), rect.y()),
+                    NodeAlignment::Top => (rect.x(), bounds.y()),
+                    NodeAlignment::Bottom => {
+                        (rect.x(), bounds.y() + bounds.height() - rect.height())
+                    }
+                    NodeAlignment::Center => {
+                        (bounds.x() + (bounds.width() - rect.width()) / 2.0, rect.y())
+                    }
+                    NodeAlignment::Middle => (
+                        rect.x(),
+                        bounds.y() + (bounds.height() - rect.height()) / 2.0,
+                    ),
+                };
+                (
+                    *node_id,
+                    graphene::Point::new(rect.x(), rect.y()),
+                    graphene::Point::new(x, y),
+                )
+            })
+            .collect();
+        self.move_nodes(moves)
+    }
+
+    /// Distribute the selected nodes with the same gap between them, horizontally or
+    /// vertically. The first and the last nodes keep their position.
+    ///
+    /// Returns false if less than three nodes are selected or no node has moved.
+    pub fn distribute_selected_nodes(&self, orientation: gtk::Orientation) -> bool {
+        let mut rects = self.selected_node_rects();
+        if rects.len() < 3 {
+            return false;
+        }
+        let horizontal = orientation == gtk::Orientation::Horizontal;
+        let start = |rect: &graphene::Rect| if horizontal { rect.x() } else { rect.y() };
+        let size = |rect: &graphene::Rect| {
+            if horizontal {
+                rect.width()
+            } else {
+                rect.height()
+            }
+        };
+        rects.sort_by(|(_, a), (_, b)| start(a).partial_cmp(&start(b)).unwrap_or(Ordering::Equal));
+        let first = start(&rects[0].1);
+        let last = rects
+            .last()
+            .map(|(_, rect)| start(rect) + size(rect))
+            .unwrap();
+        let total_size: f32 = rects.iter().map(|(_, rect)| size(rect)).sum();
+        let gap = (last - first - total_size) / (rects.len() - 1) as f32;
+
+        let mut position = first;
+        let mut moves = Vec::new();
+        for (node_id, rect) in &rects {
+            let new_position = if horizontal {
+                graphene::Point::new(position, rect.y())
+            } else {
+                graphene::Point::new(rect.x(), position)
+            };
+            moves.push((
+                *node_id,
+                graphene::Point::new(rect.x(), rect.y()),
+                new_position,
+            ));
+            position += size(rect) + gap;
+        }
+        self.move_nodes(moves)
+    }
+
+    fn point_on_link(&self, point: &graphene::Point) -> Option<Link> {
+        let private = imp::GraphView::from_obj(self);
+        self.unselect_all();
+        let obstacles = private.route_obstacles();
+        for link in private.links.borrow_mut().values() {
+            if let Some((from_x, from_y, to_x, to_y)) = private.link_coordinates(link) {
+                let route = private.route_link(
+                    &graphene::Point::new(from_x as f32, from_y as f32),
+                    &graphene::Point::new(to_x as f32, to_y as f32),
+                    &obstacles,
+                );
+                if route_contains(&route, point, link.thickness as f32) {
+                    link.toggle_selected();
+                    self.queue_draw();
+                    return Some(link.clone());
+                }
+            }
+        }
+        self.queue_draw();
+        None
+    }
+
+    pub fn graph_updated(&self) {
+        let private = imp::GraphView::from_obj(self);
+        self.queue_allocate();
+        self.emit_by_name::<()>("graph-updated", &[&private.id.get()]);
+    }
+
+    fn next_node_id(&self) -> u32 {
+        let private = imp::GraphView::from_obj(self);
+        private
+            .current_node_id
+            .set(private.current_node_id.get() + 1);
+        private.current_node_id.get()
+    }
+
+    fn next_port_id(&self) -> u32 {
+        let private = imp::GraphView::from_obj(self);
+        private
+            .current_port_id
+            .set(private.current_port_id.get() + 1);
+        private.current_port_id.get()
+    }
+
+    fn next_link_id(&self) -> u32 {
+        let private = imp::GraphView::from_obj(self);
+        private
+            .current_link_id
+            .set(private.current_link_id.get() + 1);
+        private.current_link_id.get()
+    }
+
+    fn set_selected_port(&self, port: Option<&Port>) {
+        if port.is_some() {
+            self.unselect_all();
+        }
+        let private = imp::GraphView::from_obj(self);
+        *private.port_selected.borrow_mut() = port.cloned();
+    }
+
+    fn selected_port(&self) -> RefMut<'_, Option<Port>> {
+        let private = imp::GraphView::from_obj(self);
+        private.port_selected.borrow_mut()
+    }
+
+    fn set_mouse_position(&self, x: f64, y: f64) {
         let private = imp::GraphView::from_obj(self);
         private.mouse_position.set((x, y));
     }
@@ -2473,14 +3346,906 @@ impl GraphView {
         }
     }
 
+    /// Retrieves the position and the size of a node in graph coordinates.
+    pub fn node_rect(&self, node_id: u32) -> Option<graphene::Rect> {
+        let private = imp::GraphView::from_obj(self);
+        private.nodes.borrow().get(&node_id).map(|(node, point)| {
+            graphene::Rect::new(
+                point.x(),
+                point.y(),
+                node.width() as f32,
+                node.height() as f32,
+            )
+        })
+    }
+
+    /// Retrieves the bounding box of all the nodes, or of the selected ones only, in
+    /// graph coordinates.
+    ///
+    /// Returns `None` if there is no such node.
+    pub fn nodes_bounds(&self, selected_only: bool) -> Option<graphene::Rect> {
+        let private = imp::GraphView::from_obj(self);
+        private
+            .nodes
+            .borrow()
+            .values()
+            .filter(|(node, _)| !selected_only || node.selected())
+            .map(|(node, point)| {
+                graphene::Rect::new(
+                    point.x(),
+                    point.y(),
+                    node.width() as f32,
+                    node.height() as f32,
+                )
+            })
+            .reduce(|bounds, rect| bounds.union(&rect))
+    }
+
+    /// Retrieves the area of the graph visible in the view, in graph coordinates.
+    pub fn visible_rect(&self) -> Option<graphene::Rect> {
+        let private = imp::GraphView::from_obj(self);
+        let zoom_factor = private.zoom_factor.get() as f32;
+        let hadjustment = private.hadjustment.borrow();
+        let vadjustment = private.vadjustment.borrow();
+        let (hadjustment, vadjustment) = (hadjustment.as_ref()?, vadjustment.as_ref()?);
+        Some(graphene::Rect::new(
+            hadjustment.value() as f32 / zoom_factor,
+            vadjustment.value() as f32 / zoom_factor,
+            self.width() as f32 / zoom_factor,
+            self.height() as f32 / zoom_factor,
+        ))
+    }
+
+    /// Zoom and scroll the view so the given area, in graph coordinates, is fully
+    /// visible. The view is never zoomed in above the default zoom level.
+    pub fn zoom_to_rect(&self, rect: &graphene::Rect) {
+        let private = imp::GraphView::from_obj(self);
+        let (width, height) = (self.width() as f64, self.height() as f64);
+        if width <= 0.0 || height <= 0.0 {
+            return;
+        }
+        let zoom_factor = (width / (rect.width() as f64 + 2.0 * ZOOM_TO_RECT_MARGIN))
+            .min(height / (rect.height() as f64 + 2.0 * ZOOM_TO_RECT_MARGIN))
+            .clamp(Self::ZOOM_MIN, 1.0);
+        self.set_property("zoom-factor", zoom_factor);
+
+        // Update the adjustment bounds to the new zoom level before scrolling
+        if let Some(ref hadjustment) = *private.hadjustment.borrow() {
+            self.set_adjustment_values(self, hadjustment, gtk::Orientation::Horizontal);
+        }
+        if let Some(ref vadjustment) = *private.vadjustment.borrow() {
+            self.set_adjustment_values(self, vadjustment, gtk::Orientation::Vertical);
+        }
+        let center = rect.center();
+        self.scroll_to_position(center.x(), center.y());
+    }
+
+    /// Zoom and scroll the view to display all the nodes.
+    ///
+    /// Returns false if the graph is empty.
+    pub fn zoom_to_fit(&self) -> bool {
+        match self.nodes_bounds(false) {
+            Some(bounds) => {
+                self.zoom_to_rect(&bounds);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Zoom and scroll the view to display the selected nodes.
+    ///
+    /// Returns false if no node is selected.
+    pub fn zoom_to_selection(&self) -> bool {
+        match self.nodes_bounds(true) {
+            Some(bounds) => {
+                self.zoom_to_rect(&bounds);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Search the nodes matching a text, ignoring the case.
+    ///
+    /// A node matches if the text is found in its factory name, its unique name, the
+    /// name of one of its ports, the name or value of one of its visible properties or
+    /// the caps filter of one of its links.
+    ///
+    /// Returns the node ids sorted from top to bottom and left to right.
+    pub fn search_nodes(&self, text: &str) -> Vec<u32> {
+        let private = imp::GraphView::from_obj(self);
+        let text = text.trim().to_lowercase();
+        if text.is_empty() {
+            return Vec::new();
+        }
+        let matches = |value: &str| value.to_lowercase().contains(&text);
+
+        let mut found: Vec<(u32, graphene::Point)> = Vec::new();
+        let nodes = private.nodes.borrow();
+        let links = private.links.borrow();
+        for (node, point) in nodes.values() {
+            let node_id = node.id();
+            let found_in_ports = node.ports().values().any(|port| matches(&port.name()));
+            let found_in_properties = node.properties().iter().any(|(name, value)| {
+                !node.hidden_property(name) && (matches(name) || matches(value))
+            });
+            let found_in_links = links.values().any(|link| {
+                (link.node_from == node_id || link.node_to == node_id) && matches(&link.name())
+            });
+            if matches(&node.name())
+                || matches(&node.unique_name())
+                || found_in_ports
+                || found_in_properties
+                || found_in_links
+            {
+                found.push((node_id, *point));
+            }
+        }
+        found.sort_by(|(id_a, a), (id_b, b)| {
+            a.y()
+                .total_cmp(&b.y())
+                .then(a.x().total_cmp(&b.x()))
+                .then(id_a.cmp(id_b))
+        });
+        found.into_iter().map(|(id, _)| id).collect()
+    }
+
+    /// Highlight the given nodes and remove the highlight from all the others.
+    pub fn highlight_nodes(&self, node_ids: &[u32]) {
+        let private = imp::GraphView::from_obj(self);
+        for (node, _) in private.nodes.borrow().values() {
+            node.set_highlighted(node_ids.contains(&node.id()));
+        }
+    }
+
+    /// Highlight a node and bring it into view, the selection is kept. The view is
+    /// centered on the node, and zoomed out if the node does not fit in it at the
+    /// current zoom level.
+    ///
+    /// Returns false if the node is not in the graph.
+    pub fn reveal_node(&self, node_id: u32) -> bool {
+        let Some(rect) = self.node_rect(node_id) else {
+            return false;
+        };
+        if let Some(node) = self.node(node_id) {
+            node.set_highlighted(true);
+        }
+        let fits = self
+            .visible_rect()
+            .map(|visible| rect.width() <= visible.width() && rect.height() <= visible.height())
+            .unwrap_or(true);
+        if fits {
+            self.center_on_node(node_id)
+        } else {
+            self.zoom_to_rect(&rect);
+            true
+        }
+    }
+
+    /// Select a link and bring the node it comes from into view.
+    ///
+    /// Returns false if the link is not in the graph.
+    pub fn reveal_link(&self, link_id: u32) -> bool {
+        let Some(link) = self.link(link_id) else {
+            return false;
+        };
+        self.reveal_node(link.node_from);
+        let private = imp::GraphView::from_obj(self);
+        if let Some(link) = private.links.borrow().get(&link_id) {
+            link.set_selected(true);
+        }
+        self.queue_draw();
+        true
+    }
+
+    /// Scroll the view to center it on a node, keeping the zoom level.
+    ///
+    /// Returns false if the node is not in the graph.
+    pub fn center_on_node(&self, node_id: u32) -> bool {
+        match self.node_rect(node_id) {
+            Some(rect) => {
+                let center = rect.center();
+                self.scroll_to_position(center.x(), center.y());
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Templates
+
+    /// Add the nodes embedded in the given nodes and drop the nodes embedded in a node
+    /// which is not given, so that embedded nodes are kept with their parent.
+    fn with_embedded_nodes(&self, node_ids: &[u32]) -> Vec<u32> {
+        let mut node_ids = node_ids.to_vec();
+        let mut index = 0;
+        while index < node_ids.len() {
+            for embedded_id in self.embedded_node_ids(node_ids[index]) {
+                if !node_ids.contains(&embedded_id) {
+                    node_ids.push(embedded_id);
+                }
+            }
+            index += 1;
+        }
+        loop {
+            let kept: HashSet<u32> = node_ids.iter().copied().collect();
+            let count = node_ids.len();
+            node_ids.retain(
+                |id| match self.node(*id).and_then(|node| node.embedded_parent()) {
+                    Some((parent_id, _)) => kept.contains(&parent_id),
+                    None => true,
+                },
+            );
+            if node_ids.len() == count {
+                return node_ids;
+            }
+        }
+    }
+
+    /// Copy nodes with their ports and the links between them into a template. The
+    /// ports which are not linked to another node of the template are exposed. The
+    /// nodes embedded in the copied nodes are copied with them.
+    pub fn template_from_nodes(&self, name: &str, node_ids: &[u32]) -> GraphTemplate {
+        let private = imp::GraphView::from_obj(self);
+        let node_ids = &self.with_embedded_nodes(node_ids);
+        let mut nodes: Vec<Node> = node_ids.iter().filter_map(|id| self.node(*id)).collect();
+        nodes.sort_by_key(|node| node.id());
+        let links: Vec<TemplateLink> = private
+            .links
+            .borrow()
+            .values()
+            .filter(|link| node_ids.contains(&link.node_from) && node_ids.contains(&link.node_to))
+            .map(|link| TemplateLink {
+                node_from: link.node_from,
+                node_to: link.node_to,
+                port_from: link.port_from,
+                port_to: link.port_to,
+                name: link.name(),
+            })
+            .collect();
+        let origin_x = nodes
+            .iter()
+            .map(|node| node.position().0)
+            .fold(f32::INFINITY, f32::min);
+        let origin_y = nodes
+            .iter()
+            .map(|node| node.position().1)
+            .fold(f32::INFINITY, f32::min);
+
+        let nodes = nodes
+            .iter()
+            .map(|node| {
+                let mut ports: Vec<Port> = node.ports().values().cloned().collect();
+                ports.sort_by_key(|port| port.name());
+                TemplateNode {
+                    id: node.id(),
+                    name: node.name(),
+                    node_type: node.node_type().cloned().unwrap_or(NodeType::Unknown),
+                    position: (node.position().0 - origin_x, node.position().1 - origin_y),
+                    properties: node.properties().clone(),
+                    ports: ports
+                        .iter()
+                        .map(|port| TemplatePort {
+                            id: port.id(),
+                            name: port.name(),
+                            direction: port.direction(),
+                            presence: port.presence(),
+                            properties: port.properties().clone(),
+                            exposed: !links.iter().any(|link| {
+                                link.port_from == port.id() || link.port_to == port.id()
+                            }),
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+        let mut links = links;
+        links.sort_by_key(|link| (link.node_from, link.port_from));
+        GraphTemplate {
+            name: name.to_string(),
+            nodes,
+            links,
+            parameters: vec![],
+        }
+    }
+
+    /// Insert a copy of the template with its top left corner at the given canvas
+    /// position. The new nodes are selected so they can be moved as a group.
+    ///
+    /// Returns the ids of the new nodes.
+    pub fn insert_template(&self, template: &GraphTemplate, origin: &graphene::Point) -> Vec<u32> {
+        let mut node_ids = HashMap::new();
+        let mut port_ids = HashMap::new();
+        self.unselect_all();
+        // Undo the insertion in a single step
+        self.begin_batch();
+        let nodes: Vec<Node> = template
+            .nodes
+            .iter()
+            .map(|template_node| {
+                let node = self.create_node(&template_node.name, template_node.node_type.clone());
+                node_ids.insert(template_node.id, node.id());
+                node
+            })
+            .collect();
+        for (template_node, node) in template.nodes.iter().zip(nodes) {
+            node.update_properties(&template_node.properties);
+            // Embed the node in the copy of its parent
+            if let Some((parent_id, property)) = node.embedded_parent() {
+                match node_ids.get(&parent_id) {
+                    Some(parent_id) => {
+                        node.add_property(EMBEDDED_PROPERTY, &format!("{parent_id}:{property}"))
+                    }
+                    None => node.remove_property(EMBEDDED_PROPERTY),
+                }
+            }
+            let node_id = node.id();
+            self.add_node(node);
+            for template_port in &template_node.ports {
+                let port = self.create_port(
+                    &template_port.name,
+                    template_port.direction,
+                    template_port.presence,
+                );
+                port.update_properties(&template_port.properties);
+                port_ids.insert(template_port.id, port.id());
+                if let Some(mut node) = self.node(node_id) {
+                    self.add_port_to_node(&mut node, port);
+                }
+            }
+            if let Some(node) = self.node(node_id) {
+                let (x, y) = template_node.position;
+                self.move_node(&node, &graphene::Point::new(origin.x() + x, origin.y() + y));
+                node.set_selected(true);
+            }
+        }
+        for template_link in &template.links {
+            if let (Some(node_from), Some(node_to), Some(port_from), Some(port_to)) = (
+                node_ids.get(&template_link.node_from),
+                node_ids.get(&template_link.node_to),
+                port_ids.get(&template_link.port_from),
+                port_ids.get(&template_link.port_to),
+            ) {
+                let link = self.create_link(*node_from, *node_to, *port_from, *port_to);
+                link.set_name(&template_link.name);
+                self.add_link(link);
+            }
+        }
+        self.end_batch();
+        self.graph_updated();
+        let mut new_ids: Vec<u32> = node_ids.into_values().collect();
+        new_ids.sort();
+        new_ids
+    }
+
+    /// Replace nodes by a single macro node holding them as a template. The macro node
+    /// gets one port per exposed port of the template, and the links from the rest of
+    /// the graph are moved to these ports.
+    ///
+    /// Returns the id of the macro node.
+    pub fn collapse_into_macro(&self, name: &str, node_ids: &[u32]) -> anyhow::Result<u32> {
+        if node_ids.is_empty() {
+            anyhow::bail!("No node to group");
+        }
+        if node_ids.iter().any(|id| {
+            self.node(*id)
+                .is_some_and(|node| node.node_type() == Some(&NodeType::Macro))
+        }) {
+            anyhow::bail!("A macro node can not contain another macro node");
+        }
+        let node_ids = &self.with_embedded_nodes(node_ids);
+        let template = self.template_from_nodes(name, node_ids);
+        let xml = String::from_utf8(template.render_xml()?)?;
+        let origin = node_ids
+            .iter()
+            .filter_map(|id| self.node(*id))
+            .map(|node| node.position())
+            .fold(
+                (f32::INFINITY, f32::INFINITY),
+                |(x, y), (node_x, node_y)| (x.min(node_x), y.min(node_y)),
+            );
+        // Links between the grouped nodes and the rest of the graph
+        let mut external_links = self.all_links(true);
+        external_links.extend(self.all_links(false));
+        external_links
+            .retain(|link| node_ids.contains(&link.node_from) != node_ids.contains(&link.node_to));
+        external_links.sort_by_key(|link| link.id);
+
+        let mut node = self.create_node(name, NodeType::Macro);
+        node.add_property(MACRO_TEMPLATE_PROPERTY, &xml);
+        let mut port_ids = HashMap::new();
+        for direction in [PortDirection::Input, PortDirection::Output] {
+            let prefix = if direction == PortDirection::Input {
+                "sink"
+            } else {
+                "src"
+            };
+            for (index, template_port) in template.exposed_ports(direction).iter().enumerate() {
+                let port = self.create_port(
+                    &format!("{prefix}_{index}"),
+                    direction,
+                    PortPresence::Always,
+                );
+                port.add_property(MACRO_PORT_PROPERTY, &template_port.id.to_string());
+                port_ids.insert(template_port.id, port.id());
+                self.add_port_to_node(&mut node, port);
+            }
+        }
+        let macro_id = node.id();
+        // Undo the grouping in a single step
+        self.begin_batch();
+        for node_id in node_ids {
+            // An embedded node is already removed along with its parent
+            if self.node(*node_id).is_some() {
+                self.remove_node(*node_id);
+            }
+        }
+        self.add_node(node);
+        if let Some(node) = self.node(macro_id) {
+            self.move_node(&node, &graphene::Point::new(origin.0, origin.1));
+        }
+        for external_link in external_links {
+            let (node_from, port_from) = match port_ids.get(&external_link.port_from) {
+                Some(port_id) => (macro_id, *port_id),
+                None => (external_link.node_from, external_link.port_from),
+            };
+            let (node_to, port_to) = match port_ids.get(&external_link.port_to) {
+                Some(port_id) => (macro_id, *port_id),
+                None => (external_link.node_to, external_link.port_to),
+            };
+            let link = self.create_link(node_from, node_to, port_from, port_to);
+            link.set_name(&external_link.name());
+            link.set_active(external_link.active());
+            self.add_link(link);
+        }
+        self.end_batch();
+        self.graph_updated();
+        Ok(macro_id)
+    }
+
+    /// Replace a node by another node at the same position. The links of the ports of
+    /// the replaced node found in `port_map`, from the old port id to the new port id,
+    /// are moved to the new node, the others are dropped. The nodes embedded in the
+    /// replaced node are kept in the new node.
+    ///
+    /// The whole replacement is recorded as a single undo step.
+    ///
+    /// Returns the id of the new node.
+    pub fn replace_node(
+        &self,
+        node_id: u32,
+        node: Node,
+        port_map: &HashMap<u32, u32>,
+    ) -> anyhow::Result<u32> {
+        use crate::graphmanager::undo::{LinkData, NodeData, UndoAction};
+
+        let private = imp::GraphView::from_obj(self);
+        let (old_node, position) = private
+            .nodes
+            .borrow()
+            .get(&node_id)
+            .map(|(node, position)| (NodeData::from_node(node), *position))
+            .ok_or_else(|| anyhow::anyhow!("No node with the id {}", node_id))?;
+        let link_data = |link: &Link| LinkData {
+            id: link.id,
+            node_from: link.node_from,
+            node_to: link.node_to,
+            port_from: link.port_from,
+            port_to: link.port_to,
+            active: link.active(),
+            name: link.name(),
+        };
+        let mut old_links: Vec<LinkData> = private
+            .links
+            .borrow()
+            .values()
+            .filter(|link| link.node_from == node_id || link.node_to == node_id)
+            .map(link_data)
+            .collect();
+        old_links.sort_by_key(|link| link.id);
+
+        let new_id = node.id();
+        self.begin_batch();
+        for embedded_id in self.embedded_node_ids(node_id) {
+            if let Some((_, property)) = self.node(embedded_id).and_then(|n| n.embedded_parent()) {
+                self.modify_node_property(
+                    embedded_id,
+                    EMBEDDED_PROPERTY,
+                    &format!("{new_id}:{property}"),
+                );
+            }
+        }
+        let recording = private.undo_stack.borrow().is_recording();
+        private.undo_stack.borrow_mut().disable_recording();
+        self.remove_node_and_links(node_id);
+        self.add_node(node);
+        let new_node = self.node(new_id);
+        if let Some(new_node) = &new_node {
+            self.move_node(new_node, &position);
+        }
+        let mut new_links = vec![];
+        for old_link in &old_links {
+            let (node_from, port_from) = match port_map.get(&old_link.port_from) {
+                Some(port_id) if old_link.node_from == node_id => (new_id, *port_id),
+                None if old_link.node_from == node_id => continue,
+                _ => (old_link.node_from, old_link.port_from),
+            };
+            let (node_to, port_to) = match port_map.get(&old_link.port_to) {
+                Some(port_id) if old_link.node_to == node_id => (new_id, *port_id),
+                None if old_link.node_to == node_id => continue,
+                _ => (old_link.node_to, old_link.port_to),
+            };
+            let link = self.create_link(node_from, node_to, port_from, port_to);
+            link.set_name(&old_link.name);
+            link.set_active(old_link.active);
+            new_links.push(link_data(&link));
+            self.add_link(link);
+        }
+        if recording {
+            private.undo_stack.borrow_mut().enable_recording();
+        }
+
+        if let Some(new_node) = new_node {
+            private
+                .undo_stack
+                .borrow_mut()
+                .push(UndoAction::ReplaceNode {
+                    old_node,
+                    old_links,
+                    new_node: NodeData::from_node(&new_node),
+                    new_links,
+                    position,
+                });
+        }
+        self.end_batch();
+        self.graph_updated();
+        Ok(new_id)
+    }
+
+    /// Replace the template of a macro node, keeping its ports. The change can be undone.
+    pub fn set_macro_template(&self, node_id: u32, template: &GraphTemplate) -> anyhow::Result<()> {
+        let node = self
+            .node(node_id)
+            .ok_or_else(|| anyhow::anyhow!("No node with the id {}", node_id))?;
+        if node.node_type() != Some(&NodeType::Macro) {
+            anyhow::bail!("The node {} is not a macro", node.unique_name());
+        }
+        let xml = String::from_utf8(template.render_xml()?)?;
+        self.modify_node_property(node_id, MACRO_TEMPLATE_PROPERTY, &xml);
+        Ok(())
+    }
+
+    // Annotations
+
+    /// Convert a point of the widget to canvas space, taking the zoom level and the
+    /// scrolled position into account.
+    pub fn screen_to_canvas(&self, x: f64, y: f64) -> graphene::Point {
+        imp::GraphView::from_obj(self)
+            .screen_space_to_canvas_space_transform()
+            .transform_point(&graphene::Point::new(x as f32, y as f32))
+    }
+
+    /// Create a new note or frame with a new id, covering `rect` in canvas space.
+    pub fn create_annotation(
+        &self,
+        kind: AnnotationKind,
+        text: &str,
+        rect: graphene::Rect,
+    ) -> Annotation {
+        let private = imp::GraphView::from_obj(self);
+        let id = private.current_annotation_id.get() + 1;
+        private.current_annotation_id.set(id);
+        Annotation::new(id, kind, text, rect)
+    }
+
+    /// Add a note or frame to the graph.
+    pub fn add_annotation(&self, annotation: Annotation) {
+        let private = imp::GraphView::from_obj(self);
+        private.undo_stack.borrow_mut().push(
+            crate::graphmanager::undo::UndoAction::AddAnnotation {
+                annotation: annotation.clone(),
+            },
+        );
+        self.insert_annotation(annotation);
+        self.graph_updated();
+    }
+
+    /// Remove a note or frame from the graph, the nodes it contains are kept.
+    pub fn remove_annotation(&self, id: u32) {
+        let private = imp::GraphView::from_obj(self);
+        if let Some(annotation) = self.remove_annotation_internal(id) {
+            private
+                .undo_stack
+                .borrow_mut()
+                .push(crate::graphmanager::undo::UndoAction::RemoveAnnotation { annotation });
+            self.graph_updated();
+        }
+    }
+
+    /// Retrieves the note or frame with the given id.
+    pub fn annotation(&self, id: u32) -> Option<Annotation> {
+        let private = imp::GraphView::from_obj(self);
+        private
+            .annotations
+            .borrow()
+            .iter()
+            .find(|annotation| annotation.id == id)
+            .cloned()
+    }
+
+    /// Retrieves all the notes and frames, in drawing order.
+    pub fn all_annotations(&self) -> Vec<Annotation> {
+        let private = imp::GraphView::from_obj(self);
+        private.annotations.borrow().clone()
+    }
+
+    /// Change the text of a note or the title of a frame.
+    pub fn set_annotation_text(&self, id: u32, text: &str) -> bool {
+        self.update_annotation(id, |annotation| annotation.text = text.to_string())
+    }
+
+    pub fn set_annotation_color(&self, id: u32, color: &gdk::RGBA) -> bool {
+        self.update_annotation(id, |annotation| annotation.color = *color)
+    }
+
+    /// Move or resize a note or frame. The nodes and notes inside a frame follow it
+    /// when it moves, as a single undoable action.
+    pub fn set_annotation_rect(&self, id: u32, rect: graphene::Rect) -> bool {
+        let Some(annotation) = self.annotation(id) else {
+            return false;
+        };
+        let (nodes, annotations) = self.annotation_contents(&annotation);
+        let drag = imp::AnnotationDragState {
+            annotation,
+            offset: graphene::Point::zero(),
+            resize: false,
+            nodes,
+            annotations,
+        };
+        self.place_annotation(&drag, rect);
+        self.end_annotation_drag(drag)
+    }
+
+    /// Add a frame around the selected nodes.
+    ///
+    /// Returns the id of the new frame, or `None` if no node is selected.
+    pub fn frame_selected_nodes(&self, title: &str) -> Option<u32> {
+        let bounds = self.nodes_bounds(true)?;
+        let rect = graphene::Rect::new(
+            bounds.x() - FRAME_SELECTION_MARGIN,
+            bounds.y() - FRAME_SELECTION_MARGIN - FRAME_TITLE_HEIGHT,
+            bounds.width() + 2.0 * FRAME_SELECTION_MARGIN,
+            bounds.height() + 2.0 * FRAME_SELECTION_MARGIN + FRAME_TITLE_HEIGHT,
+        );
+        let annotation = self.create_annotation(AnnotationKind::Frame, title, rect);
+        let id = annotation.id();
+        self.add_annotation(annotation);
+        Some(id)
+    }
+
+    fn remove_all_annotations(&self) {
+        let private = imp::GraphView::from_obj(self);
+        private.annotations.borrow_mut().clear();
+        private.current_annotation_id.set(0);
+    }
+
+    /// Insert an annotation keeping the frames below the notes.
+    fn insert_annotation(&self, annotation: Annotation) {
+        let private = imp::GraphView::from_obj(self);
+        let mut annotations = private.annotations.borrow_mut();
+        if annotation.id > private.current_annotation_id.get() {
+            private.current_annotation_id.set(annotation.id);
+        }
+        if annotation.kind == AnnotationKind::Frame {
+            let index = annotations
+                .iter()
+                .position(|a| a.kind == AnnotationKind::Note)
+                .unwrap_or(annotations.len());
+            annotations.insert(index, annotation);
+        } else {
+            annotations.push(annotation);
+        }
+        drop(annotations);
+        self.queue_draw();
+    }
+
+    fn remove_annotation_internal(&self, id: u32) -> Option<Annotation> {
+        let private = imp::GraphView::from_obj(self);
+        let mut annotations = private.annotations.borrow_mut();
+        let index = annotations.iter().position(|a| a.id == id)?;
+        let annotation = annotations.remove(index);
+        drop(annotations);
+        self.queue_draw();
+        Some(annotation)
+    }
+
+    /// Replace the annotation with the same id, keeping its drawing order.
+    fn replace_annotation(&self, annotation: &Annotation) {
+        let private = imp::GraphView::from_obj(self);
+        if let Some(current) = private
+            .annotations
+            .borrow_mut()
+            .iter_mut()
+            .find(|a| a.id == annotation.id)
+        {
+            let selected = current.selected();
+            *current = annotation.clone();
+            current.set_selected(selected);
+        }
+        self.queue_draw();
+    }
+
+    fn update_annotation<F: FnOnce(&mut Annotation)>(&self, id: u32, f: F) -> bool {
+        let private = imp::GraphView::from_obj(self);
+        let Some(old) = self.annotation(id) else {
+            return false;
+        };
+        let mut new = old.clone();
+        f(&mut new);
+        self.replace_annotation(&new);
+        private.undo_stack.borrow_mut().push(
+            crate::graphmanager::undo::UndoAction::UpdateAnnotations {
+                changes: vec![(old, new)],
+                node_moves: Vec::new(),
+            },
+        );
+        self.graph_updated();
+        true
+    }
+
+    /// Select a single annotation, unselecting everything else.
+    fn select_annotation(&self, id: u32) {
+        self.unselect_all();
+        let private = imp::GraphView::from_obj(self);
+        if let Some(annotation) = private.annotations.borrow().iter().find(|a| a.id == id) {
+            annotation.set_selected(true);
+        }
+        self.queue_draw();
+    }
+
+    /// Retrieves the topmost annotation which can be grabbed at a point in canvas space.
+    fn annotation_at(&self, point: &graphene::Point) -> Option<Annotation> {
+        let private = imp::GraphView::from_obj(self);
+        private
+            .annotations
+            .borrow()
+            .iter()
+            .rev()
+            .find(|a| a.grab_contains(point) || a.handle_contains(point))
+            .cloned()
+    }
+
+    /// Retrieves the nodes and the other annotations fully inside a frame.
+    fn annotation_contents(
+        &self,
+        annotation: &Annotation,
+    ) -> (Vec<(u32, graphene::Point)>, Vec<Annotation>) {
+        let private = imp::GraphView::from_obj(self);
+        if annotation.kind != AnnotationKind::Frame {
+            return (Vec::new(), Vec::new());
+        }
+        let nodes = private
+            .nodes
+            .borrow()
+            .values()
+            .filter(|(node, point)| {
+                annotation.frame_contains(&graphene::Rect::new(
+                    point.x(),
+                    point.y(),
+                    node.width() as f32,
+                    node.height() as f32,
+                ))
+            })
+            .map(|(node, point)| (node.id(), *point))
+            .collect();
+        let annotations = private
+            .annotations
+            .borrow()
+            .iter()
+            .filter(|a| a.id != annotation.id && annotation.frame_contains(&a.rect))
+            .cloned()
+            .collect();
+        (nodes, annotations)
+    }
+
+    /// Follow the pointer while moving or resizing an annotation.
+    fn drag_annotation(&self, drag: &imp::AnnotationDragState, cursor: &graphene::Point) {
+        let rect = &drag.annotation.rect;
+        let rect = if drag.resize {
+            graphene::Rect::new(
+                rect.x(),
+                rect.y(),
+                (cursor.x() - rect.x()).max(ANNOTATION_MIN_SIZE),
+                (cursor.y() - rect.y()).max(ANNOTATION_MIN_SIZE),
+            )
+        } else {
+            let origin = self.snap_to_grid(&graphene::Point::new(
+                cursor.x() - drag.offset.x(),
+                cursor.y() - drag.offset.y(),
+            ));
+            graphene::Rect::new(origin.x(), origin.y(), rect.width(), rect.height())
+        };
+        self.place_annotation(drag, rect);
+    }
+
+    /// Set the area of the dragged annotation and move its contents along.
+    fn place_annotation(&self, drag: &imp::AnnotationDragState, rect: graphene::Rect) {
+        let (dx, dy) = (
+            rect.x() - drag.annotation.rect.x(),
+            rect.y() - drag.annotation.rect.y(),
+        );
+        let mut annotation = drag.annotation.clone();
+        annotation.rect = rect;
+        self.replace_annotation(&annotation);
+        for (node_id, position) in &drag.nodes {
+            if let Some(node) = self.node(*node_id) {
+                self.move_node(
+                    &node,
+                    &graphene::Point::new(position.x() + dx, position.y() + dy),
+                );
+            }
+        }
+        for inner in &drag.annotations {
+            let mut inner = inner.clone();
+            inner.rect = graphene::Rect::new(
+                inner.rect.x() + dx,
+                inner.rect.y() + dy,
+                inner.rect.width(),
+                inner.rect.height(),
+            );
+            self.replace_annotation(&inner);
+        }
+    }
+
+    /// Record the changes of an annotation drag as a single undoable action.
+    ///
+    /// Returns true if something has moved.
+    fn end_annotation_drag(&self, drag: imp::AnnotationDragState) -> bool {
+        let private = imp::GraphView::from_obj(self);
+        let rect_changed = |a: &graphene::Rect, b: &graphene::Rect| {
+            (a.x() - b.x()).abs() > 0.1
+                || (a.y() - b.y()).abs() > 0.1
+                || (a.width() - b.width()).abs() > 0.1
+                || (a.height() - b.height()).abs() > 0.1
+        };
+        let changes: Vec<(Annotation, Annotation)> = std::iter::once(&drag.annotation)
+            .chain(drag.annotations.iter())
+            .filter_map(|old| {
+                let new = self.annotation(old.id)?;
+                rect_changed(&old.rect, &new.rect).then(|| (old.clone(), new))
+            })
+            .collect();
+        let node_moves: Vec<(u32, graphene::Point, graphene::Point)> = drag
+            .nodes
+            .iter()
+            .filter_map(|(node_id, old_position)| {
+                let node = self.node(*node_id)?;
+                let new_position = self.node_position(&node)?;
+                ((new_position.x() - old_position.x()).abs() > 0.1
+                    || (new_position.y() - old_position.y()).abs() > 0.1)
+                    .then_some((*node_id, *old_position, new_position))
+            })
+            .collect();
+        if changes.is_empty() && node_moves.is_empty() {
+            return false;
+        }
+        private.undo_stack.borrow_mut().push(
+            crate::graphmanager::undo::UndoAction::UpdateAnnotations {
+                changes,
+                node_moves,
+            },
+        );
+        self.graph_updated();
+        true
+    }
+
     // Undo/Redo API
 
     /// Undo the last action
     ///
     /// Returns true if an action was undone, false if there was nothing to undo
     pub fn undo(&self) -> bool {
-        use crate::graphmanager::undo::UndoAction;
-
         let private = imp::GraphView::from_obj(self);
 
         // Disable recording and pop the action
@@ -2492,92 +4257,48 @@ impl GraphView {
 
         let result = if let Some(action) = action {
             // Execute the reverse of the action
-            match &action {
-                UndoAction::AddNode { node_data, .. } => {
-                    // Undo: Remove the node that was added
-                    self.remove_node_internal(node_data.id);
-                }
-                UndoAction::RemoveNode {
-                    node_data,
-                    position,
-                    connected_links,
-                } => {
-                    // Undo: Re-add the node that was removed
-                    self.restore_node(node_data, position);
-                    // Restore connected links
-                    for link_data in connected_links {
-                        self.restore_link(link_data);
-                    }
-                }
-                UndoAction::AddLink { link_data } => {
-                    // Undo: Remove the link that was added
-                    self.remove_link_internal(link_data.id);
-                }
-                UndoAction::RemoveLink { link_data } => {
-                    // Undo: Re-add the link that was removed
-                    self.restore_link(link_data);
-                }
-                UndoAction::MoveNode {
-                    node_id,
-                    old_position,
-                    ..
-                } => {
-                    // Undo: Move node back to old position
-                    if let Some(node) = self.node(*node_id) {
-                        self.move_node(&node, old_position);
-                    }
-                }
-                UndoAction::AddPort { node_id, port_data } => {
-                    // Undo: Remove the port that was added
-                    self.remove_port(*node_id, port_data.id);
-                }
-                UndoAction::RemovePort { node_id, port_data } => {
-                    // Undo: Re-add the port that was removed
-                    if let Some(mut node) = self.node(*node_id) {
-                        let port = self.restore_port(port_data);
-                        self.add_port_to_node(&mut node, port);
-                    }
-                }
-                UndoAction::ModifyProperty {
-                    node_id,
-                    port_id,
-                    property_name,
-                    old_value,
-                    ..
-                } => {
-                    // Undo: Restore old property value
-                    if let Some(node) = self.node(*node_id) {
-                        if let Some(port_id) = port_id {
-                            if let Some(port) = node.port(*port_id) {
-                                if old_value.is_empty() {
-                                    port.remove_property(property_name);
-                                } else {
-                                    port.add_property(property_name, old_value);
-                                }
-                            }
-                        } else if old_value.is_empty() {
-                            node.remove_property(property_name);
-                        } else {
-                            node.add_property(property_name, old_value);
-                        }
-                    }
-                }
-                UndoAction::BatchMoveNodes { moves } => {
-                    // Undo: Move all nodes back to their old positions
-                    for (node_id, old_position, _) in moves {
-                        if let Some(node) = self.node(*node_id) {
-                            self.move_node(&node, old_position);
-                        }
-                    }
-                }
-            }
+            self.undo_action(&action);
+
+            // Push the original action to redo stack so it can be redone
+            private.undo_stack.borrow_mut().push_redo(action);
+            private.undo_stack.borrow_mut().enable_recording();
+            true
+        } else {
+            // Re-enable recording even if there was nothing to undo
+            private.undo_stack.borrow_mut().enable_recording();
+            false
+        };
+
+        if result {
+            self.graph_updated();
+        }
+
+        result
+    }
+
+    /// Redo the last undone action
+    ///
+    /// Returns true if an action was redone, false if there was nothing to redo
+    pub fn redo(&self) -> bool {
+        let private = imp::GraphView::from_obj(self);
+
+        // Disable recording and pop the action from redo stack
+        let action = {
+            let mut undo_stack = private.undo_stack.borrow_mut();
+            undo_stack.disable_recording();
+            undo_stack.pop_redo()
+        };
+
+        let result = if let Some(action) = action {
+            // Re-execute the original action
+            self.redo_action(&action);
 
-            // Push the original action to redo stack so it can be redone
-            private.undo_stack.borrow_mut().push_redo(action);
+            // Push the original action back to undo stack
+            private.undo_stack.borrow_mut().push_undo(action);
             private.undo_stack.borrow_mut().enable_recording();
             true
         } else {
-            // Re-enable recording even if there was nothing to undo
+            // Re-enable recording even if there was nothing to redo
             private.undo_stack.borrow_mut().enable_recording();
             false
         };
@@ -2589,113 +4310,253 @@ impl GraphView {
         result
     }
 
-    /// Redo the last undone action
-    ///
-    /// Returns true if an action was redone, false if there was nothing to redo
-    pub fn redo(&self) -> bool {
+    /// Execute the reverse of an action.
+    fn undo_action(&self, action: &crate::graphmanager::undo::UndoAction) {
         use crate::graphmanager::undo::UndoAction;
 
-        let private = imp::GraphView::from_obj(self);
-
-        // Disable recording and pop the action from redo stack
-        let action = {
-            let mut undo_stack = private.undo_stack.borrow_mut();
-            undo_stack.disable_recording();
-            undo_stack.pop_redo()
-        };
-
-        let result = if let Some(action) = action {
-            // Re-execute the original action
-            match &action {
-                UndoAction::AddNode {
-                    node_data,
-                    position,
-                } => {
-                    // Redo: Add the node back
-                    self.restore_node(node_data, position);
+        match action {
+            UndoAction::AddNode { node_data, .. } => {
+                // Undo: Remove the node that was added
+                self.remove_node_internal(node_data.id);
+            }
+            UndoAction::RemoveNode {
+                node_data,
+                position,
+                connected_links,
+            } => {
+                // Undo: Re-add the node that was removed
+                self.restore_node(node_data, position);
+                // Restore connected links
+                for link_data in connected_links {
+                    self.restore_link(link_data);
                 }
-                UndoAction::RemoveNode { node_data, .. } => {
-                    // Redo: Remove the node again
-                    self.remove_node_internal(node_data.id);
+            }
+            UndoAction::AddLink { link_data } => {
+                // Undo: Remove the link that was added
+                self.remove_link_internal(link_data.id);
+            }
+            UndoAction::RemoveLink { link_data } => {
+                // Undo: Re-add the link that was removed
+                self.restore_link(link_data);
+            }
+            UndoAction::MoveNode {
+                node_id,
+                old_position,
+                ..
+            } => {
+                // Undo: Move node back to old position
+                if let Some(node) = self.node(*node_id) {
+                    self.move_node(&node, old_position);
                 }
-                UndoAction::AddLink { link_data } => {
-                    // Redo: Add the link back
-                    self.restore_link(link_data);
+            }
+            UndoAction::AddPort { node_id, port_data } => {
+                // Undo: Remove the port that was added
+                self.remove_port(*node_id, port_data.id);
+            }
+            UndoAction::RemovePort { node_id, port_data } => {
+                // Undo: Re-add the port that was removed
+                if let Some(mut node) = self.node(*node_id) {
+                    let port = self.restore_port(port_data);
+                    self.add_port_to_node(&mut node, port);
                 }
-                UndoAction::RemoveLink { link_data } => {
-                    // Redo: Remove the link again
-                    self.remove_link_internal(link_data.id);
+            }
+            UndoAction::ModifyProperty {
+                node_id,
+                port_id,
+                property_name,
+                old_value,
+                ..
+            } => {
+                // Undo: Restore old property value
+                if let Some(node) = self.node(*node_id) {
+                    if let Some(port_id) = port_id {
+                        if let Some(port) = node.port(*port_id) {
+                            if old_value.is_empty() {
+                                port.remove_property(property_name);
+                            } else {
+                                port.add_property(property_name, old_value);
+                            }
+                        }
+                    } else if old_value.is_empty() {
+                        node.remove_property(property_name);
+                    } else {
+                        node.add_property(property_name, old_value);
+                    }
                 }
-                UndoAction::MoveNode {
-                    node_id,
-                    new_position,
-                    ..
-                } => {
-                    // Redo: Move to the new position
+            }
+            UndoAction::BatchMoveNodes { moves } => {
+                // Undo: Move all nodes back to their old positions
+                for (node_id, old_position, _) in moves {
                     if let Some(node) = self.node(*node_id) {
-                        self.move_node(&node, new_position);
+                        self.move_node(&node, old_position);
                     }
                 }
-                UndoAction::AddPort { node_id, port_data } => {
-                    // Redo: Add the port back
-                    if let Some(mut node) = self.node(*node_id) {
-                        let port = self.restore_port(port_data);
-                        self.add_port_to_node(&mut node, port);
+            }
+            UndoAction::AddAnnotation { annotation } => {
+                // Undo: Remove the annotation that was added
+                self.remove_annotation_internal(annotation.id);
+            }
+            UndoAction::RemoveAnnotation { annotation } => {
+                // Undo: Re-add the annotation that was removed
+                self.insert_annotation(annotation.clone());
+            }
+            UndoAction::UpdateAnnotations {
+                changes,
+                node_moves,
+            } => {
+                // Undo: Restore the annotations and the nodes they carried
+                for (old_annotation, _) in changes {
+                    self.replace_annotation(old_annotation);
+                }
+                for (node_id, old_position, _) in node_moves {
+                    if let Some(node) = self.node(*node_id) {
+                        self.move_node(&node, old_position);
                     }
                 }
-                UndoAction::RemovePort { node_id, port_data } => {
-                    // Redo: Remove the port again
-                    self.remove_port(*node_id, port_data.id);
+            }
+            UndoAction::ReplaceNode {
+                old_node,
+                old_links,
+                new_node,
+                position,
+                ..
+            } => {
+                // Undo: Put the replaced node back with its links
+                self.remove_node_internal(new_node.id);
+                self.restore_node(old_node, position);
+                for link_data in old_links {
+                    self.restore_link(link_data);
                 }
-                UndoAction::ModifyProperty {
-                    node_id,
-                    port_id,
-                    property_name,
-                    new_value,
-                    ..
-                } => {
-                    // Redo: Apply the new value
-                    if let Some(node) = self.node(*node_id) {
-                        if let Some(port_id) = port_id {
-                            if let Some(port) = node.port(*port_id) {
-                                if new_value.is_empty() {
-                                    port.remove_property(property_name);
-                                } else {
-                                    port.add_property(property_name, new_value);
-                                }
+            }
+            UndoAction::Batch { actions } => {
+                // Undo: Reverse the actions, the last one first
+                for action in actions.iter().rev() {
+                    self.undo_action(action);
+                }
+            }
+        }
+    }
+
+    /// Re-execute an undone action.
+    fn redo_action(&self, action: &crate::graphmanager::undo::UndoAction) {
+        use crate::graphmanager::undo::UndoAction;
+
+        match action {
+            UndoAction::AddNode {
+                node_data,
+                position,
+            } => {
+                // Redo: Add the node back
+                self.restore_node(node_data, position);
+            }
+            UndoAction::RemoveNode { node_data, .. } => {
+                // Redo: Remove the node again
+                self.remove_node_internal(node_data.id);
+            }
+            UndoAction::AddLink { link_data } => {
+                // Redo: Add the link back
+                self.restore_link(link_data);
+            }
+            UndoAction::RemoveLink { link_data } => {
+                // Redo: Remove the link again
+                self.remove_link_internal(link_data.id);
+            }
+            UndoAction::MoveNode {
+                node_id,
+                new_position,
+                ..
+            } => {
+                // Redo: Move to the new position
+                if let Some(node) = self.node(*node_id) {
+                    self.move_node(&node, new_position);
+                }
+            }
+            UndoAction::AddPort { node_id, port_data } => {
+                // Redo: Add the port back
+                if let Some(mut node) = self.node(*node_id) {
+                    let port = self.restore_port(port_data);
+                    self.add_port_to_node(&mut node, port);
+                }
+            }
+            UndoAction::RemovePort { node_id, port_data } => {
+                // Redo: Remove the port again
+                self.remove_port(*node_id, port_data.id);
+            }
+            UndoAction::ModifyProperty {
+                node_id,
+                port_id,
+                property_name,
+                new_value,
+                ..
+            } => {
+                // Redo: Apply the new value
+                if let Some(node) = self.node(*node_id) {
+                    if let Some(port_id) = port_id {
+                        if let Some(port) = node.port(*port_id) {
+                            if new_value.is_empty() {
+                                port.remove_property(property_name);
+                            } else {
+                                port.add_property(property_name, new_value);
                             }
-                        } else if new_value.is_empty() {
-                            node.remove_property(property_name);
-                        } else {
-                            node.add_property(property_name, new_value);
                         }
+                    } else if new_value.is_empty() {
+                        node.remove_property(property_name);
+                    } else {
+                        node.add_property(property_name, new_value);
                     }
                 }
-                UndoAction::BatchMoveNodes { moves } => {
-                    // Redo: Move all nodes to their new positions
-                    for (node_id, _, new_position) in moves {
-                        if let Some(node) = self.node(*node_id) {
-                            self.move_node(&node, new_position);
-                        }
+            }
+            UndoAction::BatchMoveNodes { moves } => {
+                // Redo: Move all nodes to their new positions
+                for (node_id, _, new_position) in moves {
+                    if let Some(node) = self.node(*node_id) {
+                        self.move_node(&node, new_position);
                     }
                 }
             }
-
-            // Push the original action back to undo stack
-            private.undo_stack.borrow_mut().push_undo(action);
-            private.undo_stack.borrow_mut().enable_recording();
-            true
-        } else {
-            // Re-enable recording even if there was nothing to redo
-            private.undo_stack.borrow_mut().enable_recording();
-            false
-        };
-
-        if result {
-            self.graph_updated();
+            UndoAction::AddAnnotation { annotation } => {
+                // Redo: Add the annotation back
+                self.insert_annotation(annotation.clone());
+            }
+            UndoAction::RemoveAnnotation { annotation } => {
+                // Redo: Remove the annotation again
+                self.remove_annotation_internal(annotation.id);
+            }
+            UndoAction::UpdateAnnotations {
+                changes,
+                node_moves,
+            } => {
+                // Redo: Apply the changes again
+                for (_, new_annotation) in changes {
+                    self.replace_annotation(new_annotation);
+                }
+                for (node_id, _, new_position) in node_moves {
+                    if let Some(node) = self.node(*node_id) {
+                        self.move_node(&node, new_position);
+                    }
+                }
+            }
+            UndoAction::ReplaceNode {
+                old_node,
+                new_node,
+                new_links,
+                position,
+                ..
+            } => {
+                // Redo: Replace the node again
+                self.remove_node_internal(old_node.id);
+                self.restore_node(new_node, position);
+                for link_data in new_links {
+                    self.restore_link(link_data);
+                }
+            }
+            UndoAction::Batch { actions } => {
+                // Redo: Apply the actions again in their order
+                for action in actions {
+                    self.redo_action(action);
+                }
+            }
         }
-
-        result
     }
 
     /// Check if undo is available
@@ -2716,6 +4577,19 @@ impl GraphView {
         private.undo_stack.borrow_mut().clear();
     }
 
+    /// Record the following changes as a single undo step, until `end_batch` is called.
+    /// Batches can be nested, the step is recorded by the outermost `end_batch`.
+    pub fn begin_batch(&self) {
+        let private = imp::GraphView::from_obj(self);
+        private.undo_stack.borrow_mut().begin_batch();
+    }
+
+    /// Record the changes made since `begin_batch` as a single undo step.
+    pub fn end_batch(&self) {
+        let private = imp::GraphView::from_obj(self);
+        private.undo_stack.borrow_mut().end_batch();
+    }
+
     /// Set maximum undo depth
     pub fn set_max_undo_depth(&self, depth: usize) {
         let private = imp::GraphView::from_obj(self);
@@ -2849,13 +4723,18 @@ impl GraphView {
 
     // Auto-arrange methods
 
-    /// Automatically arrange all nodes in the graph from source to sink.
+    /// Automatically arrange the nodes in the graph from source to sink.
     ///
     /// Groups nodes into stages (based on distance from sources) and
-    /// distributes nodes vertically within each stage using barycenter ordering.
+    /// distributes nodes within each stage using barycenter ordering.
+    ///
+    /// The spacing between stages is dynamic: each stage starts after the widest node
+    /// (the tallest one from top to bottom) in the previous stage plus the configured gap.
+    /// In [`LayoutMode::Compact`] the crossings between adjacent stages are then reduced
+    /// by swapping neighbour nodes, and each node is aligned with the nodes it is linked to.
     ///
-    /// The horizontal spacing between stages is dynamic: each stage starts after
-    /// the widest node in the previous stage plus the configured `horizontal_spacing` gap.
+    /// Pinned nodes, and the unselected nodes when `selected_only` is set, keep their
+    /// position and the arranged nodes are placed around them.
     ///
     /// This operation is undoable as a single action.
     ///
@@ -2863,7 +4742,7 @@ impl GraphView {
     /// * `options` - Optional layout configuration. Uses defaults if None.
     ///
     /// # Returns
-    /// `true` if layout was applied, `false` if graph is empty.
+    /// `true` if layout was applied, `false` if there is no node to arrange.
     ///
     /// # Note
     /// Node widths are determined by GTK widget allocation. If called before nodes
@@ -2874,15 +4753,72 @@ impl GraphView {
         use std::collections::VecDeque;
 
         let options = options.unwrap_or_default();
-        let private = imp::GraphView::from_obj(self);
 
-        let nodes = self.all_nodes(NodeType::All);
+        // Pinned nodes, and the unselected ones when arranging the selection, stay in place
+        let (nodes, fixed_nodes): (Vec<Node>, Vec<Node>) = self
+            .all_nodes(NodeType::All)
+            .into_iter()
+            .partition(|node| !node.pinned() && (!options.selected_only || node.selected()));
         if nodes.is_empty() {
             return false;
         }
+        let node_ids: HashSet<u32> = nodes.iter().map(|node| node.id()).collect();
+        let node_rects: HashMap<u32, graphene::Rect> = nodes
+            .iter()
+            .filter_map(|node| Some((node.id(), self.node_rect(node.id())?)))
+            .collect();
+        let fixed_rects: Vec<graphene::Rect> = fixed_nodes
+            .iter()
+            .filter_map(|node| self.node_rect(node.id()))
+            .collect();
+
+        // Work along the flow (main axis) and across it (cross axis)
+        let vertical = options.orientation == LayoutOrientation::TopToBottom;
+        let main_size = |node_id: &u32| {
+            node_rects.get(node_id).map_or(0.0, |rect| {
+                if vertical {
+                    rect.height()
+                } else {
+                    rect.width()
+                }
+            })
+        };
+        let cross_size = |node_id: &u32| {
+            node_rects.get(node_id).map_or(0.0, |rect| {
+                if vertical {
+                    rect.width()
+                } else {
+                    rect.height()
+                }
+            })
+        };
+        let cross_position = |node_id: &u32| {
+            node_rects
+                .get(node_id)
+                .map_or(0.0, |rect| if vertical { rect.x() } else { rect.y() })
+        };
+        let (stage_spacing, cross_spacing) = if vertical {
+            (options.vertical_spacing, options.horizontal_spacing)
+        } else {
+            (options.horizontal_spacing, options.vertical_spacing)
+        };
+        // The selection is arranged where it currently is
+        let (start_x, start_y) = match node_rects
+            .values()
+            .cloned()
+            .reduce(|bounds, rect| bounds.union(&rect))
+        {
+            Some(bounds) if options.selected_only => (bounds.x(), bounds.y()),
+            _ => (options.start_x, options.start_y),
+        };
+        let (start_main, start_cross) = if vertical {
+            (start_y, start_x)
+        } else {
+            (start_x, start_y)
+        };
 
         // Build edge maps (with port index info for crossing minimization)
-        let (forward_edges, backward_edges) = self.build_edge_maps();
+        let (forward_edges, backward_edges) = self.build_edge_maps(&node_ids);
 
         // Compute input count for each node
         let mut input_count: HashMap<u32, usize> = HashMap::new();
@@ -2940,7 +4876,7 @@ impl GraphView {
             stage.entry(node.id()).or_insert(max_stage + 1);
         }
 
-        // Group nodes by stage
+        // Group nodes by stage, keeping their current order across the flow
         let num_stages = stage.values().max().copied().unwrap_or(0) + 1;
         let mut stages: Vec<Vec<u32>> = vec![Vec::new(); num_stages];
         for (&node_id, &stage_idx) in &stage {
@@ -2948,116 +4884,158 @@ impl GraphView {
                 stages[stage_idx].push(node_id);
             }
         }
+        for stage_nodes in &mut stages {
+            stage_nodes.sort_by(|a, b| {
+                cross_position(a)
+                    .partial_cmp(&cross_position(b))
+                    .unwrap_or(Ordering::Equal)
+                    .then(a.cmp(b))
+            });
+        }
 
-        // Compute vertical positions using barycenter ordering with multiple passes
-        let y_positions =
-            self.compute_vertical_positions(&stages, &forward_edges, &backward_edges, &options);
-
-        // Compute maximum width for each stage
-        let mut stage_max_widths: Vec<f32> = vec![0.0; num_stages];
-        for (stage_idx, stage_nodes) in stages.iter().enumerate() {
-            for &node_id in stage_nodes {
-                if let Some(node) = self.node(node_id) {
-                    let node_width = node.width() as f32;
-                    if node_width > stage_max_widths[stage_idx] {
-                        stage_max_widths[stage_idx] = node_width;
-                    }
-                }
+        // Order the nodes of each stage using barycenter ordering with multiple passes
+        let mut orderings = self.order_stages(&stages, &forward_edges, &backward_edges, &options);
+
+        // Compute the positions across the flow
+        let (cross_positions, cross_gap) = match options.mode {
+            LayoutMode::Layered => (
+                orderings
+                    .iter()
+                    .flat_map(|stage_nodes| {
+                        stage_nodes.iter().enumerate().map(move |(i, &node_id)| {
+                            (node_id, start_cross + (i as f32 * cross_spacing))
+                        })
+                    })
+                    .collect::<HashMap<u32, f32>>(),
+                ARRANGE_MIN_GAP,
+            ),
+            LayoutMode::Compact => {
+                let gap = cross_spacing * COMPACT_SPACING_FACTOR;
+                Self::reduce_crossings(&mut orderings, &forward_edges, &options);
+                (
+                    Self::align_stages(
+                        &orderings,
+                        &forward_edges,
+                        &backward_edges,
+                        &cross_size,
+                        gap,
+                        start_cross,
+                        options.barycenter_iterations,
+                    ),
+                    gap,
+                )
             }
-        }
+        };
 
-        // Compute cumulative X positions for each stage based on actual widths
-        // Each stage starts after the previous stage's max width + horizontal_spacing gap
-        let mut stage_x_positions: Vec<f32> = vec![options.start_x; num_stages];
+        // Compute cumulative positions along the flow for each stage based on actual sizes
+        // Each stage starts after the previous stage's biggest node + stage spacing gap
+        let mut stage_main_positions: Vec<f32> = vec![start_main; num_stages];
         for i in 1..num_stages {
-            stage_x_positions[i] =
-                stage_x_positions[i - 1] + stage_max_widths[i - 1] + options.horizontal_spacing;
+            let stage_size = orderings[i - 1]
+                .iter()
+                .map(main_size)
+                .fold(0.0f32, f32::max);
+            stage_main_positions[i] = stage_main_positions[i - 1] + stage_size + stage_spacing;
         }
 
-        // Collect old positions and compute new positions
+        // Collect old positions and compute new positions, the nodes of a stage never
+        // overlap each other nor the nodes staying in place
         let mut moves: Vec<(u32, graphene::Point, graphene::Point)> = Vec::new();
-
-        for node in &nodes {
-            let node_id = node.id();
-            let stage_idx = stage.get(&node_id).copied().unwrap_or(0);
-            let new_x = stage_x_positions
-                .get(stage_idx)
-                .copied()
-                .unwrap_or(options.start_x);
-            let new_y = y_positions
-                .get(&node_id)
-                .copied()
-                .unwrap_or(options.start_y);
-
-            // Get old position from the nodes HashMap
-            if let Some((_, old_point)) = private.nodes.borrow().get(&node_id) {
-                let old_pos = *old_point;
-                let new_pos = graphene::Point::new(new_x, new_y);
-
-                // Only record if position actually changed
-                if (new_pos.x() - old_pos.x()).abs() > 0.1
-                    || (new_pos.y() - old_pos.y()).abs() > 0.1
+        for (stage_idx, stage_nodes) in orderings.iter().enumerate() {
+            let main = stage_main_positions[stage_idx];
+            let mut next_cross = f32::MIN;
+            for node_id in stage_nodes {
+                let Some(old_rect) = node_rects.get(node_id) else {
+                    continue;
+                };
+                let mut cross = cross_positions
+                    .get(node_id)
+                    .copied()
+                    .unwrap_or(start_cross)
+                    .max(next_cross);
+                let new_rect = |cross: f32| {
+                    if vertical {
+                        graphene::Rect::new(cross, main, old_rect.width(), old_rect.height())
+                    } else {
+                        graphene::Rect::new(main, cross, old_rect.width(), old_rect.height())
+                    }
+                };
+                while let Some(fixed_rect) = fixed_rects
+                    .iter()
+                    .find(|rect| rect.intersection(&new_rect(cross)).is_some())
                 {
-                    moves.push((node_id, old_pos, new_pos));
+                    cross = if vertical {
+                        fixed_rect.x() + fixed_rect.width()
+                    } else {
+                        fixed_rect.y() + fixed_rect.height()
+                    } + cross_gap;
                 }
+                next_cross = cross + cross_size(node_id) + cross_gap;
+                let new_origin = new_rect(cross);
+                moves.push((
+                    *node_id,
+                    graphene::Point::new(old_rect.x(), old_rect.y()),
+                    graphene::Point::new(new_origin.x(), new_origin.y()),
+                ));
             }
         }
 
-        // Apply new positions
-        for (node_id, _, new_pos) in &moves {
-            if let Some(node) = self.node(*node_id) {
-                self.move_node(&node, new_pos);
-            }
-        }
+        // Single undo for entire layout
+        self.move_nodes(moves);
 
-        // Record batch undo action (single undo for entire layout)
-        if !moves.is_empty() {
-            private
-                .undo_stack
-                .borrow_mut()
-                .push(crate::graphmanager::undo::UndoAction::BatchMoveNodes { moves });
+        // Center the view on the arranged nodes
+        if let Some(bounds) = node_ids
+            .iter()
+            .filter_map(|node_id| self.node_rect(*node_id))
+            .reduce(|bounds, rect| bounds.union(&rect))
+        {
+            let center = bounds.center();
+            self.scroll_to_position(center.x(), center.y());
         }
 
-        // Center the view on the pipeline
-        // Calculate bounding box of all nodes after arrangement
-        let mut min_x = f32::MAX;
-        let mut max_x = f32::MIN;
-        let mut min_y = f32::MAX;
-        let mut max_y = f32::MIN;
+        true
+    }
 
-        for node in &nodes {
-            if let Some((_, point)) = private.nodes.borrow().get(&node.id()) {
-                let node_width = node.width() as f32;
-                let node_height = node.height() as f32;
+    /// Positions of all the nodes, to restore them later with
+    /// [`GraphView::restore_node_positions`].
+    pub fn node_positions(&self) -> HashMap<u32, graphene::Point> {
+        let private = imp::GraphView::from_obj(self);
+        private
+            .nodes
+            .borrow()
+            .iter()
+            .map(|(node_id, (_, point))| (*node_id, *point))
+            .collect()
+    }
 
-                min_x = min_x.min(point.x());
-                max_x = max_x.max(point.x() + node_width);
-                min_y = min_y.min(point.y());
-                max_y = max_y.max(point.y() + node_height);
+    /// Move the nodes back to saved positions without recording an undo action, used
+    /// to preview a layout.
+    pub fn restore_node_positions(&self, positions: &HashMap<u32, graphene::Point>) {
+        for (node_id, point) in positions {
+            if let Some(node) = self.node(*node_id) {
+                self.move_node(&node, point);
             }
         }
-
-        // Scroll to center of the pipeline
-        if min_x != f32::MAX {
-            let center_x = (min_x + max_x) / 2.0;
-            let center_y = (min_y + max_y) / 2.0;
-            self.scroll_to_position(center_x, center_y);
-        }
-
         self.graph_updated();
-        true
     }
 
-    /// Build forward and backward edge maps from the links
+    /// Build forward and backward edge maps from the links between the given nodes
     ///
     /// Returns (forward_edges, backward_edges) where each maps:
     /// - node_id -> list of (connected_node_id, port_index)
     ///
     /// The port_index helps order connections to minimize crossings
-    fn build_edge_maps(&self) -> (HashMap<u32, Vec<EdgeInfo>>, HashMap<u32, Vec<EdgeInfo>>) {
+    fn build_edge_maps(
+        &self,
+        node_ids: &HashSet<u32>,
+    ) -> (HashMap<u32, Vec<EdgeInfo>>, HashMap<u32, Vec<EdgeInfo>>) {
         let private = imp::GraphView::from_obj(self);
         let links = private.links.borrow();
-        let nodes = self.all_nodes(NodeType::All);
+        let nodes: Vec<Node> = self
+            .all_nodes(NodeType::All)
+            .into_iter()
+            .filter(|node| node_ids.contains(&node.id()))
+            .collect();
 
         let mut forward_edges: HashMap<u32, Vec<EdgeInfo>> = HashMap::new();
         let mut backward_edges: HashMap<u32, Vec<EdgeInfo>> = HashMap::new();
@@ -3074,7 +5052,10 @@ impl GraphView {
         for node in &nodes {
             let mut input_idx = 0usize;
             let mut output_idx = 0usize;
-            for port in node.all_ports(PortDirection::All) {
+            // Ports are displayed in the order they were added
+            let mut ports = node.all_ports(PortDirection::All);
+            ports.sort_by_key(|port| port.id());
+            for port in ports {
                 match port.direction() {
                     PortDirection::Input => {
                         port_indices.insert(port.id(), input_idx);
@@ -3089,49 +5070,51 @@ impl GraphView {
             }
         }
 
-        // Build edges from links with port index info
-        for link in links.values() {
-            // For forward edges: use port_to index (which port on destination)
+        // Build edges from links with port index info, ignoring the nodes left in place
+        for link in links
+            .values()
+            .filter(|link| node_ids.contains(&link.node_from) && node_ids.contains(&link.node_to))
+        {
             let port_to_idx = port_indices.get(&link.port_to).copied().unwrap_or(0);
+            let port_from_idx = port_indices.get(&link.port_from).copied().unwrap_or(0);
+
+            // For forward edges: use port_to index (which port on destination)
             forward_edges
                 .entry(link.node_from)
                 .or_default()
                 .push(EdgeInfo {
                     node_id: link.node_to,
                     port_index: port_to_idx,
+                    local_port_index: port_from_idx,
                 });
 
             // For backward edges: use port_from index (which port on source)
-            let port_from_idx = port_indices.get(&link.port_from).copied().unwrap_or(0);
             backward_edges
                 .entry(link.node_to)
                 .or_default()
                 .push(EdgeInfo {
                     node_id: link.node_from,
                     port_index: port_from_idx,
+                    local_port_index: port_to_idx,
                 });
         }
 
         (forward_edges, backward_edges)
     }
 
-    /// Compute vertical positions for nodes using iterative barycenter ordering
+    /// Order the nodes within each stage using iterative barycenter ordering
     ///
     /// Uses multiple passes (forward and backward sweeps) to minimize edge crossings.
-    /// Each pass reorders nodes within stages based on the average Y position of
+    /// Each pass reorders nodes within stages based on the average position of
     /// their connected nodes in adjacent stages, taking port indices into account.
-    fn compute_vertical_positions(
+    fn order_stages(
         &self,
         stages: &[Vec<u32>],
         forward_edges: &HashMap<u32, Vec<EdgeInfo>>,
         backward_edges: &HashMap<u32, Vec<EdgeInfo>>,
         options: &AutoArrangeOptions,
-    ) -> HashMap<u32, f32> {
-        if stages.is_empty() {
-            return HashMap::new();
-        }
-
-        // Store the ordering of nodes within each stage (indices determine Y position)
+    ) -> Vec<Vec<u32>> {
+        // Store the ordering of nodes within each stage (indices determine the position)
         let mut stage_orderings: Vec<Vec<u32>> = stages.to_vec();
 
         // Initial assignment: first stage keeps original order
@@ -3148,7 +5131,7 @@ impl GraphView {
 
         // Perform multiple passes to refine the ordering
         for _ in 0..options.barycenter_iterations {
-            // Backward pass (right to left): reorder based on downstream nodes
+            // Backward pass (sinks to sources): reorder based on downstream nodes
             for stage_idx in (0..stage_orderings.len().saturating_sub(1)).rev() {
                 let adjacent = stage_orderings[stage_idx + 1].clone();
                 self.reorder_stage_by_barycenter(
@@ -3159,7 +5142,7 @@ impl GraphView {
                 );
             }
 
-            // Forward pass (left to right): reorder based on upstream nodes
+            // Forward pass (sources to sinks): reorder based on upstream nodes
             for stage_idx in 1..stage_orderings.len() {
                 let adjacent = stage_orderings[stage_idx - 1].clone();
                 self.reorder_stage_by_barycenter(
@@ -3171,16 +5154,7 @@ impl GraphView {
             }
         }
 
-        // Convert orderings to Y positions
-        let mut y_positions: HashMap<u32, f32> = HashMap::new();
-        for stage_nodes in &stage_orderings {
-            for (i, &node_id) in stage_nodes.iter().enumerate() {
-                let y = options.start_y + (i as f32 * options.vertical_spacing);
-                y_positions.insert(node_id, y);
-            }
-        }
-
-        y_positions
+        stage_orderings
     }
 
     /// Reorder nodes in a stage based on barycenter of connected nodes in adjacent stage
@@ -3243,6 +5217,165 @@ impl GraphView {
         }
     }
 
+    /// Reduce the crossings between adjacent stages by swapping neighbour nodes.
+    ///
+    /// Links are located by node and then by port on both ends, so the branches of a
+    /// tee or the inputs of a muxer end up in the order of their pads.
+    fn reduce_crossings(
+        orderings: &mut [Vec<u32>],
+        forward_edges: &HashMap<u32, Vec<EdgeInfo>>,
+        options: &AutoArrangeOptions,
+    ) {
+        for _ in 0..options.barycenter_iterations.max(1) {
+            let mut improved = false;
+            for stage_idx in 0..orderings.len() {
+                for i in 0..orderings[stage_idx].len().saturating_sub(1) {
+                    let crossings = Self::stage_crossings(orderings, stage_idx, forward_edges);
+                    orderings[stage_idx].swap(i, i + 1);
+                    if Self::stage_crossings(orderings, stage_idx, forward_edges) < crossings {
+                        improved = true;
+                    } else {
+                        orderings[stage_idx].swap(i, i + 1);
+                    }
+                }
+            }
+            if !improved {
+                break;
+            }
+        }
+    }
+
+    /// Number of crossings between the links of a stage and the ones of its neighbour stages.
+    fn stage_crossings(
+        orderings: &[Vec<u32>],
+        stage_idx: usize,
+        forward_edges: &HashMap<u32, Vec<EdgeInfo>>,
+    ) -> usize {
+        let mut crossings = 0;
+        if stage_idx > 0 {
+            crossings += Self::count_crossings(
+                &orderings[stage_idx - 1],
+                &orderings[stage_idx],
+                forward_edges,
+            );
+        }
+        if stage_idx + 1 < orderings.len() {
+            crossings += Self::count_crossings(
+                &orderings[stage_idx],
+                &orderings[stage_idx + 1],
+                forward_edges,
+            );
+        }
+        crossings
+    }
+
+    /// Count the crossings between the links going from a stage to the next one.
+    fn count_crossings(
+        upstream: &[u32],
+        downstream: &[u32],
+        forward_edges: &HashMap<u32, Vec<EdgeInfo>>,
+    ) -> usize {
+        let downstream_indices: HashMap<u32, usize> = downstream
+            .iter()
+            .enumerate()
+            .map(|(i, &node_id)| (node_id, i))
+            .collect();
+        let mut links: Vec<((usize, usize), (usize, usize))> = Vec::new();
+        for (i, node_id) in upstream.iter().enumerate() {
+            for edge in forward_edges.get(node_id).into_iter().flatten() {
+                if let Some(&j) = downstream_indices.get(&edge.node_id) {
+                    links.push(((i, edge.local_port_index), (j, edge.port_index)));
+                }
+            }
+        }
+
+        let mut crossings = 0;
+        for (k, (from_a, to_a)) in links.iter().enumerate() {
+            for (from_b, to_b) in &links[k + 1..] {
+                if (from_a < from_b && to_a > to_b) || (from_a > from_b && to_a < to_b) {
+                    crossings += 1;
+                }
+            }
+        }
+        crossings
+    }
+
+    /// Place the nodes of each stage across the flow, packed using their actual size
+    /// and aligned with the centre of the nodes they are linked to.
+    ///
+    /// Each pass computes the wanted position of the nodes of a stage, then solves the
+    /// overlaps once pushing the nodes forward and once backward. The average of both
+    /// placements keeps the order and the gaps of the stage while centring a tee on its
+    /// branches or a muxer on its inputs.
+    fn align_stages(
+        orderings: &[Vec<u32>],
+        forward_edges: &HashMap<u32, Vec<EdgeInfo>>,
+        backward_edges: &HashMap<u32, Vec<EdgeInfo>>,
+        cross_size: &impl Fn(&u32) -> f32,
+        gap: f32,
+        start: f32,
+        iterations: usize,
+    ) -> HashMap<u32, f32> {
+        // Initial placement: nodes packed one after the other
+        let mut positions: HashMap<u32, f32> = HashMap::new();
+        for stage_nodes in orderings {
+            let mut next = start;
+            for node_id in stage_nodes {
+                positions.insert(*node_id, next);
+                next += cross_size(node_id) + gap;
+            }
+        }
+
+        for _ in 0..iterations.max(1) {
+            for stage_nodes in orderings {
+                let wanted: Vec<f32> = stage_nodes
+                    .iter()
+                    .map(|node_id| {
+                        let centres: Vec<f32> = forward_edges
+                            .get(node_id)
+                            .into_iter()
+                            .flatten()
+                            .chain(backward_edges.get(node_id).into_iter().flatten())
+                            .filter_map(|edge| {
+                                positions
+                                    .get(&edge.node_id)
+                                    .map(|position| position + cross_size(&edge.node_id) / 2.0)
+                            })
+                            .collect();
+                        if centres.is_empty() {
+                            positions[node_id]
+                        } else {
+                            centres.iter().sum::<f32>() / centres.len() as f32
+                                - cross_size(node_id) / 2.0
+                        }
+                    })
+                    .collect();
+                let sizes: Vec<f32> = stage_nodes.iter().map(cross_size).collect();
+
+                let mut pushed_forward = wanted.clone();
+                for i in 1..pushed_forward.len() {
+                    pushed_forward[i] =
+                        pushed_forward[i].max(pushed_forward[i - 1] + sizes[i - 1] + gap);
+                }
+                let mut pushed_backward = wanted;
+                for i in (0..pushed_backward.len().saturating_sub(1)).rev() {
+                    pushed_backward[i] =
+                        pushed_backward[i].min(pushed_backward[i + 1] - sizes[i] - gap);
+                }
+                for (i, node_id) in stage_nodes.iter().enumerate() {
+                    positions.insert(*node_id, (pushed_forward[i] + pushed_backward[i]) / 2.0);
+                }
+            }
+        }
+
+        // Start the layout at the requested position
+        let min_position = positions.values().copied().fold(f32::MAX, f32::min);
+        for position in positions.values_mut() {
+            *position += start - min_position;
+        }
+        positions
+    }
+
     // Helper methods for undo/redo
 
     /// Remove node without recording undo action
@@ -3277,6 +5410,7 @@ impl GraphView {
             self.create_node_with_id(node_data.id, &node_data.name, node_data.node_type.clone());
         node.set_position(node_data.position.0, node_data.position.1);
         node.set_light(node_data.light);
+        node.set_pinned(node_data.pinned);
         node.set_unique_name(&node_data.unique_name);
         node.update_properties(&node_data.properties);
 
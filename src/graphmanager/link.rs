@@ -8,7 +8,45 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use super::SelectionExt;
+use gtk::graphene;
 use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Space between a port and the first bend of a routed link, before zooming.
+pub const LINK_ROUTE_STUB: f32 = 20.0;
+/// Space kept between the orthogonal links and the nodes they go around, before zooming.
+pub const LINK_ROUTE_MARGIN: f32 = 10.0;
+/// Number of segments used to follow a bezier link when hit-testing it.
+const BEZIER_SEGMENTS: usize = 24;
+
+/// Shape of the links drawn between the ports, chosen for each graph.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LinkRouting {
+    /// Straight line from port to port
+    #[default]
+    Straight,
+    /// Curve leaving and entering the ports horizontally
+    Bezier,
+    /// Horizontal and vertical segments going around the nodes
+    Orthogonal,
+}
+
+impl fmt::Display for LinkRouting {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl LinkRouting {
+    pub fn from_str(routing_name: &str) -> LinkRouting {
+        match routing_name {
+            "Bezier" => LinkRouting::Bezier,
+            "Orthogonal" => LinkRouting::Orthogonal,
+            _ => LinkRouting::Straight,
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Link {
@@ -76,3 +114,173 @@ impl SelectionExt for Link {
         self.selected.get()
     }
 }
+
+/// Control points of the bezier curve of a link, leaving and entering the ports horizontally.
+pub fn bezier_control_points(
+    from: &graphene::Point,
+    to: &graphene::Point,
+    stub: f32,
+) -> (graphene::Point, graphene::Point) {
+    let offset = ((to.x() - from.x()).abs() / 2.0).max(stub * 2.0);
+    (
+        graphene::Point::new(from.x() + offset, from.y()),
+        graphene::Point::new(to.x() - offset, to.y()),
+    )
+}
+
+/// Points of the path followed by a link from an output port to an input port.
+///
+/// Bezier curves are flattened into segments so the path can be hit-tested. Orthogonal
+/// routes leave and enter the ports horizontally after `stub` and go around the
+/// `obstacles`, kept `margin` away, when a free route exists.
+pub fn link_route(
+    routing: LinkRouting,
+    from: &graphene::Point,
+    to: &graphene::Point,
+    obstacles: &[graphene::Rect],
+    stub: f32,
+    margin: f32,
+) -> Vec<graphene::Point> {
+    match routing {
+        LinkRouting::Straight => vec![*from, *to],
+        LinkRouting::Bezier => {
+            let (control_from, control_to) = bezier_control_points(from, to, stub);
+            (0..=BEZIER_SEGMENTS)
+                .map(|i| {
+                    let t = i as f32 / BEZIER_SEGMENTS as f32;
+                    let u = 1.0 - t;
+                    let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+                    graphene::Point::new(
+                        a * from.x() + b * control_from.x() + c * control_to.x() + d * to.x(),
+                        a * from.y() + b * control_from.y() + c * control_to.y() + d * to.y(),
+                    )
+                })
+                .collect()
+        }
+        LinkRouting::Orthogonal => orthogonal_route(from, to, obstacles, stub, margin),
+    }
+}
+
+fn orthogonal_route(
+    from: &graphene::Point,
+    to: &graphene::Point,
+    obstacles: &[graphene::Rect],
+    stub: f32,
+    margin: f32,
+) -> Vec<graphene::Point> {
+    let start = graphene::Point::new(from.x() + stub, from.y());
+    let end = graphene::Point::new(to.x() - stub, to.y());
+    let obstacles: Vec<graphene::Rect> = obstacles
+        .iter()
+        .map(|rect| {
+            graphene::Rect::new(
+                rect.x() - margin,
+                rect.y() - margin,
+                rect.width() + 2.0 * margin,
+                rect.height() + 2.0 * margin,
+            )
+        })
+        .collect();
+
+    // Forward links can go down or up once between the ports, the others have to
+    // go through a horizontal channel. The channels are taken along the obstacles.
+    let mut candidates: Vec<Vec<graphene::Point>> = Vec::new();
+    if start.x() <= end.x() {
+        let mut xs = vec![(start.x() + end.x()) / 2.0];
+        xs.extend(
+            obstacles
+                .iter()
+                .flat_map(|rect| [rect.x(), rect.x() + rect.width()]),
+        );
+        for x in xs.into_iter().filter(|x| (start.x()..=end.x()).contains(x)) {
+            candidates.push(vec![
+                start,
+                graphene::Point::new(x, start.y()),
+                graphene::Point::new(x, end.y()),
+                end,
+            ]);
+        }
+    }
+    let mut ys = vec![(start.y() + end.y()) / 2.0];
+    ys.extend(
+        obstacles
+            .iter()
+            .flat_map(|rect| [rect.y(), rect.y() + rect.height()]),
+    );
+    for y in ys {
+        candidates.push(vec![
+            start,
+            graphene::Point::new(start.x(), y),
+            graphene::Point::new(end.x(), y),
+            end,
+        ]);
+    }
+
+    // Prefer the shortest free route, each bend costing as much as a stub
+    let cost = |path: &[graphene::Point]| route_length(path) + path.len() as f32 * stub;
+    let path = candidates
+        .iter()
+        .filter(|path| {
+            !path.windows(2).any(|segment| {
+                obstacles
+                    .iter()
+                    .any(|rect| segment_crosses_rect(&segment[0], &segment[1], rect))
+            })
+        })
+        .min_by(|a, b| cost(a).partial_cmp(&cost(b)).unwrap_or(Ordering::Equal))
+        .unwrap_or(&candidates[0]);
+
+    let mut route = vec![*from];
+    for point in path.iter().chain([to]) {
+        if route.last() != Some(point) {
+            route.push(*point);
+        }
+    }
+    route
+}
+
+/// Check if an horizontal or vertical segment goes through a rectangle.
+fn segment_crosses_rect(a: &graphene::Point, b: &graphene::Point, rect: &graphene::Rect) -> bool {
+    a.x().min(b.x()) < rect.x() + rect.width()
+        && a.x().max(b.x()) > rect.x()
+        && a.y().min(b.y()) < rect.y() + rect.height()
+        && a.y().max(b.y()) > rect.y()
+}
+
+fn route_length(route: &[graphene::Point]) -> f32 {
+    route
+        .windows(2)
+        .map(|segment| segment[0].distance(&segment[1]).0)
+        .sum()
+}
+
+/// Point halfway along a route, where the name of the link is displayed.
+pub fn route_midpoint(route: &[graphene::Point]) -> graphene::Point {
+    let mut remaining = route_length(route) / 2.0;
+    for segment in route.windows(2) {
+        let length = segment[0].distance(&segment[1]).0;
+        if length >= remaining && length > 0.0 {
+            return segment[0].interpolate(&segment[1], (remaining / length) as f64);
+        }
+        remaining -= length;
+    }
+    route.first().copied().unwrap_or_else(graphene::Point::zero)
+}
+
+/// Check if a point is within `tolerance` of a route.
+pub fn route_contains(route: &[graphene::Point], point: &graphene::Point, tolerance: f32) -> bool {
+    route.windows(2).any(|segment| {
+        let (a, b) = (&segment[0], &segment[1]);
+        let (dx, dy) = (b.x() - a.x(), b.y() - a.y());
+        let length = dx * dx + dy * dy;
+        let t = if length > 0.0 {
+            (((point.x() - a.x()) * dx + (point.y() - a.y()) * dy) / length).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        point
+            .distance(&graphene::Point::new(a.x() + t * dx, a.y() + t * dy))
+            .0
+            <= tolerance
+    })
+}
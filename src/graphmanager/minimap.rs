@@ -0,0 +1,262 @@
+// minimap.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GraphManager
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Overview of a whole [`GraphView`] canvas.
+//!
+//! The minimap draws every node and link of the graph at a reduced scale together with
+//! the area currently visible in the graph view. Clicking or dragging in the minimap
+//! centres the graph view on the pointed position.
+
+use gtk::{
+    glib::{self, clone},
+    graphene,
+    prelude::*,
+    subclass::prelude::*,
+};
+use log::warn;
+use std::cell::Cell;
+
+use super::{GraphView, NodeType, SelectionExt};
+
+const MINIMAP_WIDTH: i32 = 200;
+const MINIMAP_HEIGHT: i32 = 140;
+/// Space kept around the overview inside the minimap, in pixels.
+const MINIMAP_PADDING: f64 = 6.0;
+
+// Minimap colors (RGBA values 0.0-1.0)
+const BACKGROUND_COLOR: (f64, f64, f64, f64) = (0.5, 0.5, 0.5, 0.25);
+const NODE_COLOR: (f64, f64, f64, f64) = (0.35, 0.35, 0.35, 0.9);
+const NODE_SELECTED_COLOR: (f64, f64, f64, f64) = (1.0, 0.18, 0.18, 0.9);
+const LINK_COLOR: (f64, f64, f64, f64) = (0.35, 0.35, 0.35, 0.6);
+const VIEWPORT_COLOR: (f64, f64, f64, f64) = (0.2, 0.5, 1.0, 1.0);
+
+mod imp {
+    use super::*;
+
+    #[derive(Default)]
+    pub struct Minimap {
+        pub(super) graphview: glib::WeakRef<GraphView>,
+        /// Scale and origin, in graph coordinates, of the last drawn overview
+        pub(super) mapping: Cell<(f64, f64, f64)>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Minimap {
+        const NAME: &'static str = "Minimap";
+        type Type = super::Minimap;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_css_name("minimap");
+        }
+    }
+
+    impl ObjectImpl for Minimap {
+        fn constructed(&self) {
+            self.parent_constructed();
+            let obj = self.obj();
+            obj.set_size_request(MINIMAP_WIDTH, MINIMAP_HEIGHT);
+            obj.set_overflow(gtk::Overflow::Hidden);
+
+            let drag_controller = gtk::GestureDrag::new();
+            drag_controller.connect_drag_begin(|drag_controller, x, y| {
+                if let Some(minimap) = drag_controller.widget().and_downcast::<super::Minimap>() {
+                    minimap.pan_to(x, y);
+                }
+            });
+            drag_controller.connect_drag_update(|drag_controller, x, y| {
+                let Some((start_x, start_y)) = drag_controller.start_point() else {
+                    return;
+                };
+                if let Some(minimap) = drag_controller.widget().and_downcast::<super::Minimap>() {
+                    minimap.pan_to(start_x + x, start_y + y);
+                }
+            });
+            obj.add_controller(drag_controller);
+        }
+    }
+
+    impl WidgetImpl for Minimap {
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            let Some(graphview) = self.graphview.upgrade() else {
+                return;
+            };
+            let widget = &*self.obj();
+            let (width, height) = (widget.width() as f64, widget.height() as f64);
+            let cr =
+                snapshot.append_cairo(&graphene::Rect::new(0.0, 0.0, width as f32, height as f32));
+            set_source(&cr, BACKGROUND_COLOR);
+            cr.rectangle(0.0, 0.0, width, height);
+            let _ = cr.fill();
+
+            // The overview covers all the nodes and the visible area of the graph view
+            let visible = graphview.visible_rect();
+            let bounds = match (graphview.nodes_bounds(false), visible) {
+                (Some(nodes), Some(visible)) => nodes.union(&visible),
+                (Some(nodes), None) => nodes,
+                (None, Some(visible)) => visible,
+                (None, None) => return,
+            };
+            let scale = ((width - 2.0 * MINIMAP_PADDING) / bounds.width() as f64)
+                .min((height - 2.0 * MINIMAP_PADDING) / bounds.height() as f64);
+            if !scale.is_finite() || scale <= 0.0 {
+                return;
+            }
+            self.mapping
+                .set((scale, bounds.x() as f64, bounds.y() as f64));
+            let to_minimap = |x: f32, y: f32| {
+                (
+                    (x - bounds.x()) as f64 * scale + MINIMAP_PADDING,
+                    (y - bounds.y()) as f64 * scale + MINIMAP_PADDING,
+                )
+            };
+
+            set_source(&cr, LINK_COLOR);
+            cr.set_line_width(1.0);
+            for link in [graphview.all_links(true), graphview.all_links(false)].concat() {
+                if let (Some(from), Some(to)) = (
+                    graphview.node_rect(link.node_from),
+                    graphview.node_rect(link.node_to),
+                ) {
+                    let (from_x, from_y) =
+                        to_minimap(from.x() + from.width(), from.y() + from.height() / 2.0);
+                    let (to_x, to_y) = to_minimap(to.x(), to.y() + to.height() / 2.0);
+                    cr.move_to(from_x, from_y);
+                    cr.line_to(to_x, to_y);
+                }
+            }
+            let _ = cr.stroke();
+
+            for node in graphview.all_nodes(NodeType::All) {
+                let Some(rect) = graphview.node_rect(node.id()) else {
+                    continue;
+                };
+                if node.selected() {
+                    set_source(&cr, NODE_SELECTED_COLOR);
+                } else {
+                    set_source(&cr, NODE_COLOR);
+                }
+                let (x, y) = to_minimap(rect.x(), rect.y());
+                // Keep the nodes visible when they are not allocated yet or very small
+                cr.rectangle(
+                    x,
+                    y,
+                    (rect.width() as f64 * scale).max(2.0),
+                    (rect.height() as f64 * scale).max(2.0),
+                );
+                let _ = cr.fill();
+            }
+
+            if let Some(visible) = visible {
+                let (x, y) = to_minimap(visible.x(), visible.y());
+                set_source(&cr, VIEWPORT_COLOR);
+                cr.set_line_width(1.5);
+                cr.rectangle(
+                    x,
+                    y,
+                    visible.width() as f64 * scale,
+                    visible.height() as f64 * scale,
+                );
+                if let Err(e) = cr.stroke() {
+                    warn!("Failed to draw the minimap viewport: {}", e);
+                }
+            }
+        }
+    }
+
+    fn set_source(cr: &gtk::cairo::Context, color: (f64, f64, f64, f64)) {
+        cr.set_source_rgba(color.0, color.1, color.2, color.3);
+    }
+}
+
+glib::wrapper! {
+    pub struct Minimap(ObjectSubclass<imp::Minimap>)
+        @extends gtk::Widget, gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl Minimap {
+    /// Create a minimap displaying the overview of a graph view.
+    pub fn new(graphview: &GraphView) -> Self {
+        let minimap: Self = glib::Object::new();
+        minimap.imp().graphview.set(Some(graphview));
+
+        // Redraw when the graph, the zoom level or the scrolled position change
+        graphview.connect_local(
+            "graph-updated",
+            false,
+            clone!(
+                #[weak]
+                minimap,
+                #[upgrade_or]
+                None,
+                move |_| {
+                    minimap.queue_draw();
+                    None
+                }
+            ),
+        );
+        graphview.connect_notify_local(
+            Some("zoom-factor"),
+            clone!(
+                #[weak]
+                minimap,
+                move |_, _| minimap.queue_draw()
+            ),
+        );
+        for name in ["hadjustment", "vadjustment"] {
+            // The adjustments are set by the scrolled window, possibly after this call
+            if let Some(adjustment) = graphview.property::<Option<gtk::Adjustment>>(name) {
+                minimap.follow_adjustment(&adjustment);
+            }
+            graphview.connect_notify_local(
+                Some(name),
+                clone!(
+                    #[weak]
+                    minimap,
+                    move |graphview, pspec| {
+                        if let Some(adjustment) =
+                            graphview.property::<Option<gtk::Adjustment>>(pspec.name())
+                        {
+                            minimap.follow_adjustment(&adjustment);
+                        }
+                    }
+                ),
+            );
+        }
+        minimap
+    }
+
+    fn follow_adjustment(&self, adjustment: &gtk::Adjustment) {
+        adjustment.connect_value_changed(clone!(
+            #[weak(rename_to = minimap)]
+            self,
+            move |_| minimap.queue_draw()
+        ));
+    }
+
+    /// Centre the graph view on the graph position displayed at (x, y) in the minimap.
+    fn pan_to(&self, x: f64, y: f64) {
+        let Some(graphview) = self.imp().graphview.upgrade() else {
+            return;
+        };
+        let (scale, origin_x, origin_y) = self.imp().mapping.get();
+        if scale <= 0.0 {
+            return;
+        }
+        graphview.scroll_to_position(
+            ((x - MINIMAP_PADDING) / scale + origin_x) as f32,
+            ((y - MINIMAP_PADDING) / scale + origin_y) as f32,
+        );
+    }
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
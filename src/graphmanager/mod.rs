@@ -1,20 +1,28 @@
+mod annotation;
 pub mod dot_parser;
 mod graphview;
 mod link;
+mod minimap;
 mod node;
 mod port;
 mod property;
 mod selection;
+mod template;
 mod undo;
 
-#[cfg(test)]
-pub use graphview::AutoArrangeOptions;
+pub use annotation::{Annotation, AnnotationKind, ANNOTATION_COLORS};
 pub use graphview::GraphView;
+pub use graphview::NodeAlignment;
+pub use graphview::{AutoArrangeOptions, LayoutMode, LayoutOrientation};
+pub use link::LinkRouting;
+pub use minimap::Minimap;
 pub use node::Node;
 pub use node::NodeType;
-pub use port::{Port, PortDirection, PortPresence};
+pub use node::EMBEDDED_PROPERTY;
+pub use port::{Port, PortDirection, PortPresence, STUB_LABEL_PROPERTY};
 pub use property::PropertyExt;
 pub use selection::SelectionExt;
+pub use template::{GraphTemplate, TemplateNode, TemplateParameter, MACRO_PORT_PROPERTY};
 
 #[cfg(test)]
 mod test;
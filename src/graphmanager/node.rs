@@ -20,11 +20,23 @@ use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Write as _;
 
+/// Hidden property of a node set as the value of a property of another node, as
+/// `parent_node_id:property`. Such a node is removed along with its parent.
+pub const EMBEDDED_PROPERTY: &str = "_embedded_in";
+
+/// Parent node id and property from the value of an `EMBEDDED_PROPERTY`.
+pub(super) fn parse_embedded_parent(value: &str) -> Option<(u32, String)> {
+    let (node_id, property) = value.split_once(':')?;
+    Some((node_id.parse().ok()?, property.to_string()))
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum NodeType {
     Source,
     Transform,
     Sink,
+    /// Group of nodes expanded into a sub-pipeline, see `GraphTemplate::from_macro_node`
+    Macro,
     All,
     Unknown,
 }
@@ -41,6 +53,7 @@ impl NodeType {
             "Source" => NodeType::Source,
             "Transform" => NodeType::Transform,
             "Sink" => NodeType::Sink,
+            "Macro" => NodeType::Macro,
             "All" => NodeType::All,
             _ => NodeType::Unknown,
         }
@@ -64,6 +77,8 @@ mod imp {
         pub(super) properties: RefCell<HashMap<String, String>>,
         pub(super) selected: Cell<bool>,
         pub(super) light: Cell<bool>,
+        pub(super) highlighted: Cell<bool>,
+        pub(super) pinned: Cell<bool>,
         pub(super) position: Cell<(f32, f32)>,
         pub(super) unique_name: RefCell<String>,
     }
@@ -151,6 +166,11 @@ glib::wrapper! {
 }
 
 impl Node {
+    /// Parent node id and property of a node embedded in a property of another node.
+    pub fn embedded_parent(&self) -> Option<(u32, String)> {
+        parse_embedded_parent(&PropertyExt::property(self, EMBEDDED_PROPERTY)?)
+    }
+
     /// Create a new node
     ///
     pub fn new(id: u32, name: &str, node_type: NodeType) -> Self {
@@ -162,6 +182,9 @@ impl Node {
         unique_name.push_str(&id.to_string());
         private.unique_name.replace(unique_name);
         res.add_css_class("node");
+        if node_type == NodeType::Macro {
+            res.add_css_class("node-macro");
+        }
         private
             .node_type
             .set(node_type)
@@ -329,6 +352,38 @@ impl Node {
         self_.light.get()
     }
 
+    /// Mark the node as matching a search.
+    pub fn set_highlighted(&self, highlighted: bool) {
+        let self_ = imp::Node::from_obj(self);
+        self_.highlighted.set(highlighted);
+        if highlighted {
+            self.add_css_class("node-highlighted");
+        } else {
+            self.remove_css_class("node-highlighted");
+        }
+    }
+
+    pub fn highlighted(&self) -> bool {
+        let self_ = imp::Node::from_obj(self);
+        self_.highlighted.get()
+    }
+
+    /// Pin the node to its position, auto-arrange will not move it.
+    pub fn set_pinned(&self, pinned: bool) {
+        let self_ = imp::Node::from_obj(self);
+        self_.pinned.set(pinned);
+        if pinned {
+            self.add_css_class("node-pinned");
+        } else {
+            self.remove_css_class("node-pinned");
+        }
+    }
+
+    pub fn pinned(&self) -> bool {
+        let self_ = imp::Node::from_obj(self);
+        self_.pinned.get()
+    }
+
     //Private
 
     fn set_name(&self, name: &str) {
@@ -17,6 +17,10 @@ use std::fmt;
 
 use super::{PropertyExt, SelectionExt};
 
+/// Hidden port property holding a label drawn as a stub next to the port, for
+/// connections which are not represented by a link in this graph.
+pub const STUB_LABEL_PROPERTY: &str = "_stub_label";
+
 #[derive(Debug, Clone, PartialOrd, PartialEq, Eq, Copy)]
 pub enum PortDirection {
     Input,
@@ -194,6 +198,11 @@ impl Port {
         *private.presence.get().expect("Port presence is not set")
     }
 
+    /// Retrieves the stub label of the port if any
+    pub fn stub_label(&self) -> Option<String> {
+        PropertyExt::property(self, STUB_LABEL_PROPERTY)
+    }
+
     /// Get link anchor point for drawing connections
     pub fn get_link_anchor(&self) -> graphene::Point {
         graphene::Point::new(
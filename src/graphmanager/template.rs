@@ -0,0 +1,352 @@
+// template.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GraphManager
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Reusable groups of nodes saved out of a graph.
+//!
+//! A template holds a copy of some nodes with their properties and ports, the links
+//! between them, and marks the ports left free to link the group to the rest of a
+//! graph. Inserting a template creates new nodes, ports and links with fresh ids.
+//!
+//! A template can also be collapsed into a single macro node, which keeps the template
+//! in a hidden property and has one port per exposed port of the template. The
+//! parameters of the template are the properties of the macro node, forwarded to the
+//! properties of the inner nodes.
+
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use xml::reader::EventReader;
+use xml::reader::XmlEvent as XMLREvent;
+use xml::writer::EmitterConfig;
+use xml::writer::XmlEvent as XMLWEvent;
+
+use super::node::parse_embedded_parent;
+use super::{Node, NodeType, PortDirection, PortPresence, PropertyExt, EMBEDDED_PROPERTY};
+
+const TEMPLATE_XML_VERSION: &str = "1";
+
+/// Hidden property of a macro node holding its template rendered as XML.
+pub const MACRO_TEMPLATE_PROPERTY: &str = "_macro";
+/// Hidden property of a port of a macro node holding the id of the template port it maps to.
+pub const MACRO_PORT_PROPERTY: &str = "_macro_port";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplatePort {
+    /// Id of the port in the template, only used by its links
+    pub id: u32,
+    pub name: String,
+    pub direction: PortDirection,
+    pub presence: PortPresence,
+    pub properties: HashMap<String, String>,
+    /// The port is not linked inside the template and can be linked to the graph
+    pub exposed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateNode {
+    /// Id of the node in the template, only used by its links and embedded nodes
+    pub id: u32,
+    pub name: String,
+    pub node_type: NodeType,
+    /// Position relative to the top left corner of the template
+    pub position: (f32, f32),
+    pub properties: HashMap<String, String>,
+    pub ports: Vec<TemplatePort>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateLink {
+    pub node_from: u32,
+    pub node_to: u32,
+    pub port_from: u32,
+    pub port_to: u32,
+    pub name: String,
+}
+
+/// Named value forwarded to a property of a node of the template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateParameter {
+    pub name: String,
+    /// Id of the node in the template
+    pub node: u32,
+    pub property: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphTemplate {
+    pub name: String,
+    pub nodes: Vec<TemplateNode>,
+    pub links: Vec<TemplateLink>,
+    pub parameters: Vec<TemplateParameter>,
+}
+
+fn attribute<'a>(attrs: &'a HashMap<String, String>, name: &str) -> anyhow::Result<&'a String> {
+    attrs
+        .get(name)
+        .ok_or_else(|| anyhow::anyhow!("Missing template attribute {}", name))
+}
+
+fn id_attribute(attrs: &HashMap<String, String>, name: &str) -> anyhow::Result<u32> {
+    Ok(attribute(attrs, name)?.parse::<u32>()?)
+}
+
+fn write_properties<W: std::io::Write>(
+    writer: &mut xml::EventWriter<W>,
+    properties: &HashMap<String, String>,
+) -> anyhow::Result<()> {
+    let mut properties: Vec<_> = properties.iter().collect();
+    properties.sort();
+    for (name, value) in properties {
+        writer.write(
+            XMLWEvent::start_element("Property")
+                .attr("name", name)
+                .attr("value", value),
+        )?;
+        writer.write(XMLWEvent::end_element())?;
+    }
+    Ok(())
+}
+
+impl TemplateNode {
+    /// Parent node id and property of a node embedded in a property of another node of
+    /// the template.
+    pub fn embedded_parent(&self) -> Option<(u32, String)> {
+        parse_embedded_parent(self.properties.get(EMBEDDED_PROPERTY)?)
+    }
+}
+
+impl GraphTemplate {
+    /// Ports which are not linked inside the template, in the order of the nodes.
+    pub fn exposed_ports(&self, direction: PortDirection) -> Vec<&TemplatePort> {
+        self.nodes
+            .iter()
+            .flat_map(|node| node.ports.iter())
+            .filter(|port| port.exposed && port.direction == direction)
+            .collect()
+    }
+
+    /// Template of a macro node, `None` if the node is not a macro.
+    pub fn from_macro_node(node: &Node) -> Option<GraphTemplate> {
+        let xml = PropertyExt::property(node, MACRO_TEMPLATE_PROPERTY)?;
+        GraphTemplate::load_from_xml(xml.as_bytes()).ok()
+    }
+
+    pub fn node(&self, id: u32) -> Option<&TemplateNode> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    /// Node and port of the template with the given port id.
+    pub fn port(&self, id: u32) -> Option<(&TemplateNode, &TemplatePort)> {
+        self.nodes.iter().find_map(|node| {
+            node.ports
+                .iter()
+                .find(|port| port.id == id)
+                .map(|port| (node, port))
+        })
+    }
+
+    /// Nodes of the template embedded in the properties of a node of the template, by
+    /// property name.
+    pub fn embedded_nodes(&self, parent_id: u32) -> Vec<(String, &TemplateNode)> {
+        let mut nodes: Vec<(String, &TemplateNode)> = self
+            .nodes
+            .iter()
+            .filter_map(|node| match node.embedded_parent() {
+                Some((node_id, property)) if node_id == parent_id => Some((property, node)),
+                _ => None,
+            })
+            .collect();
+        nodes.sort_by(|a, b| a.0.cmp(&b.0));
+        nodes
+    }
+
+    /// Properties of a node of the template with the values of the parameters applied.
+    pub fn node_properties(
+        &self,
+        node: &TemplateNode,
+        values: &HashMap<String, String>,
+    ) -> HashMap<String, String> {
+        let mut properties = node.properties.clone();
+        for parameter in self.parameters.iter().filter(|p| p.node == node.id) {
+            if let Some(value) = values.get(&parameter.name).filter(|v| !v.is_empty()) {
+                properties.insert(parameter.property.clone(), value.clone());
+            }
+        }
+        properties
+    }
+
+    /// Render the template with the XML format of the graph.
+    pub fn render_xml(&self) -> anyhow::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        let mut writer = EmitterConfig::new()
+            .perform_indent(true)
+            .create_writer(&mut buffer);
+
+        writer.write(
+            XMLWEvent::start_element("Template")
+                .attr("name", &self.name)
+                .attr("version", TEMPLATE_XML_VERSION),
+        )?;
+        for node in &self.nodes {
+            writer.write(
+                XMLWEvent::start_element("Node")
+                    .attr("name", &node.name)
+                    .attr("id", &node.id.to_string())
+                    .attr("type", &node.node_type.to_string())
+                    .attr("pos_x", &node.position.0.to_string())
+                    .attr("pos_y", &node.position.1.to_string()),
+            )?;
+            for port in &node.ports {
+                writer.write(
+                    XMLWEvent::start_element("Port")
+                        .attr("name", &port.name)
+                        .attr("id", &port.id.to_string())
+                        .attr("direction", &port.direction.to_string())
+                        .attr("presence", &port.presence.to_string())
+                        .attr("exposed", &port.exposed.to_string()),
+                )?;
+                write_properties(&mut writer, &port.properties)?;
+                writer.write(XMLWEvent::end_element())?;
+            }
+            write_properties(&mut writer, &node.properties)?;
+            writer.write(XMLWEvent::end_element())?;
+        }
+        for link in &self.links {
+            writer.write(
+                XMLWEvent::start_element("Link")
+                    .attr("node_from", &link.node_from.to_string())
+                    .attr("node_to", &link.node_to.to_string())
+                    .attr("port_from", &link.port_from.to_string())
+                    .attr("port_to", &link.port_to.to_string())
+                    .attr("name", &link.name),
+            )?;
+            writer.write(XMLWEvent::end_element())?;
+        }
+        for parameter in &self.parameters {
+            writer.write(
+                XMLWEvent::start_element("Parameter")
+                    .attr("name", &parameter.name)
+                    .attr("node", &parameter.node.to_string())
+                    .attr("property", &parameter.property),
+            )?;
+            writer.write(XMLWEvent::end_element())?;
+        }
+        writer.write(XMLWEvent::end_element())?;
+        Ok(buffer)
+    }
+
+    /// Load a template rendered by `render_xml`.
+    pub fn load_from_xml(buffer: &[u8]) -> anyhow::Result<GraphTemplate> {
+        let parser = EventReader::new(Cursor::new(buffer));
+        let mut template: Option<GraphTemplate> = None;
+        let mut current_node: Option<TemplateNode> = None;
+        let mut current_port: Option<TemplatePort> = None;
+
+        for e in parser {
+            match e? {
+                XMLREvent::StartElement {
+                    name, attributes, ..
+                } => {
+                    let attrs: HashMap<String, String> = attributes
+                        .into_iter()
+                        .map(|a| (a.name.to_string(), a.value))
+                        .collect();
+                    match name.to_string().as_str() {
+                        "Template" => {
+                            template = Some(GraphTemplate {
+                                name: attribute(&attrs, "name")?.clone(),
+                                ..Default::default()
+                            });
+                        }
+                        "Node" => {
+                            let position = |name: &str| {
+                                attrs
+                                    .get(name)
+                                    .and_then(|v| v.parse::<f32>().ok())
+                                    .unwrap_or(0.0)
+                            };
+                            current_node = Some(TemplateNode {
+                                id: id_attribute(&attrs, "id")?,
+                                name: attribute(&attrs, "name")?.clone(),
+                                node_type: NodeType::from_str(attribute(&attrs, "type")?),
+                                position: (position("pos_x"), position("pos_y")),
+                                properties: HashMap::new(),
+                                ports: vec![],
+                            });
+                        }
+                        "Port" => {
+                            current_port = Some(TemplatePort {
+                                id: id_attribute(&attrs, "id")?,
+                                name: attribute(&attrs, "name")?.clone(),
+                                direction: PortDirection::from_str(attribute(&attrs, "direction")?),
+                                presence: attrs
+                                    .get("presence")
+                                    .map(|presence| PortPresence::from_str(presence))
+                                    .unwrap_or(PortPresence::Always),
+                                properties: HashMap::new(),
+                                exposed: attrs
+                                    .get("exposed")
+                                    .map(|exposed| exposed == "true")
+                                    .unwrap_or(false),
+                            });
+                        }
+                        "Property" => {
+                            let name = attribute(&attrs, "name")?.clone();
+                            let value = attribute(&attrs, "value")?.clone();
+                            if let Some(port) = current_port.as_mut() {
+                                port.properties.insert(name, value);
+                            } else if let Some(node) = current_node.as_mut() {
+                                node.properties.insert(name, value);
+                            }
+                        }
+                        "Link" => {
+                            if let Some(template) = template.as_mut() {
+                                template.links.push(TemplateLink {
+                                    node_from: id_attribute(&attrs, "node_from")?,
+                                    node_to: id_attribute(&attrs, "node_to")?,
+                                    port_from: id_attribute(&attrs, "port_from")?,
+                                    port_to: id_attribute(&attrs, "port_to")?,
+                                    name: attrs.get("name").cloned().unwrap_or_default(),
+                                });
+                            }
+                        }
+                        "Parameter" => {
+                            if let Some(template) = template.as_mut() {
+                                template.parameters.push(TemplateParameter {
+                                    name: attribute(&attrs, "name")?.clone(),
+                                    node: id_attribute(&attrs, "node")?,
+                                    property: attribute(&attrs, "property")?.clone(),
+                                });
+                            }
+                        }
+                        other => anyhow::bail!("Unknown template element {}", other),
+                    }
+                }
+                XMLREvent::EndElement { name } => match name.to_string().as_str() {
+                    "Node" => {
+                        if let (Some(template), Some(node)) =
+                            (template.as_mut(), current_node.take())
+                        {
+                            template.nodes.push(node);
+                        }
+                    }
+                    "Port" => {
+                        if let (Some(node), Some(port)) =
+                            (current_node.as_mut(), current_port.take())
+                        {
+                            node.ports.push(port);
+                        }
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        template.ok_or_else(|| anyhow::anyhow!("No template found"))
+    }
+}
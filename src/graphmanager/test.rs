@@ -36,7 +36,10 @@ static TEST_THREAD_WORKER: once_cell::sync::Lazy<gtk::glib::ThreadPool> =
         pool
     });
 
-use crate::graphmanager::{GraphView, Node, NodeType, PortDirection, PortPresence, PropertyExt};
+use crate::graphmanager::{
+    AnnotationKind, GraphTemplate, GraphView, LinkRouting, Node, NodeAlignment, NodeType,
+    PortDirection, PortPresence, PropertyExt, SelectionExt,
+};
 use gtk::prelude::WidgetExt;
 
 #[test]
@@ -1053,6 +1056,266 @@ fn auto_arrange_custom_options() {
     });
 }
 
+#[test]
+fn auto_arrange_keeps_pinned_and_unselected_nodes() {
+    test_synced(|| {
+        use crate::graphmanager::AutoArrangeOptions;
+
+        let graphview = GraphView::new();
+        // Placed by add_node at (20, 20), (320, 20) and (620, 20)
+        let src = graphview.create_node_with_port("src", NodeType::Source, 0, 1);
+        graphview.add_node(src.clone());
+        let filter = graphview.create_node_with_port("filter", NodeType::Transform, 1, 1);
+        graphview.add_node(filter.clone());
+        let sink = graphview.create_node_with_port("sink", NodeType::Sink, 1, 0);
+        graphview.add_node(sink.clone());
+        graphview.add_link(graphview.create_link(1, 2, 1, 2));
+        graphview.add_link(graphview.create_link(2, 3, 3, 4));
+        graphview.clear_undo_history();
+
+        // The pinned sink stays in place, the other nodes are arranged
+        sink.set_pinned(true);
+        assert!(graphview.auto_arrange_graph(None));
+        assert_eq!(src.position(), (50.0, 50.0));
+        assert_eq!(filter.position(), (150.0, 50.0));
+        assert_eq!(sink.position(), (620.0, 20.0));
+        assert_eq!(graphview.undo_count(), 1);
+        assert!(graphview.undo());
+        assert_eq!(filter.position(), (320.0, 20.0));
+
+        // The pin is saved with the graph
+        let buffer = graphview
+            .render_xml()
+            .expect("Should be able to render graph to xml");
+        graphview
+            .load_from_xml(buffer)
+            .expect("Should be able to load from XML data");
+        let sink = graphview.node(3).expect("sink");
+        assert!(sink.pinned());
+        assert!(!graphview.node(1).expect("src").pinned());
+        sink.set_pinned(false);
+
+        // Only the selection is arranged, where it is
+        graphview.node(1).expect("src").set_selected(true);
+        graphview.node(2).expect("filter").set_selected(true);
+        let options = AutoArrangeOptions {
+            selected_only: true,
+            ..Default::default()
+        };
+        assert!(graphview.auto_arrange_graph(Some(options)));
+        assert_eq!(graphview.node(1).expect("src").position(), (20.0, 20.0));
+        assert_eq!(graphview.node(2).expect("filter").position(), (120.0, 20.0));
+        assert_eq!(sink.position(), (620.0, 20.0));
+
+        // Nothing to arrange when all the nodes are pinned
+        for node in graphview.all_nodes(NodeType::All) {
+            node.set_pinned(true);
+        }
+        assert!(!graphview.auto_arrange_graph(None));
+    });
+}
+
+#[test]
+fn auto_arrange_top_to_bottom_compact_orders_tee_branches() {
+    test_synced(|| {
+        use crate::graphmanager::{AutoArrangeOptions, LayoutMode, LayoutOrientation};
+
+        let graphview = GraphView::new();
+        let tee = graphview.create_node_with_port("tee", NodeType::Source, 0, 2);
+        graphview.add_node(tee.clone());
+        let sink_a = graphview.create_node_with_port("sink_a", NodeType::Sink, 1, 0);
+        graphview.add_node(sink_a.clone());
+        let sink_b = graphview.create_node_with_port("sink_b", NodeType::Sink, 1, 0);
+        graphview.add_node(sink_b.clone());
+        // The first branch of the tee goes to the second sink and the other way around
+        graphview.add_link(graphview.create_link(1, 3, 1, 3));
+        graphview.add_link(graphview.create_link(1, 2, 2, 3));
+        graphview.clear_undo_history();
+
+        let options = AutoArrangeOptions {
+            orientation: LayoutOrientation::TopToBottom,
+            mode: LayoutMode::Compact,
+            ..Default::default()
+        };
+        assert!(graphview.auto_arrange_graph(Some(options)));
+
+        // Stages follow each other from top to bottom
+        let (tee_pos, a_pos, b_pos) = (tee.position(), sink_a.position(), sink_b.position());
+        assert!(tee_pos.1 < a_pos.1);
+        assert_eq!(a_pos.1, b_pos.1);
+        // Branches in the order of the tee pads, with the tee centred above them
+        assert!(
+            b_pos.0 < a_pos.0,
+            "Branches should not cross: {:?} {:?}",
+            b_pos,
+            a_pos
+        );
+        assert!(b_pos.0 < tee_pos.0 && tee_pos.0 < a_pos.0);
+        assert_eq!(b_pos.0, 50.0);
+        assert_eq!(graphview.undo_count(), 1);
+    });
+}
+
+#[test]
+fn align_selected_nodes_single_undo() {
+    test_synced(|| {
+        let graphview = GraphView::new();
+        // Placed by add_node at (20, 20) and (320, 20), (320, 140)
+        let src = graphview.create_node_with_port("src", NodeType::Source, 0, 1);
+        graphview.add_node(src.clone());
+        let filter1 = graphview.create_node_with_port("filter", NodeType::Transform, 1, 1);
+        graphview.add_node(filter1);
+        let filter2 = graphview.create_node_with_port("filter", NodeType::Transform, 1, 1);
+        graphview.add_node(filter2.clone());
+        graphview.clear_undo_history();
+
+        // Nothing to align with a single selected node
+        src.set_selected(true);
+        assert!(!graphview.align_selected_nodes(NodeAlignment::Left));
+
+        filter2.set_selected(true);
+        assert_eq!(graphview.selected_nodes().len(), 2);
+        assert!(graphview.align_selected_nodes(NodeAlignment::Left));
+        assert_eq!(src.position(), (20.0, 20.0));
+        assert_eq!(filter2.position(), (20.0, 140.0));
+        assert_eq!(graphview.undo_count(), 1);
+
+        assert!(graphview.align_selected_nodes(NodeAlignment::Top));
+        assert_eq!(filter2.position(), (20.0, 20.0));
+
+        // Each alignment is a single undo step
+        assert!(graphview.undo());
+        assert_eq!(filter2.position(), (20.0, 140.0));
+        assert!(graphview.undo());
+        assert_eq!(filter2.position(), (320.0, 140.0));
+        assert_eq!(src.position(), (20.0, 20.0));
+    });
+}
+
+#[test]
+fn distribute_selected_nodes_horizontally() {
+    test_synced(|| {
+        let graphview = GraphView::new();
+        // Placed by add_node at x = 20, 320, 320 and 620
+        let nodes = [
+            graphview.create_node_with_port("src", NodeType::Source, 0, 1),
+            graphview.create_node_with_port("filter", NodeType::Transform, 1, 1),
+            graphview.create_node_with_port("filter", NodeType::Transform, 1, 1),
+            graphview.create_node_with_port("sink", NodeType::Sink, 1, 0),
+        ];
+        for node in &nodes {
+            graphview.add_node(node.clone());
+            node.set_selected(true);
+        }
+        graphview.clear_undo_history();
+
+        assert!(graphview.distribute_selected_nodes(gtk::Orientation::Horizontal));
+        let mut xs: Vec<f32> = nodes.iter().map(|node| node.position().0).collect();
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        // Widgets are not realized in tests so the nodes have no width
+        assert_eq!(xs, vec![20.0, 220.0, 420.0, 620.0]);
+        assert_eq!(graphview.undo_count(), 1);
+
+        // Already distributed, nothing to move
+        assert!(!graphview.distribute_selected_nodes(gtk::Orientation::Horizontal));
+    });
+}
+
+#[test]
+fn snap_to_grid() {
+    test_synced(|| {
+        let graphview = GraphView::new();
+        let point = gtk::graphene::Point::new(37.0, 52.0);
+        // No grid by default, the point is kept
+        assert_eq!(graphview.grid_snap(), None);
+        assert_eq!(graphview.snap_to_grid(&point), point);
+
+        graphview.set_grid_snap(Some(20.0));
+        assert_eq!(graphview.grid_snap(), Some(20.0));
+        assert_eq!(
+            graphview.snap_to_grid(&point),
+            gtk::graphene::Point::new(40.0, 60.0)
+        );
+        assert_eq!(
+            graphview.snap_to_grid(&gtk::graphene::Point::new(-9.0, 9.0)),
+            gtk::graphene::Point::new(0.0, 0.0)
+        );
+
+        // A null spacing disables the grid
+        graphview.set_grid_snap(Some(0.0));
+        assert_eq!(graphview.grid_snap(), None);
+        assert_eq!(graphview.snap_to_grid(&point), point);
+    });
+}
+
+#[test]
+fn delete_selected_single_undo() {
+    test_synced(|| {
+        let graphview = GraphView::new();
+        let node1 = graphview.create_node_with_port("node1", NodeType::Source, 0, 1);
+        graphview.add_node(node1.clone());
+        let node2 = graphview.create_node_with_port("node2", NodeType::Transform, 1, 1);
+        graphview.add_node(node2.clone());
+        let node3 = graphview.create_node_with_port("node3", NodeType::Sink, 1, 0);
+        graphview.add_node(node3);
+        graphview.add_link(graphview.create_link(1, 2, 1, 2));
+        graphview.add_link(graphview.create_link(2, 3, 3, 4));
+        graphview.clear_undo_history();
+
+        node1.set_selected(true);
+        node2.set_selected(true);
+        graphview.delete_selected();
+        assert_eq!(graphview.all_nodes(NodeType::All).len(), 1);
+        assert_eq!(graphview.all_links(true).len(), 0);
+        assert_eq!(graphview.undo_count(), 1);
+
+        // The nodes and their links come back in a single step
+        assert!(graphview.undo());
+        assert_eq!(graphview.all_nodes(NodeType::All).len(), 3);
+        assert_eq!(graphview.all_links(true).len(), 2);
+        assert!(!graphview.can_undo());
+
+        assert!(graphview.redo());
+        assert_eq!(graphview.all_nodes(NodeType::All).len(), 1);
+        assert_eq!(graphview.all_links(true).len(), 0);
+    });
+}
+
+#[test]
+fn nested_batches_single_undo() {
+    test_synced(|| {
+        let graphview = GraphView::new();
+        let node1 = graphview.create_node_with_port("node1", NodeType::Source, 0, 1);
+        graphview.add_node(node1);
+        let node2 = graphview.create_node_with_port("node2", NodeType::Sink, 1, 0);
+        graphview.add_node(node2);
+        graphview.add_link(graphview.create_link(1, 2, 1, 2));
+        graphview.clear_undo_history();
+
+        // Insert a node on the link, as done from the application
+        graphview.begin_batch();
+        let node3 = graphview.create_node_with_port("node3", NodeType::Transform, 1, 1);
+        graphview.add_node(node3);
+        graphview.begin_batch();
+        graphview.remove_link(graphview.all_links(true)[0].id);
+        graphview.add_link(graphview.create_link(1, 3, 1, 3));
+        graphview.end_batch();
+        assert_eq!(graphview.undo_count(), 0);
+        graphview.add_link(graphview.create_link(3, 2, 4, 2));
+        graphview.end_batch();
+        assert_eq!(graphview.undo_count(), 1);
+
+        assert!(graphview.undo());
+        assert_eq!(graphview.all_nodes(NodeType::All).len(), 2);
+        assert_eq!(graphview.all_links(true).len(), 1);
+        assert!(!graphview.can_undo());
+
+        assert!(graphview.redo());
+        assert_eq!(graphview.all_nodes(NodeType::All).len(), 3);
+        assert_eq!(graphview.all_links(true).len(), 2);
+    });
+}
+
 #[test]
 fn xml_ports_saved_in_sorted_order() {
     test_synced(|| {
@@ -1698,3 +1961,342 @@ inner0\";
     assert_eq!(graph.nodes.len(), 1, "Should only include top-level node");
     assert_eq!(graph.nodes[0].instance_name, "bin0");
 }
+
+#[test]
+fn nodes_bounds_follow_selection() {
+    test_synced(|| {
+        let graphview = GraphView::new();
+        assert!(graphview.nodes_bounds(false).is_none());
+        // Placed by add_node at (20, 20) and (320, 20)
+        let src = graphview.create_node_with_port("src", NodeType::Source, 0, 1);
+        graphview.add_node(src.clone());
+        let sink = graphview.create_node_with_port("sink", NodeType::Sink, 1, 0);
+        graphview.add_node(sink.clone());
+
+        let rect = graphview.node_rect(sink.id()).expect("sink rect");
+        assert_eq!((rect.x(), rect.y()), (320.0, 20.0));
+        assert!(graphview.node_rect(sink.id() + 100).is_none());
+
+        let bounds = graphview.nodes_bounds(false).expect("graph bounds");
+        assert_eq!((bounds.x(), bounds.y()), (20.0, 20.0));
+        assert!(bounds.x() + bounds.width() >= 320.0);
+
+        assert!(graphview.nodes_bounds(true).is_none());
+        assert!(!graphview.zoom_to_selection());
+        sink.set_selected(true);
+        let bounds = graphview.nodes_bounds(true).expect("selection bounds");
+        assert_eq!((bounds.x(), bounds.y()), (320.0, 20.0));
+    });
+}
+
+#[test]
+fn search_nodes_by_name_property_and_caps() {
+    test_synced(|| {
+        let graphview = GraphView::new();
+        let src = graphview.create_node_with_port("videotestsrc", NodeType::Source, 0, 1);
+        src.add_property("pattern", "ball");
+        src.add_property("_hidden", "secret");
+        graphview.add_node(src.clone());
+        let enc = graphview.create_node_with_port("x264enc", NodeType::Transform, 1, 1);
+        enc.add_property("bitrate", "4000");
+        graphview.add_node(enc.clone());
+        let sink = graphview.create_node_with_port("filesink", NodeType::Sink, 1, 0);
+        graphview.add_node(sink.clone());
+        let link = graphview.create_link(src.id(), enc.id(), 1, 2);
+        link.set_name("video/x-raw,width=640");
+        graphview.add_link(link);
+
+        assert!(graphview.search_nodes("").is_empty());
+        assert_eq!(graphview.search_nodes("X264"), vec![enc.id()]);
+        assert_eq!(graphview.search_nodes("ball"), vec![src.id()]);
+        assert_eq!(graphview.search_nodes("bitrate"), vec![enc.id()]);
+        // Hidden properties are not searched
+        assert!(graphview.search_nodes("secret").is_empty());
+        // Both ends of a link match its caps, sorted by position
+        assert_eq!(
+            graphview.search_nodes("video/x-raw"),
+            vec![src.id(), enc.id()]
+        );
+
+        graphview.highlight_nodes(&[sink.id()]);
+        assert!(sink.highlighted());
+        assert!(!src.highlighted());
+        graphview.highlight_nodes(&[]);
+        assert!(!sink.highlighted());
+
+        // Revealing a node highlights it and keeps the selection
+        sink.set_selected(true);
+        assert!(graphview.reveal_node(enc.id()));
+        assert!(enc.highlighted());
+        assert!(!enc.selected());
+        assert_eq!(graphview.selected_nodes().len(), 1);
+        assert!(sink.selected());
+        assert!(!graphview.reveal_node(enc.id() + 100));
+    });
+}
+
+#[test]
+fn annotations_saved_and_moved_with_frame() {
+    test_synced(|| {
+        let graphview = GraphView::new();
+        // Placed by add_node at (20, 20) and (320, 20)
+        let src = graphview.create_node_with_port("src", NodeType::Source, 0, 1);
+        graphview.add_node(src.clone());
+        let sink = graphview.create_node_with_port("sink", NodeType::Sink, 1, 0);
+        graphview.add_node(sink.clone());
+
+        let frame = graphview.create_annotation(
+            AnnotationKind::Frame,
+            "Recording branch",
+            gtk::graphene::Rect::new(0.0, 0.0, 200.0, 200.0),
+        );
+        let frame_id = frame.id();
+        graphview.add_annotation(frame);
+        let note = graphview.create_annotation(
+            AnnotationKind::Note,
+            "Tweak <bitrate> here\nand there",
+            gtk::graphene::Rect::new(10.0, 120.0, 100.0, 50.0),
+        );
+        let note_id = note.id();
+        graphview.add_annotation(note);
+        graphview.clear_undo_history();
+
+        // Moving the frame carries the node and the note inside it
+        assert!(graphview.set_annotation_rect(
+            frame_id,
+            gtk::graphene::Rect::new(50.0, 100.0, 200.0, 200.0)
+        ));
+        assert_eq!(src.position(), (70.0, 120.0));
+        assert_eq!(sink.position(), (320.0, 20.0));
+        let note = graphview.annotation(note_id).expect("note");
+        assert_eq!((note.rect.x(), note.rect.y()), (60.0, 220.0));
+        assert_eq!(graphview.undo_count(), 1);
+
+        assert!(graphview.undo());
+        assert_eq!(src.position(), (20.0, 20.0));
+        let frame = graphview.annotation(frame_id).expect("frame");
+        assert_eq!((frame.rect.x(), frame.rect.y()), (0.0, 0.0));
+        assert!(graphview.redo());
+        assert_eq!(src.position(), (70.0, 120.0));
+
+        assert!(graphview.set_annotation_text(frame_id, "Preview"));
+        assert!(graphview.undo());
+        assert_eq!(
+            graphview.annotation(frame_id).expect("frame").text,
+            "Recording branch"
+        );
+
+        // Annotations are saved with the graph
+        let buffer = graphview
+            .render_xml()
+            .expect("Should be able to render graph to xml");
+        graphview
+            .load_from_xml(buffer)
+            .expect("Should be able to load from XML data");
+        let annotations = graphview.all_annotations();
+        assert_eq!(annotations.len(), 2);
+        assert_eq!(annotations[0].kind, AnnotationKind::Frame);
+        let note = graphview.annotation(note_id).expect("note");
+        assert_eq!(note.text, "Tweak <bitrate> here\nand there");
+        assert_eq!((note.rect.width(), note.rect.height()), (100.0, 50.0));
+        assert_eq!(graphview.all_nodes(NodeType::All).len(), 2);
+
+        graphview.remove_annotation(note_id);
+        assert!(graphview.annotation(note_id).is_none());
+        assert!(graphview.undo());
+        assert!(graphview.annotation(note_id).is_some());
+    });
+}
+
+#[test]
+fn malformed_annotations_skipped_on_load() {
+    test_synced(|| {
+        let graphview = GraphView::new();
+        let note = graphview.create_annotation(
+            AnnotationKind::Note,
+            "Kept",
+            gtk::graphene::Rect::new(10.0, 10.0, 100.0, 50.0),
+        );
+        graphview.add_annotation(note);
+        let buffer = graphview
+            .render_xml()
+            .expect("Should be able to render graph to xml");
+
+        // Annotations without an id or with an invalid one are skipped
+        let xml = String::from_utf8(buffer).expect("xml should be utf8");
+        let position = xml.find("<Annotation").expect("annotation element");
+        let xml = format!(
+            "{}<Annotation kind=\"Note\">No id</Annotation><Annotation id=\"x\" kind=\"Frame\" />{}",
+            &xml[..position],
+            &xml[position..]
+        );
+        graphview
+            .load_from_xml(xml.into_bytes())
+            .expect("Should be able to load from XML data");
+        let annotations = graphview.all_annotations();
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(annotations[0].text, "Kept");
+    });
+}
+
+#[test]
+fn link_routes_go_around_nodes_and_routing_saved() {
+    test_synced(|| {
+        use crate::graphmanager::link::{link_route, route_contains};
+        use gtk::graphene::{Point, Rect};
+
+        let from = Point::new(0.0, 100.0);
+        let to = Point::new(300.0, 100.0);
+        // A node stands between the two ports
+        let obstacles = [Rect::new(100.0, 50.0, 100.0, 100.0)];
+
+        let route = link_route(LinkRouting::Straight, &from, &to, &obstacles, 20.0, 10.0);
+        assert_eq!(route, vec![from, to]);
+
+        let route = link_route(LinkRouting::Bezier, &from, &to, &obstacles, 20.0, 10.0);
+        assert_eq!(route.first(), Some(&from));
+        assert_eq!(route.last(), Some(&to));
+        assert!(route_contains(&route, &Point::new(150.0, 100.0), 1.0));
+
+        let route = link_route(LinkRouting::Orthogonal, &from, &to, &obstacles, 20.0, 10.0);
+        assert_eq!(route.first(), Some(&from));
+        assert_eq!(route.last(), Some(&to));
+        for segment in route.windows(2) {
+            assert!(segment[0].x() == segment[1].x() || segment[0].y() == segment[1].y());
+        }
+        // Goes over the node, outside of its margin
+        assert!(route_contains(&route, &Point::new(150.0, 40.0), 1.0));
+        assert!(!route_contains(&route, &Point::new(150.0, 100.0), 55.0));
+
+        // The routing is saved with the graph
+        let graphview = GraphView::new();
+        assert_eq!(graphview.link_routing(), LinkRouting::Straight);
+        graphview.set_link_routing(LinkRouting::Orthogonal);
+        let buffer = graphview
+            .render_xml()
+            .expect("Should be able to render graph to xml");
+        let other_graphview = GraphView::new();
+        other_graphview
+            .load_from_xml(buffer)
+            .expect("Should be able to load from XML data");
+        assert_eq!(other_graphview.link_routing(), LinkRouting::Orthogonal);
+    });
+}
+
+#[test]
+fn template_saved_from_nodes_and_inserted() {
+    test_synced(|| {
+        let graphview = GraphView::new();
+        let source = graphview.create_node_with_port("videotestsrc", NodeType::Source, 0, 1);
+        source.add_property("pattern", "ball");
+        graphview.add_node(source);
+        let filter = graphview.create_node_with_port("videoconvert", NodeType::Transform, 1, 1);
+        graphview.add_node(filter);
+        let sink = graphview.create_node_with_port("autovideosink", NodeType::Sink, 1, 0);
+        graphview.add_node(sink);
+        // Port ids: source out 1, filter in 2 and out 3, sink in 4
+        graphview.add_link(graphview.create_link(1, 2, 1, 2));
+        graphview.add_link(graphview.create_link(2, 3, 3, 4));
+
+        // Only the link between the nodes of the template is kept
+        let template = graphview.template_from_nodes("test pattern", &[1, 2]);
+        assert_eq!(template.nodes.len(), 2);
+        assert_eq!(template.links.len(), 1);
+        assert_eq!(template.nodes[0].properties.get("pattern").unwrap(), "ball");
+        let exposed = template.exposed_ports(PortDirection::Output);
+        assert_eq!(exposed.len(), 1);
+        assert_eq!(exposed[0].id, 3);
+        assert!(template.exposed_ports(PortDirection::Input).is_empty());
+
+        let buffer = template
+            .render_xml()
+            .expect("Should be able to render the template to xml");
+        let loaded =
+            GraphTemplate::load_from_xml(&buffer).expect("Should be able to load the template");
+        assert_eq!(loaded, template);
+
+        graphview.clear_undo_history();
+
+        // The inserted nodes get new ids and are linked together
+        let node_ids = graphview.insert_template(&loaded, &gtk::graphene::Point::new(0.0, 0.0));
+        assert_eq!(node_ids.len(), 2);
+        assert!(node_ids.iter().all(|id| *id > 3));
+        assert_eq!(graphview.all_nodes(NodeType::All).len(), 5);
+        assert_eq!(graphview.all_links(true).len(), 3);
+        let inserted_source = graphview.node(node_ids[0]).unwrap();
+        assert_eq!(&inserted_source.property("pattern").unwrap(), "ball");
+        assert!(graphview.node_is_linked(node_ids[1]).is_some());
+        assert_eq!(graphview.selected_nodes().len(), 2);
+
+        // The insertion is undone in a single step
+        assert_eq!(graphview.undo_count(), 1);
+        assert!(graphview.undo());
+        assert_eq!(graphview.all_nodes(NodeType::All).len(), 3);
+        assert_eq!(graphview.all_links(true).len(), 2);
+    });
+}
+
+#[test]
+fn replace_node_keeps_links_as_one_undo_step() {
+    test_synced(|| {
+        let graphview = GraphView::new();
+        let source = graphview.create_node_with_port("videotestsrc", NodeType::Source, 0, 1);
+        graphview.add_node(source);
+        let encoder = graphview.create_node_with_port("x264enc", NodeType::Transform, 1, 1);
+        encoder.add_property("bitrate", "2048");
+        graphview.add_node(encoder);
+        let sink = graphview.create_node_with_port("fakesink", NodeType::Sink, 1, 0);
+        graphview.add_node(sink);
+        // Port ids: source out 1, encoder in 2 and out 3, sink in 4
+        graphview.add_link(graphview.create_link(1, 2, 1, 2));
+        let link = graphview.create_link(2, 3, 3, 4);
+        link.set_name("video/x-h264");
+        graphview.add_link(link);
+        graphview.restore_node_positions(&std::collections::HashMap::from([(
+            2,
+            gtk::graphene::Point::new(300.0, 50.0),
+        )]));
+        graphview.clear_undo_history();
+
+        // Only the output port of the new node gets a link
+        let replacement =
+            graphview.create_node_with_port("vaapih264enc", NodeType::Transform, 1, 1);
+        replacement.add_property("bitrate", "2048");
+        let port_map = std::collections::HashMap::from([(3, 6)]);
+        let new_id = graphview
+            .replace_node(2, replacement, &port_map)
+            .expect("Should be able to replace the node");
+        assert_eq!(new_id, 4);
+        assert!(graphview.node(2).is_none());
+        let new_node = graphview.node(new_id).unwrap();
+        assert_eq!(new_node.name(), "vaapih264enc");
+        assert_eq!(new_node.position(), (300.0, 50.0));
+        let links = graphview.all_links(true);
+        assert_eq!(links.len(), 1);
+        assert_eq!((links[0].node_from, links[0].port_from), (new_id, 6));
+        assert_eq!((links[0].node_to, links[0].port_to), (3, 4));
+        assert_eq!(links[0].name(), "video/x-h264");
+        assert_eq!(graphview.undo_count(), 1);
+
+        assert!(graphview.undo());
+        assert!(graphview.node(new_id).is_none());
+        let old_node = graphview.node(2).unwrap();
+        assert_eq!(old_node.name(), "x264enc");
+        assert_eq!(&old_node.property("bitrate").unwrap(), "2048");
+        assert_eq!(graphview.all_links(true).len(), 2);
+        assert!(!graphview.can_undo());
+
+        assert!(graphview.redo());
+        assert!(graphview.node(2).is_none());
+        assert_eq!(graphview.node(new_id).unwrap().name(), "vaapih264enc");
+        assert_eq!(graphview.all_links(true).len(), 1);
+
+        assert!(graphview
+            .replace_node(
+                42,
+                graphview.create_node("fakesink", NodeType::Sink),
+                &port_map
+            )
+            .is_err());
+    });
+}
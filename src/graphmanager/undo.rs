@@ -19,8 +19,12 @@
 //! - **Add/Remove Node**: Complete node state including all ports and properties
 //! - **Add/Remove Link**: Connection data between nodes
 //! - **Move Node**: Position changes from drag operations
+//! - **Batch Move Nodes**: Selection drags, alignment and auto-arrange as a single step
 //! - **Add/Remove Port**: Dynamic port modifications
+//! - **Replace Node**: A node swapped for another one with its links moved over
 //! - **Modify Property**: Node and port property changes with old/new values
+//! - **Batch**: Several actions undone as one step, such as deleting a selection
+//! - **Add/Remove/Update Annotation**: Notes and frames, a moved frame carries its nodes
 //!
 //! # API Usage
 //!
@@ -52,7 +56,7 @@
 //! - Removing a node captures connected links for atomic restoration
 //! - Maximum depth defaults to 100 operations
 
-use super::{Node, NodeType, Port, PortDirection, PortPresence, PropertyExt};
+use super::{Annotation, Node, NodeType, Port, PortDirection, PortPresence, PropertyExt};
 use gtk::graphene;
 use std::collections::{HashMap, VecDeque};
 
@@ -67,6 +71,7 @@ pub struct NodeData {
     pub node_type: NodeType,
     pub position: (f32, f32),
     pub light: bool,
+    pub pinned: bool,
     pub unique_name: String,
     pub properties: HashMap<String, String>,
     pub ports: Vec<PortData>,
@@ -83,6 +88,7 @@ impl NodeData {
             node_type: node.node_type().cloned().unwrap_or(NodeType::Unknown),
             position: node.position(),
             light: node.light(),
+            pinned: node.pinned(),
             unique_name: node.unique_name(),
             properties: node.properties().clone(),
             ports,
@@ -169,6 +175,29 @@ pub enum UndoAction {
         /// Vector of (node_id, old_position, new_position)
         moves: Vec<(u32, graphene::Point, graphene::Point)>,
     },
+    /// Several actions recorded as a single step, undone in reverse order
+    Batch { actions: Vec<UndoAction> },
+    /// A note or frame was added to the graph
+    AddAnnotation { annotation: Annotation },
+    /// A note or frame was removed from the graph
+    RemoveAnnotation { annotation: Annotation },
+    /// Notes or frames were moved, resized or edited, with the nodes moved along
+    UpdateAnnotations {
+        /// Vector of (old_annotation, new_annotation)
+        changes: Vec<(Annotation, Annotation)>,
+        /// Vector of (node_id, old_position, new_position)
+        node_moves: Vec<(u32, graphene::Point, graphene::Point)>,
+    },
+    /// A node was replaced by another node, its links moved to the new ports
+    ReplaceNode {
+        old_node: NodeData,
+        /// Links that were connected to the replaced node
+        old_links: Vec<LinkData>,
+        new_node: NodeData,
+        /// Links moved to the new node
+        new_links: Vec<LinkData>,
+        position: graphene::Point,
+    },
 }
 
 /// Manages undo/redo history for graph operations
@@ -181,6 +210,10 @@ pub struct UndoStack {
     max_depth: usize,
     /// Flag to prevent recording actions during undo/redo
     recording_enabled: bool,
+    /// Actions recorded since `begin_batch`, pushed as a single step by `end_batch`
+    batch: Option<Vec<UndoAction>>,
+    /// Number of nested `begin_batch` calls not ended yet
+    batch_depth: usize,
 }
 
 impl UndoStack {
@@ -196,6 +229,8 @@ impl UndoStack {
             redo_stack: VecDeque::with_capacity(max_depth),
             max_depth,
             recording_enabled: true,
+            batch: None,
+            batch_depth: 0,
         }
     }
 
@@ -206,6 +241,10 @@ impl UndoStack {
         if !self.recording_enabled {
             return;
         }
+        if let Some(batch) = self.batch.as_mut() {
+            batch.push(action);
+            return;
+        }
 
         // Clear redo stack when new action is performed
         self.redo_stack.clear();
@@ -219,6 +258,30 @@ impl UndoStack {
         }
     }
 
+    /// Start recording the following actions as a single step.
+    ///
+    /// Batches can be nested, the step is pushed by the outermost `end_batch`.
+    pub fn begin_batch(&mut self) {
+        self.batch_depth += 1;
+        self.batch.get_or_insert_with(Vec::new);
+    }
+
+    /// Push the actions recorded since `begin_batch` as a single step.
+    pub fn end_batch(&mut self) {
+        self.batch_depth = self.batch_depth.saturating_sub(1);
+        if self.batch_depth > 0 {
+            return;
+        }
+        let Some(mut actions) = self.batch.take() else {
+            return;
+        };
+        match actions.len() {
+            0 => {}
+            1 => self.push(actions.remove(0)),
+            _ => self.push(UndoAction::Batch { actions }),
+        }
+    }
+
     /// Pop an action from the undo stack (most recent action)
     pub fn pop_undo(&mut self) -> Option<UndoAction> {
         self.undo_stack.pop_back()
@@ -288,6 +351,11 @@ impl UndoStack {
         self.recording_enabled = true;
     }
 
+    /// Check if new actions are recorded
+    pub fn is_recording(&self) -> bool {
+        self.recording_enabled
+    }
+
     /// Get number of actions in undo stack
     pub fn undo_count(&self) -> usize {
         self.undo_stack.len()
@@ -37,12 +37,50 @@ use structopt::StructOpt;
 struct Command {
     #[structopt(about = "Sets the pipeline description", default_value = "")]
     pipeline: String,
+    /// Applies a preset to the elements of the pipeline, as element:preset
+    #[structopt(long = "preset", number_of_values = 1)]
+    presets: Vec<String>,
+    /// Lists the presets of an element and exits
+    #[structopt(long = "list-presets", value_name = "element")]
+    list_presets: Option<String>,
+}
+
+/// Print the studio and GstPreset presets of an element with their properties.
+fn list_presets(factory: &str) -> gtk::glib::ExitCode {
+    if let Err(e) = init_gst() {
+        eprintln!("Failed to initialize GStreamer: {e}");
+        return gtk::glib::ExitCode::FAILURE;
+    }
+    if gst::ElementFactory::find(factory).is_none() {
+        eprintln!("No such element: {factory}");
+        return gtk::glib::ExitCode::FAILURE;
+    }
+    for (source, name) in gps::preset::preset_names(factory) {
+        let source = match source {
+            gps::preset::PresetSource::Studio => "studio",
+            gps::preset::PresetSource::Element => "element",
+        };
+        println!("{name} ({source})");
+        match gps::preset::preset_properties(factory, &name) {
+            Ok(properties) => {
+                for (property, value) in properties {
+                    println!("  {property}={value}");
+                }
+            }
+            Err(e) => eprintln!("  {e}"),
+        }
+    }
+    gtk::glib::ExitCode::SUCCESS
 }
 
 /// Delay before showing splash to let main window stabilize
 const SPLASH_SHOW_DELAY_MS: u64 = 100;
 
 fn main() -> gtk::glib::ExitCode {
+    if let Some(factory) = Command::from_args().list_presets {
+        return list_presets(&factory);
+    }
+
     // Initialize GTK first so we can show UI
     init_gtk().expect("Unable to init GTK");
 
@@ -76,6 +114,7 @@ fn main() -> gtk::glib::ExitCode {
         // The window is presented but invisible (opacity 0) until we reveal it.
         let app_clone = application.clone();
         let pipeline_desc = args.pipeline.clone();
+        let presets = args.presets.clone();
 
         glib::timeout_add_local_once(Duration::from_millis(SPLASH_SHOW_DELAY_MS), move || {
             // Reveal the window and show splash together - the window was hidden
@@ -102,7 +141,7 @@ fn main() -> gtk::glib::ExitCode {
                 // Initialize UI
                 match result {
                     Ok(Ok(())) => {
-                        gps_app.initialize_ui(&app_clone, &pipeline_desc);
+                        gps_app.initialize_ui(&app_clone, &pipeline_desc, &presets);
                     }
                     Ok(Err(e)) => {
                         let msg = format!("Failed to initialize GStreamer: {}", e);
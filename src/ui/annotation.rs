@@ -0,0 +1,54 @@
+// annotation.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::app::core::graphbook;
+use crate::app::GPSApp;
+use crate::graphmanager::AnnotationKind;
+use crate::ui as GPSUI;
+use gtk::glib;
+use gtk::prelude::*;
+
+/// Display the dialog editing the text of a note or the title of a frame of the current tab.
+pub fn display_annotation_dialog(app: &GPSApp, annotation_id: u32) {
+    let graphtab = graphbook::current_graphtab(app);
+    let Some(annotation) = graphtab.graphview().annotation(annotation_id) else {
+        return;
+    };
+
+    let text_view = gtk::TextView::builder()
+        .wrap_mode(gtk::WrapMode::WordChar)
+        .hexpand(true)
+        .vexpand(true)
+        .build();
+    text_view.buffer().set_text(&annotation.text);
+
+    let title = match annotation.kind {
+        AnnotationKind::Note => "Edit note",
+        AnnotationKind::Frame => "Edit frame title",
+    };
+    let dialog = GPSUI::dialog::create(
+        title,
+        app,
+        &text_view,
+        glib::clone!(
+            #[weak]
+            text_view,
+            move |app, dialog| {
+                let buffer = text_view.buffer();
+                let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false);
+                graphbook::current_graphtab(&app)
+                    .graphview()
+                    .set_annotation_text(annotation_id, text.as_str());
+                dialog.close();
+            }
+        ),
+    );
+    dialog.set_default_size(420, 240);
+    dialog.present();
+    text_view.grab_focus();
+}
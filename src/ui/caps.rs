@@ -0,0 +1,440 @@
+// caps.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::app::core::graphbook;
+use crate::app::GPSApp;
+use crate::gps as GPS;
+use crate::graphmanager::PropertyExt;
+use crate::logger;
+use crate::ui as GPSUI;
+use crate::GPS_DEBUG;
+use gtk::glib;
+use gtk::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::str::FromStr;
+
+/// Widgets editing one field of the caps filter.
+struct FieldRow {
+    row: gtk::Box,
+    name: gtk::Entry,
+    value_type: gtk::DropDown,
+    types: Vec<String>,
+    value: gtk::Entry,
+}
+
+impl FieldRow {
+    fn field(&self) -> GPS::CapsField {
+        let value_type = self
+            .types
+            .get(self.value_type.selected() as usize)
+            .cloned()
+            .unwrap_or_default();
+        GPS::CapsField::new(
+            self.name.text().trim(),
+            &value_type,
+            self.value.text().trim(),
+        )
+    }
+}
+
+/// Widgets of the caps editor.
+#[derive(Clone)]
+struct CapsEditor {
+    media_type: gtk::Entry,
+    features: gtk::Entry,
+    fields_box: gtk::Box,
+    rows: Rc<RefCell<Vec<FieldRow>>>,
+    /// Filter parsed from the edited description
+    original: GPS::CapsFilter,
+    /// Entry editing the description as a whole, when it can not be split into fields
+    raw: Option<gtk::Entry>,
+}
+
+impl CapsEditor {
+    fn filter(&self) -> GPS::CapsFilter {
+        GPS::CapsFilter {
+            media_type: self.media_type.text().trim().to_string(),
+            features: self
+                .features
+                .text()
+                .split(',')
+                .map(|feature| feature.trim().to_string())
+                .filter(|feature| !feature.is_empty())
+                .collect(),
+            fields: self.rows.borrow().iter().map(|row| row.field()).collect(),
+        }
+    }
+
+    /// Add a row editing a field, calling `changed` whenever the field is modified or removed.
+    fn add_field(&self, field: &GPS::CapsField, changed: &Rc<dyn Fn()>) {
+        let mut types: Vec<String> = GPS::CAPS_FIELD_TYPES
+            .iter()
+            .map(|value_type| value_type.to_string())
+            .collect();
+        if !field.value_type.is_empty() && !types.contains(&field.value_type) {
+            types.push(field.value_type.clone());
+        }
+        let type_names: Vec<&str> = types.iter().map(|value_type| value_type.as_str()).collect();
+        let value_type = gtk::DropDown::from_strings(&type_names);
+        if let Some(position) = types.iter().position(|t| *t == field.value_type) {
+            value_type.set_selected(position as u32);
+        }
+
+        let name = gtk::Entry::builder()
+            .text(field.name.as_str())
+            .placeholder_text("name")
+            .width_chars(12)
+            .build();
+        let value = gtk::Entry::builder()
+            .text(field.value.as_str())
+            .placeholder_text("value, [ min, max ] or { a, b }")
+            .hexpand(true)
+            .build();
+        let remove_button = gtk::Button::from_icon_name("list-remove-symbolic");
+        remove_button.set_tooltip_text(Some("Remove the field"));
+
+        let row = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        row.append(&name);
+        row.append(&value_type);
+        row.append(&value);
+        row.append(&remove_button);
+        self.fields_box.append(&row);
+
+        for entry in [&name, &value] {
+            entry.connect_changed(glib::clone!(
+                #[strong]
+                changed,
+                move |_| changed()
+            ));
+        }
+        value_type.connect_selected_notify(glib::clone!(
+            #[strong]
+            changed,
+            move |_| changed()
+        ));
+        remove_button.connect_clicked(glib::clone!(
+            #[strong(rename_to = editor)]
+            self,
+            #[weak]
+            row,
+            #[strong]
+            changed,
+            move |_| {
+                editor.fields_box.remove(&row);
+                editor
+                    .rows
+                    .borrow_mut()
+                    .retain(|field_row| field_row.row != row);
+                changed();
+            }
+        ));
+
+        self.rows.borrow_mut().push(FieldRow {
+            row,
+            name,
+            value_type,
+            types,
+            value,
+        });
+    }
+}
+
+fn attach_row(grid: &gtk::Grid, row: i32, label: &str, widget: &impl IsA<gtk::Widget>) {
+    let label = gtk::Label::builder()
+        .label(label)
+        .halign(gtk::Align::Start)
+        .build();
+    grid.attach(&label, 0, row, 1, 1);
+    grid.attach(widget, 1, row, 1, 1);
+}
+
+/// Check the filter against the caps of the linked pads, an empty filter removes it.
+fn check_filter(
+    filter: &GPS::CapsFilter,
+    caps_from: Option<&str>,
+    caps_to: Option<&str>,
+) -> anyhow::Result<()> {
+    if filter.is_empty() && filter.features.is_empty() && filter.fields.is_empty() {
+        return Ok(());
+    }
+    filter.check_pads(caps_from, caps_to)
+}
+
+type FilterCheck = Rc<dyn Fn(&GPS::CapsFilter) -> anyhow::Result<()>>;
+
+/// Build the grid of the caps editor for `description`, holding a media type, the
+/// caps features if `with_features` is set, and the fields. The description built
+/// from the editor is displayed and checked with `check` while it is edited. A
+/// description which can not be split into fields is edited as a whole instead.
+fn editor_grid(
+    description: &str,
+    name_label: &str,
+    with_features: bool,
+    check: FilterCheck,
+) -> (CapsEditor, gtk::Grid, gtk::Label) {
+    let (filter, parse_error) = match GPS::CapsFilter::parse(description) {
+        Ok(filter) => (filter, None),
+        Err(e) => (GPS::CapsFilter::default(), Some(e.to_string())),
+    };
+    let mut editor = CapsEditor {
+        media_type: gtk::Entry::builder()
+            .text(filter.media_type.as_str())
+            .placeholder_text("video/x-raw")
+            .hexpand(true)
+            .build(),
+        features: gtk::Entry::builder()
+            .text(filter.features.join(", "))
+            .placeholder_text("memory:DMABuf")
+            .build(),
+        fields_box: gtk::Box::new(gtk::Orientation::Vertical, 6),
+        rows: Rc::new(RefCell::new(vec![])),
+        original: filter.clone(),
+        raw: None,
+    };
+
+    let grid = gtk::Grid::builder()
+        .column_spacing(8)
+        .row_spacing(8)
+        .build();
+    attach_row(&grid, 0, name_label, &editor.media_type);
+    if with_features {
+        attach_row(&grid, 1, "Features", &editor.features);
+    }
+    let fields_label = gtk::Label::builder()
+        .label("Fields")
+        .halign(gtk::Align::Start)
+        .build();
+    grid.attach(&fields_label, 0, 2, 2, 1);
+    grid.attach(&editor.fields_box, 0, 3, 2, 1);
+    let add_button = gtk::Button::with_label("Add field");
+    add_button.set_halign(gtk::Align::Start);
+    grid.attach(&add_button, 0, 4, 2, 1);
+    let caps_label = gtk::Label::builder()
+        .halign(gtk::Align::Start)
+        .wrap(true)
+        .selectable(true)
+        .build();
+    caps_label.add_css_class("dim-label");
+    grid.attach(&caps_label, 0, 5, 2, 1);
+    let error_label = gtk::Label::builder()
+        .halign(gtk::Align::Start)
+        .wrap(true)
+        .build();
+    error_label.add_css_class("error");
+    grid.attach(&error_label, 0, 6, 2, 1);
+    if parse_error.is_some() {
+        let raw_entry = gtk::Entry::builder()
+            .text(description)
+            .hexpand(true)
+            .build();
+        attach_row(&grid, 7, "Description", &raw_entry);
+        for widget in [
+            editor.media_type.upcast_ref::<gtk::Widget>(),
+            editor.features.upcast_ref(),
+            add_button.upcast_ref(),
+        ] {
+            widget.set_sensitive(false);
+        }
+        editor.raw = Some(raw_entry);
+    }
+
+    let changed: Rc<dyn Fn()> = Rc::new(glib::clone!(
+        #[strong]
+        editor,
+        #[weak]
+        caps_label,
+        #[weak]
+        error_label,
+        move || {
+            let filter = editor.filter();
+            caps_label.set_label(&filter.to_string());
+            match check(&filter) {
+                Ok(()) => error_label.set_label(""),
+                Err(e) => error_label.set_label(&e.to_string()),
+            }
+        }
+    ));
+    for field in &filter.fields {
+        editor.add_field(field, &changed);
+    }
+    for entry in [&editor.media_type, &editor.features] {
+        entry.connect_changed(glib::clone!(
+            #[strong]
+            changed,
+            move |_| changed()
+        ));
+    }
+    add_button.connect_clicked(glib::clone!(
+        #[strong]
+        editor,
+        #[strong]
+        changed,
+        move |_| {
+            editor.add_field(&GPS::CapsField::default(), &changed);
+            changed();
+        }
+    ));
+    changed();
+    if let Some(parse_error) = parse_error {
+        error_label.set_label(&format!(
+            "The description can only be edited as a whole: {}",
+            parse_error
+        ));
+    }
+    (editor, grid, error_label)
+}
+
+/// Display the dialog editing the caps filter of a link. The filter is validated
+/// and checked against the caps of both linked pads while it is edited.
+pub fn display_caps_editor(app: &GPSApp, link_id: u32) {
+    let graphview = graphbook::current_graphtab(app).graphview().clone();
+    let Some(link) = graphview.link(link_id) else {
+        return;
+    };
+    let caps_from = PropertyExt::property(&app.port(link.node_from, link.port_from), "_caps");
+    let caps_to = PropertyExt::property(&app.port(link.node_to, link.port_to), "_caps");
+    let description = link.name().trim().to_string();
+
+    let check: FilterCheck = Rc::new(glib::clone!(
+        #[strong]
+        caps_from,
+        #[strong]
+        caps_to,
+        move |filter| check_filter(filter, caps_from.as_deref(), caps_to.as_deref())
+    ));
+    let (editor, grid, error_label) = editor_grid(&description, "Media type", true, check.clone());
+
+    let dialog = GPSUI::dialog::create(
+        "Caps filter",
+        app,
+        &grid,
+        glib::clone!(
+            #[weak]
+            graphview,
+            #[weak]
+            error_label,
+            move |_app, dialog| {
+                let new_description = if let Some(raw) = &editor.raw {
+                    let new_description = raw.text().trim().to_string();
+                    if !new_description.is_empty() {
+                        if let Err(e) = GPS::CapsFilter::check_description(
+                            &new_description,
+                            caps_from.as_deref(),
+                            caps_to.as_deref(),
+                        ) {
+                            error_label.set_label(&e.to_string());
+                            return;
+                        }
+                    }
+                    new_description
+                } else {
+                    let filter = editor.filter();
+                    if let Err(e) = check(&filter) {
+                        error_label.set_label(&e.to_string());
+                        return;
+                    }
+                    // Keep the filter as written when it has not been modified
+                    if filter == editor.original {
+                        description.clone()
+                    } else {
+                        filter.to_string()
+                    }
+                };
+                if new_description != description {
+                    GPS_DEBUG!("link id={} caps filter={}", link_id, new_description);
+                    graphview.set_link_name(link_id, &new_description);
+                }
+                dialog.close();
+            }
+        ),
+    );
+    dialog.set_default_size(560, 360);
+    dialog.present();
+}
+
+// Check the description of a caps or structure property value
+fn check_value_description(description: &str, structure: bool) -> anyhow::Result<()> {
+    if description.is_empty() {
+        anyhow::bail!("The value is empty");
+    }
+    if structure {
+        gst::Structure::from_str(description)
+            .map_err(|_| anyhow::anyhow!("Invalid structure '{}'", description))?;
+    } else {
+        gst::Caps::from_str(description)
+            .map_err(|_| anyhow::anyhow!("Invalid caps '{}'", description))?;
+    }
+    Ok(())
+}
+
+// Check the value built by the editor of a caps or structure property
+fn check_value(filter: &GPS::CapsFilter, structure: bool) -> anyhow::Result<()> {
+    if filter.is_empty() && filter.fields.is_empty() {
+        anyhow::bail!("The value is empty");
+    }
+    if structure {
+        if filter.media_type.is_empty() {
+            anyhow::bail!("The structure has no name");
+        }
+        check_value_description(&filter.to_string(), true)
+    } else {
+        filter.caps().map(|_| ())
+    }
+}
+
+/// Display the caps builder for the value of a `GstCaps` property, or for a
+/// `GstStructure` property if `structure` is set. `f` is called with the serialized
+/// value once it is modified and applied.
+pub fn display_caps_value_editor<F>(app: &GPSApp, title: &str, value: &str, structure: bool, f: F)
+where
+    F: Fn(String) + 'static,
+{
+    let value = value.trim().trim_matches('"').to_string();
+    let name_label = if structure { "Name" } else { "Media type" };
+    let (editor, grid, error_label) = editor_grid(
+        &value,
+        name_label,
+        !structure,
+        Rc::new(move |filter| check_value(filter, structure)),
+    );
+
+    let dialog = GPSUI::dialog::create(
+        title,
+        app,
+        &grid,
+        glib::clone!(
+            #[weak]
+            error_label,
+            move |_app, dialog| {
+                let new_value = if let Some(raw) = &editor.raw {
+                    raw.text().trim().to_string()
+                } else if editor.filter() == editor.original {
+                    // Keep the value as written when it has not been modified
+                    value.clone()
+                } else {
+                    editor.filter().to_string()
+                };
+                if new_value != value {
+                    let checked = match &editor.raw {
+                        Some(_) => check_value_description(&new_value, structure),
+                        None => check_value(&editor.filter(), structure),
+                    };
+                    if let Err(e) = checked {
+                        error_label.set_label(&e.to_string());
+                        return;
+                    }
+                    f(new_value);
+                }
+                dialog.close();
+            }
+        ),
+    );
+    dialog.set_default_size(560, 360);
+    dialog.present();
+}
@@ -0,0 +1,77 @@
+// capture.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::app::GPSApp;
+use crate::gps::capture::{self, RECORDING_PRESETS};
+use crate::ui as GPSUI;
+use gtk::glib;
+use gtk::prelude::*;
+
+/// Display the dialog selecting the format and the location of the recording of a link.
+pub fn display_recording_dialog(app: &GPSApp, link_id: u32) {
+    let grid = gtk::Grid::builder()
+        .column_spacing(8)
+        .row_spacing(8)
+        .build();
+
+    let preset_label = gtk::Label::builder()
+        .label("Format")
+        .halign(gtk::Align::Start)
+        .build();
+    let presets: Vec<&str> = RECORDING_PRESETS.iter().map(|p| p.name).collect();
+    let preset_dropdown = gtk::DropDown::from_strings(&presets);
+    preset_dropdown.set_hexpand(true);
+    grid.attach(&preset_label, 0, 0, 1, 1);
+    grid.attach(&preset_dropdown, 1, 0, 1, 1);
+
+    let location_label = gtk::Label::builder()
+        .label("Location")
+        .halign(gtk::Align::Start)
+        .build();
+    let location_entry = gtk::Entry::builder()
+        .text(capture::default_recording_location(&RECORDING_PRESETS[0]))
+        .hexpand(true)
+        .build();
+    grid.attach(&location_label, 0, 1, 1, 1);
+    grid.attach(&location_entry, 1, 1, 1, 1);
+
+    // Follow the extension of the selected format
+    preset_dropdown.connect_selected_notify(glib::clone!(
+        #[weak]
+        location_entry,
+        move |dropdown| {
+            let Some(preset) = RECORDING_PRESETS.get(dropdown.selected() as usize) else {
+                return;
+            };
+            let location = std::path::PathBuf::from(location_entry.text().as_str())
+                .with_extension(preset.extension);
+            location_entry.set_text(&location.to_string_lossy());
+        }
+    ));
+
+    let dialog = GPSUI::dialog::create(
+        "Record link",
+        app,
+        &grid,
+        glib::clone!(
+            #[weak]
+            preset_dropdown,
+            #[weak]
+            location_entry,
+            move |app, dialog| {
+                let preset = RECORDING_PRESETS
+                    .get(preset_dropdown.selected() as usize)
+                    .unwrap_or(&RECORDING_PRESETS[0]);
+                app.start_recording(link_id, preset, location_entry.text().as_str());
+                dialog.close();
+            }
+        ),
+    );
+    dialog.set_default_size(520, 160);
+    dialog.present();
+}
@@ -0,0 +1,480 @@
+// control.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::app::core::graphbook;
+use crate::app::GPSApp;
+use crate::gps as GPS;
+use crate::gps::control::{self, InterpolationMode, Keyframe, PropertyControl};
+use crate::graphmanager::PropertyExt;
+use crate::logger;
+use crate::ui as GPSUI;
+use crate::GPS_INFO;
+use gst::prelude::*;
+use gtk::glib;
+use gtk::prelude::*;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+const TIMELINE_HEIGHT: i32 = 160;
+const TIMELINE_PADDING: f64 = 8.0;
+/// Span of the timeline in seconds when the duration of the pipeline is unknown.
+const DEFAULT_SPAN_S: f64 = 10.0;
+/// Ranges wider than this are displayed around the values of the keyframes.
+const MAX_DISPLAY_RANGE: f64 = 10000.0;
+const POSITION_UPDATE_TIMEOUT_MS: u64 = 200;
+const MAX_TIME_S: f64 = 86400.0;
+
+const BACKGROUND_COLOR: (f64, f64, f64, f64) = (0.5, 0.5, 0.5, 0.15);
+const CURVE_COLOR: (f64, f64, f64, f64) = (0.2, 0.5, 1.0, 1.0);
+const KEYFRAME_COLOR: (f64, f64, f64, f64) = (1.0, 0.6, 0.0, 1.0);
+const POSITION_COLOR: (f64, f64, f64, f64) = (1.0, 0.18, 0.18, 0.9);
+
+struct ControlEditor {
+    app: GPSApp,
+    params: HashMap<String, glib::ParamSpec>,
+    /// Keyframes being edited, by property name
+    controls: RefCell<HashMap<String, PropertyControl>>,
+    property: RefCell<String>,
+    timeline: gtk::DrawingArea,
+    mode_dropdown: gtk::DropDown,
+    keyframes_box: gtk::Box,
+    rows: RefCell<Vec<(gtk::SpinButton, gtk::SpinButton)>>,
+}
+
+fn set_source(cr: &gtk::cairo::Context, color: (f64, f64, f64, f64)) {
+    cr.set_source_rgba(color.0, color.1, color.2, color.3);
+}
+
+fn integer_param(param: &glib::ParamSpec) -> bool {
+    param.downcast_ref::<glib::ParamSpecInt>().is_some()
+        || param.downcast_ref::<glib::ParamSpecUInt>().is_some()
+        || param.downcast_ref::<glib::ParamSpecInt64>().is_some()
+        || param.downcast_ref::<glib::ParamSpecUInt64>().is_some()
+}
+
+fn default_value(param: &glib::ParamSpec) -> f64 {
+    param
+        .default_value()
+        .transform::<f64>()
+        .ok()
+        .and_then(|value| value.get::<f64>().ok())
+        .unwrap_or_default()
+}
+
+impl ControlEditor {
+    fn param(&self) -> glib::ParamSpec {
+        self.params[&*self.property.borrow()].clone()
+    }
+
+    fn control(&self) -> PropertyControl {
+        self.controls
+            .borrow()
+            .get(&*self.property.borrow())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn set_control(&self, control: PropertyControl) {
+        self.controls
+            .borrow_mut()
+            .insert(self.property.borrow().clone(), control);
+        self.timeline.queue_draw();
+    }
+
+    fn position(&self) -> f64 {
+        graphbook::current_graphtab(&self.app).player().position() as f64 / 1000.0
+    }
+
+    /// Seconds covered by the timeline, the duration of the pipeline as for the
+    /// position slider, extended to the last keyframe.
+    fn span(&self) -> f64 {
+        let duration = graphbook::current_graphtab(&self.app).player().duration() as f64 / 1000.0;
+        let last = self
+            .control()
+            .keyframes()
+            .last()
+            .map(|k| control::time_to_seconds(k.time) * 1.1)
+            .unwrap_or_default();
+        let span = duration.max(last).max(self.position());
+        if span > 0.0 {
+            span
+        } else {
+            DEFAULT_SPAN_S
+        }
+    }
+
+    /// Values covered by the height of the timeline.
+    fn display_range(&self) -> (f64, f64) {
+        let param = self.param();
+        let (minimum, maximum) = control::value_range(&param).unwrap_or((0.0, 1.0));
+        if maximum - minimum <= MAX_DISPLAY_RANGE {
+            return (minimum, maximum);
+        }
+        let values = self
+            .control()
+            .keyframes()
+            .iter()
+            .map(|k| k.value)
+            .chain([default_value(&param)])
+            .collect::<Vec<f64>>();
+        let low = values.iter().cloned().fold(f64::INFINITY, f64::min);
+        let high = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let margin = ((high - low) * 0.1).max(1.0);
+        ((low - margin).max(minimum), (high + margin).min(maximum))
+    }
+
+    fn draw(&self, cr: &gtk::cairo::Context, width: f64, height: f64) {
+        set_source(cr, BACKGROUND_COLOR);
+        cr.rectangle(0.0, 0.0, width, height);
+        let _ = cr.fill();
+
+        let span = self.span();
+        let (low, high) = self.display_range();
+        let to_x =
+            |seconds: f64| TIMELINE_PADDING + seconds / span * (width - 2.0 * TIMELINE_PADDING);
+        let to_y = |value: f64| {
+            height
+                - TIMELINE_PADDING
+                - (value - low) / (high - low) * (height - 2.0 * TIMELINE_PADDING)
+        };
+
+        let control = self.control();
+        if !control.is_empty() {
+            // Sample the control source, which interpolates as the binding will
+            let source = control.control_source();
+            set_source(cr, CURVE_COLOR);
+            cr.set_line_width(1.5);
+            let steps = (width as usize).max(2);
+            for step in 0..=steps {
+                let seconds = span * step as f64 / steps as f64;
+                if let Some(value) =
+                    ControlSourceExt::value(&source, control::seconds_to_time(seconds))
+                {
+                    if step == 0 {
+                        cr.move_to(to_x(seconds), to_y(value));
+                    } else {
+                        cr.line_to(to_x(seconds), to_y(value));
+                    }
+                }
+            }
+            let _ = cr.stroke();
+
+            set_source(cr, KEYFRAME_COLOR);
+            for keyframe in control.keyframes() {
+                let x = to_x(control::time_to_seconds(keyframe.time));
+                let y = to_y(keyframe.value);
+                cr.arc(x, y, 4.0, 0.0, 2.0 * std::f64::consts::PI);
+                let _ = cr.fill();
+            }
+        }
+
+        set_source(cr, POSITION_COLOR);
+        cr.set_line_width(1.0);
+        let x = to_x(self.position());
+        cr.move_to(x, 0.0);
+        cr.line_to(x, height);
+        let _ = cr.stroke();
+    }
+
+    /// Add a keyframe where the timeline was clicked.
+    fn add_keyframe_at(&self, x: f64, y: f64, width: f64, height: f64) {
+        let span = self.span();
+        let (low, high) = self.display_range();
+        let seconds = ((x - TIMELINE_PADDING) / (width - 2.0 * TIMELINE_PADDING) * span).max(0.0);
+        let value = low
+            + (height - TIMELINE_PADDING - y) / (height - 2.0 * TIMELINE_PADDING) * (high - low);
+        self.add_keyframe(seconds, value);
+    }
+
+    fn add_keyframe(&self, seconds: f64, value: f64) {
+        let param = self.param();
+        let (minimum, maximum) = control::value_range(&param).unwrap_or((f64::MIN, f64::MAX));
+        let mut value = value.clamp(minimum, maximum);
+        if integer_param(&param) {
+            value = value.round();
+        }
+        // Keep the times to the precision of the time spinners
+        let seconds = (seconds * 100.0).round() / 100.0;
+        let mut control = self.control();
+        control.set_keyframe(Keyframe::new(control::seconds_to_time(seconds), value));
+        self.set_control(control);
+    }
+
+    /// Keyframes from the time and value spinners.
+    fn update_from_rows(&self) {
+        let mut control = PropertyControl::new(self.control().mode);
+        for (time, value) in self.rows.borrow().iter() {
+            control.set_keyframe(Keyframe::new(
+                control::seconds_to_time(time.value()),
+                value.value(),
+            ));
+        }
+        self.set_control(control);
+    }
+}
+
+fn refresh_keyframes(editor: &Rc<ControlEditor>) {
+    while let Some(child) = editor.keyframes_box.first_child() {
+        editor.keyframes_box.remove(&child);
+    }
+    editor.rows.borrow_mut().clear();
+    let param = editor.param();
+    let (minimum, maximum) = control::value_range(&param).unwrap_or((f64::MIN, f64::MAX));
+    let integer = integer_param(&param);
+    for (index, keyframe) in editor.control().keyframes().iter().enumerate() {
+        let row = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        row.append(&gtk::Label::new(Some("Time (s)")));
+        let time = gtk::SpinButton::with_range(0.0, MAX_TIME_S, 0.1);
+        time.set_digits(2);
+        time.set_value(control::time_to_seconds(keyframe.time));
+        row.append(&time);
+        row.append(&gtk::Label::new(Some("Value")));
+        let value = gtk::SpinButton::with_range(minimum, maximum, if integer { 1.0 } else { 0.01 });
+        value.set_digits(if integer { 0 } else { 3 });
+        value.set_hexpand(true);
+        value.set_value(keyframe.value);
+        row.append(&value);
+        let remove_button = gtk::Button::from_icon_name("list-remove-symbolic");
+        remove_button.set_tooltip_text(Some("Remove the keyframe"));
+        row.append(&remove_button);
+
+        let editor_weak = Rc::downgrade(editor);
+        let spin_changed = move |_: &gtk::SpinButton| {
+            if let Some(editor) = editor_weak.upgrade() {
+                editor.update_from_rows();
+            }
+        };
+        time.connect_value_changed(spin_changed.clone());
+        value.connect_value_changed(spin_changed);
+        let editor_weak = Rc::downgrade(editor);
+        remove_button.connect_clicked(move |_| {
+            let Some(editor) = editor_weak.upgrade() else {
+                return;
+            };
+            // The time of the keyframe may have been edited since the rows were built
+            let time = editor.rows.borrow()[index].0.value();
+            let mut control = editor.control();
+            control.remove_keyframe(control::seconds_to_time(time));
+            editor.set_control(control);
+            refresh_keyframes(&editor);
+        });
+
+        editor.keyframes_box.append(&row);
+        editor.rows.borrow_mut().push((time, value));
+    }
+}
+
+fn select_property(editor: &Rc<ControlEditor>, property: &str) {
+    editor.property.replace(property.to_string());
+    let mode = editor.control().mode;
+    let position = InterpolationMode::ALL
+        .iter()
+        .position(|m| *m == mode)
+        .unwrap_or_default();
+    editor.mode_dropdown.set_selected(position as u32);
+    refresh_keyframes(editor);
+    editor.timeline.queue_draw();
+}
+
+/// Follow the playback position until the dialog is closed.
+fn redraw_while_visible(editor: Weak<ControlEditor>) {
+    glib::timeout_add_local(
+        std::time::Duration::from_millis(POSITION_UPDATE_TIMEOUT_MS),
+        move || {
+            let Some(editor) = editor.upgrade() else {
+                return glib::ControlFlow::Break;
+            };
+            if editor.timeline.root().is_none() {
+                return glib::ControlFlow::Break;
+            }
+            editor.timeline.queue_draw();
+            glib::ControlFlow::Continue
+        },
+    );
+}
+
+/// Display the keyframes of the controllable properties of a node on a timeline
+/// following the position of the player. The keyframes are bound to the element
+/// properties when the pipeline starts.
+pub fn display_property_control(app: &GPSApp, node_id: u32) {
+    let node = app.node(node_id);
+    let element_name = node.name();
+    let params: HashMap<String, glib::ParamSpec> =
+        GPS::ElementInfo::element_properties_by_feature_name(&element_name)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, param)| control::controllable(param))
+            .collect();
+    if params.is_empty() {
+        GPSUI::message::display_error_dialog(
+            false,
+            &format!("{element_name} has no property which can be animated"),
+        );
+        return;
+    }
+    let mut names: Vec<String> = params.keys().cloned().collect();
+    names.sort();
+    let controls: HashMap<String, PropertyControl> =
+        control::node_controls(&node).into_iter().collect();
+    // Start with a property already animated
+    let property = names
+        .iter()
+        .find(|name| controls.contains_key(*name))
+        .unwrap_or(&names[0])
+        .clone();
+
+    let main_box = gtk::Box::new(gtk::Orientation::Vertical, 8);
+    let header = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    header.append(&gtk::Label::new(Some("Property")));
+    let name_refs: Vec<&str> = names.iter().map(String::as_str).collect();
+    let property_dropdown = gtk::DropDown::from_strings(&name_refs);
+    let position = name_refs
+        .iter()
+        .position(|name| *name == property)
+        .unwrap_or_default();
+    property_dropdown.set_hexpand(true);
+    header.append(&property_dropdown);
+    header.append(&gtk::Label::new(Some("Interpolation")));
+    let modes: Vec<&str> = InterpolationMode::ALL.iter().map(|m| m.as_str()).collect();
+    let mode_dropdown = gtk::DropDown::from_strings(&modes);
+    header.append(&mode_dropdown);
+    main_box.append(&header);
+
+    let timeline = gtk::DrawingArea::builder()
+        .content_height(TIMELINE_HEIGHT)
+        .hexpand(true)
+        .tooltip_text("Click to add a keyframe, the red line is the playback position")
+        .build();
+    main_box.append(&timeline);
+
+    let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    let add_button = gtk::Button::with_label("Add at playback position");
+    let clear_button = gtk::Button::with_label("Clear");
+    buttons.append(&add_button);
+    buttons.append(&clear_button);
+    main_box.append(&buttons);
+
+    let keyframes_box = gtk::Box::new(gtk::Orientation::Vertical, 6);
+    main_box.append(&keyframes_box);
+
+    let editor = Rc::new(ControlEditor {
+        app: app.clone(),
+        params,
+        controls: RefCell::new(controls),
+        property: RefCell::new(property.clone()),
+        timeline: timeline.clone(),
+        mode_dropdown: mode_dropdown.clone(),
+        keyframes_box,
+        rows: RefCell::new(vec![]),
+    });
+
+    let editor_weak = Rc::downgrade(&editor);
+    timeline.set_draw_func(move |_, cr, width, height| {
+        if let Some(editor) = editor_weak.upgrade() {
+            editor.draw(cr, width as f64, height as f64);
+        }
+    });
+    let click = gtk::GestureClick::new();
+    let editor_weak = Rc::downgrade(&editor);
+    click.connect_pressed(move |gesture, _, x, y| {
+        let (Some(editor), Some(widget)) = (editor_weak.upgrade(), gesture.widget()) else {
+            return;
+        };
+        editor.add_keyframe_at(x, y, widget.width() as f64, widget.height() as f64);
+        refresh_keyframes(&editor);
+    });
+    timeline.add_controller(click);
+
+    let editor_weak = Rc::downgrade(&editor);
+    add_button.connect_clicked(move |_| {
+        let Some(editor) = editor_weak.upgrade() else {
+            return;
+        };
+        // Keep the current value of the curve, or the default value of the property
+        let position = editor.position();
+        let control = editor.control();
+        let value = (!control.is_empty())
+            .then(|| {
+                ControlSourceExt::value(
+                    &control.control_source(),
+                    control::seconds_to_time(position),
+                )
+            })
+            .flatten()
+            .unwrap_or_else(|| default_value(&editor.param()));
+        editor.add_keyframe(position, value);
+        refresh_keyframes(&editor);
+    });
+    let editor_weak = Rc::downgrade(&editor);
+    clear_button.connect_clicked(move |_| {
+        let Some(editor) = editor_weak.upgrade() else {
+            return;
+        };
+        let mode = editor.control().mode;
+        editor.set_control(PropertyControl::new(mode));
+        refresh_keyframes(&editor);
+    });
+
+    let editor_weak = Rc::downgrade(&editor);
+    mode_dropdown.connect_selected_notify(move |dropdown| {
+        let Some(editor) = editor_weak.upgrade() else {
+            return;
+        };
+        let Some(mode) = InterpolationMode::ALL.get(dropdown.selected() as usize) else {
+            return;
+        };
+        let mut control = editor.control();
+        if control.mode != *mode {
+            control.mode = *mode;
+            editor.set_control(control);
+        }
+    });
+    let editor_weak = Rc::downgrade(&editor);
+    property_dropdown.connect_selected_notify(move |dropdown| {
+        let (Some(editor), Some(name)) = (
+            editor_weak.upgrade(),
+            names.get(dropdown.selected() as usize),
+        ) else {
+            return;
+        };
+        select_property(&editor, name);
+    });
+    property_dropdown.set_selected(position as u32);
+    select_property(&editor, &property);
+    redraw_while_visible(Rc::downgrade(&editor));
+
+    let dialog = GPSUI::dialog::create(
+        &format!("{} animation", node.unique_name()),
+        app,
+        &main_box,
+        move |app, dialog| {
+            let node = app.node(node_id);
+            let mut properties: HashMap<String, String> = HashMap::new();
+            for (property, control) in editor.controls.borrow().iter() {
+                let name = control::control_property_name(property);
+                let value = if control.is_empty() {
+                    String::new()
+                } else {
+                    control.to_string()
+                };
+                if PropertyExt::property(&node, &name).unwrap_or_default() != value {
+                    properties.insert(name, value);
+                }
+            }
+            if !properties.is_empty() {
+                GPS_INFO!("Updating the keyframes of {}", node.unique_name());
+                app.update_element_properties(node_id, &properties);
+            }
+            dialog.close();
+        },
+    );
+    dialog.set_default_size(720, 480);
+    dialog.present();
+}
@@ -21,6 +21,8 @@ pub enum FileDialogType {
     Open,
     OpenAll,
     SaveAll,
+    SaveLog,
+    SaveSnapshot,
 }
 
 pub fn create<W, F>(name: &str, app: &GPSApp, content: &W, f: F) -> gtk::Window
@@ -217,7 +219,15 @@ pub fn get_file<F: Fn(GPSApp, String) + 'static>(app: &GPSApp, dlg_type: FileDia
     let file_dialog = FileDialog::builder().modal(true).build();
 
     // Set title and accept button label based on dialog type
-    if dlg_type == FileDialogType::Save || dlg_type == FileDialogType::SaveAll {
+    if dlg_type == FileDialogType::SaveLog {
+        file_dialog.set_title("Save log");
+        file_dialog.set_accept_label(Some("Save"));
+        file_dialog.set_initial_name(Some("gst-debug.log"));
+    } else if dlg_type == FileDialogType::SaveSnapshot {
+        file_dialog.set_title("Save snapshot");
+        file_dialog.set_accept_label(Some("Save"));
+        file_dialog.set_initial_name(Some("snapshot.png"));
+    } else if dlg_type == FileDialogType::Save || dlg_type == FileDialogType::SaveAll {
         file_dialog.set_title("Save file");
         file_dialog.set_accept_label(Some("Save"));
         file_dialog.set_initial_name(Some("untitled.gps"));
@@ -255,7 +265,13 @@ pub fn get_file<F: Fn(GPSApp, String) + 'static>(app: &GPSApp, dlg_type: FileDia
     let app_weak = app.downgrade();
 
     // Use the appropriate method based on dialog type
-    if dlg_type == FileDialogType::Save || dlg_type == FileDialogType::SaveAll {
+    if matches!(
+        dlg_type,
+        FileDialogType::Save
+            | FileDialogType::SaveAll
+            | FileDialogType::SaveLog
+            | FileDialogType::SaveSnapshot
+    ) {
         file_dialog.save(Some(&window), None::<&gio::Cancellable>, move |result| {
             let app = upgrade_weak!(app_weak);
             if let Ok(file) = result {
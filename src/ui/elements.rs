@@ -6,62 +6,181 @@
 //
 // SPDX-License-Identifier: GPL-3.0-only
 
+use crate::app::core::templates;
 use crate::app::settings::Settings;
 use crate::app::GPSApp;
 use crate::gps as GPS;
+use crate::graphmanager as GM;
 use crate::logger;
 use crate::ui::common::create_column_view_column_with_width;
 use crate::ui::models::ElementInfoObject;
-use crate::GPS_DEBUG;
+use crate::{GPS_DEBUG, GPS_ERROR};
 use gtk::prelude::*;
 use gtk::{gdk::BUTTON_SECONDARY, Box, Label, SearchEntry};
 use gtk::{gio, glib};
 use gtk::{ColumnView, FilterListModel, SingleSelection};
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
 
 // Column width constants
 const COL_WIDTH_NAME: i32 = 200;
 const COL_WIDTH_PLUGIN: i32 = 150;
 const COL_WIDTH_RANK: i32 = 100;
 
-fn setup_search_entry(column_view: &ColumnView, app: &GPSApp) {
+/// Choices of the caps facet, matched against the sink or the src pads of the factories.
+const CAPS_DIRECTIONS: [(&str, GM::PortDirection); 2] = [
+    ("Accepts", GM::PortDirection::Input),
+    ("Produces", GM::PortDirection::Output),
+];
+
+/// Fill the choices of the filter facets of the element lists.
+fn setup_element_filters(app: &GPSApp, elements: &[GPS::ElementInfo]) {
+    let set_choices = |id: &str, choices: Vec<String>| {
+        let dropdown: gtk::DropDown = app
+            .builder
+            .object(id)
+            .unwrap_or_else(|| panic!("Couldn't get {}", id));
+        let choices: Vec<&str> = choices.iter().map(|choice| choice.as_str()).collect();
+        dropdown.set_model(Some(&gtk::StringList::new(&choices)));
+        dropdown.set_selected(0);
+    };
+    let mut classes = vec!["All".to_string()];
+    classes.extend(GPS::ElementClass::ALL.iter().map(|class| class.to_string()));
+    set_choices("dropdown-elements-class", classes);
+    set_choices(
+        "dropdown-elements-rank",
+        GPS::ELEMENT_RANKS
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect(),
+    );
+    let mut plugins: Vec<String> = elements.iter().map(|e| e.plugin_name.clone()).collect();
+    plugins.sort();
+    plugins.dedup();
+    plugins.insert(0, "All".to_string());
+    set_choices("dropdown-elements-plugin", plugins);
+    set_choices(
+        "dropdown-elements-caps-direction",
+        CAPS_DIRECTIONS
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect(),
+    );
+}
+
+/// Build the filter of the element lists from the search entry and the filter facets.
+fn element_filter(app: &GPSApp) -> GPS::ElementFilter {
+    let mut filter = GPS::ElementFilter::default();
+    let selected = |id: &str| {
+        app.builder
+            .object::<gtk::DropDown>(id)
+            .map(|dropdown| dropdown.selected())
+            .filter(|selected| *selected != gtk::INVALID_LIST_POSITION)
+            .unwrap_or(0) as usize
+    };
+    if let Some(search_entry) = app.builder.object::<SearchEntry>("elements-search-entry") {
+        filter.text = search_entry.text().trim().to_string();
+    }
+    // The first choice of the class and plugin facets is "All"
+    filter.class = selected("dropdown-elements-class")
+        .checked_sub(1)
+        .and_then(|index| GPS::ElementClass::ALL.get(index).copied());
+    filter.min_rank = GPS::ELEMENT_RANKS
+        .get(selected("dropdown-elements-rank"))
+        .map(|(_, rank)| *rank)
+        .unwrap_or(0);
+    if selected("dropdown-elements-plugin") > 0 {
+        filter.plugin = app
+            .builder
+            .object::<gtk::DropDown>("dropdown-elements-plugin")
+            .and_then(|dropdown| dropdown.selected_item())
+            .and_downcast::<gtk::StringObject>()
+            .map(|item| item.string().to_string());
+    }
+    if let Some(caps_entry) = app.builder.object::<gtk::Entry>("entry-elements-caps") {
+        let direction = CAPS_DIRECTIONS
+            .get(selected("dropdown-elements-caps-direction"))
+            .map(|(_, direction)| *direction)
+            .unwrap_or(GM::PortDirection::Input);
+        if filter.set_caps(direction, &caps_entry.text()).is_err() {
+            caps_entry.add_css_class("error");
+        } else {
+            caps_entry.remove_css_class("error");
+        }
+    }
+    filter
+}
+
+fn setup_search_entry(column_view: &ColumnView, app: &GPSApp, elements: Vec<GPS::ElementInfo>) {
     let search_entry: SearchEntry = app
         .builder
         .object("elements-search-entry")
         .expect("Couldn't get elements-search-entry");
 
     // Get the filter model from the column view
-    if let Some(selection_model) = column_view.model() {
-        if let Some(selection) = selection_model.downcast_ref::<SingleSelection>() {
-            if let Some(filter_model) = selection.model() {
-                if let Some(filter) = filter_model.downcast_ref::<FilterListModel>() {
-                    search_entry.connect_changed(glib::clone!(
-                        #[weak]
-                        filter,
-                        move |entry| {
-                            let entry_text = entry.text().to_string();
-
-                            // Update the filter
-                            if entry_text.is_empty() {
-                                filter.set_filter(None::<&gtk::Filter>);
-                            } else {
-                                let custom_filter = gtk::CustomFilter::new(move |obj| {
-                                    if let Some(element_info) =
-                                        obj.downcast_ref::<ElementInfoObject>()
-                                    {
-                                        let name = element_info.property::<String>("name");
-                                        name.contains(&entry_text)
-                                    } else {
-                                        false
-                                    }
-                                });
-                                filter.set_filter(Some(&custom_filter));
-                            }
-                        }
-                    ));
-                }
+    let Some(filter) = column_view
+        .model()
+        .and_downcast::<SingleSelection>()
+        .and_then(|selection| selection.model())
+        .and_downcast::<FilterListModel>()
+    else {
+        return;
+    };
+    // Metadata and caps of the factories, looked up by name while filtering
+    let elements: Rc<HashMap<String, GPS::FilterableElement>> = Rc::new(
+        elements
+            .into_iter()
+            .map(|element| (element.name.clone(), GPS::FilterableElement::from(element)))
+            .collect(),
+    );
+    let app_weak = app.downgrade();
+    let update_filter = Rc::new(glib::clone!(
+        #[weak]
+        filter,
+        move || {
+            let app = upgrade_weak!(app_weak);
+            let element_filter = element_filter(&app);
+            if element_filter.is_empty() {
+                filter.set_filter(None::<&gtk::Filter>);
+                return;
             }
+            let elements = elements.clone();
+            let custom_filter = gtk::CustomFilter::new(move |obj| {
+                obj.downcast_ref::<ElementInfoObject>()
+                    .and_then(|element_info| elements.get(&element_info.property::<String>("name")))
+                    .is_some_and(|element| element_filter.matches(element))
+            });
+            filter.set_filter(Some(&custom_filter));
         }
+    ));
+
+    search_entry.connect_changed(glib::clone!(
+        #[strong]
+        update_filter,
+        move |_| update_filter()
+    ));
+    for id in [
+        "dropdown-elements-class",
+        "dropdown-elements-rank",
+        "dropdown-elements-plugin",
+        "dropdown-elements-caps-direction",
+    ] {
+        let dropdown: gtk::DropDown = app
+            .builder
+            .object(id)
+            .unwrap_or_else(|| panic!("Couldn't get {}", id));
+        dropdown.connect_selected_notify(glib::clone!(
+            #[strong]
+            update_filter,
+            move |_| update_filter()
+        ));
     }
+    let caps_entry: gtk::Entry = app
+        .builder
+        .object("entry-elements-caps")
+        .expect("Couldn't get entry-elements-caps");
+    caps_entry.connect_changed(move |_| update_filter());
 }
 
 pub fn setup_favorite_list(app: &GPSApp) {
@@ -97,7 +216,12 @@ pub fn setup_favorite_list(app: &GPSApp) {
             .collect()
     };
 
-    reset_elements_list(&favorite_list, get_favorite_elements());
+    let elements = GPS::ElementInfo::elements_list().expect("Unable to obtain element's list");
+    let favorite_names = Settings::favorites_list();
+    reset_elements_list(
+        &favorite_list,
+        elements.iter().filter(|e| favorite_names.contains(&e.name)),
+    );
 
     let app_weak = app.downgrade();
     favorite_list.connect_activate(move |column_view, position| {
@@ -157,7 +281,7 @@ pub fn setup_favorite_list(app: &GPSApp) {
                                     Settings::remove_favorite(&element_name);
                                     reset_elements_list(
                                         &favorite_list_clone,
-                                        get_favorite_elements(),
+                                        &get_favorite_elements(),
                                     );
                                 });
 
@@ -171,7 +295,7 @@ pub fn setup_favorite_list(app: &GPSApp) {
     ));
     favorite_list.add_controller(gesture);
 
-    setup_search_entry(&favorite_list, app)
+    setup_search_entry(&favorite_list, app, elements)
 }
 
 pub fn add_to_favorite_list(app: &GPSApp, element_name: String) {
@@ -211,7 +335,90 @@ pub fn add_to_favorite_list(app: &GPSApp, element_name: String) {
     }
 }
 
-fn reset_elements_list(elements_list: &ColumnView, elements: Vec<GPS::ElementInfo>) {
+fn template_row(app: &GPSApp, template_file: &templates::TemplateFile) -> gtk::ListBoxRow {
+    let template = &template_file.template;
+    let chain: Vec<String> = template
+        .nodes
+        .iter()
+        .map(|node| node.name.clone())
+        .collect();
+    let vbox = Box::new(gtk::Orientation::Vertical, 2);
+    let name_label = Label::builder().label(&template.name).xalign(0.0).build();
+    let chain_label = Label::builder()
+        .label(chain.join(" → "))
+        .xalign(0.0)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .build();
+    chain_label.add_css_class("dim-label");
+    vbox.append(&name_label);
+    vbox.append(&chain_label);
+    vbox.set_hexpand(true);
+
+    let remove_button = gtk::Button::from_icon_name("user-trash-symbolic");
+    remove_button.set_tooltip_text(Some("Remove the template from the library"));
+    remove_button.set_valign(gtk::Align::Center);
+    let template_name = template.name.clone();
+    let template_path = template_file.path.clone();
+    let app_weak = app.downgrade();
+    remove_button.connect_clicked(move |_| {
+        let app = upgrade_weak!(app_weak);
+        if let Err(e) = templates::remove_template(&template_path) {
+            GPS_ERROR!("Unable to remove the template {}: {}", template_name, e);
+        }
+        reset_template_list(&app);
+    });
+
+    let hbox = Box::builder()
+        .spacing(6)
+        .margin_start(6)
+        .margin_end(6)
+        .margin_top(4)
+        .margin_bottom(4)
+        .build();
+    hbox.append(&vbox);
+    hbox.append(&remove_button);
+    let row = gtk::ListBoxRow::builder().child(&hbox).build();
+    // The row is named after the file of the template, inserted when activated
+    row.set_widget_name(&template_file.path.to_string_lossy());
+    row.set_tooltip_text(Some("Double click to insert the template in the graph"));
+    row
+}
+
+/// Fill the template list with the templates of the library.
+pub fn reset_template_list(app: &GPSApp) {
+    let template_list: gtk::ListBox = app
+        .builder
+        .object("listbox-templates")
+        .expect("Couldn't get listbox-templates");
+    while let Some(child) = template_list.first_child() {
+        template_list.remove(&child);
+    }
+    for template_file in templates::templates() {
+        template_list.append(&template_row(app, &template_file));
+    }
+}
+
+pub fn setup_template_list(app: &GPSApp) {
+    let template_list: gtk::ListBox = app
+        .builder
+        .object("listbox-templates")
+        .expect("Couldn't get listbox-templates");
+    let app_weak = app.downgrade();
+    template_list.connect_row_activated(move |_, row| {
+        let app = upgrade_weak!(app_weak);
+        let template_path = row.widget_name();
+        GPS_DEBUG!("Template {} selected", template_path);
+        if let Err(e) = app.insert_template(Path::new(template_path.as_str())) {
+            GPS_ERROR!("Unable to insert the template {}: {}", template_path, e);
+        }
+    });
+    reset_template_list(app);
+}
+
+fn reset_elements_list<'a>(
+    elements_list: &ColumnView,
+    elements: impl IntoIterator<Item = &'a GPS::ElementInfo>,
+) {
     let model = gio::ListStore::new::<ElementInfoObject>();
 
     for element in elements {
@@ -252,7 +459,8 @@ pub fn setup_elements_list(app: &GPSApp) {
     ));
 
     let elements = GPS::ElementInfo::elements_list().expect("Unable to obtain element's list");
-    reset_elements_list(&tree, elements);
+    setup_element_filters(app, &elements);
+    reset_elements_list(&tree, &elements);
 
     let app_weak = app.downgrade();
     tree.connect_activate(move |column_view, position| {
@@ -326,12 +534,51 @@ pub fn setup_elements_list(app: &GPSApp) {
     ));
     tree.add_controller(gesture);
 
-    setup_search_entry(&tree, app)
+    setup_search_entry(&tree, app, elements)
+}
+
+/// Widgets of a section of the inspector with the widgets of its entries, and the text
+/// of the entries to search in.
+type InspectorSection = (gtk::Expander, Vec<(Box, GPS::inspect::InspectEntry)>);
+
+fn inspector_section(section: &GPS::inspect::InspectSection) -> InspectorSection {
+    let section_box = Box::new(gtk::Orientation::Vertical, 6);
+    section_box.set_margin_start(12);
+    let mut entries = vec![];
+    for entry in &section.entries {
+        let entry_box = Box::new(gtk::Orientation::Vertical, 2);
+        let name = Label::builder()
+            .label(&entry.name)
+            .xalign(0.0)
+            .selectable(true)
+            .css_classes(vec!["heading"])
+            .build();
+        entry_box.append(&name);
+        if !entry.details.is_empty() {
+            let details = Label::builder()
+                .label(entry.details.join("\n"))
+                .xalign(0.0)
+                .wrap(true)
+                .selectable(true)
+                .margin_start(12)
+                .css_classes(vec!["monospace"])
+                .build();
+            entry_box.append(&details);
+        }
+        section_box.append(&entry_box);
+        entries.push((entry_box, entry.clone()));
+    }
+    let expander = gtk::Expander::builder()
+        .label(format!("{} ({})", section.title, section.entries.len()))
+        .expanded(true)
+        .child(&section_box)
+        .build();
+    (expander, entries)
 }
 
+/// Display the documentation of an element factory: pad templates, properties, signals,
+/// interfaces and hierarchy, with a search entry and a button to copy it as text.
 pub fn display_properties(app: &GPSApp, element_name: &str) {
-    let description = GPS::ElementInfo::element_description(element_name)
-        .expect("Unable to get element description from GStreamer");
     let box_property: Box = app
         .builder
         .object("box-property")
@@ -340,11 +587,62 @@ pub fn display_properties(app: &GPSApp, element_name: &str) {
     while let Some(child) = box_property.first_child() {
         box_property.remove(&child);
     }
-    let label = Label::new(Some(""));
-    label.set_hexpand(true);
-    label.set_halign(gtk::Align::Start);
-    label.set_margin_start(4);
-    label.set_markup(&description);
-    label.set_selectable(true);
-    box_property.append(&label);
+    let inspection = match GPS::inspect::inspect_element(element_name) {
+        Ok(inspection) => inspection,
+        Err(e) => {
+            GPS_DEBUG!("Unable to inspect the element {}: {}", element_name, e);
+            let description = GPS::ElementInfo::element_description(element_name)
+                .expect("Unable to get element description from GStreamer");
+            let label = Label::new(Some(""));
+            label.set_hexpand(true);
+            label.set_halign(gtk::Align::Start);
+            label.set_margin_start(4);
+            label.set_markup(&description);
+            label.set_selectable(true);
+            box_property.append(&label);
+            return;
+        }
+    };
+
+    let toolbar = Box::new(gtk::Orientation::Horizontal, 6);
+    toolbar.set_margin_start(4);
+    toolbar.set_margin_end(4);
+    toolbar.set_margin_top(4);
+    let search_entry = SearchEntry::builder()
+        .placeholder_text("Search the documentation...")
+        .hexpand(true)
+        .build();
+    toolbar.append(&search_entry);
+    let copy_button = gtk::Button::from_icon_name("edit-copy-symbolic");
+    copy_button.set_tooltip_text(Some("Copy the documentation as text"));
+    let text = inspection.to_text();
+    copy_button.connect_clicked(move |button| {
+        button.clipboard().set_text(&text);
+    });
+    toolbar.append(&copy_button);
+    box_property.append(&toolbar);
+
+    let sections: Vec<InspectorSection> = inspection
+        .sections
+        .iter()
+        .filter(|section| !section.entries.is_empty())
+        .map(inspector_section)
+        .collect();
+    for (expander, _) in &sections {
+        expander.set_margin_start(4);
+        expander.set_margin_end(4);
+        box_property.append(expander);
+    }
+    search_entry.connect_search_changed(move |entry| {
+        let text = entry.text().to_string();
+        for (expander, entries) in &sections {
+            let mut visible = false;
+            for (entry_box, entry) in entries {
+                let matches = text.is_empty() || entry.matches(&text);
+                entry_box.set_visible(matches);
+                visible |= matches;
+            }
+            expander.set_visible(visible);
+        }
+    });
 }
@@ -0,0 +1,150 @@
+// interlink.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::app::core::graphbook::{self, GraphTab};
+use crate::app::GPSApp;
+use crate::gps::interlink::{self, InterLinkKind};
+use crate::graphmanager as GM;
+use crate::graphmanager::PropertyExt;
+use crate::logger;
+use crate::ui as GPSUI;
+use crate::GPS_WARN;
+use gtk::glib;
+use gtk::prelude::*;
+
+/// Free ports of the other tabs which can be the peer of a port with `direction`,
+/// as (tab id, node id, port id, label).
+fn peer_candidates(
+    app: &GPSApp,
+    tab_id: u32,
+    direction: GM::PortDirection,
+) -> Vec<(u32, u32, u32, String)> {
+    let peer_direction = if direction == GM::PortDirection::Output {
+        GM::PortDirection::Input
+    } else {
+        GM::PortDirection::Output
+    };
+    let mut tabs: Vec<GraphTab> = app.graphbook.borrow().values().cloned().collect();
+    tabs.sort_by_key(|tab| tab.id());
+    let mut candidates = Vec::new();
+    for tab in tabs.iter().filter(|tab| tab.id() != tab_id) {
+        let graphview = tab.graphview();
+        for node in graphview.all_nodes(GM::NodeType::All) {
+            for port in node.all_ports(peer_direction) {
+                if graphview.port_is_linked(port.id()).is_some()
+                    || interlink::port_inter_link(&port).is_some()
+                {
+                    continue;
+                }
+                candidates.push((
+                    tab.id(),
+                    node.id(),
+                    port.id(),
+                    format!(
+                        "{} / {} / {}",
+                        tab.basename(),
+                        node.unique_name(),
+                        port.name()
+                    ),
+                ));
+            }
+        }
+    }
+    candidates
+}
+
+/// Display the dialog linking a port of the current tab to a port of another tab.
+pub fn display_inter_link_dialog(app: &GPSApp, node_id: u32, port_id: u32) {
+    let tab_id = graphbook::current_graphtab(app).id();
+    let port = app.port(node_id, port_id);
+    let direction = port.direction();
+    let candidates = peer_candidates(app, tab_id, direction);
+    if candidates.is_empty() {
+        GPSUI::message::display_error_dialog(
+            false,
+            "No free port available in the other tabs to link with.",
+        );
+        return;
+    }
+
+    let grid = gtk::Grid::builder()
+        .column_spacing(8)
+        .row_spacing(8)
+        .build();
+
+    let peer_label = gtk::Label::builder()
+        .label("Peer port")
+        .halign(gtk::Align::Start)
+        .build();
+    let labels: Vec<&str> = candidates.iter().map(|c| c.3.as_str()).collect();
+    let peer_dropdown = gtk::DropDown::from_strings(&labels);
+    peer_dropdown.set_hexpand(true);
+    grid.attach(&peer_label, 0, 0, 1, 1);
+    grid.attach(&peer_dropdown, 1, 0, 1, 1);
+
+    let kind_label = gtk::Label::builder()
+        .label("Transport")
+        .halign(gtk::Align::Start)
+        .build();
+    let kinds: Vec<String> = InterLinkKind::ALL
+        .iter()
+        .map(|kind| {
+            format!(
+                "{} ({} / {})",
+                kind,
+                kind.sink_factory(),
+                kind.src_factory()
+            )
+        })
+        .collect();
+    let kinds: Vec<&str> = kinds.iter().map(|k| k.as_str()).collect();
+    let kind_dropdown = gtk::DropDown::from_strings(&kinds);
+    let caps = PropertyExt::property(&port, "_caps").unwrap_or_default();
+    let default_kind = InterLinkKind::from_caps(&caps);
+    if let Some(position) = InterLinkKind::ALL.iter().position(|k| *k == default_kind) {
+        kind_dropdown.set_selected(position as u32);
+    }
+    grid.attach(&kind_label, 0, 1, 1, 1);
+    grid.attach(&kind_dropdown, 1, 1, 1, 1);
+
+    let dialog = GPSUI::dialog::create(
+        &format!("Link {} to another tab", port.name()),
+        app,
+        &grid,
+        glib::clone!(
+            #[weak]
+            peer_dropdown,
+            #[weak]
+            kind_dropdown,
+            move |app, dialog| {
+                let Some((peer_tab, peer_node, peer_port, _)) =
+                    candidates.get(peer_dropdown.selected() as usize)
+                else {
+                    GPS_WARN!("No peer port selected");
+                    return;
+                };
+                let kind = InterLinkKind::ALL
+                    .get(kind_dropdown.selected() as usize)
+                    .copied()
+                    .unwrap_or(default_kind);
+                if direction == GM::PortDirection::Output {
+                    app.create_inter_link(
+                        kind, tab_id, node_id, port_id, *peer_tab, *peer_node, *peer_port,
+                    );
+                } else {
+                    app.create_inter_link(
+                        kind, *peer_tab, *peer_node, *peer_port, tab_id, node_id, port_id,
+                    );
+                }
+                dialog.close();
+            }
+        ),
+    );
+    dialog.set_default_size(520, 160);
+    dialog.present();
+}
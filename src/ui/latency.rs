@@ -0,0 +1,246 @@
+// latency.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::app::core::graphbook;
+use crate::app::GPSApp;
+use crate::gps::latency::{ElementLatency, Latency};
+use crate::logger;
+use crate::ui as GPSUI;
+use crate::{GPS_ERROR, GPS_INFO};
+use gst::prelude::*;
+use gtk::glib;
+use gtk::prelude::*;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Queue properties which can be tuned from the latency budget.
+const QUEUE_SIZE_PROPERTIES: &[&str] = &["max-size-time", "max-size-buffers", "max-size-bytes"];
+/// Elements whose size properties are displayed in the latency budget.
+const QUEUE_FACTORIES: &[&str] = &["queue", "queue2"];
+/// Upper bound of the pipeline latency spin button in milliseconds.
+const MAX_PIPELINE_LATENCY_MS: f64 = 60000.0;
+
+// (node id, unique name) -> (property name -> value)
+type QueueChanges = HashMap<(u32, String), HashMap<String, String>>;
+
+fn latency_to_string(latency: &Option<Latency>) -> (String, String, String) {
+    match latency {
+        Some(latency) => (
+            latency.live.to_string(),
+            latency.min.to_string(),
+            latency
+                .max
+                .map(|m| m.to_string())
+                .unwrap_or_else(|| String::from("none")),
+        ),
+        None => (String::from("-"), String::from("-"), String::from("-")),
+    }
+}
+
+fn grid_label(text: &str, heading: bool) -> gtk::Label {
+    let label = gtk::Label::builder()
+        .label(text)
+        .halign(gtk::Align::Start)
+        .margin_start(4)
+        .margin_end(4)
+        .selectable(!heading)
+        .build();
+    if heading {
+        label.add_css_class("heading");
+    }
+    label
+}
+
+fn create_queue_editor(
+    app: &GPSApp,
+    element: &ElementLatency,
+    queue_changes: &Rc<RefCell<QueueChanges>>,
+) -> gtk::Box {
+    let queue_box = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+    let graphtab = graphbook::current_graphtab(app);
+    let player = graphtab.player();
+    for property in QUEUE_SIZE_PROPERTIES {
+        let entry = gtk::Entry::builder().width_chars(10).build();
+        entry.set_tooltip_text(Some(property));
+        entry.set_placeholder_text(Some(property));
+        if let Some(pipeline) = player.pipeline() {
+            if let Some(value) = pipeline
+                .by_name(&element.unique_name)
+                .and_then(|e| e.property_value(property).transform::<String>().ok())
+                .and_then(|v| v.get::<String>().ok())
+            {
+                entry.set_text(&value);
+            }
+        }
+        let key = (element.node_id, element.unique_name.clone());
+        entry.connect_changed(glib::clone!(
+            #[strong]
+            queue_changes,
+            move |e| {
+                queue_changes
+                    .borrow_mut()
+                    .entry(key.clone())
+                    .or_default()
+                    .insert(property.to_string(), e.text().to_string());
+            }
+        ));
+        queue_box.append(&entry);
+    }
+    queue_box
+}
+
+fn create_path_grid(
+    app: &GPSApp,
+    path: &[ElementLatency],
+    queue_changes: &Rc<RefCell<QueueChanges>>,
+) -> gtk::Grid {
+    let grid = gtk::Grid::builder()
+        .column_spacing(8)
+        .row_spacing(4)
+        .margin_bottom(12)
+        .build();
+    for (column, title) in [
+        "Element",
+        "Factory",
+        "Live",
+        "Min",
+        "Max",
+        "Added",
+        "Queue size",
+    ]
+    .iter()
+    .enumerate()
+    {
+        grid.attach(&grid_label(title, true), column as i32, 0, 1, 1);
+    }
+
+    for (row, element) in path.iter().enumerate() {
+        let row = row as i32 + 1;
+        let (live, min, max) = latency_to_string(&element.latency);
+        let own = element
+            .own_min
+            .map(|o| o.to_string())
+            .unwrap_or_else(|| String::from("-"));
+        for (column, text) in [
+            element.unique_name.as_str(),
+            element.factory_name.as_str(),
+            &live,
+            &min,
+            &max,
+            &own,
+        ]
+        .iter()
+        .enumerate()
+        {
+            grid.attach(&grid_label(text, false), column as i32, row, 1, 1);
+        }
+        if QUEUE_FACTORIES.contains(&element.factory_name.as_str()) {
+            grid.attach(
+                &create_queue_editor(app, element, queue_changes),
+                6,
+                row,
+                1,
+                1,
+            );
+        }
+    }
+    grid
+}
+
+/// Display the latency of each element along every source to sink path of the running
+/// pipeline, and allow to tune the pipeline latency and the queue sizes.
+pub fn display_latency_budget(app: &GPSApp) {
+    let graphtab = graphbook::current_graphtab(app);
+    let player = graphtab.player();
+    let queue_changes: Rc<RefCell<QueueChanges>> = Rc::new(RefCell::new(HashMap::new()));
+
+    let main_box = gtk::Box::new(gtk::Orientation::Vertical, 8);
+
+    let pipeline_latency = player
+        .pipeline_latency()
+        .map(|l| l.description())
+        .unwrap_or_else(|| String::from("unavailable"));
+    let summary = gtk::Label::builder()
+        .label(format!("Pipeline latency: {pipeline_latency}"))
+        .halign(gtk::Align::Start)
+        .css_classes(vec!["heading"])
+        .build();
+    main_box.append(&summary);
+
+    // Pipeline latency override, 0 lets the pipeline compute it
+    let latency_box = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    let latency_label = gtk::Label::builder()
+        .label("Configured latency (ms, 0 = automatic)")
+        .halign(gtk::Align::Start)
+        .hexpand(true)
+        .build();
+    let latency_spin = gtk::SpinButton::with_range(0.0, MAX_PIPELINE_LATENCY_MS, 1.0);
+    latency_spin.set_value(player.latency().map(|l| l.mseconds() as f64).unwrap_or(0.0));
+    latency_box.append(&latency_label);
+    latency_box.append(&latency_spin);
+    main_box.append(&latency_box);
+
+    let budget = player.latency_budget(&graphtab.graphview());
+    if budget.is_empty() {
+        main_box.append(&grid_label(
+            "The latency is only available while the pipeline is playing.",
+            false,
+        ));
+    }
+    for path in budget.iter() {
+        let title = path
+            .iter()
+            .map(|e| e.unique_name.as_str())
+            .collect::<Vec<&str>>()
+            .join(" → ");
+        let frame = gtk::Frame::builder().label(title).build();
+        frame.set_child(Some(&create_path_grid(app, path, &queue_changes)));
+        main_box.append(&frame);
+    }
+
+    let dialog = GPSUI::dialog::create(
+        "Latency budget",
+        app,
+        &main_box,
+        glib::clone!(
+            #[weak]
+            latency_spin,
+            #[strong]
+            queue_changes,
+            #[strong]
+            graphtab,
+            move |_app, _dialog| {
+                let latency = latency_spin.value() as u64;
+                let latency = (latency > 0).then(|| gst::ClockTime::from_mseconds(latency));
+                GPS_INFO!("Set pipeline latency to {:?}", latency);
+                graphtab.player().set_latency(latency);
+
+                for ((node_id, unique_name), properties) in queue_changes.borrow().iter() {
+                    for (name, value) in properties {
+                        if let Err(e) =
+                            graphtab
+                                .player()
+                                .set_element_property(unique_name, name, value)
+                        {
+                            GPS_ERROR!("Unable to set {} on {}: {}", name, unique_name, e);
+                        }
+                    }
+                    // Keep the new sizes in the graph for the next launch
+                    graphtab
+                        .graphview()
+                        .update_node_properties(*node_id, properties);
+                }
+                queue_changes.borrow_mut().clear();
+            }
+        ),
+    );
+    dialog.set_default_size(900, 500);
+    dialog.present();
+}
@@ -0,0 +1,197 @@
+// layout.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::app::core::graphbook;
+use crate::app::GPSApp;
+use crate::graphmanager as GM;
+use crate::ui as GPSUI;
+use gtk::prelude::*;
+use gtk::{glib, graphene};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Widgets of the dialog holding the layout options.
+#[derive(Clone)]
+struct LayoutControls {
+    orientation: gtk::DropDown,
+    mode: gtk::DropDown,
+    stage_spacing: gtk::SpinButton,
+    node_spacing: gtk::SpinButton,
+    selected_only: gtk::CheckButton,
+}
+
+impl LayoutControls {
+    fn options(&self) -> GM::AutoArrangeOptions {
+        let orientation = match self.orientation.selected() {
+            1 => GM::LayoutOrientation::TopToBottom,
+            _ => GM::LayoutOrientation::LeftToRight,
+        };
+        let mode = match self.mode.selected() {
+            1 => GM::LayoutMode::Compact,
+            _ => GM::LayoutMode::Layered,
+        };
+        let stage_spacing = self.stage_spacing.value() as f32;
+        let node_spacing = self.node_spacing.value() as f32;
+        let (horizontal_spacing, vertical_spacing) = match orientation {
+            GM::LayoutOrientation::LeftToRight => (stage_spacing, node_spacing),
+            GM::LayoutOrientation::TopToBottom => (node_spacing, stage_spacing),
+        };
+        GM::AutoArrangeOptions {
+            horizontal_spacing,
+            vertical_spacing,
+            orientation,
+            mode,
+            selected_only: self.selected_only.is_active(),
+            ..Default::default()
+        }
+    }
+}
+
+fn attach_row(grid: &gtk::Grid, row: i32, label: &str, widget: &impl IsA<gtk::Widget>) {
+    let label = gtk::Label::builder()
+        .label(label)
+        .halign(gtk::Align::Start)
+        .build();
+    grid.attach(&label, 0, row, 1, 1);
+    grid.attach(widget, 1, row, 1, 1);
+}
+
+/// Arrange the graph from its original positions without recording an undo action.
+fn preview_layout(
+    graphview: &GM::GraphView,
+    positions: &HashMap<u32, graphene::Point>,
+    options: GM::AutoArrangeOptions,
+) {
+    graphview.restore_node_positions(positions);
+    graphview.set_undo_recording(false);
+    graphview.auto_arrange_graph(Some(options));
+    graphview.set_undo_recording(true);
+}
+
+/// Display the dialog arranging the graph of the current tab, previewing the layout
+/// while its options are changed. The original positions are restored if the dialog
+/// is closed without applying the layout.
+pub fn display_layout_dialog(app: &GPSApp) {
+    let graphview = graphbook::current_graphtab(app).graphview().clone();
+    let positions = Rc::new(graphview.node_positions());
+    let applied = Rc::new(Cell::new(false));
+    let defaults = GM::AutoArrangeOptions::default();
+
+    let grid = gtk::Grid::builder()
+        .column_spacing(8)
+        .row_spacing(8)
+        .build();
+    let controls = LayoutControls {
+        orientation: gtk::DropDown::from_strings(&["Left to right", "Top to bottom"]),
+        mode: gtk::DropDown::from_strings(&["Layered", "Compact"]),
+        stage_spacing: gtk::SpinButton::with_range(10.0, 1000.0, 10.0),
+        node_spacing: gtk::SpinButton::with_range(10.0, 1000.0, 10.0),
+        selected_only: gtk::CheckButton::with_label("Selected nodes only"),
+    };
+    controls.orientation.set_hexpand(true);
+    controls
+        .stage_spacing
+        .set_value(defaults.horizontal_spacing as f64);
+    controls
+        .node_spacing
+        .set_value(defaults.vertical_spacing as f64);
+    let selected_count = graphview.selected_nodes().len();
+    controls.selected_only.set_sensitive(selected_count > 0);
+    controls.selected_only.set_active(selected_count > 1);
+    let preview = gtk::CheckButton::with_label("Preview");
+    preview.set_active(true);
+
+    attach_row(&grid, 0, "Direction", &controls.orientation);
+    attach_row(&grid, 1, "Placement", &controls.mode);
+    attach_row(&grid, 2, "Spacing between stages", &controls.stage_spacing);
+    attach_row(&grid, 3, "Spacing between nodes", &controls.node_spacing);
+    grid.attach(&controls.selected_only, 0, 4, 2, 1);
+    grid.attach(&preview, 0, 5, 2, 1);
+    let hint = gtk::Label::builder()
+        .label("Pinned nodes keep their position.")
+        .halign(gtk::Align::Start)
+        .build();
+    hint.add_css_class("dim-label");
+    grid.attach(&hint, 0, 6, 2, 1);
+
+    let update = Rc::new(glib::clone!(
+        #[weak]
+        graphview,
+        #[weak]
+        preview,
+        #[strong]
+        positions,
+        #[strong]
+        controls,
+        move || {
+            if preview.is_active() {
+                preview_layout(&graphview, &positions, controls.options());
+            } else {
+                graphview.restore_node_positions(&positions);
+            }
+        }
+    ));
+    for dropdown in [&controls.orientation, &controls.mode] {
+        dropdown.connect_selected_notify(glib::clone!(
+            #[strong]
+            update,
+            move |_| update()
+        ));
+    }
+    for spin_button in [&controls.stage_spacing, &controls.node_spacing] {
+        spin_button.connect_value_changed(glib::clone!(
+            #[strong]
+            update,
+            move |_| update()
+        ));
+    }
+    for check_button in [&controls.selected_only, &preview] {
+        check_button.connect_toggled(glib::clone!(
+            #[strong]
+            update,
+            move |_| update()
+        ));
+    }
+    update();
+
+    let dialog = GPSUI::dialog::create(
+        "Arrange layout",
+        app,
+        &grid,
+        glib::clone!(
+            #[weak]
+            graphview,
+            #[strong]
+            positions,
+            #[strong]
+            applied,
+            move |_app, dialog| {
+                // Arrange again from the original positions to record a single undo action
+                graphview.restore_node_positions(&positions);
+                graphview.auto_arrange_graph(Some(controls.options()));
+                applied.set(true);
+                dialog.close();
+            }
+        ),
+    );
+    dialog.connect_close_request(glib::clone!(
+        #[weak]
+        graphview,
+        #[upgrade_or]
+        glib::Propagation::Proceed,
+        move |_| {
+            if !applied.get() {
+                graphview.restore_node_positions(&positions);
+            }
+            glib::Propagation::Proceed
+        }
+    ));
+    dialog.set_default_size(420, 280);
+    dialog.present();
+}
@@ -6,12 +6,19 @@
 //
 // SPDX-License-Identifier: GPL-3.0-only
 
+use crate::app::settings::Settings;
 use crate::app::GPSApp;
+use crate::gps::debug_log;
 use crate::logger;
+use crate::ui as GPSUI;
 use crate::ui::common::{create_column_view_column, create_column_view_column_with_width};
 use crate::ui::models::LogEntry;
+use crate::{GPS_ERROR, GPS_INFO};
 use gtk::prelude::*;
 use gtk::{gio, glib};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
 
 use gtk::{ColumnView, MultiSelection};
 
@@ -23,6 +30,8 @@ const COL_WIDTH_TYPE: i32 = 150;
 const COL_WIDTH_FUNCTION: i32 = 300;
 const COL_WIDTH_CATEGORY: i32 = 150;
 const COL_WIDTH_FILE: i32 = 200;
+const COL_WIDTH_OBJECT: i32 = 150;
+const COL_WIDTH_THREAD: i32 = 120;
 
 // Reset loggers (ColumnView)
 fn reset_logger_column_view(column_view: &ColumnView) {
@@ -54,6 +63,8 @@ fn copy_logger_to_clipboard(column_view: &ColumnView, log_type: logger::LogType)
                         let level: String = entry.property("level");
                         let category: String = entry.property("category");
                         let file: String = entry.property("file");
+                        let object: String = entry.property("object");
+                        let thread: String = entry.property("thread");
                         let log: String = entry.property("log");
 
                         let line = match log_type {
@@ -61,7 +72,10 @@ fn copy_logger_to_clipboard(column_view: &ColumnView, log_type: logger::LogType)
                                 format!("{}\t{}\t{}\t{}", time, level, category, log)
                             }
                             logger::LogType::Gst => {
-                                format!("{}\t{}\t{}\t{}\t{}", time, level, category, file, log)
+                                format!(
+                                    "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                                    time, level, category, object, file, thread, log
+                                )
                             }
                             logger::LogType::Message => {
                                 format!("{}\t{}\t{}\t{}", time, level, category, log)
@@ -143,10 +157,20 @@ pub fn setup_logger_list(app: &GPSApp, logger_name: &str, log_type: logger::LogT
                 Some(COL_WIDTH_CATEGORY),
             ));
             column_view.append_column(&create_column_view_column_with_width(
-                "FILE",
+                "OBJECT",
+                "object",
+                Some(COL_WIDTH_OBJECT),
+            ));
+            column_view.append_column(&create_column_view_column_with_width(
+                "FILE:LINE",
                 "file",
                 Some(COL_WIDTH_FILE),
             ));
+            column_view.append_column(&create_column_view_column_with_width(
+                "THREAD",
+                "thread",
+                Some(COL_WIDTH_THREAD),
+            ));
             column_view.append_column(&create_column_view_column("LOG", "log"));
             // Expandable
         }
@@ -164,11 +188,19 @@ pub fn setup_logger_list(app: &GPSApp, logger_name: &str, log_type: logger::LogT
         column_view,
         move |gesture, _n_press, x, y| {
             let app = upgrade_weak!(app_weak);
+            let app_weak_menu = app.downgrade();
             if gesture.current_button() == gtk::gdk::BUTTON_SECONDARY {
+                // The GStreamer logger has extra entries to save/load a session and
+                // to edit the category thresholds
+                let menu_name = if log_type_clone == logger::LogType::Gst {
+                    "gst_logger_menu"
+                } else {
+                    "logger_menu"
+                };
                 let menu: gio::MenuModel = app
                     .builder
-                    .object("logger_menu")
-                    .expect("Couldn't get logger_menu model");
+                    .object(menu_name)
+                    .unwrap_or_else(|| panic!("Couldn't get {} model", menu_name));
 
                 let column_view_clone = column_view.clone();
                 app.connect_app_menu_action("logger.clear", move |_, _| {
@@ -181,6 +213,41 @@ pub fn setup_logger_list(app: &GPSApp, logger_name: &str, log_type: logger::LogT
                     copy_logger_to_clipboard(&column_view_copy, log_type_copy.clone());
                 });
 
+                if log_type_clone == logger::LogType::Gst {
+                    let column_view_save = column_view.clone();
+                    app.connect_app_menu_action("logger.save", move |_, _| {
+                        let column_view = column_view_save.clone();
+                        let app = upgrade_weak!(app_weak_menu);
+                        GPSUI::dialog::get_file(
+                            &app,
+                            GPSUI::dialog::FileDialogType::SaveLog,
+                            move |_app, filename| {
+                                save_gst_log(&column_view, &filename);
+                            },
+                        );
+                    });
+
+                    let column_view_load = column_view.clone();
+                    let app_weak_load = app.downgrade();
+                    app.connect_app_menu_action("logger.load", move |_, _| {
+                        let column_view = column_view_load.clone();
+                        let app = upgrade_weak!(app_weak_load);
+                        GPSUI::dialog::get_file(
+                            &app,
+                            GPSUI::dialog::FileDialogType::OpenAll,
+                            move |_app, filename| {
+                                load_gst_log(&column_view, &filename);
+                            },
+                        );
+                    });
+
+                    let app_weak_categories = app.downgrade();
+                    app.connect_app_menu_action("logger.categories", move |_, _| {
+                        let app = upgrade_weak!(app_weak_categories);
+                        display_gst_debug_categories(&app);
+                    });
+                }
+
                 app.show_context_menu_at_position(&column_view, x, y, &menu);
             }
         }
@@ -217,13 +284,19 @@ pub fn add_to_logger_list(app: &GPSApp, log_type: logger::LogType, log_entry: &s
             // Parse log entry based on type
             let entry = match log_type {
                 logger::LogType::Gst => {
-                    let log: Vec<&str> = log_entry.splitn(5, '\t').collect();
-                    LogEntry::new(
-                        log.first().unwrap_or(&""),
+                    // Format: time\ttimestamp\tlevel\tcategory\tobject\tfile:line:function\tthread\tmessage
+                    // The GStreamer timestamp is displayed instead of the wall clock time
+                    // to match the GST_DEBUG output.
+                    let log: Vec<&str> = log_entry.splitn(8, '\t').collect();
+                    LogEntry::new_gst(
                         log.get(1).unwrap_or(&""),
                         log.get(2).unwrap_or(&""),
                         log.get(3).unwrap_or(&""),
                         log.get(4).unwrap_or(&""),
+                        log.get(5).unwrap_or(&""),
+                        std::process::id(),
+                        log.get(6).unwrap_or(&""),
+                        log.get(7).unwrap_or(&""),
                     )
                 }
                 logger::LogType::Message => {
@@ -327,3 +400,229 @@ pub fn add_to_logger_list(app: &GPSApp, log_type: logger::LogType, log_entry: &s
         }
     }
 }
+
+fn gst_log_list_store(column_view: &ColumnView) -> Option<gio::ListStore> {
+    column_view
+        .model()?
+        .downcast_ref::<MultiSelection>()?
+        .model()?
+        .downcast::<gio::ListStore>()
+        .ok()
+}
+
+fn log_record_from_entry(entry: &LogEntry) -> debug_log::GstLogRecord {
+    let mut record = debug_log::GstLogRecord {
+        timestamp: entry.time(),
+        // Keep the pid of the records loaded from a file
+        pid: entry.pid(),
+        thread: entry.thread(),
+        level: entry.level(),
+        category: entry.category(),
+        object: entry.object(),
+        message: entry.log(),
+        ..Default::default()
+    };
+    record.set_location(&entry.file());
+    record
+}
+
+// Save the content of the GStreamer logger as a GST_DEBUG text file
+fn save_gst_log(column_view: &ColumnView, filename: &str) {
+    let Some(list_store) = gst_log_list_store(column_view) else {
+        return;
+    };
+    let records: Vec<debug_log::GstLogRecord> = list_store
+        .iter::<LogEntry>()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| log_record_from_entry(&entry))
+        .collect();
+    match debug_log::save_log_file(filename, &records) {
+        Ok(()) => GPS_INFO!(
+            "Saved {} GStreamer log lines to {}",
+            records.len(),
+            filename
+        ),
+        Err(e) => GPS_ERROR!("Unable to save the GStreamer log to {}: {}", filename, e),
+    }
+}
+
+// Replace the content of the GStreamer logger with a GST_DEBUG text file
+fn load_gst_log(column_view: &ColumnView, filename: &str) {
+    let records = match debug_log::load_log_file(filename) {
+        Ok(records) => records,
+        Err(e) => {
+            GPS_ERROR!("Unable to load the GStreamer log {}: {}", filename, e);
+            return;
+        }
+    };
+    reset_logger_column_view(column_view);
+    if let Some(list_store) = gst_log_list_store(column_view) {
+        let entries: Vec<LogEntry> = records
+            .iter()
+            .map(|record| {
+                LogEntry::new_gst(
+                    &record.timestamp,
+                    &record.level,
+                    &record.category,
+                    &record.object,
+                    &record.location(),
+                    record.pid,
+                    &record.thread,
+                    &record.message,
+                )
+            })
+            .collect();
+        list_store.extend_from_slice(&entries);
+        GPS_INFO!(
+            "Loaded {} GStreamer log lines from {}",
+            entries.len(),
+            filename
+        );
+    }
+}
+
+fn create_debug_level_dropdown(level: gst::DebugLevel) -> gtk::DropDown {
+    let names: Vec<String> = debug_log::DEBUG_LEVELS
+        .iter()
+        .map(|l| debug_log::debug_level_name(*l))
+        .collect();
+    let names: Vec<&str> = names.iter().map(|s| s.as_str()).collect();
+    let dropdown = gtk::DropDown::from_strings(&names);
+    if let Some(position) = debug_log::DEBUG_LEVELS.iter().position(|l| *l == level) {
+        dropdown.set_selected(position as u32);
+    }
+    dropdown
+}
+
+/// Display the registered GStreamer debug categories with their current threshold.
+/// Thresholds are applied immediately and saved to the GStreamer log level preference
+/// on Apply so they are restored on the next pipeline launch.
+pub fn display_gst_debug_categories(app: &GPSApp) {
+    // Sorted so that the changes are applied in the same order, `*` first
+    let changed: Rc<RefCell<BTreeMap<String, gst::DebugLevel>>> =
+        Rc::new(RefCell::new(BTreeMap::new()));
+
+    let main_box = gtk::Box::new(gtk::Orientation::Vertical, 8);
+
+    let search_entry = gtk::SearchEntry::builder()
+        .placeholder_text("Search categories...")
+        .build();
+    main_box.append(&search_entry);
+
+    // Default threshold applies to every category without a specific threshold
+    let default_row = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+    let default_label = gtk::Label::builder()
+        .label("Default threshold (*)")
+        .halign(gtk::Align::Start)
+        .hexpand(true)
+        .css_classes(vec!["heading"])
+        .build();
+    let default_dropdown = create_debug_level_dropdown(gst::log::get_default_threshold());
+    default_dropdown.connect_selected_notify(glib::clone!(
+        #[strong]
+        changed,
+        move |d| {
+            if let Some(level) = debug_log::DEBUG_LEVELS.get(d.selected() as usize) {
+                gst::log::set_default_threshold(*level);
+                changed.borrow_mut().insert(String::from("*"), *level);
+            }
+        }
+    ));
+    default_row.append(&default_label);
+    default_row.append(&default_dropdown);
+    main_box.append(&default_row);
+
+    let listbox = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(vec!["boxed-list"])
+        .build();
+
+    for category in debug_log::debug_categories() {
+        let row = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+        row.set_margin_start(8);
+        row.set_margin_end(8);
+        row.set_margin_top(4);
+        row.set_margin_bottom(4);
+
+        let label = gtk::Label::builder()
+            .label(&category.name)
+            .halign(gtk::Align::Start)
+            .hexpand(true)
+            .xalign(0.0)
+            .build();
+        if !category.description.is_empty() {
+            label.set_tooltip_text(Some(&category.description));
+        }
+
+        let dropdown = create_debug_level_dropdown(category.threshold);
+        let name = category.name.clone();
+        dropdown.connect_selected_notify(glib::clone!(
+            #[strong]
+            changed,
+            move |d| {
+                if let Some(level) = debug_log::DEBUG_LEVELS.get(d.selected() as usize) {
+                    debug_log::set_category_threshold(&name, *level);
+                    changed.borrow_mut().insert(name.clone(), *level);
+                }
+            }
+        ));
+
+        row.append(&label);
+        row.append(&dropdown);
+
+        let list_row = gtk::ListBoxRow::new();
+        list_row.set_widget_name(&category.name);
+        list_row.set_tooltip_text(Some(&category.description));
+        list_row.set_child(Some(&row));
+        list_row.set_activatable(false);
+        listbox.append(&list_row);
+    }
+
+    let search_text: Rc<RefCell<String>> = Rc::new(RefCell::new(String::new()));
+    listbox.set_filter_func(glib::clone!(
+        #[strong]
+        search_text,
+        move |row| {
+            let search_text = search_text.borrow();
+            search_text.is_empty()
+                || row
+                    .widget_name()
+                    .to_lowercase()
+                    .contains(search_text.as_str())
+                || row
+                    .tooltip_text()
+                    .map(|t| t.to_lowercase().contains(search_text.as_str()))
+                    .unwrap_or(false)
+        }
+    ));
+    search_entry.connect_search_changed(glib::clone!(
+        #[weak]
+        listbox,
+        move |entry| {
+            *search_text.borrow_mut() = entry.text().to_lowercase();
+            listbox.invalidate_filter();
+        }
+    ));
+    main_box.append(&listbox);
+
+    let dialog = GPSUI::dialog::create(
+        "GStreamer debug categories",
+        app,
+        &main_box,
+        glib::clone!(
+            #[strong]
+            changed,
+            move |_app, dialog| {
+                let mut thresholds = Settings::gst_log_level();
+                for (name, level) in changed.borrow().iter() {
+                    thresholds = debug_log::threshold_string_with(&thresholds, name, *level);
+                }
+                GPS_INFO!("GStreamer log thresholds set to {}", thresholds);
+                Settings::set_gst_log_level(&thresholds);
+                dialog.close();
+            }
+        ),
+    );
+    dialog.set_default_size(500, 600);
+    dialog.present();
+}
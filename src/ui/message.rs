@@ -57,6 +57,33 @@ pub fn display_error_dialog(fatal: bool, message: &str) {
     });
 }
 
+/// Ask the user to confirm an action, `f` is only called when it is confirmed.
+pub fn display_confirm_dialog<F: Fn() + 'static>(message: &str, confirm_label: &str, f: F) {
+    let app = gio::Application::default()
+        .expect("No default application")
+        .downcast::<gtk::Application>()
+        .expect("Default application has wrong type");
+
+    let dialog = AlertDialog::builder()
+        .message("Confirmation")
+        .detail(message)
+        .modal(true)
+        .buttons(["Cancel", confirm_label])
+        .default_button(0)
+        .cancel_button(0)
+        .build();
+
+    dialog.choose(
+        app.active_window().as_ref(),
+        gio::Cancellable::NONE,
+        move |result| {
+            if result == Ok(1) {
+                f();
+            }
+        },
+    );
+}
+
 /// Display an error dialog during startup when the application may not be fully initialized.
 /// This variant takes explicit window and application references.
 pub fn display_startup_error_dialog(
@@ -0,0 +1,221 @@
+// missing.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::app::core::graphbook;
+use crate::app::GPSApp;
+use crate::gps::missing::{self, MissingElement};
+use crate::graphmanager as GM;
+use crate::logger;
+use crate::ui as GPSUI;
+use crate::{GPS_ERROR, GPS_INFO};
+use gtk::glib;
+use gtk::prelude::*;
+
+/// Mark a node as the placeholder of a missing factory, or clear the mark once the
+/// factory is available.
+pub fn mark_missing_node(node: &GM::Node, is_missing: bool) {
+    node.set_light(is_missing);
+    if is_missing {
+        node.add_css_class("node-missing");
+        node.set_tooltip_markup(Some(&format!(
+            "<b>{}</b>\nMissing element, install the {}",
+            glib::markup_escape_text(&node.name()),
+            glib::markup_escape_text(&missing::node_provider(node))
+        )));
+    } else {
+        node.remove_css_class("node-missing");
+        node.set_tooltip_markup(None);
+    }
+}
+
+fn report_text(missing: &[MissingElement]) -> String {
+    missing
+        .iter()
+        .map(|m| {
+            format!(
+                "{}\t{}\t{}\n",
+                m.factory,
+                m.provider(),
+                m.installer_detail()
+            )
+        })
+        .collect()
+}
+
+fn missing_element_row(
+    app: &GPSApp,
+    listbox: &gtk::ListBox,
+    summary: &gtk::Label,
+    element: &MissingElement,
+) -> gtk::ListBoxRow {
+    let vbox = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(2)
+        .margin_start(8)
+        .margin_end(8)
+        .margin_top(4)
+        .margin_bottom(4)
+        .build();
+    let title = gtk::Label::builder()
+        .label(format!(
+            "<b>{}</b> used by {} node(s)",
+            glib::markup_escape_text(&element.factory),
+            element.node_ids.len()
+        ))
+        .use_markup(true)
+        .xalign(0.0)
+        .build();
+    vbox.append(&title);
+    vbox.append(
+        &gtk::Label::builder()
+            .label(format!("Provided by the {}", element.provider()))
+            .xalign(0.0)
+            .build(),
+    );
+    vbox.append(
+        &gtk::Label::builder()
+            .label(element.installer_detail())
+            .selectable(true)
+            .xalign(0.0)
+            .css_classes(vec!["dim-label", "caption"])
+            .build(),
+    );
+
+    let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    let show_button = gtk::Button::with_label("Show");
+    show_button.set_tooltip_text(Some("Select the first node using the element"));
+    let node_id = element.node_ids.first().copied();
+    let app_weak = app.downgrade();
+    show_button.connect_clicked(move |_| {
+        let app = upgrade_weak!(app_weak);
+        if let Some(node_id) = node_id {
+            graphbook::current_graphtab(&app)
+                .graphview()
+                .reveal_node(node_id);
+        }
+    });
+    buttons.append(&show_button);
+    let replace_button = gtk::Button::with_label("Replace...");
+    replace_button.set_tooltip_text(Some(
+        "Replace the first node using the element by an available one",
+    ));
+    let app_weak = app.downgrade();
+    replace_button.connect_clicked(move |_| {
+        let app = upgrade_weak!(app_weak);
+        if let Some(node_id) = node_id {
+            GPSUI::substitute::display_replace_element(&app, node_id);
+        }
+    });
+    buttons.append(&replace_button);
+    let remove_button = gtk::Button::with_label("Remove nodes");
+    let node_ids = element.node_ids.clone();
+    let app_weak = app.downgrade();
+    remove_button.connect_clicked(glib::clone!(
+        #[weak]
+        listbox,
+        #[weak]
+        summary,
+        move |_| {
+            let app = upgrade_weak!(app_weak);
+            let graphview = graphbook::current_graphtab(&app).graphview().clone();
+            for node_id in &node_ids {
+                graphview.remove_node(*node_id);
+            }
+            fill_missing_list(&app, &listbox, &summary);
+        }
+    ));
+    buttons.append(&remove_button);
+    vbox.append(&buttons);
+
+    let row = gtk::ListBoxRow::builder().child(&vbox).build();
+    row.set_activatable(false);
+    row
+}
+
+fn fill_missing_list(app: &GPSApp, listbox: &gtk::ListBox, summary: &gtk::Label) {
+    while let Some(child) = listbox.first_child() {
+        listbox.remove(&child);
+    }
+    let missing = missing::missing_elements(&graphbook::current_graphtab(app).graphview());
+    if missing.is_empty() {
+        summary.set_label("All the elements of the graph are available");
+    } else {
+        summary.set_label(&format!(
+            "{} element(s) are not available, install, replace or remove them to launch the pipeline",
+            missing.len()
+        ));
+    }
+    for element in &missing {
+        listbox.append(&missing_element_row(app, listbox, summary, element));
+    }
+}
+
+/// Display the factories of the current graph which are not in the registry, with the
+/// plugin providing them and their installer detail.
+pub fn display_missing_elements(app: &GPSApp) {
+    let main_box = gtk::Box::new(gtk::Orientation::Vertical, 8);
+    let summary = gtk::Label::builder().xalign(0.0).wrap(true).build();
+    main_box.append(&summary);
+
+    let listbox = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(vec!["boxed-list"])
+        .build();
+    let scrolled = gtk::ScrolledWindow::builder()
+        .vexpand(true)
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .min_content_height(200)
+        .child(&listbox)
+        .build();
+    main_box.append(&scrolled);
+    fill_missing_list(app, &listbox, &summary);
+
+    let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    let rescan_button = gtk::Button::with_label("Rescan plugins");
+    rescan_button.set_tooltip_text(Some(
+        "Update the registry after installing plugins and clear the resolved placeholders",
+    ));
+    let app_weak = app.downgrade();
+    rescan_button.connect_clicked(glib::clone!(
+        #[weak]
+        listbox,
+        #[weak]
+        summary,
+        move |_| {
+            let app = upgrade_weak!(app_weak);
+            if let Err(e) = gst::Registry::update() {
+                GPS_ERROR!("Unable to update the registry: {}", e);
+            }
+            let graphview = graphbook::current_graphtab(&app).graphview().clone();
+            for node in graphview.all_nodes(GM::NodeType::All) {
+                if node.has_css_class("node-missing") && missing::factory_exists(&node.name()) {
+                    GPS_INFO!("The element {} is now available", node.name());
+                    mark_missing_node(&node, false);
+                    missing::record_node_plugin(&node);
+                }
+            }
+            fill_missing_list(&app, &listbox, &summary);
+        }
+    ));
+    buttons.append(&rescan_button);
+    let copy_button = gtk::Button::with_label("Copy report");
+    let app_weak = app.downgrade();
+    copy_button.connect_clicked(move |button| {
+        let app = upgrade_weak!(app_weak);
+        let missing = missing::missing_elements(&graphbook::current_graphtab(&app).graphview());
+        button.clipboard().set_text(&report_text(&missing));
+    });
+    buttons.append(&copy_button);
+    main_box.append(&buttons);
+
+    let dialog = GPSUI::dialog::create("Missing elements", app, &main_box, |_app, dialog| {
+        dialog.close();
+    });
+    dialog.set_default_size(600, 400);
+    dialog.present();
+}
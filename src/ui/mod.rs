@@ -7,13 +7,27 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 pub mod about;
+pub mod annotation;
+pub mod caps;
+pub mod capture;
 pub mod common;
+pub mod control;
 pub mod dialog;
 pub mod elements;
+pub mod interlink;
+pub mod latency;
+pub mod layout;
 pub mod logger;
 pub mod message;
+pub mod missing;
 pub mod models;
+pub mod playback;
 pub mod preferences;
+pub mod preset;
 pub mod properties;
+pub mod rank;
 pub mod resources;
 pub mod splash;
+pub mod substitute;
+pub mod validation;
+pub mod value;
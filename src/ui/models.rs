@@ -9,7 +9,7 @@
 use gtk::glib;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 // LogEntry GObject for logger displays
 mod imp_log_entry {
@@ -27,6 +27,12 @@ mod imp_log_entry {
         #[property(get, set)]
         file: RefCell<String>,
         #[property(get, set)]
+        object: RefCell<String>,
+        #[property(get, set)]
+        thread: RefCell<String>,
+        #[property(get, set)]
+        pid: Cell<u32>,
+        #[property(get, set)]
         log: RefCell<String>,
     }
 
@@ -58,6 +64,30 @@ impl LogEntry {
     pub fn new_simple(time: &str, level: &str, log: &str) -> Self {
         Self::new(time, level, "", "", log)
     }
+
+    /// Create an entry for a GStreamer debug message, `file` holds "file:line:function".
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_gst(
+        time: &str,
+        level: &str,
+        category: &str,
+        object: &str,
+        file: &str,
+        pid: u32,
+        thread: &str,
+        log: &str,
+    ) -> Self {
+        glib::Object::builder()
+            .property("time", time)
+            .property("level", level)
+            .property("category", category)
+            .property("object", object)
+            .property("file", file)
+            .property("pid", pid)
+            .property("thread", thread)
+            .property("log", log)
+            .build()
+    }
 }
 
 // ElementInfo GObject for element browser
@@ -0,0 +1,60 @@
+// playback.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::app::core::graphbook::GraphTab;
+use crate::app::GPSApp;
+use crate::logger;
+use crate::ui as GPSUI;
+use crate::GPS_INFO;
+use gtk::glib;
+use gtk::prelude::*;
+
+/// Display the list of graph tabs and let the user select the ones which share a
+/// common clock and base time when they are started.
+pub fn display_clock_sync_dialog(app: &GPSApp) {
+    let mut tabs: Vec<GraphTab> = app.graphbook.borrow().values().cloned().collect();
+    tabs.sort_by_key(|tab| tab.id());
+
+    let main_box = gtk::Box::new(gtk::Orientation::Vertical, 6);
+    let help = gtk::Label::builder()
+        .label("Pipelines sharing the clock use the same base time, the change applies the next time a pipeline is started.")
+        .halign(gtk::Align::Start)
+        .wrap(true)
+        .build();
+    main_box.append(&help);
+
+    let mut check_buttons = Vec::new();
+    for tab in tabs.iter() {
+        let check_button = gtk::CheckButton::builder()
+            .label(format!("{} ({})", tab.basename(), tab.player().state()))
+            .active(tab.clock_shared())
+            .build();
+        main_box.append(&check_button);
+        check_buttons.push((tab.id(), check_button));
+    }
+
+    let dialog = GPSUI::dialog::create(
+        "Clock synchronization",
+        app,
+        &main_box,
+        move |app, _dialog| {
+            for (tab_id, check_button) in check_buttons.iter() {
+                if let Some(tab) = app.graphbook.borrow().get(tab_id) {
+                    GPS_INFO!(
+                        "Tab {} shares the clock: {}",
+                        tab.basename(),
+                        check_button.is_active()
+                    );
+                    tab.set_clock_shared(check_button.is_active());
+                }
+            }
+        },
+    );
+    dialog.set_default_size(480, 320);
+    dialog.present();
+}
@@ -45,6 +45,14 @@ const LOG_LEVEL_MAX: f64 = 5.0;
 /// Step increment for log level spinner
 const LOG_LEVEL_STEP: f64 = 1.0;
 
+// Grid constants
+/// Minimum spacing of the graph grid
+const GRID_SPACING_MIN: f64 = 5.0;
+/// Maximum spacing of the graph grid
+const GRID_SPACING_MAX: f64 = 200.0;
+/// Step increment for grid spacing spinner
+const GRID_SPACING_STEP: f64 = 5.0;
+
 // Search constants
 /// Debounce delay in milliseconds for search filtering
 const SEARCH_DEBOUNCE_MS: u32 = 300;
@@ -66,6 +74,8 @@ const STR_TAB_GENERAL: &str = "General";
 const STR_TAB_LOGGING: &str = "Logging";
 /// Header for the Appearance category
 const STR_CATEGORY_APPEARANCE: &str = "Appearance";
+/// Header for the Graph Editing category
+const STR_CATEGORY_GRAPH_EDITING: &str = "Graph Editing";
 /// Header for the Video Rendering category
 const STR_CATEGORY_VIDEO: &str = "Video Rendering";
 /// Header for the Startup category
@@ -78,6 +88,14 @@ const STR_CATEGORY_GST_LOGGING: &str = "GStreamer Logging";
 const STR_PREF_DARK_THEME: &str = "Dark Theme";
 /// Tooltip for dark theme preference
 const STR_TOOLTIP_DARK_THEME: &str = "Enable dark theme for the graph view with gradient styling";
+/// Label for snap to grid preference
+const STR_PREF_SNAP_TO_GRID: &str = "Snap Nodes to Grid";
+/// Tooltip for snap to grid preference
+const STR_TOOLTIP_SNAP_TO_GRID: &str = "Align the dragged nodes on a grid";
+/// Label for grid spacing preference
+const STR_PREF_GRID_SPACING: &str = "Grid Spacing";
+/// Description for grid spacing preference
+const STR_DESC_GRID_SPACING: &str = "Distance between two grid lines, in pixels at 100% zoom";
 /// Label for GTK4 sink preference
 const STR_PREF_GTK4_SINK: &str = "Use GTK4 Paintable Sink";
 /// Tooltip for GTK4 sink preference
@@ -98,6 +116,12 @@ const STR_DESC_GST_LOG_LEVEL: &str =
     "GST_DEBUG environment variable format (e.g., *:WARNING,element:DEBUG)";
 /// Placeholder for GStreamer log level entry
 const STR_PLACEHOLDER_GST_LOG: &str = "e.g., *:WARNING,GST_ELEMENT:DEBUG";
+/// Label for the GStreamer debug categories button
+const STR_PREF_GST_CATEGORIES: &str = "GStreamer Debug Categories";
+/// Description for the GStreamer debug categories button
+const STR_DESC_GST_CATEGORIES: &str = "Edit the threshold of each registered debug category";
+/// Label of the button opening the debug categories dialog
+const STR_BUTTON_GST_CATEGORIES: &str = "Edit categories...";
 /// Title for the preferences dialog
 const STR_DIALOG_TITLE: &str = "Preferences";
 
@@ -320,6 +344,51 @@ pub fn display_settings(app: &GPSApp) {
 
     general_box.append(&appearance_category);
 
+    // Graph Editing Category
+    let (graph_category, graph_listbox) = create_settings_category(STR_CATEGORY_GRAPH_EDITING);
+
+    let snap_to_grid_switch = gtk::CheckButton::new();
+    snap_to_grid_switch.set_active(settings::Settings::grid_snap().is_some());
+    let grid_spacing_spin =
+        gtk::SpinButton::with_range(GRID_SPACING_MIN, GRID_SPACING_MAX, GRID_SPACING_STEP);
+    grid_spacing_spin.set_value(settings::Settings::grid_spacing());
+
+    // Apply the grid to all open graph views
+    let app_weak = app.downgrade();
+    let apply_grid_snap = Rc::new(move || {
+        if let Some(app) = app_weak.upgrade() {
+            let grid_snap = settings::Settings::grid_snap();
+            for graph_tab in app.graphbook.borrow().values() {
+                graph_tab.graphview().set_grid_snap(grid_snap);
+            }
+        }
+    });
+    snap_to_grid_switch.connect_toggled(glib::clone!(
+        #[strong]
+        apply_grid_snap,
+        move |c| {
+            settings::Settings::set_snap_to_grid(c.is_active());
+            apply_grid_snap();
+        }
+    ));
+    grid_spacing_spin.connect_value_changed(move |c| {
+        settings::Settings::set_grid_spacing(c.value());
+        apply_grid_snap();
+    });
+
+    graph_listbox.append(&create_checkbox_preference_row(
+        STR_PREF_SNAP_TO_GRID,
+        &snap_to_grid_switch,
+        Some(STR_TOOLTIP_SNAP_TO_GRID),
+    ));
+    graph_listbox.append(&create_preference_row(
+        STR_PREF_GRID_SPACING,
+        &grid_spacing_spin.upcast::<gtk::Widget>(),
+        Some(STR_DESC_GRID_SPACING),
+    ));
+
+    general_box.append(&graph_category);
+
     // Video Rendering Category
     let (video_category, video_listbox) = create_settings_category(STR_CATEGORY_VIDEO);
 
@@ -426,6 +495,19 @@ pub fn display_settings(app: &GPSApp) {
     );
     gst_log_listbox.append(&gst_log_row);
 
+    let gst_categories_button = gtk::Button::with_label(STR_BUTTON_GST_CATEGORIES);
+    let app_weak = app.downgrade();
+    gst_categories_button.connect_clicked(move |_| {
+        let app = upgrade_weak!(app_weak);
+        GPSUI::logger::display_gst_debug_categories(&app);
+    });
+    let gst_categories_row = create_preference_row(
+        STR_PREF_GST_CATEGORIES,
+        &gst_categories_button.upcast::<gtk::Widget>(),
+        Some(STR_DESC_GST_CATEGORIES),
+    );
+    gst_log_listbox.append(&gst_categories_row);
+
     logging_box.append(&gst_log_category);
     logging_scrolled.set_child(Some(&logging_box));
 
@@ -0,0 +1,235 @@
+// preset.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::app::GPSApp;
+use crate::gps::preset::{self, PresetProperties, PresetSource, StudioPresets};
+use crate::graphmanager::PropertyExt;
+use crate::logger;
+use crate::ui as GPSUI;
+use crate::{GPS_ERROR, GPS_INFO};
+use gtk::glib;
+use gtk::prelude::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+type Presets = Rc<RefCell<Vec<(PresetSource, String)>>>;
+
+fn preset_label(source: PresetSource, name: &str) -> String {
+    match source {
+        PresetSource::Studio => name.to_string(),
+        PresetSource::Element => format!("{name} (element)"),
+    }
+}
+
+fn fill_presets(model: &gtk::StringList, presets: &Presets, element_name: &str) {
+    *presets.borrow_mut() = preset::preset_names(element_name);
+    model.splice(
+        0,
+        model.n_items(),
+        &presets
+            .borrow()
+            .iter()
+            .map(|(source, name)| preset_label(*source, name))
+            .collect::<Vec<_>>()
+            .iter()
+            .map(|label| label.as_str())
+            .collect::<Vec<_>>(),
+    );
+}
+
+fn selected_preset(dropdown: &gtk::DropDown, presets: &Presets) -> Option<(PresetSource, String)> {
+    presets.borrow().get(dropdown.selected() as usize).cloned()
+}
+
+/// Properties of the node with the pending changes of the properties dialog.
+fn node_properties(
+    app: &GPSApp,
+    node_id: u32,
+    pending: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let node = app.node(node_id);
+    let mut properties: HashMap<String, String> = node
+        .properties()
+        .iter()
+        .filter(|(name, _)| !node.hidden_property(name))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    properties.extend(pending.iter().map(|(n, v)| (n.clone(), v.clone())));
+    properties
+}
+
+fn save_preset(
+    element_name: &str,
+    name: &str,
+    studio: bool,
+    properties: &HashMap<String, String>,
+) -> anyhow::Result<()> {
+    if studio {
+        let mut presets = StudioPresets::load();
+        presets.set_preset(
+            element_name,
+            name,
+            properties
+                .iter()
+                .map(|(n, v)| (n.clone(), v.clone()))
+                .collect::<PresetProperties>(),
+        );
+        presets.save()
+    } else {
+        preset::save_element_preset(element_name, name, properties)
+    }
+}
+
+fn delete_preset(element_name: &str, source: PresetSource, name: &str) -> anyhow::Result<()> {
+    match source {
+        PresetSource::Studio => {
+            let mut presets = StudioPresets::load();
+            if !presets.remove_preset(element_name, name) {
+                anyhow::bail!("No studio preset {}", name);
+            }
+            presets.save()
+        }
+        PresetSource::Element => preset::delete_element_preset(element_name, name),
+    }
+}
+
+/// Bar of the properties dialog to load a preset into the node, save the properties of
+/// the node as a preset and delete a preset. Once the properties of a preset are set on
+/// the node, the dialog holding the bar is closed and `on_loaded` is called.
+pub fn preset_bar<F>(
+    app: &GPSApp,
+    element_name: &str,
+    node_id: u32,
+    pending: &Rc<RefCell<HashMap<String, String>>>,
+    on_loaded: F,
+) -> gtk::Box
+where
+    F: Fn(&GPSApp) + 'static,
+{
+    let supports_preset = preset::supports_preset(element_name);
+    let presets: Presets = Rc::new(RefCell::new(vec![]));
+    let model = gtk::StringList::new(&[]);
+    fill_presets(&model, &presets, element_name);
+
+    let bar = gtk::Box::builder()
+        .spacing(8)
+        .margin_start(12)
+        .margin_end(12)
+        .margin_bottom(8)
+        .build();
+    bar.append(&gtk::Label::new(Some("Preset")));
+    let dropdown = gtk::DropDown::builder().model(&model).hexpand(true).build();
+    bar.append(&dropdown);
+
+    let load_button = gtk::Button::with_label("Load");
+    load_button.set_tooltip_text(Some("Set the properties of the preset on the node"));
+    let app_weak = app.downgrade();
+    load_button.connect_clicked(glib::clone!(
+        #[strong]
+        presets,
+        #[weak]
+        dropdown,
+        #[to_owned]
+        element_name,
+        move |button| {
+            let app = upgrade_weak!(app_weak);
+            let Some((_, name)) = selected_preset(&dropdown, &presets) else {
+                return;
+            };
+            match preset::preset_properties(&element_name, &name) {
+                Ok(properties) => {
+                    GPS_INFO!("Load the preset {} of {}", name, element_name);
+                    app.apply_element_preset(node_id, &properties);
+                    if let Some(dialog) = button.root().and_downcast::<gtk::Window>() {
+                        dialog.close();
+                    }
+                    on_loaded(&app);
+                }
+                Err(e) => GPS_ERROR!("Unable to load the preset {}: {}", name, e),
+            }
+        }
+    ));
+    bar.append(&load_button);
+
+    let studio_check = gtk::CheckButton::with_label("Studio");
+    studio_check.set_tooltip_text(Some(
+        "Save in the studio presets instead of the GstPreset presets of the element",
+    ));
+    studio_check.set_active(true);
+    studio_check.set_sensitive(supports_preset);
+    let save_button = gtk::Button::with_label("Save as...");
+    let app_weak = app.downgrade();
+    save_button.connect_clicked(glib::clone!(
+        #[strong]
+        presets,
+        #[strong]
+        pending,
+        #[weak]
+        model,
+        #[weak]
+        studio_check,
+        #[to_owned]
+        element_name,
+        move |_| {
+            let app = upgrade_weak!(app_weak);
+            let properties = node_properties(&app, node_id, &pending.borrow());
+            let studio = studio_check.is_active();
+            GPSUI::dialog::get_input(
+                &app,
+                "Save preset",
+                "Preset name",
+                "",
+                glib::clone!(
+                    #[strong]
+                    presets,
+                    #[weak]
+                    model,
+                    #[to_owned]
+                    element_name,
+                    move |_app, name| {
+                        let name = name.trim();
+                        if name.is_empty() {
+                            return;
+                        }
+                        match save_preset(&element_name, name, studio, &properties) {
+                            Ok(()) => fill_presets(&model, &presets, &element_name),
+                            Err(e) => GPS_ERROR!("Unable to save the preset {}: {}", name, e),
+                        }
+                    }
+                ),
+            );
+        }
+    ));
+    bar.append(&save_button);
+    bar.append(&studio_check);
+
+    let delete_button = gtk::Button::from_icon_name("user-trash-symbolic");
+    delete_button.set_tooltip_text(Some("Delete the preset"));
+    delete_button.connect_clicked(glib::clone!(
+        #[strong]
+        presets,
+        #[weak]
+        dropdown,
+        #[weak]
+        model,
+        #[to_owned]
+        element_name,
+        move |_| {
+            let Some((source, name)) = selected_preset(&dropdown, &presets) else {
+                return;
+            };
+            match delete_preset(&element_name, source, &name) {
+                Ok(()) => fill_presets(&model, &presets, &element_name),
+                Err(e) => GPS_ERROR!("Unable to delete the preset {}: {}", name, e),
+            }
+        }
+    ));
+    bar.append(&delete_button);
+    bar
+}
@@ -10,9 +10,10 @@ use crate::app::core::graphbook;
 use crate::app::GPSApp;
 use crate::common;
 use crate::gps as GPS;
+use crate::graphmanager as GM;
 use crate::logger;
 use crate::ui as GPSUI;
-use crate::{GPS_INFO, GPS_TRACE};
+use crate::{GPS_ERROR, GPS_INFO, GPS_TRACE};
 use gtk::glib;
 use gtk::prelude::*;
 
@@ -111,31 +112,70 @@ fn filter_pad_property_rows(listbox: &gtk::ListBox, search_text: &str) {
     }
 }
 
-/// Helper function to populate an entry widget with the current property value.
+/// Current value of a property of a node.
 /// Checks multiple sources in order: stored element property, live element property, default value.
-fn populate_entry_from_property(
-    entry: &gtk::Entry,
+pub fn current_property_value(
     app: &GPSApp,
     node_id: u32,
     element_name: &str,
     property_name: &str,
     param: &glib::ParamSpec,
-) {
+) -> Option<String> {
     if let Some(value) = app.element_property(node_id, property_name) {
-        entry.set_text(&value);
+        Some(value)
     } else if (param.flags() & glib::ParamFlags::READABLE) == glib::ParamFlags::READABLE
         || (param.flags() & glib::ParamFlags::READWRITE) == glib::ParamFlags::READWRITE
     {
-        if let Ok(value) =
-            GPS::ElementInfo::element_property_by_feature_name(element_name, param.name())
-        {
-            entry.set_text(&value);
-        }
-    } else if let Some(value) = common::value_as_str(param.default_value()) {
+        GPS::ElementInfo::element_property_by_feature_name(element_name, param.name()).ok()
+    } else {
+        common::value_as_str(param.default_value())
+    }
+}
+
+/// Helper function to populate an entry widget with the current property value.
+pub fn populate_entry_from_property(
+    entry: &gtk::Entry,
+    app: &GPSApp,
+    node_id: u32,
+    element_name: &str,
+    property_name: &str,
+    param: &glib::ParamSpec,
+) {
+    if let Some(value) = current_property_value(app, node_id, element_name, property_name, param) {
         entry.set_text(&value);
     }
 }
 
+/// Check the value of an entry editing a property, see `show_entry_check`.
+pub fn check_entry(entry: &gtk::Entry, element_name: &str, param: &glib::ParamSpec) -> bool {
+    show_entry_check(
+        entry,
+        param,
+        &GPS::validation::check_property(element_name, param, &entry.text()),
+    )
+}
+
+/// Flag an entry editing a property and give the reason in its tooltip if its value is
+/// invalid, the range of the property otherwise.
+pub fn show_entry_check(
+    entry: &gtk::Entry,
+    param: &glib::ParamSpec,
+    check: &anyhow::Result<()>,
+) -> bool {
+    match check {
+        Ok(()) => {
+            entry.remove_css_class("error");
+            entry.set_tooltip_text(GPS::value::value_hint(param).as_deref());
+            true
+        }
+        Err(e) => {
+            entry.add_css_class("error");
+            entry.set_tooltip_text(Some(&e.to_string()));
+            false
+        }
+    }
+}
+
 /// Check if an element handles file URIs (either as source or sink).
 fn element_is_file_handler(element_name: &str) -> bool {
     GPS::ElementInfo::element_is_uri_src_handler(element_name)
@@ -144,7 +184,9 @@ fn element_is_file_handler(element_name: &str) -> bool {
             .is_some_and(|(_, supports_file)| supports_file)
 }
 
-pub fn property_to_widget<F: Fn(String, String) + 'static>(
+/// Widget editing a property. `f` is called with the name and the value of the property
+/// when it is changed, and returns whether the value is valid.
+pub fn property_to_widget<F: Fn(String, String) -> anyhow::Result<()> + 'static>(
     app: &GPSApp,
     node_id: u32,
     element_name: &str,
@@ -152,6 +194,16 @@ pub fn property_to_widget<F: Fn(String, String) + 'static>(
     param: &glib::ParamSpec,
     f: F,
 ) -> Option<gtk::Widget> {
+    if GPS::value::property_kind(param) != GPS::value::PropertyKind::Other {
+        return GPSUI::value::typed_property_widget(
+            app,
+            node_id,
+            element_name,
+            property_name,
+            param,
+            f,
+        );
+    }
     match param.type_() {
         _t if param.type_() == glib::ParamSpecBoolean::static_type() => {
             let check_button = gtk::CheckButton::new();
@@ -171,7 +223,7 @@ pub fn property_to_widget<F: Fn(String, String) + 'static>(
                 check_button.set_active(value.parse::<bool>().unwrap_or(false));
             }
             check_button.connect_toggled(glib::clone!(move |c| {
-                f(c.widget_name().to_string(), c.is_active().to_string());
+                let _ = f(c.widget_name().to_string(), c.is_active().to_string());
             }));
             Some(check_button.upcast::<gtk::Widget>())
         }
@@ -182,6 +234,7 @@ pub fn property_to_widget<F: Fn(String, String) + 'static>(
             glib::ParamSpecUInt64::static_type(),
             glib::ParamSpecString::static_type(),
             glib::ParamSpecFloat::static_type(),
+            glib::ParamSpecDouble::static_type(),
         ]
         .contains(&t) =>
         {
@@ -217,9 +270,15 @@ pub fn property_to_widget<F: Fn(String, String) + 'static>(
                 let f = std::rc::Rc::new(f);
                 let f_for_entry = f.clone();
 
-                entry.connect_changed(glib::clone!(move |e| {
-                    f_for_entry(e.widget_name().to_string(), e.text().to_string())
-                }));
+                check_entry(&entry, element_name, param);
+                entry.connect_changed(glib::clone!(
+                    #[to_owned]
+                    param,
+                    move |e| {
+                        let check = f_for_entry(e.widget_name().to_string(), e.text().to_string());
+                        show_entry_check(e, &param, &check);
+                    }
+                ));
 
                 let app_weak = app.downgrade();
                 browse_button.connect_clicked(glib::clone!(
@@ -244,50 +303,43 @@ pub fn property_to_widget<F: Fn(String, String) + 'static>(
             GPS_TRACE!("Add Edit property : {}", entry.widget_name());
             populate_entry_from_property(&entry, app, node_id, element_name, property_name, param);
 
-            entry.connect_changed(glib::clone!(move |e| {
-                f(e.widget_name().to_string(), e.text().to_string())
-            }));
+            check_entry(&entry, element_name, param);
+            entry.connect_changed(glib::clone!(
+                #[to_owned]
+                param,
+                move |e| {
+                    let check = f(e.widget_name().to_string(), e.text().to_string());
+                    show_entry_check(e, &param, &check);
+                }
+            ));
             Some(entry.upcast::<gtk::Widget>())
         }
-        t if [
-            glib::ParamSpecEnum::static_type(),
-            glib::ParamSpecFlags::static_type(),
-        ]
-        .contains(&t) =>
-        {
+        t if t == glib::ParamSpecFlags::static_type() => Some(GPSUI::value::flags_widget(
+            app,
+            node_id,
+            element_name,
+            property_name,
+            param,
+            f,
+        )),
+        t if t == glib::ParamSpecEnum::static_type() => {
             let string_list = gtk::StringList::new(&[]);
 
             // Add an empty entry to be able to reset the value
             string_list.append("");
 
-            if t.is_a(glib::ParamSpecEnum::static_type()) {
-                let param = param
-                    .clone()
-                    .downcast::<glib::ParamSpecEnum>()
-                    .expect("Should be a ParamSpecEnum");
-                let enums = param.enum_class();
-                for value in enums.values() {
-                    string_list.append(&format!(
-                        "{}:{}:{}",
-                        value.value(),
-                        value.nick(),
-                        value.name()
-                    ));
-                }
-            } else if t.is_a(glib::ParamSpecFlags::static_type()) {
-                let param = param
-                    .clone()
-                    .downcast::<glib::ParamSpecFlags>()
-                    .expect("Should be a ParamSpecFlags");
-                let flags = param.flags_class();
-                for value in flags.values() {
-                    string_list.append(&format!(
-                        "{}:{}:{}",
-                        value.value(),
-                        value.nick(),
-                        value.name()
-                    ));
-                }
+            let param = param
+                .clone()
+                .downcast::<glib::ParamSpecEnum>()
+                .expect("Should be a ParamSpecEnum");
+            let enums = param.enum_class();
+            for value in enums.values() {
+                string_list.append(&format!(
+                    "{}:{}:{}",
+                    value.value(),
+                    value.nick(),
+                    value.name()
+                ));
             }
 
             let dropdown =
@@ -295,17 +347,25 @@ pub fn property_to_widget<F: Fn(String, String) + 'static>(
             dropdown.set_widget_name(property_name);
             GPS_TRACE!("add DropDown property : {}", dropdown.widget_name());
 
-            if let Some(value) = app.element_property(node_id, property_name) {
-                //Retrieve the first value (index) from the property
-                dropdown.set_selected(value.parse::<u32>().unwrap_or(0) + 1);
-            } else if (param.flags() & glib::ParamFlags::READABLE) == glib::ParamFlags::READABLE
-                || (param.flags() & glib::ParamFlags::READWRITE) == glib::ParamFlags::READWRITE
-            {
-                if let Ok(value) =
-                    GPS::ElementInfo::element_property_by_feature_name(element_name, param.name())
-                {
-                    dropdown.set_selected(value.parse::<u32>().unwrap_or(0) + 1);
-                }
+            // The values of an enum are not always contiguous, look up the value by its
+            // number, nick or name
+            let value = app.element_property(node_id, property_name).or_else(|| {
+                ((param.flags() & glib::ParamFlags::READABLE) == glib::ParamFlags::READABLE)
+                    .then(|| {
+                        GPS::ElementInfo::element_property_by_feature_name(
+                            element_name,
+                            param.name(),
+                        )
+                        .ok()
+                    })
+                    .flatten()
+            });
+            if let Some(position) = value.and_then(|value| {
+                enums.values().iter().position(|v| {
+                    v.value().to_string() == value || v.nick() == value || v.name() == value
+                })
+            }) {
+                dropdown.set_selected(position as u32 + 1);
             }
 
             dropdown.connect_selected_notify(move |d| {
@@ -314,7 +374,7 @@ pub fn property_to_widget<F: Fn(String, String) + 'static>(
                         let text = string_object.string();
                         let value = text.to_string();
                         let value = value.split_once(':');
-                        f(
+                        let _ = f(
                             d.widget_name().to_string(),
                             value.unwrap_or_default().0.to_string(),
                         );
@@ -341,6 +401,7 @@ fn create_property_category_ui(
     node_id: u32,
     element_name: &str,
     update_properties: &Rc<RefCell<HashMap<String, String>>>,
+    invalid_properties: &Rc<RefCell<HashMap<String, String>>>,
 ) -> gtk::Box {
     let category_box = gtk::Box::new(gtk::Orientation::Vertical, 8);
     category_box.set_margin_start(12);
@@ -363,7 +424,7 @@ fn create_property_category_ui(
             .css_classes(vec!["boxed-list"])
             .build();
 
-        for (name, param) in properties {
+        for &(name, param) in properties {
             let widget = property_to_widget(
                 app,
                 node_id,
@@ -373,9 +434,26 @@ fn create_property_category_ui(
                 glib::clone!(
                     #[strong]
                     update_properties,
+                    #[strong]
+                    invalid_properties,
+                    #[to_owned]
+                    element_name,
+                    #[to_owned]
+                    param,
                     move |name, value| {
                         GPS_INFO!("property changed: {}:{}", name, value);
-                        update_properties.borrow_mut().insert(name, value);
+                        // Invalid values are kept out of the changes until they are fixed
+                        let check = GPS::validation::check_property(&element_name, &param, &value);
+                        match &check {
+                            Ok(()) => {
+                                invalid_properties.borrow_mut().remove(&name);
+                                update_properties.borrow_mut().insert(name, value);
+                            }
+                            Err(e) => {
+                                invalid_properties.borrow_mut().insert(name, e.to_string());
+                            }
+                        }
+                        check
                     }
                 ),
             );
@@ -436,6 +514,9 @@ fn categorize_properties(properties: &HashMap<String, glib::ParamSpec>) -> Prope
 pub fn display_plugin_properties(app: &GPSApp, element_name: &str, node_id: u32) {
     let update_properties: Rc<RefCell<HashMap<String, String>>> =
         Rc::new(RefCell::new(HashMap::new()));
+    // Properties holding an invalid value, with the reason
+    let invalid_properties: Rc<RefCell<HashMap<String, String>>> =
+        Rc::new(RefCell::new(HashMap::new()));
     let properties = GPS::ElementInfo::element_properties_by_feature_name(element_name).unwrap();
 
     // Main container
@@ -452,6 +533,19 @@ pub fn display_plugin_properties(app: &GPSApp, element_name: &str, node_id: u32)
 
     main_box.append(&search_entry);
 
+    // Load the properties of a preset then show them
+    main_box.append(&GPSUI::preset::preset_bar(
+        app,
+        element_name,
+        node_id,
+        &update_properties,
+        glib::clone!(
+            #[to_owned]
+            element_name,
+            move |app| display_plugin_properties(app, &element_name, node_id)
+        ),
+    ));
+
     // Categorize properties
     let (basic_props, advanced_props) = categorize_properties(&properties);
 
@@ -484,6 +578,7 @@ pub fn display_plugin_properties(app: &GPSApp, element_name: &str, node_id: u32)
             node_id,
             element_name,
             &update_properties,
+            &invalid_properties,
         ));
     }
 
@@ -497,6 +592,7 @@ pub fn display_plugin_properties(app: &GPSApp, element_name: &str, node_id: u32)
             node_id,
             element_name,
             &update_properties,
+            &invalid_properties,
         ));
     }
 
@@ -520,6 +616,7 @@ pub fn display_plugin_properties(app: &GPSApp, element_name: &str, node_id: u32)
             node_id,
             element_name,
             &update_properties,
+            &invalid_properties,
         ));
     } else {
         let empty_label = gtk::Label::builder()
@@ -549,6 +646,7 @@ pub fn display_plugin_properties(app: &GPSApp, element_name: &str, node_id: u32)
             node_id,
             element_name,
             &update_properties,
+            &invalid_properties,
         ));
     }
     advanced_scrolled.set_child(Some(&advanced_box));
@@ -587,6 +685,19 @@ pub fn display_plugin_properties(app: &GPSApp, element_name: &str, node_id: u32)
             #[strong]
             update_properties,
             move |app, _dialog| {
+                let mut invalid: Vec<String> = invalid_properties
+                    .borrow()
+                    .iter()
+                    .map(|(name, error)| format!("{name}: {error}"))
+                    .collect();
+                if !invalid.is_empty() {
+                    invalid.sort();
+                    GPSUI::message::display_error_dialog(
+                        false,
+                        &format!("The properties can not be applied:\n{}", invalid.join("\n")),
+                    );
+                    return;
+                }
                 app.update_element_properties(node_id, &update_properties.borrow());
             }
         ),
@@ -976,6 +1087,250 @@ pub fn display_pad_properties(
     dialog.present();
 }
 
+/// Row of the parameters of a macro node, with an entry for its value.
+fn macro_parameter_row(
+    name: &str,
+    target: &str,
+    value: &str,
+    update_properties: &Rc<RefCell<HashMap<String, String>>>,
+) -> (gtk::ListBoxRow, gtk::Box) {
+    let row_box = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+    row_box.set_margin_start(12);
+    row_box.set_margin_end(12);
+    row_box.set_margin_top(8);
+    row_box.set_margin_bottom(8);
+
+    let label_box = gtk::Box::new(gtk::Orientation::Vertical, 2);
+    label_box.set_hexpand(true);
+    label_box.append(
+        &gtk::Label::builder()
+            .label(name)
+            .halign(gtk::Align::Start)
+            .xalign(0.0)
+            .build(),
+    );
+    label_box.append(
+        &gtk::Label::builder()
+            .label(format!("→ {target}"))
+            .halign(gtk::Align::Start)
+            .xalign(0.0)
+            .css_classes(vec!["dim-label"])
+            .build(),
+    );
+    row_box.append(&label_box);
+
+    let entry = gtk::Entry::new();
+    entry.set_text(value);
+    let name = name.to_string();
+    entry.connect_changed(glib::clone!(
+        #[strong]
+        update_properties,
+        move |entry| {
+            update_properties
+                .borrow_mut()
+                .insert(name.clone(), entry.text().to_string());
+        }
+    ));
+    row_box.append(&entry);
+
+    let list_row = gtk::ListBoxRow::new();
+    list_row.set_child(Some(&row_box));
+    list_row.set_activatable(false);
+    (list_row, row_box)
+}
+
+/// Edit the parameters of a macro node: their values, and the inner properties they are
+/// forwarded to.
+pub fn display_macro_properties(app: &GPSApp, node_id: u32) {
+    let node = app.node(node_id);
+    let Some(template) = GM::GraphTemplate::from_macro_node(&node) else {
+        GPS_ERROR!("The node {} is not a macro", node.unique_name());
+        return;
+    };
+    let update_properties: Rc<RefCell<HashMap<String, String>>> =
+        Rc::new(RefCell::new(HashMap::new()));
+    let deleted_parameters: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(vec![]));
+    let added_parameters: Rc<RefCell<Vec<(String, u32, String)>>> = Rc::new(RefCell::new(vec![]));
+
+    let content_box = gtk::Box::new(gtk::Orientation::Vertical, 8);
+    content_box.set_margin_start(12);
+    content_box.set_margin_end(12);
+    content_box.set_margin_top(12);
+    content_box.set_margin_bottom(12);
+
+    let elements: Vec<String> = template
+        .nodes
+        .iter()
+        .map(|template_node| template_node.name.clone())
+        .collect();
+    content_box.append(
+        &gtk::Label::builder()
+            .label(elements.join(" → "))
+            .halign(gtk::Align::Start)
+            .wrap(true)
+            .css_classes(vec!["dim-label"])
+            .build(),
+    );
+    content_box.append(
+        &gtk::Label::builder()
+            .label("Parameters")
+            .halign(gtk::Align::Start)
+            .css_classes(vec!["heading"])
+            .build(),
+    );
+
+    let listbox = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(vec!["boxed-list"])
+        .build();
+    // Inner node and property a parameter is forwarded to, as `element.property`
+    let target_name = |template_node_id: u32, property: &str| {
+        template
+            .node(template_node_id)
+            .map(|template_node| format!("{}{}.{}", template_node.name, template_node.id, property))
+            .unwrap_or_else(|| property.to_string())
+    };
+    for parameter in &template.parameters {
+        let value = app
+            .element_property(node_id, &parameter.name)
+            .unwrap_or_default();
+        let (list_row, row_box) = macro_parameter_row(
+            &parameter.name,
+            &target_name(parameter.node, &parameter.property),
+            &value,
+            &update_properties,
+        );
+        let remove_button = gtk::Button::builder()
+            .icon_name("user-trash-symbolic")
+            .tooltip_text("Remove parameter")
+            .build();
+        let name = parameter.name.clone();
+        remove_button.connect_clicked(glib::clone!(
+            #[weak]
+            list_row,
+            #[weak]
+            listbox,
+            #[strong]
+            update_properties,
+            #[strong]
+            deleted_parameters,
+            move |_| {
+                update_properties.borrow_mut().remove(&name);
+                deleted_parameters.borrow_mut().push(name.clone());
+                listbox.remove(&list_row);
+            }
+        ));
+        row_box.append(&remove_button);
+        listbox.append(&list_row);
+    }
+    content_box.append(&listbox);
+
+    // New parameter forwarded to a property of an inner element
+    let targets: Vec<(u32, String)> = template
+        .nodes
+        .iter()
+        .flat_map(|template_node| {
+            let mut properties: Vec<String> =
+                GPS::ElementInfo::element_properties_by_feature_name(&template_node.name)
+                    .map(|params| params.into_keys().collect())
+                    .unwrap_or_default();
+            properties.sort();
+            properties
+                .into_iter()
+                .filter(|property| property != "name" && property != "parent")
+                .map(|property| (template_node.id, property))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    let target_names: Vec<String> = targets
+        .iter()
+        .map(|(template_node_id, property)| target_name(*template_node_id, property))
+        .collect();
+    let target_names: Vec<&str> = target_names.iter().map(|name| name.as_str()).collect();
+
+    content_box.append(
+        &gtk::Label::builder()
+            .label("Add New Parameter")
+            .halign(gtk::Align::Start)
+            .margin_top(12)
+            .css_classes(vec!["heading"])
+            .build(),
+    );
+    let new_row_box = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    let parameter_name = gtk::Entry::builder()
+        .placeholder_text("Parameter name")
+        .hexpand(true)
+        .build();
+    new_row_box.append(&parameter_name);
+    let target_dropdown = gtk::DropDown::from_strings(&target_names);
+    target_dropdown.set_enable_search(true);
+    new_row_box.append(&target_dropdown);
+    let add_button = gtk::Button::with_label("Add Parameter");
+    new_row_box.append(&add_button);
+    content_box.append(&new_row_box);
+
+    add_button.connect_clicked(glib::clone!(
+        #[weak]
+        parameter_name,
+        #[weak]
+        target_dropdown,
+        #[weak]
+        listbox,
+        #[strong]
+        update_properties,
+        #[strong]
+        added_parameters,
+        move |_| {
+            let name = parameter_name.text().trim().to_string();
+            let Some((template_node_id, property)) =
+                targets.get(target_dropdown.selected() as usize).cloned()
+            else {
+                return;
+            };
+            if name.is_empty() || added_parameters.borrow().iter().any(|p| p.0 == name) {
+                return;
+            }
+            let target = target_dropdown
+                .selected_item()
+                .and_downcast::<gtk::StringObject>()
+                .map(|item| item.string().to_string())
+                .unwrap_or_default();
+            let (list_row, _) = macro_parameter_row(&name, &target, "", &update_properties);
+            listbox.append(&list_row);
+            added_parameters
+                .borrow_mut()
+                .push((name, template_node_id, property));
+            parameter_name.set_text("");
+        }
+    ));
+
+    let grid = gtk::Grid::new();
+    grid.attach(&content_box, 0, 0, 1, 1);
+
+    let dialog = GPSUI::dialog::create(
+        &format!("{} parameters", node.name()),
+        app,
+        &grid,
+        move |app, _dialog| {
+            // The dialog stays open after Apply, each change is applied once
+            for name in deleted_parameters.take() {
+                if let Err(e) = app.remove_macro_parameter(node_id, &name) {
+                    GPS_ERROR!("Unable to remove the parameter {}: {}", name, e);
+                }
+            }
+            for (name, template_node_id, property) in added_parameters.take() {
+                if let Err(e) = app.add_macro_parameter(node_id, &name, template_node_id, &property)
+                {
+                    GPS_ERROR!("Unable to add the parameter {}: {}", name, e);
+                }
+            }
+            app.update_element_properties(node_id, &update_properties.borrow());
+        },
+    );
+    dialog.set_default_size(550, 450);
+    dialog.present();
+}
+
 pub fn display_pipeline_details(app: &GPSApp) {
     let grid = gtk::Grid::new();
     grid.set_column_spacing(4);
@@ -0,0 +1,386 @@
+// rank.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::app::settings::Settings;
+use crate::app::GPSApp;
+use crate::gps::rank::{self, FeatureRank};
+use crate::logger;
+use crate::ui as GPSUI;
+use crate::{GPS_INFO, GPS_WARN};
+use gtk::glib;
+use gtk::prelude::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+type RankProfiles = Rc<RefCell<HashMap<String, Vec<FeatureRank>>>>;
+
+fn rank_row(
+    listbox: &gtk::ListBox,
+    profiles: &RankProfiles,
+    profile: &str,
+    feature_rank: &FeatureRank,
+) -> gtk::ListBoxRow {
+    let hbox = gtk::Box::builder()
+        .spacing(8)
+        .margin_start(8)
+        .margin_end(8)
+        .margin_top(2)
+        .margin_bottom(2)
+        .build();
+    let label = gtk::Label::builder()
+        .label(&feature_rank.feature)
+        .hexpand(true)
+        .xalign(0.0)
+        .build();
+    hbox.append(&label);
+
+    let entry = gtk::Entry::builder()
+        .text(rank::rank_name(feature_rank.rank))
+        .width_chars(10)
+        .tooltip_text("NONE, MARGINAL, SECONDARY, PRIMARY, MAX or a number")
+        .build();
+    let feature = feature_rank.feature.clone();
+    entry.connect_changed(glib::clone!(
+        #[strong]
+        profiles,
+        #[strong]
+        feature,
+        #[to_owned]
+        profile,
+        move |entry| match rank::rank_from_str(&entry.text()) {
+            Some(value) => {
+                entry.remove_css_class("error");
+                if let Some(ranks) = profiles.borrow_mut().get_mut(&profile) {
+                    for r in ranks.iter_mut().filter(|r| r.feature == feature) {
+                        r.rank = value;
+                    }
+                }
+            }
+            None => entry.add_css_class("error"),
+        }
+    ));
+    hbox.append(&entry);
+
+    let row = gtk::ListBoxRow::builder().child(&hbox).build();
+    row.set_activatable(false);
+    let delete_button = gtk::Button::from_icon_name("user-trash-symbolic");
+    delete_button.set_tooltip_text(Some("Remove the override"));
+    delete_button.connect_clicked(glib::clone!(
+        #[strong]
+        profiles,
+        #[weak]
+        listbox,
+        #[weak]
+        row,
+        #[to_owned]
+        profile,
+        move |_| {
+            if let Some(ranks) = profiles.borrow_mut().get_mut(&profile) {
+                ranks.retain(|r| r.feature != feature);
+            }
+            listbox.remove(&row);
+        }
+    ));
+    hbox.append(&delete_button);
+    row
+}
+
+fn fill_rank_list(listbox: &gtk::ListBox, profiles: &RankProfiles, profile: &str) {
+    while let Some(child) = listbox.first_child() {
+        listbox.remove(&child);
+    }
+    let ranks = profiles.borrow().get(profile).cloned().unwrap_or_default();
+    for feature_rank in &ranks {
+        listbox.append(&rank_row(listbox, profiles, profile, feature_rank));
+    }
+}
+
+/// Add or replace the rank of features in a profile and show the new list.
+fn add_ranks(
+    listbox: &gtk::ListBox,
+    profiles: &RankProfiles,
+    profile: &str,
+    new_ranks: Vec<FeatureRank>,
+) {
+    if let Some(ranks) = profiles.borrow_mut().get_mut(profile) {
+        for feature_rank in new_ranks {
+            ranks.retain(|r| r.feature != feature_rank.feature);
+            ranks.push(feature_rank);
+        }
+    }
+    fill_rank_list(listbox, profiles, profile);
+}
+
+/// Edit the rank profiles, lists of features with the rank forced on them when a
+/// pipeline is created. The profile selected with "Use at launch" becomes active on
+/// Apply.
+pub fn display_rank_profiles(app: &GPSApp) {
+    let profiles: RankProfiles = Rc::new(RefCell::new(rank::rank_profiles()));
+    let active = Settings::active_rank_profile();
+
+    let main_box = gtk::Box::new(gtk::Orientation::Vertical, 8);
+
+    let mut names: Vec<String> = profiles.borrow().keys().cloned().collect();
+    names.sort();
+    let model = gtk::StringList::new(&names.iter().map(|n| n.as_str()).collect::<Vec<_>>());
+    let dropdown = gtk::DropDown::builder().model(&model).hexpand(true).build();
+    if let Some(position) = names.iter().position(|n| *n == active) {
+        dropdown.set_selected(position as u32);
+    }
+    let selected_profile = move |dropdown: &gtk::DropDown| {
+        dropdown
+            .selected_item()
+            .and_downcast::<gtk::StringObject>()
+            .map(|s| s.string().to_string())
+            .unwrap_or_default()
+    };
+
+    let profile_box = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    profile_box.append(&gtk::Label::new(Some("Profile")));
+    profile_box.append(&dropdown);
+    let new_button = gtk::Button::with_label("New...");
+    profile_box.append(&new_button);
+    let delete_button = gtk::Button::from_icon_name("user-trash-symbolic");
+    delete_button.set_tooltip_text(Some("Delete the profile"));
+    // The built-in profiles can be edited but not deleted
+    delete_button.set_sensitive(!rank::is_builtin_profile(&selected_profile(&dropdown)));
+    profile_box.append(&delete_button);
+    main_box.append(&profile_box);
+
+    let use_at_launch = gtk::CheckButton::with_label("Use at launch");
+    use_at_launch.set_tooltip_text(Some(
+        "Apply the ranks of this profile when a pipeline is created",
+    ));
+    use_at_launch.set_active(!active.is_empty() && selected_profile(&dropdown) == active);
+    main_box.append(&use_at_launch);
+    let active = Rc::new(RefCell::new(active));
+
+    let listbox = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(vec!["boxed-list"])
+        .build();
+    let scrolled = gtk::ScrolledWindow::builder()
+        .vexpand(true)
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .min_content_height(250)
+        .child(&listbox)
+        .build();
+    main_box.append(&scrolled);
+    fill_rank_list(&listbox, &profiles, &selected_profile(&dropdown));
+
+    dropdown.connect_selected_notify(glib::clone!(
+        #[strong]
+        profiles,
+        #[strong]
+        active,
+        #[weak]
+        listbox,
+        #[weak]
+        use_at_launch,
+        #[weak]
+        delete_button,
+        move |dropdown| {
+            let profile = selected_profile(dropdown);
+            use_at_launch.set_active(!profile.is_empty() && *active.borrow() == profile);
+            delete_button.set_sensitive(!rank::is_builtin_profile(&profile));
+            fill_rank_list(&listbox, &profiles, &profile);
+        }
+    ));
+    use_at_launch.connect_toggled(glib::clone!(
+        #[strong]
+        active,
+        #[weak]
+        dropdown,
+        move |check| {
+            let profile = selected_profile(&dropdown);
+            if check.is_active() {
+                *active.borrow_mut() = profile;
+            } else if *active.borrow() == profile {
+                active.borrow_mut().clear();
+            }
+        }
+    ));
+
+    let app_weak = app.downgrade();
+    new_button.connect_clicked(glib::clone!(
+        #[strong]
+        profiles,
+        #[weak]
+        dropdown,
+        #[weak]
+        model,
+        move |_| {
+            let app = upgrade_weak!(app_weak);
+            GPSUI::dialog::get_input(
+                &app,
+                "New rank profile",
+                "Name",
+                "",
+                glib::clone!(
+                    #[strong]
+                    profiles,
+                    #[weak]
+                    dropdown,
+                    #[weak]
+                    model,
+                    move |_app, name| {
+                        let name = name.trim().to_string();
+                        if name.is_empty() || profiles.borrow().contains_key(&name) {
+                            GPS_WARN!("Invalid or existing rank profile name '{}'", name);
+                            return;
+                        }
+                        profiles.borrow_mut().insert(name.clone(), vec![]);
+                        model.append(&name);
+                        dropdown.set_selected(model.n_items() - 1);
+                    }
+                ),
+            );
+        }
+    ));
+    delete_button.connect_clicked(glib::clone!(
+        #[strong]
+        profiles,
+        #[strong]
+        active,
+        #[weak]
+        dropdown,
+        #[weak]
+        model,
+        move |_| {
+            let profile = selected_profile(&dropdown);
+            if rank::is_builtin_profile(&profile)
+                || profiles.borrow_mut().remove(&profile).is_none()
+            {
+                return;
+            }
+            if *active.borrow() == profile {
+                active.borrow_mut().clear();
+            }
+            model.remove(dropdown.selected());
+        }
+    ));
+
+    // Override of a single feature
+    let add_box = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    let feature_entry = gtk::Entry::builder()
+        .placeholder_text("Feature, e.g. avdec_h264")
+        .hexpand(true)
+        .build();
+    let rank_entry = gtk::Entry::builder().text("MAX").width_chars(10).build();
+    let add_button = gtk::Button::with_label("Add");
+    add_button.connect_clicked(glib::clone!(
+        #[strong]
+        profiles,
+        #[weak]
+        listbox,
+        #[weak]
+        dropdown,
+        #[weak]
+        feature_entry,
+        #[weak]
+        rank_entry,
+        move |_| {
+            let feature = feature_entry.text().trim().to_string();
+            let Some(rank) = rank::rank_from_str(&rank_entry.text()) else {
+                rank_entry.add_css_class("error");
+                return;
+            };
+            rank_entry.remove_css_class("error");
+            if feature.is_empty() {
+                return;
+            }
+            add_ranks(
+                &listbox,
+                &profiles,
+                &selected_profile(&dropdown),
+                vec![FeatureRank { feature, rank }],
+            );
+            feature_entry.set_text("");
+        }
+    ));
+    add_box.append(&feature_entry);
+    add_box.append(&rank_entry);
+    add_box.append(&add_button);
+    main_box.append(&add_box);
+
+    // Every feature of a plugin set to NONE
+    let plugin_box = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    let plugin_entry = gtk::Entry::builder()
+        .placeholder_text("Plugin, e.g. vaapi")
+        .hexpand(true)
+        .build();
+    let blacklist_button = gtk::Button::with_label("Disable plugin");
+    blacklist_button.set_tooltip_text(Some(
+        "Set the rank of all the features of the plugin to NONE",
+    ));
+    blacklist_button.connect_clicked(glib::clone!(
+        #[strong]
+        profiles,
+        #[weak]
+        listbox,
+        #[weak]
+        dropdown,
+        #[weak]
+        plugin_entry,
+        move |_| {
+            let ranks = rank::plugin_blacklist(plugin_entry.text().trim());
+            if ranks.is_empty() {
+                plugin_entry.add_css_class("error");
+                return;
+            }
+            plugin_entry.remove_css_class("error");
+            add_ranks(&listbox, &profiles, &selected_profile(&dropdown), ranks);
+            plugin_entry.set_text("");
+        }
+    ));
+    plugin_box.append(&plugin_entry);
+    plugin_box.append(&blacklist_button);
+    main_box.append(&plugin_box);
+
+    let copy_button = gtk::Button::with_label("Copy as GST_PLUGIN_FEATURE_RANK");
+    copy_button.set_halign(gtk::Align::Start);
+    copy_button.connect_clicked(glib::clone!(
+        #[strong]
+        profiles,
+        #[weak]
+        dropdown,
+        move |button| {
+            let ranks = profiles
+                .borrow()
+                .get(&selected_profile(&dropdown))
+                .cloned()
+                .unwrap_or_default();
+            button.clipboard().set_text(&format!(
+                "GST_PLUGIN_FEATURE_RANK={}",
+                rank::format_feature_ranks(&ranks)
+            ));
+        }
+    ));
+    main_box.append(&copy_button);
+
+    let dialog = GPSUI::dialog::create("Element ranks", app, &main_box, move |_app, dialog| {
+        let saved: HashMap<String, String> = profiles
+            .borrow()
+            .iter()
+            .map(|(name, ranks)| (name.clone(), rank::format_feature_ranks(ranks)))
+            .collect();
+        Settings::set_rank_profiles(&saved);
+        Settings::set_active_rank_profile(&active.borrow());
+        let ranks = rank::active_profile_ranks();
+        GPS_INFO!(
+            "Rank profile '{}' applied: {}",
+            active.borrow(),
+            rank::format_feature_ranks(&ranks)
+        );
+        rank::apply_feature_ranks(&ranks);
+        dialog.close();
+    });
+    dialog.set_default_size(500, 600);
+    dialog.present();
+}
@@ -0,0 +1,149 @@
+// substitute.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::app::core::graphbook;
+use crate::app::GPSApp;
+use crate::gps::{rank, substitute, ElementInfo};
+use crate::logger;
+use crate::ui as GPSUI;
+use crate::{GPS_ERROR, GPS_WARN};
+use gtk::glib;
+use gtk::prelude::*;
+
+fn candidate_row(element: &ElementInfo) -> gtk::ListBoxRow {
+    let vbox = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(2)
+        .margin_start(8)
+        .margin_end(8)
+        .margin_top(4)
+        .margin_bottom(4)
+        .build();
+    vbox.append(
+        &gtk::Label::builder()
+            .label(format!(
+                "<b>{}</b>  {}",
+                glib::markup_escape_text(&element.name),
+                glib::markup_escape_text(&element.long_name)
+            ))
+            .use_markup(true)
+            .xalign(0.0)
+            .build(),
+    );
+    vbox.append(
+        &gtk::Label::builder()
+            .label(format!(
+                "{}, plugin {}, rank {}",
+                element.klass,
+                element.plugin_name,
+                rank::rank_name(element.rank)
+            ))
+            .xalign(0.0)
+            .css_classes(vec!["dim-label", "caption"])
+            .build(),
+    );
+    let row = gtk::ListBoxRow::builder().child(&vbox).build();
+    row.set_widget_name(&element.name);
+    row
+}
+
+fn replace_node(app: &GPSApp, node_id: u32, element_name: &str) {
+    if let Err(e) = app.replace_element(node_id, element_name) {
+        GPS_ERROR!(
+            "Unable to replace the node {} by {}: {}",
+            node_id,
+            element_name,
+            e
+        );
+    }
+}
+
+/// Display the factories able to replace the element of a node, the ones with pads in
+/// the same directions accepting its caps.
+pub fn display_replace_element(app: &GPSApp, node_id: u32) {
+    let Some(node) = graphbook::current_graphtab(app).graphview().node(node_id) else {
+        GPS_WARN!("Unable to retrieve node with id {}", node_id);
+        return;
+    };
+    let candidates = substitute::replacement_candidates(&node);
+
+    let main_box = gtk::Box::new(gtk::Orientation::Vertical, 8);
+    main_box.append(
+        &gtk::Label::builder()
+            .label(format!(
+                "{} element(s) can replace {}, the matching properties and links are kept",
+                candidates.len(),
+                node.name()
+            ))
+            .xalign(0.0)
+            .wrap(true)
+            .build(),
+    );
+    let search_entry = gtk::SearchEntry::builder()
+        .placeholder_text("Search an element")
+        .build();
+    main_box.append(&search_entry);
+
+    let listbox = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::Single)
+        .css_classes(vec!["boxed-list"])
+        .build();
+    for element in &candidates {
+        listbox.append(&candidate_row(element));
+    }
+    listbox.set_filter_func(glib::clone!(
+        #[weak]
+        search_entry,
+        #[upgrade_or]
+        true,
+        move |row| {
+            let text = search_entry.text().to_lowercase();
+            text.is_empty() || row.widget_name().to_lowercase().contains(&text)
+        }
+    ));
+    search_entry.connect_search_changed(glib::clone!(
+        #[weak]
+        listbox,
+        move |_| listbox.invalidate_filter()
+    ));
+    let scrolled = gtk::ScrolledWindow::builder()
+        .vexpand(true)
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .min_content_height(300)
+        .child(&listbox)
+        .build();
+    main_box.append(&scrolled);
+
+    let dialog = GPSUI::dialog::create(
+        &format!("Replace {}", node.unique_name()),
+        app,
+        &main_box,
+        glib::clone!(
+            #[weak]
+            listbox,
+            move |app, dialog| {
+                if let Some(row) = listbox.selected_row() {
+                    replace_node(&app, node_id, &row.widget_name());
+                }
+                dialog.close();
+            }
+        ),
+    );
+    let app_weak = app.downgrade();
+    listbox.connect_row_activated(glib::clone!(
+        #[weak]
+        dialog,
+        move |_, row| {
+            let app = upgrade_weak!(app_weak);
+            replace_node(&app, node_id, &row.widget_name());
+            dialog.close();
+        }
+    ));
+    dialog.set_default_size(500, 500);
+    dialog.present();
+}
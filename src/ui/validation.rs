@@ -0,0 +1,111 @@
+// validation.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::app::core::graphbook;
+use crate::app::GPSApp;
+use crate::gps::validation::{Diagnostic, DiagnosticSeverity};
+use gtk::prelude::*;
+
+/// Connect the list of the Problems panel to the graph of the current tab.
+pub fn setup_problems_panel(app: &GPSApp) {
+    let listbox: gtk::ListBox = app
+        .builder
+        .object("listbox-problems")
+        .expect("Couldn't get listbox-problems");
+    let app_weak = app.downgrade();
+    listbox.connect_row_activated(move |_, row| {
+        let app = upgrade_weak!(app_weak);
+        let diagnostics = graphbook::current_graphtab(&app)
+            .diagnostics()
+            .unwrap_or_default();
+        if let Some(diagnostic) = diagnostics.get(row.index() as usize) {
+            app.reveal_diagnostic_target(diagnostic.target);
+        }
+    });
+}
+
+/// Switch the debug notebook to the Problems panel.
+pub fn show_problems_panel(app: &GPSApp) {
+    let notebook: gtk::Notebook = app
+        .builder
+        .object("notebook-debug")
+        .expect("Couldn't get notebook-debug");
+    let page: gtk::Widget = app
+        .builder
+        .object("box-problems")
+        .expect("Couldn't get box-problems");
+    if let Some(page_num) = notebook.page_num(&page) {
+        notebook.set_current_page(Some(page_num));
+    }
+}
+
+fn diagnostic_row(app: &GPSApp, diagnostic: &Diagnostic) -> gtk::ListBoxRow {
+    let icon_name = match diagnostic.severity {
+        DiagnosticSeverity::Error => "dialog-error-symbolic",
+        DiagnosticSeverity::Warning => "dialog-warning-symbolic",
+    };
+    let hbox = gtk::Box::builder()
+        .spacing(6)
+        .margin_start(6)
+        .margin_end(6)
+        .margin_top(2)
+        .margin_bottom(2)
+        .build();
+    let icon = gtk::Image::from_icon_name(icon_name);
+    icon.set_tooltip_text(Some(&diagnostic.severity.to_string()));
+    hbox.append(&icon);
+    let label = gtk::Label::builder()
+        .label(&diagnostic.message)
+        .hexpand(true)
+        .xalign(0.0)
+        .wrap(true)
+        .build();
+    hbox.append(&label);
+    if let Some(fix) = &diagnostic.fix {
+        let button = gtk::Button::with_label(&fix.to_string());
+        let fix = fix.clone();
+        let app_weak = app.downgrade();
+        button.connect_clicked(move |_| {
+            let app = upgrade_weak!(app_weak);
+            app.apply_quick_fix(&fix);
+        });
+        hbox.append(&button);
+    }
+    gtk::ListBoxRow::builder().child(&hbox).build()
+}
+
+/// Fill the Problems panel with the problems found by the last check of a graph,
+/// `None` if the graph has not been checked.
+pub fn display_diagnostics(app: &GPSApp, diagnostics: Option<Vec<Diagnostic>>) {
+    let (Some(listbox), Some(summary)) = (
+        app.builder.object::<gtk::ListBox>("listbox-problems"),
+        app.builder.object::<gtk::Label>("label-problems-summary"),
+    ) else {
+        return;
+    };
+    while let Some(child) = listbox.first_child() {
+        listbox.remove(&child);
+    }
+    let Some(diagnostics) = diagnostics else {
+        summary.set_label("The graph has not been checked");
+        return;
+    };
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+    if diagnostics.is_empty() {
+        summary.set_label("No problem found");
+    } else {
+        summary.set_label(&format!("{errors} error(s), {warnings} warning(s)"));
+    }
+    for diagnostic in &diagnostics {
+        listbox.append(&diagnostic_row(app, diagnostic));
+    }
+}
@@ -0,0 +1,484 @@
+// value.rs
+//
+// Copyright 2025 Stéphane Cerveau <scerveau@igalia.com>
+//
+// This file is part of GstPipelineStudio
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::app::core::graphbook;
+use crate::app::GPSApp;
+use crate::gps as GPS;
+use crate::gps::value::{self, PropertyKind};
+use crate::logger;
+use crate::ui as GPSUI;
+use crate::{GPS_ERROR, GPS_TRACE};
+use gtk::glib;
+use gtk::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+/// Entry holding the serialized value with a button opening its editor. The editor
+/// sets the text of the entry, which reports the change.
+fn entry_with_editor<F, E>(
+    app: &GPSApp,
+    node_id: u32,
+    element_name: &str,
+    property_name: &str,
+    param: &glib::ParamSpec,
+    f: F,
+    open_editor: E,
+) -> gtk::Widget
+where
+    F: Fn(String, String) -> anyhow::Result<()> + 'static,
+    E: Fn(&GPSApp, &gtk::Entry) + 'static,
+{
+    let entry = gtk::Entry::new();
+    entry.set_widget_name(property_name);
+    entry.set_hexpand(true);
+    GPSUI::properties::populate_entry_from_property(
+        &entry,
+        app,
+        node_id,
+        element_name,
+        property_name,
+        param,
+    );
+    GPSUI::properties::check_entry(&entry, element_name, param);
+    entry.connect_changed(glib::clone!(
+        #[to_owned]
+        param,
+        move |e| {
+            let check = f(e.widget_name().to_string(), e.text().to_string());
+            GPSUI::properties::show_entry_check(e, &param, &check);
+        }
+    ));
+
+    let edit_button = gtk::Button::from_icon_name("document-edit-symbolic");
+    edit_button.set_tooltip_text(Some("Edit the value"));
+    let app_weak = app.downgrade();
+    edit_button.connect_clicked(glib::clone!(
+        #[weak]
+        entry,
+        move |_| {
+            let app = upgrade_weak!(app_weak);
+            open_editor(&app, &entry);
+        }
+    ));
+
+    let hbox = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+    hbox.append(&entry);
+    hbox.append(&edit_button);
+    hbox.upcast::<gtk::Widget>()
+}
+
+/// Widgets editing one item of an array.
+fn add_array_item(items_box: &gtk::Box, items: &Rc<RefCell<Vec<gtk::Entry>>>, item: &str) {
+    let entry = gtk::Entry::builder()
+        .text(item)
+        .placeholder_text("value")
+        .hexpand(true)
+        .build();
+    let remove_button = gtk::Button::from_icon_name("list-remove-symbolic");
+    remove_button.set_tooltip_text(Some("Remove the item"));
+    let row = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+    row.append(&entry);
+    row.append(&remove_button);
+    items_box.append(&row);
+    remove_button.connect_clicked(glib::clone!(
+        #[weak]
+        items_box,
+        #[weak]
+        row,
+        #[weak]
+        entry,
+        #[strong]
+        items,
+        move |_| {
+            items_box.remove(&row);
+            items.borrow_mut().retain(|e| *e != entry);
+        }
+    ));
+    items.borrow_mut().push(entry);
+}
+
+/// Display the dialog editing the items of an array value, `< a, b >`. `f` is called
+/// with the serialized array once it is applied.
+pub fn display_array_editor<F>(app: &GPSApp, title: &str, value: &str, f: F)
+where
+    F: Fn(String) + 'static,
+{
+    let main_box = gtk::Box::new(gtk::Orientation::Vertical, 8);
+    let items_box = gtk::Box::new(gtk::Orientation::Vertical, 6);
+    let items: Rc<RefCell<Vec<gtk::Entry>>> = Rc::new(RefCell::new(vec![]));
+    for item in value::split_array(value) {
+        add_array_item(&items_box, &items, &item);
+    }
+    main_box.append(&items_box);
+    let add_button = gtk::Button::with_label("Add item");
+    add_button.set_halign(gtk::Align::Start);
+    add_button.connect_clicked(glib::clone!(
+        #[weak]
+        items_box,
+        #[strong]
+        items,
+        move |_| add_array_item(&items_box, &items, "")
+    ));
+    main_box.append(&add_button);
+
+    let dialog = GPSUI::dialog::create(title, app, &main_box, move |_app, dialog| {
+        let values: Vec<String> = items
+            .borrow()
+            .iter()
+            .map(|entry| entry.text().trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect();
+        f(value::format_array(&values));
+        dialog.close();
+    });
+    dialog.set_default_size(400, 300);
+    dialog.present();
+}
+
+/// Numerator and denominator spinners of a fraction property, such as a framerate.
+fn fraction_widget<F>(
+    app: &GPSApp,
+    node_id: u32,
+    element_name: &str,
+    property_name: &str,
+    param: &glib::ParamSpec,
+    f: F,
+) -> gtk::Widget
+where
+    F: Fn(String, String) -> anyhow::Result<()> + 'static,
+{
+    let (numerator, denominator) =
+        GPSUI::properties::current_property_value(app, node_id, element_name, property_name, param)
+            .and_then(|value| value::parse_fraction(&value))
+            .unwrap_or((0, 1));
+    let numerator_button = gtk::SpinButton::with_range(i32::MIN as f64, i32::MAX as f64, 1.0);
+    numerator_button.set_value(numerator as f64);
+    let denominator_button = gtk::SpinButton::with_range(1.0, i32::MAX as f64, 1.0);
+    denominator_button.set_value(denominator as f64);
+
+    let hbox = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+    hbox.set_widget_name(property_name);
+    hbox.append(&numerator_button);
+    hbox.append(&gtk::Label::new(Some("/")));
+    hbox.append(&denominator_button);
+
+    let f = Rc::new(f);
+    for button in [&numerator_button, &denominator_button] {
+        button.connect_value_changed(glib::clone!(
+            #[weak]
+            numerator_button,
+            #[weak]
+            denominator_button,
+            #[weak]
+            hbox,
+            #[strong]
+            f,
+            move |_| {
+                let _ = f(
+                    hbox.widget_name().to_string(),
+                    value::format_fraction(
+                        numerator_button.value_as_int(),
+                        denominator_button.value_as_int(),
+                    ),
+                );
+            }
+        ));
+    }
+    hbox.upcast::<gtk::Widget>()
+}
+
+/// Button showing the flags set on a property, `video+audio+soft-volume`, with a
+/// popover holding a check button for each flag. An unset property keeps the default
+/// flags of the element.
+pub fn flags_widget<F>(
+    app: &GPSApp,
+    node_id: u32,
+    element_name: &str,
+    property_name: &str,
+    param: &glib::ParamSpec,
+    f: F,
+) -> gtk::Widget
+where
+    F: Fn(String, String) -> anyhow::Result<()> + 'static,
+{
+    let flags = Rc::new(
+        param
+            .downcast_ref::<glib::ParamSpecFlags>()
+            .expect("Should be a ParamSpecFlags")
+            .flags_class(),
+    );
+    let default_mask = value::flags_mask(param.default_value()).unwrap_or_default();
+    let mask =
+        GPSUI::properties::current_property_value(app, node_id, element_name, property_name, param)
+            .and_then(|value| value::parse_flags(&flags, &value).ok())
+            .unwrap_or(default_mask);
+    let mask = Rc::new(Cell::new(mask));
+    // Set while the check buttons are updated from the mask
+    let updating = Rc::new(Cell::new(false));
+
+    let menu_button = gtk::MenuButton::builder()
+        .label(value::format_flags(&flags, mask.get()))
+        .hexpand(true)
+        .build();
+    menu_button.set_widget_name(property_name);
+    let checks_box = gtk::Box::new(gtk::Orientation::Vertical, 4);
+    let mut checks: Vec<(gtk::CheckButton, u32)> = vec![];
+    for flag in flags.values().iter().filter(|flag| flag.value() != 0) {
+        let check = gtk::CheckButton::with_label(flag.nick());
+        check.set_tooltip_text(Some(flag.name()));
+        checks_box.append(&check);
+        checks.push((check, flag.value()));
+    }
+    let checks = Rc::new(checks);
+    let update_checks = glib::clone!(
+        #[strong]
+        checks,
+        #[strong]
+        mask,
+        #[strong]
+        updating,
+        move || {
+            updating.set(true);
+            for (check, bits) in checks.iter() {
+                check.set_active(mask.get() & bits == *bits);
+            }
+            updating.set(false);
+        }
+    );
+    update_checks();
+
+    let f = Rc::new(f);
+    for (check, bits) in checks.iter() {
+        let bits = *bits;
+        check.connect_toggled(glib::clone!(
+            #[strong]
+            mask,
+            #[strong]
+            updating,
+            #[strong]
+            update_checks,
+            #[strong]
+            f,
+            #[strong]
+            flags,
+            #[weak]
+            menu_button,
+            move |check| {
+                if updating.get() {
+                    return;
+                }
+                if check.is_active() {
+                    mask.set(mask.get() | bits);
+                } else {
+                    mask.set(mask.get() & !bits);
+                }
+                // The flags grouping several bits follow the single ones
+                update_checks();
+                let value = value::format_flags(&flags, mask.get());
+                menu_button.set_label(&value);
+                let _ = f(menu_button.widget_name().to_string(), value);
+            }
+        ));
+    }
+
+    let reset_button = gtk::Button::with_label("Default");
+    reset_button.set_tooltip_text(Some("Reset the property to the default flags"));
+    reset_button.connect_clicked(glib::clone!(
+        #[strong]
+        mask,
+        #[strong]
+        f,
+        #[weak]
+        menu_button,
+        move |_| {
+            mask.set(default_mask);
+            update_checks();
+            menu_button.set_label(&value::format_flags(&flags, default_mask));
+            let _ = f(menu_button.widget_name().to_string(), String::new());
+        }
+    ));
+    checks_box.append(&reset_button);
+
+    let popover = gtk::Popover::builder().child(&checks_box).build();
+    menu_button.set_popover(Some(&popover));
+    menu_button.upcast::<gtk::Widget>()
+}
+
+/// Label of the element embedded in a property, with the buttons to embed a new one,
+/// edit its properties or remove it. The graph is changed right away.
+fn element_widget(app: &GPSApp, node_id: u32, property_name: &str) -> gtk::Widget {
+    let label = gtk::Label::builder()
+        .hexpand(true)
+        .xalign(0.0)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .build();
+    let embed_button = gtk::Button::with_label("Embed...");
+    embed_button.set_tooltip_text(Some("Set a new element as the value of the property"));
+    let edit_button = gtk::Button::from_icon_name("document-properties-symbolic");
+    edit_button.set_tooltip_text(Some("Edit the properties of the embedded element"));
+    let remove_button = gtk::Button::from_icon_name("user-trash-symbolic");
+    remove_button.set_tooltip_text(Some("Remove the embedded element"));
+
+    let app_weak = app.downgrade();
+    let property_name = property_name.to_string();
+    let refresh: Rc<dyn Fn()> = Rc::new(glib::clone!(
+        #[weak]
+        label,
+        #[weak]
+        edit_button,
+        #[weak]
+        remove_button,
+        #[strong]
+        property_name,
+        move || {
+            let app = upgrade_weak!(app_weak);
+            let tab = graphbook::current_graphtab(&app);
+            let child = GPS::embed::embedded_child(&tab.graphview(), node_id, &property_name);
+            match &child {
+                Some(child) => {
+                    label.set_label(&format!("{} ({})", child.unique_name(), child.name()))
+                }
+                None => label.set_label("None"),
+            }
+            edit_button.set_sensitive(child.is_some());
+            remove_button.set_sensitive(child.is_some());
+        }
+    ));
+    refresh();
+
+    let app_weak = app.downgrade();
+    embed_button.connect_clicked(glib::clone!(
+        #[strong]
+        property_name,
+        #[strong]
+        refresh,
+        move |_| {
+            let app = upgrade_weak!(app_weak);
+            let tab = graphbook::current_graphtab(&app);
+            let current = GPS::embed::embedded_child(&tab.graphview(), node_id, &property_name)
+                .map(|child| child.name())
+                .unwrap_or_default();
+            GPSUI::dialog::get_input(
+                &app,
+                "Embed element",
+                "Element",
+                &current,
+                glib::clone!(
+                    #[strong]
+                    property_name,
+                    #[strong]
+                    refresh,
+                    move |app, element_name| {
+                        if let Err(e) =
+                            app.embed_element(node_id, &property_name, element_name.trim())
+                        {
+                            GPS_ERROR!("Unable to embed {}: {}", element_name, e);
+                        }
+                        refresh();
+                    }
+                ),
+            );
+        }
+    ));
+    let app_weak = app.downgrade();
+    edit_button.connect_clicked(glib::clone!(
+        #[strong]
+        property_name,
+        move |_| {
+            let app = upgrade_weak!(app_weak);
+            let tab = graphbook::current_graphtab(&app);
+            let child = GPS::embed::embedded_child(&tab.graphview(), node_id, &property_name);
+            if let Some(child) = child {
+                GPSUI::properties::display_plugin_properties(&app, &child.name(), child.id());
+            }
+        }
+    ));
+    let app_weak = app.downgrade();
+    remove_button.connect_clicked(glib::clone!(
+        #[strong]
+        property_name,
+        #[strong]
+        refresh,
+        move |_| {
+            let app = upgrade_weak!(app_weak);
+            app.remove_embedded_element(node_id, &property_name);
+            refresh();
+        }
+    ));
+
+    let hbox = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+    hbox.append(&label);
+    hbox.append(&embed_button);
+    hbox.append(&edit_button);
+    hbox.append(&remove_button);
+    hbox.upcast::<gtk::Widget>()
+}
+
+/// Widget editing a caps, structure, array, fraction or element property, `None` for
+/// the other properties.
+pub fn typed_property_widget<F>(
+    app: &GPSApp,
+    node_id: u32,
+    element_name: &str,
+    property_name: &str,
+    param: &glib::ParamSpec,
+    f: F,
+) -> Option<gtk::Widget>
+where
+    F: Fn(String, String) -> anyhow::Result<()> + 'static,
+{
+    let kind = value::property_kind(param);
+    GPS_TRACE!("add {:?} property : {}", kind, property_name);
+    let title = format!("{} of {}", property_name, element_name);
+    match kind {
+        PropertyKind::Caps | PropertyKind::Structure => Some(entry_with_editor(
+            app,
+            node_id,
+            element_name,
+            property_name,
+            param,
+            f,
+            move |app, entry| {
+                GPSUI::caps::display_caps_value_editor(
+                    app,
+                    &title,
+                    &entry.text(),
+                    kind == PropertyKind::Structure,
+                    {
+                        let entry = entry.clone();
+                        move |value| entry.set_text(&value)
+                    },
+                );
+            },
+        )),
+        PropertyKind::Array => Some(entry_with_editor(
+            app,
+            node_id,
+            element_name,
+            property_name,
+            param,
+            f,
+            move |app, entry| {
+                display_array_editor(app, &title, &entry.text(), {
+                    let entry = entry.clone();
+                    move |value| entry.set_text(&value)
+                });
+            },
+        )),
+        PropertyKind::Fraction => Some(fraction_widget(
+            app,
+            node_id,
+            element_name,
+            property_name,
+            param,
+            f,
+        )),
+        PropertyKind::Element => Some(element_widget(app, node_id, property_name)),
+        PropertyKind::Other => None,
+    }
+}